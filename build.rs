@@ -99,11 +99,12 @@ fn compile_resources() -> io::Result<()> {
     }
 
     // Link the resource file
-    println!("cargo:rustc-link-arg-bins={}", res_path.to_string_lossy());
+    println!("cargo:rustc-link-arg-bin=compactrs={}", res_path.to_string_lossy());
 
-    // Explicitly set entry point and subsystem for no_main
-    println!("cargo:rustc-link-arg=/ENTRY:WinMainCRTStartup");
-    println!("cargo:rustc-link-arg=/SUBSYSTEM:WINDOWS");
+    // Explicitly set entry point and subsystem for no_main. Only the GUI binary: the
+    // calibrate tool is a console program with a regular main.
+    println!("cargo:rustc-link-arg-bin=compactrs=/ENTRY:WinMainCRTStartup");
+    println!("cargo:rustc-link-arg-bin=compactrs=/SUBSYSTEM:WINDOWS");
     
     // Explicitly link CRT libraries for symbols like memset
     #[cfg(target_env = "msvc")]
@@ -0,0 +1,27 @@
+//! Portable subset of `src/engine` compiled into the calibration tool. Only the WOF driver
+//! layer is replaced; the estimator, codecs and helpers are the app's own sources.
+#![allow(dead_code)]
+
+#[path = "../../engine/algorithm.rs"]
+pub mod algorithm;
+#[path = "../../engine/auto.rs"]
+pub mod auto;
+#[path = "../../engine/codec/mod.rs"]
+pub mod codec;
+#[path = "../../engine/estimator.rs"]
+pub mod estimator;
+#[path = "../../engine/knowledge.rs"]
+pub mod knowledge;
+#[path = "../../engine/sniff.rs"]
+pub mod sniff;
+#[path = "../../engine/wof_stream.rs"]
+pub mod wof_stream;
+
+pub mod wof {
+    pub use super::algorithm::{AlgoChoice, WofAlgorithm};
+
+    /// Corpus volumes are described by the manifest and never queried.
+    pub fn get_cluster_size(_path: &str) -> Option<u64> {
+        None
+    }
+}
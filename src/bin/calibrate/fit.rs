@@ -0,0 +1,43 @@
+//! Parameter fitting for the LZX curve.
+
+use crate::engine::estimator::LzxCurve;
+
+/// Curve minimising the mean absolute error over `(xpress_ratio, lzx_ratio)` pairs.
+pub fn fit_lzx_curve(rows: &[(f64, f64)]) -> Option<LzxCurve> {
+    if rows.is_empty() { return None; }
+
+    let error = |c: &LzxCurve| rows.iter().map(|&(x, t)| (c.apply(x) - t).abs()).sum::<f64>();
+    let steps = |from: f64, to: f64, step: f64| {
+        let n = ((to - from) / step).round() as usize;
+        (0..=n).map(move |i| from + i as f64 * step)
+    };
+
+    let mut best = (error(&LzxCurve::default()), LzxCurve::default());
+    for low in steps(0.30, 0.50, 0.05) {
+        for high in steps(0.80, 0.95, 0.05) {
+            for low_mult in steps(0.60, 1.00, 0.01) {
+                for high_mult in steps(0.85, 1.00, 0.01) {
+                    let c = LzxCurve { low, high, low_mult, high_mult };
+                    let e = error(&c);
+                    if e < best.0 { best = (e, c); }
+                }
+            }
+        }
+    }
+    Some(best.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_lzx_curve() {
+        let truth = LzxCurve { low: 0.35, high: 0.85, low_mult: 0.75, high_mult: 0.95 };
+        let rows: Vec<(f64, f64)> = (1..100).map(|i| { let x = i as f64 / 100.0; (x, truth.apply(x)) }).collect();
+
+        let fitted = fit_lzx_curve(&rows).unwrap();
+        let err: f64 = rows.iter().map(|&(x, t)| (fitted.apply(x) - t).abs()).sum::<f64>() / rows.len() as f64;
+        assert!(err < 0.002, "{:?} {}", fitted, err);
+    }
+}
@@ -0,0 +1,210 @@
+//! Offline estimator accuracy harness and calibration tool.
+//!
+//! Runs the app's estimator over a corpus of sample files whose real on-disk sizes were
+//! measured on Windows, reports the error per algorithm, size tier and extension along
+//! with how often the truth falls inside the estimate's 95% interval, and optionally refits
+//! the LZX curve (`EstimatorParams`). Only portable engine code is compiled in, so it runs
+//! headless on Linux:
+//!
+//! ```text
+//! cargo run --release --bin calibrate -- corpus/manifest.txt [--root DIR] [--mode accurate|fast]
+//!     [--fit] [--top N] [--tier-l BYTES] [--tier-xl BYTES] [--target HALF_WIDTH]
+//! ```
+//!
+//! See `manifest.rs` for the manifest format.
+
+mod engine;
+mod fit;
+mod manifest;
+mod report;
+
+use std::path::{Path, PathBuf};
+
+use engine::algorithm::WofAlgorithm;
+use engine::estimator::{self, AllocModel, EstimateMode, EstimatorParams};
+use engine::wof_stream;
+use manifest::Manifest;
+use report::Sample;
+
+#[macro_export]
+macro_rules! log_error {
+    ($msg:expr) => { eprintln!("{}", $msg) };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ($msg:expr) => { eprintln!("{}", $msg) };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ($msg:expr) => { eprintln!("{}", $msg) };
+}
+
+struct Options {
+    manifest: PathBuf,
+    root: Option<PathBuf>,
+    mode: EstimateMode,
+    fit: bool,
+    top: usize,
+    params: EstimatorParams,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        manifest: PathBuf::new(),
+        root: None,
+        mode: EstimateMode::Accurate,
+        fit: false,
+        top: 15,
+        // Learned ratios come from real use and must not leak into a calibration run
+        params: EstimatorParams { use_learned: false, ..EstimatorParams::default() },
+    };
+
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().cloned().ok_or_else(|| ["missing value for ", arg].concat());
+        match arg.as_str() {
+            "--root" => opts.root = Some(PathBuf::from(value()?)),
+            "--mode" => opts.mode = match value()?.as_str() {
+                "accurate" => EstimateMode::Accurate,
+                "fast" => EstimateMode::Fast,
+                other => return Err(["unknown mode '", other, "'"].concat()),
+            },
+            "--fit" => opts.fit = true,
+            "--top" => opts.top = value()?.parse().map_err(|_| "invalid --top")?,
+            "--tier-l" => opts.params.tier_l = value()?.parse().map_err(|_| "invalid --tier-l")?,
+            "--tier-xl" => opts.params.tier_xl = value()?.parse().map_err(|_| "invalid --tier-xl")?,
+            "--target" => opts.params.target_half_width = value()?.parse().map_err(|_| "invalid --target")?,
+            _ if arg.starts_with("--") => return Err(["unknown option ", arg].concat()),
+            _ => opts.manifest = PathBuf::from(arg),
+        }
+    }
+    if opts.manifest.as_os_str().is_empty() { return Err("no manifest given".into()); }
+    Ok(opts)
+}
+
+/// A manifest entry whose file exists and matches its recorded size.
+struct CorpusFile {
+    path: PathBuf,
+    ext: String,
+    size: u64,
+    truth: Vec<(WofAlgorithm, u64)>,
+}
+
+fn load_corpus(manifest: Manifest) -> Vec<CorpusFile> {
+    let mut files = Vec::new();
+    for e in manifest.entries {
+        let Ok(size) = std::fs::metadata(&e.path).map(|m| m.len()) else {
+            eprintln!("missing: {}", e.path.display());
+            continue;
+        };
+        if e.size.is_some_and(|s| s != size) {
+            eprintln!("size changed, skipped: {}", e.path.display());
+            continue;
+        }
+        if size == 0 { continue; }
+        let ext = e.path.extension().and_then(|x| x.to_str()).unwrap_or("").to_lowercase();
+        files.push(CorpusFile { path: e.path, ext, size, truth: e.truth });
+    }
+    files
+}
+
+fn evaluate(files: &[CorpusFile], mode: EstimateMode, model: AllocModel, params: EstimatorParams) -> Vec<Sample> {
+    let mut samples = Vec::new();
+    for f in files {
+        let Some(path) = f.path.to_str() else { continue };
+        for &(algo, truth) in &f.truth {
+            let e = estimator::estimate_path_range(path, algo, mode, model, params);
+            samples.push(Sample { ext: f.ext.clone(), tier: params.tier(f.size), algo, truth, est: e.size, margin: e.margin });
+        }
+    }
+    samples
+}
+
+/// Data ratio behind a measured allocation (chunk table and expected cluster round-up removed).
+fn truth_ratio(size: u64, truth: u64, algo: WofAlgorithm, model: AllocModel) -> f64 {
+    if truth >= size { return 1.0; }
+    let table = if algo == WofAlgorithm::Lznt1 { 0 } else { wof_stream::chunk_table_size(size, algo) };
+    (truth.saturating_sub(table + model.cluster_size / 2) as f64 / size as f64).clamp(0.0, 1.0)
+}
+
+/// Refits the LZX curve from sampled XPRESS16K ratios against measured LZX sizes.
+fn fit_params(files: &[CorpusFile], model: AllocModel, params: EstimatorParams) -> EstimatorParams {
+    let mut fitted = params;
+    let mut curve = Vec::new();
+    for f in files {
+        for &(algo, truth) in &f.truth {
+            if algo == WofAlgorithm::Lzx && let Some(s) = estimator::sample_ratio(&f.path, algo, EstimateMode::Fast, model, params) {
+                curve.push((s.ratio, truth_ratio(f.size, truth, algo, model)));
+            }
+        }
+    }
+
+    if let Some(c) = fit::fit_lzx_curve(&curve) { fitted.lzx_curve = c; }
+    println!("Fitted from {} LZX samples", curve.len());
+    fitted
+}
+
+fn print_params(p: &EstimatorParams) {
+    let c = &p.lzx_curve;
+    println!("lzx_curve: LzxCurve {{ low: {:.2}, high: {:.2}, low_mult: {:.2}, high_mult: {:.2} }}", c.low, c.high, c.low_mult, c.high_mult);
+}
+
+fn run(opts: Options) -> Result<(), String> {
+    let text = std::fs::read_to_string(&opts.manifest).map_err(|e| [&opts.manifest.display().to_string(), ": ", &e.to_string()].concat())?;
+    let root = opts.root.clone().unwrap_or_else(|| opts.manifest.parent().unwrap_or(Path::new(".")).to_path_buf());
+    let manifest = manifest::parse(&text, &root)?;
+    let model = AllocModel { cluster_size: manifest.cluster_size, ..AllocModel::default() };
+    let files = load_corpus(manifest);
+    if files.is_empty() { return Err("no usable corpus files".into()); }
+
+    println!("Corpus: {} files, cluster {} bytes, {:?} mode\n", files.len(), model.cluster_size, opts.mode);
+    let before = evaluate(&files, opts.mode, model, opts.params);
+    println!("{}", report::render(&before, opts.top));
+
+    if opts.fit {
+        let fitted = fit_params(&files, model, opts.params);
+        let after = evaluate(&files, opts.mode, model, fitted);
+        println!("\nWith fitted parameters:\n\n{}", report::render(&after, opts.top));
+        print_params(&fitted);
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = parse_args(&args).and_then(run) {
+        eprintln!("calibrate: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_end_to_end_report() {
+        let root = std::env::temp_dir().join(["compactrs_calibrate_", &std::process::id().to_string()].concat());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+
+        let text: Vec<u8> = b"<item id=\"42\" name=\"value\"/>\r\n".repeat(8000);
+        std::fs::write(root.join("data.xml"), &text).unwrap();
+        std::fs::write(root.join("manifest.txt"), ["cluster 4096\ndata.xml size=", &text.len().to_string(), " xpress8k=40960 lzx=24576\nmissing.bin lzx=1\n"].concat()).unwrap();
+
+        let opts = parse_args(&[root.join("manifest.txt").to_string_lossy().into_owned(), "--fit".into()]).unwrap();
+        assert!(!opts.params.use_learned);
+        let manifest = manifest::parse(&std::fs::read_to_string(&opts.manifest).unwrap(), &root).unwrap();
+        let files = load_corpus(manifest);
+        assert_eq!(files.len(), 1);
+
+        let samples = evaluate(&files, EstimateMode::Accurate, AllocModel::default(), opts.params);
+        assert_eq!(samples.len(), 2);
+        assert!(samples.iter().all(|s| s.tier == estimator::Tier::Small && s.est > 0 && s.est < text.len() as u64));
+        assert!(run(opts).is_ok());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
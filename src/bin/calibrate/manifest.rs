@@ -0,0 +1,98 @@
+//! Corpus manifest: sample files with their measured on-disk size per algorithm.
+//!
+//! ```text
+//! # Measured on NTFS, 4K clusters
+//! cluster 4096
+//! bin/app.exe        size=1048576  xpress4k=602112  xpress8k=573440  xpress16k=557056  lzx=471040
+//! assets/level0.pak  xpress16k=20480000  lzx=19800064
+//! ```
+//!
+//! Paths are relative to the manifest's directory (or `--root`) and separated from the
+//! fields by whitespace; quote paths containing spaces. Sizes are what
+//! `GetCompressedFileSizeW` reported after compressing the file with that algorithm.
+//! The optional `size=` is checked against the file so a changed corpus is not
+//! silently compared against stale measurements.
+
+use std::path::{Path, PathBuf};
+
+use crate::engine::algorithm::WofAlgorithm;
+
+#[derive(Debug)]
+pub struct Entry {
+    pub path: PathBuf,
+    /// Expected logical size, if recorded.
+    pub size: Option<u64>,
+    pub truth: Vec<(WofAlgorithm, u64)>,
+}
+
+#[derive(Debug)]
+pub struct Manifest {
+    pub cluster_size: u64,
+    pub entries: Vec<Entry>,
+}
+
+/// Parses manifest `text`, resolving paths against `root`. Errors carry the line number.
+pub fn parse(text: &str, root: &Path) -> Result<Manifest, String> {
+    let mut manifest = Manifest { cluster_size: 4096, entries: Vec::new() };
+
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+        let err = |msg: &str| ["line ", &(i + 1).to_string(), ": ", msg].concat();
+
+        if let Some(value) = line.strip_prefix("cluster ") {
+            manifest.cluster_size = value.trim().parse().ok().filter(|&c: &u64| c.is_power_of_two()).ok_or_else(|| err("invalid cluster size"))?;
+            continue;
+        }
+
+        let (path, rest) = split_path(line).ok_or_else(|| err("unterminated quote"))?;
+        let mut entry = Entry { path: root.join(path), size: None, truth: Vec::new() };
+        for field in rest.split_whitespace() {
+            let (key, value) = field.split_once('=').ok_or_else(|| err(&["expected key=bytes, got '", field, "'"].concat()))?;
+            let bytes: u64 = value.parse().map_err(|_| err(&["invalid size '", value, "'"].concat()))?;
+            match (key, WofAlgorithm::from_name(key)) {
+                ("size", _) => entry.size = Some(bytes),
+                (_, None) => return Err(err(&["unknown algorithm '", key, "'"].concat())),
+                (_, Some(algo)) => entry.truth.push((algo, bytes)),
+            }
+        }
+        if entry.truth.is_empty() { return Err(err("no measurements")); }
+        manifest.entries.push(entry);
+    }
+    Ok(manifest)
+}
+
+/// Splits off a leading (optionally quoted) path.
+fn split_path(line: &str) -> Option<(&str, &str)> {
+    if let Some(quoted) = line.strip_prefix('"') {
+        let end = quoted.find('"')?;
+        return Some((&quoted[..end], &quoted[end + 1..]));
+    }
+    Some(line.split_once(char::is_whitespace).unwrap_or((line, "")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let m = parse("
+            # comment
+            cluster 65536
+            bin/app.exe  size=1000000 xpress4k=600000 LZX=470000
+            \"my docs/a b.txt\" xpress8k=4096
+        ", Path::new("/corpus")).unwrap();
+
+        assert_eq!(m.cluster_size, 65536);
+        assert_eq!(m.entries.len(), 2);
+        assert_eq!(m.entries[0].path, Path::new("/corpus/bin/app.exe"));
+        assert_eq!(m.entries[0].size, Some(1_000_000));
+        assert_eq!(m.entries[0].truth, vec![(WofAlgorithm::Xpress4K, 600_000), (WofAlgorithm::Lzx, 470_000)]);
+        assert_eq!(m.entries[1].path, Path::new("/corpus/my docs/a b.txt"));
+
+        assert!(parse("a.bin xpress9k=1", Path::new(".")).unwrap_err().starts_with("line 1"));
+        assert!(parse("a.bin", Path::new(".")).is_err());
+        assert!(parse("cluster 3000", Path::new(".")).is_err());
+    }
+}
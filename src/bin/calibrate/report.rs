@@ -0,0 +1,150 @@
+//! Error statistics of estimates against measured sizes.
+
+use std::collections::BTreeMap;
+
+use crate::engine::algorithm::WofAlgorithm;
+use crate::engine::estimator::Tier;
+
+/// One file estimated for one algorithm.
+pub struct Sample {
+    pub ext: String,
+    pub tier: Tier,
+    pub algo: WofAlgorithm,
+    pub truth: u64,
+    pub est: u64,
+    /// Half-width of the estimate's 95% sampling interval.
+    pub margin: u64,
+}
+
+impl Sample {
+    /// Signed relative error of the estimate.
+    pub fn error(&self) -> f64 {
+        (self.est as f64 - self.truth as f64) / self.truth.max(1) as f64
+    }
+
+    /// Whether the truth lies inside the estimate's interval.
+    pub fn covered(&self) -> bool {
+        self.est.saturating_sub(self.margin) <= self.truth && self.truth <= self.est + self.margin
+    }
+}
+
+/// Error summary of a group of samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub n: usize,
+    /// Mean absolute relative error.
+    pub mean: f64,
+    /// 95th percentile absolute relative error.
+    pub p95: f64,
+    /// Mean signed relative error (positive = overestimates the on-disk size).
+    pub bias: f64,
+    /// `1 - |sum(est) - sum(truth)| / sum(truth)`, the figure a folder estimate shows.
+    pub total_accuracy: f64,
+    /// Share of samples whose truth lies inside the 95% interval (of those with one).
+    pub coverage: Option<f64>,
+}
+
+impl Stats {
+    pub fn of<'a>(samples: impl IntoIterator<Item = &'a Sample>) -> Option<Self> {
+        let mut errors = Vec::new();
+        let (mut est, mut truth) = (0u64, 0u64);
+        let (mut with_margin, mut covered) = (0usize, 0usize);
+        for s in samples {
+            errors.push(s.error());
+            est += s.est;
+            truth += s.truth;
+            if s.margin > 0 {
+                with_margin += 1;
+                covered += s.covered() as usize;
+            }
+        }
+        if errors.is_empty() { return None; }
+
+        let n = errors.len();
+        let bias = errors.iter().sum::<f64>() / n as f64;
+        let mut abs: Vec<f64> = errors.iter().map(|e| e.abs()).collect();
+        abs.sort_by(f64::total_cmp);
+        let mean = abs.iter().sum::<f64>() / n as f64;
+        let p95 = abs[((n as f64 * 0.95).ceil() as usize).clamp(1, n) - 1];
+        let total_accuracy = 1.0 - (est as f64 - truth as f64).abs() / truth.max(1) as f64;
+        let coverage = (with_margin > 0).then(|| covered as f64 / with_margin as f64);
+        Some(Self { n, mean, p95, bias, total_accuracy, coverage })
+    }
+}
+
+fn pct(x: f64) -> String {
+    format!("{:.1}%", x * 100.0)
+}
+
+fn row(label: &str, s: &Stats) -> String {
+    let coverage = s.coverage.map_or("-".to_string(), pct);
+    format!("  {:<18} {:>6} {:>9} {:>9} {:>9} {:>9} {:>9}", label, s.n, pct(s.mean), pct(s.p95), format!("{:+.1}%", s.bias * 100.0), pct(s.total_accuracy), coverage)
+}
+
+fn header(title: &str) -> String {
+    format!("{}\n  {:<18} {:>6} {:>9} {:>9} {:>9} {:>9} {:>9}", title, "", "files", "mean", "p95", "bias", "total", "in CI")
+}
+
+/// Text report per algorithm: overall, per tier and the `top` most common extensions.
+pub fn render(samples: &[Sample], top: usize) -> String {
+    let mut by_algo: BTreeMap<u32, Vec<&Sample>> = BTreeMap::new();
+    for s in samples {
+        by_algo.entry(s.algo as u32).or_default().push(s);
+    }
+
+    let mut out = Vec::new();
+    for group in by_algo.values() {
+        let algo = group[0].algo;
+        out.push(header(&["[", algo.name(), "]"].concat()));
+        if let Some(st) = Stats::of(group.iter().copied()) { out.push(row("all", &st)); }
+
+        for tier in [Tier::Small, Tier::Large, Tier::Giant] {
+            if let Some(st) = Stats::of(group.iter().copied().filter(|s| s.tier == tier)) {
+                out.push(row(&format!("tier {:?}", tier).to_lowercase(), &st));
+            }
+        }
+
+        let mut by_ext: BTreeMap<&str, Vec<&Sample>> = BTreeMap::new();
+        for s in group {
+            by_ext.entry(s.ext.as_str()).or_default().push(s);
+        }
+        let mut exts: Vec<_> = by_ext.into_iter().collect();
+        exts.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+        for (ext, list) in exts.into_iter().take(top) {
+            if let Some(st) = Stats::of(list) {
+                out.push(row(&[".", if ext.is_empty() { "(none)" } else { ext }].concat(), &st));
+            }
+        }
+        out.push(String::new());
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(est: u64, truth: u64) -> Sample {
+        Sample { ext: "dll".into(), tier: Tier::Small, algo: WofAlgorithm::Lzx, truth, est, margin: 0 }
+    }
+
+    #[test]
+    fn test_stats() {
+        let samples: Vec<Sample> = [(110, 100), (90, 100), (100, 100), (150, 100)].iter().map(|&(e, t)| sample(e, t)).collect();
+        let st = Stats::of(&samples).unwrap();
+        assert_eq!(st.n, 4);
+        assert!((st.mean - 0.175).abs() < 1e-9);
+        assert!((st.bias - 0.125).abs() < 1e-9);
+        assert!((st.p95 - 0.5).abs() < 1e-9);
+        assert!((st.total_accuracy - 0.875).abs() < 1e-9);
+        assert_eq!(st.coverage, None);
+        assert_eq!(Stats::of(&[]), None);
+
+        let mut ranged = vec![sample(110, 100), sample(150, 100)];
+        ranged.iter_mut().for_each(|s| s.margin = 20);
+        assert_eq!(Stats::of(&ranged).unwrap().coverage, Some(0.5));
+
+        let text = render(&samples, 5);
+        assert!(text.contains("[lzx]") && text.contains("tier small") && text.contains(".dll"));
+    }
+}
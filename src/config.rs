@@ -1,7 +1,7 @@
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::PathBuf;
-use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof::AlgoChoice;
 use crate::ui::state::{AppTheme, BatchAction};
 
 // NOTE: We use #[repr(C)] to ensure predictable memory layout for binary dumping.
@@ -10,9 +10,10 @@ use crate::ui::state::{AppTheme, BatchAction};
 #[derive(Clone, Copy, Debug)]
 pub struct AppConfig {
     pub magic: u32,   // 0x43505253 ("CPRS")
-    pub version: u32, // 12
+    pub version: u32, // 14
     pub theme: AppTheme,
-    pub default_algo: WofAlgorithm,
+    /// `AlgoChoice::code()`; see `default_algo()`.
+    pub default_algo_code: u32,
     pub force_compress: bool,
     pub enable_force_stop: bool,
     pub window_width: i32,
@@ -35,6 +36,10 @@ pub struct AppConfig {
     pub context_menu_dialog_only: bool,
     pub default_action: BatchAction,
     pub process_hidden_files: bool,
+    /// Skip files whose sampled byte entropy reaches this value (bits/byte); 0.0 = off.
+    pub entropy_threshold: f32,
+    /// Hash each file before compressing and check it reads back the same afterwards.
+    pub verify_after_compress: bool,
 }
 
 impl Default for AppConfig {
@@ -52,9 +57,9 @@ impl Default for AppConfig {
 
         Self {
             magic: 0x43505253,
-            version: 12,
+            version: 14,
             theme: AppTheme::System,
-            default_algo: WofAlgorithm::Xpress8K,
+            default_algo_code: AlgoChoice::default().code(),
             force_compress: false,
             enable_force_stop: false,
             window_width: 900,
@@ -77,11 +82,21 @@ impl Default for AppConfig {
             context_menu_dialog_only: true,
             default_action: BatchAction::Compress,
             process_hidden_files: true,
+            entropy_threshold: 0.0,
+            verify_after_compress: false,
         }
     }
 }
 
 impl AppConfig {
+    pub fn default_algo(&self) -> AlgoChoice {
+        AlgoChoice::from_code(self.default_algo_code)
+    }
+
+    pub fn set_default_algo(&mut self, algo: AlgoChoice) {
+        self.default_algo_code = algo.code();
+    }
+
     fn get_path() -> PathBuf {
         let mut path = std::env::current_exe().unwrap_or_default();
         path.set_file_name("compactrs.dat");
@@ -90,27 +105,45 @@ impl AppConfig {
 
     pub fn load() -> Self {
         let path = Self::get_path();
-        if let Ok(mut file) = File::open(&path) {
-            let file_len = file.metadata().map(|m| m.len()).unwrap_or(0);
-            
-            // Strict binary compatibility check: Size must match exactly.
-            if file_len == std::mem::size_of::<AppConfig>() as u64 {
-                let mut buffer = [0u8; std::mem::size_of::<AppConfig>()];
-                if file.read_exact(&mut buffer).is_ok() {
-                    unsafe {
-                        let config = std::ptr::read_unaligned(buffer.as_ptr() as *const AppConfig);
-                        // Check for version 12
-                        if config.magic == 0x43505253 && config.version == 12 {
-                            return config;
-                        }
-                    }
-                }
-            }
+        let mut buffer = Vec::new();
+        if let Ok(mut file) = File::open(&path)
+            && file.read_to_end(&mut buffer).is_ok()
+            && let Some(config) = Self::from_bytes(&buffer)
+        {
+            return config;
         }
         // If file missing, wrong size, or invalid magic/version, return default.
         Self::default()
     }
 
+    /// Size of the file an older version wrote. Fields are only ever appended, so such a
+    /// file is a prefix of the current layout.
+    fn legacy_size(version: u32) -> Option<usize> {
+        match version {
+            12 => Some(std::mem::offset_of!(AppConfig, entropy_threshold)),
+            13 => Some(std::mem::offset_of!(AppConfig, verify_after_compress)),
+            _ => None,
+        }
+    }
+
+    /// Parses a saved config. Files of an older version are migrated: the fields they have
+    /// are kept and the ones added since take their defaults.
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 8 { return None; }
+        let magic = u32::from_ne_bytes(bytes[0..4].try_into().ok()?);
+        let version = u32::from_ne_bytes(bytes[4..8].try_into().ok()?);
+        // Strict binary compatibility check: Size must match the version exactly.
+        let size = if version == 14 { std::mem::size_of::<AppConfig>() } else { Self::legacy_size(version)? };
+        if magic != 0x43505253 || bytes.len() != size { return None; }
+
+        let mut config = Self::default();
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), &mut config as *mut AppConfig as *mut u8, size);
+        }
+        config.version = 14;
+        Some(config)
+    }
+
     pub fn save(&self) {
         if let Ok(mut file) = File::create(Self::get_path()) {
             unsafe {
@@ -122,4 +155,39 @@ impl AppConfig {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_bytes(config: &AppConfig) -> Vec<u8> {
+        unsafe {
+            std::slice::from_raw_parts(config as *const _ as *const u8, std::mem::size_of::<AppConfig>()).to_vec()
+        }
+    }
+
+    #[test]
+    fn test_load_migrates_older_versions() {
+        let mut saved = AppConfig { window_width: 1234, entropy_threshold: 7.5, verify_after_compress: true, ..AppConfig::default() };
+        let current = AppConfig::from_bytes(&to_bytes(&saved)).unwrap();
+        assert_eq!((current.window_width, current.entropy_threshold, current.verify_after_compress), (1234, 7.5, true));
+
+        // Version 13 ended before `verify_after_compress`
+        saved.version = 13;
+        let bytes = to_bytes(&saved);
+        let v13 = AppConfig::from_bytes(&bytes[..std::mem::offset_of!(AppConfig, verify_after_compress)]).unwrap();
+        assert_eq!((v13.version, v13.window_width, v13.entropy_threshold, v13.verify_after_compress), (14, 1234, 7.5, false));
+
+        // Version 12 ended before `entropy_threshold`
+        saved.version = 12;
+        let bytes = to_bytes(&saved);
+        let v12 = AppConfig::from_bytes(&bytes[..std::mem::offset_of!(AppConfig, entropy_threshold)]).unwrap();
+        assert_eq!((v12.version, v12.window_width, v12.entropy_threshold), (14, 1234, 0.0));
+
+        // A size that does not match the version is rejected
+        assert!(AppConfig::from_bytes(&bytes).is_none());
+        saved.version = 11;
+        assert!(AppConfig::from_bytes(&to_bytes(&saved)).is_none());
+    }
+}
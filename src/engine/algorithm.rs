@@ -0,0 +1,265 @@
+/* --- src/engine/algorithm.rs --- */
+//! Compression algorithm and state types shared by the WOF driver layer and the portable
+//! parts of the engine (codecs, estimator, calibration), which also build off Windows, and
+//! the per-algorithm breakdown of what a scan found.
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WofAlgorithm {
+    Xpress4K = 0,
+    Lzx = 1,
+    Xpress8K = 2,
+    Xpress16K = 3,
+    Lznt1 = 4, // Legacy NTFS Compression
+}
+
+impl WofAlgorithm {
+    /// Parses a case-insensitive algorithm name ("xpress8k", "lzx", ...).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "xpress4k" => Some(WofAlgorithm::Xpress4K),
+            "xpress8k" => Some(WofAlgorithm::Xpress8K),
+            "xpress16k" => Some(WofAlgorithm::Xpress16K),
+            "lzx" => Some(WofAlgorithm::Lzx),
+            "lznt1" => Some(WofAlgorithm::Lznt1),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name accepted by `from_name`.
+    pub fn name(self) -> &'static str {
+        match self {
+            WofAlgorithm::Xpress4K => "xpress4k",
+            WofAlgorithm::Xpress8K => "xpress8k",
+            WofAlgorithm::Xpress16K => "xpress16k",
+            WofAlgorithm::Lzx => "lzx",
+            WofAlgorithm::Lznt1 => "lznt1",
+        }
+    }
+
+    /// Size of the independently compressed chunks (the 4K LZNT1 chunk for `Lznt1`).
+    pub fn chunk_size(self) -> usize {
+        match self {
+            WofAlgorithm::Xpress4K | WofAlgorithm::Lznt1 => 4 * 1024,
+            WofAlgorithm::Xpress8K => 8 * 1024,
+            WofAlgorithm::Xpress16K => 16 * 1024,
+            WofAlgorithm::Lzx => 32 * 1024,
+        }
+    }
+}
+
+/// Algorithm a batch item, setting or watcher task asks for. `Auto` is resolved per file
+/// by the worker (`engine::auto`), so only `WofAlgorithm`s reach the backend.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlgoChoice {
+    Fixed(WofAlgorithm),
+    Auto,
+}
+
+impl Default for AlgoChoice {
+    fn default() -> Self {
+        AlgoChoice::Fixed(WofAlgorithm::Xpress8K)
+    }
+}
+
+impl From<WofAlgorithm> for AlgoChoice {
+    fn from(algo: WofAlgorithm) -> Self {
+        AlgoChoice::Fixed(algo)
+    }
+}
+
+impl AlgoChoice {
+    /// `WofAlgorithm::from_name`, plus "auto".
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("auto") { return Some(AlgoChoice::Auto); }
+        WofAlgorithm::from_name(name).map(AlgoChoice::Fixed)
+    }
+
+    /// Lowercase name accepted by `from_name`.
+    pub fn name(self) -> &'static str {
+        match self {
+            AlgoChoice::Fixed(algo) => algo.name(),
+            AlgoChoice::Auto => "auto",
+        }
+    }
+
+    /// Value stored in the binary config files: the `WofAlgorithm` value, 5 for `Auto`.
+    pub fn code(self) -> u32 {
+        match self {
+            AlgoChoice::Fixed(algo) => algo as u32,
+            AlgoChoice::Auto => 5,
+        }
+    }
+
+    /// Inverse of `code`; unknown values give the default.
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            0 => AlgoChoice::Fixed(WofAlgorithm::Xpress4K),
+            1 => AlgoChoice::Fixed(WofAlgorithm::Lzx),
+            2 => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
+            3 => AlgoChoice::Fixed(WofAlgorithm::Xpress16K),
+            4 => AlgoChoice::Fixed(WofAlgorithm::Lznt1),
+            5 => AlgoChoice::Auto,
+            _ => AlgoChoice::default(),
+        }
+    }
+}
+
+/// Represents the compression state of a file or folder
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompressionState {
+    /// Not compressed (or not WOF compressed)
+    None,
+    /// Compressed with a specific WOF or LZNT1 algorithm (all files if folder)
+    Specific(WofAlgorithm),
+    /// Contains files with different compression algorithms (folder only)
+    Mixed,
+}
+
+/// Files stored one way, and their bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AlgoTally {
+    pub files: u64,
+    pub logical: u64,
+    pub disk: u64,
+}
+
+/// Storage of every file under a path, per algorithm (`None` is uncompressed).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AlgoBreakdown {
+    tallies: [AlgoTally; AlgoBreakdown::SLOTS.len()],
+}
+
+impl AlgoBreakdown {
+    /// Storage kinds counted, in display order for ties.
+    const SLOTS: [Option<WofAlgorithm>; 6] = [
+        None,
+        Some(WofAlgorithm::Xpress4K),
+        Some(WofAlgorithm::Xpress8K),
+        Some(WofAlgorithm::Xpress16K),
+        Some(WofAlgorithm::Lzx),
+        Some(WofAlgorithm::Lznt1),
+    ];
+
+    fn slot(algo: Option<WofAlgorithm>) -> usize {
+        Self::SLOTS.iter().position(|s| *s == algo).unwrap_or(0)
+    }
+
+    pub fn add(&mut self, algo: Option<WofAlgorithm>, logical: u64, disk: u64) {
+        let t = &mut self.tallies[Self::slot(algo)];
+        t.files += 1;
+        t.logical += logical;
+        t.disk += disk;
+    }
+
+    pub fn merge(&mut self, other: &AlgoBreakdown) {
+        for (t, o) in self.tallies.iter_mut().zip(other.tallies.iter()) {
+            t.files += o.files;
+            t.logical += o.logical;
+            t.disk += o.disk;
+        }
+    }
+
+    pub fn get(&self, algo: Option<WofAlgorithm>) -> AlgoTally {
+        self.tallies[Self::slot(algo)]
+    }
+
+    pub fn total(&self) -> AlgoTally {
+        let mut total = AlgoTally::default();
+        for t in &self.tallies {
+            total.files += t.files;
+            total.logical += t.logical;
+            total.disk += t.disk;
+        }
+        total
+    }
+
+    /// Storage kinds holding at least one file, most logical bytes first.
+    pub fn entries(&self) -> Vec<(Option<WofAlgorithm>, AlgoTally)> {
+        let mut entries: Vec<_> = Self::SLOTS.iter().copied().zip(self.tallies.iter().copied())
+            .filter(|(_, t)| t.files > 0)
+            .collect();
+        entries.sort_by_key(|(_, t)| std::cmp::Reverse(t.logical));
+        entries
+    }
+
+    /// Compression state in the sense of `CompressionState`: uncompressed files only count
+    /// when nothing is compressed.
+    pub fn state(&self) -> CompressionState {
+        let mut compressed = self.entries().into_iter().filter_map(|(a, _)| a);
+        match (compressed.next(), compressed.next()) {
+            (None, _) => CompressionState::None,
+            (Some(algo), None) => CompressionState::Specific(algo),
+            _ => CompressionState::Mixed,
+        }
+    }
+
+    /// True if every file is stored the same way (or there are none).
+    pub fn is_uniform(&self) -> bool {
+        self.entries().len() <= 1
+    }
+
+    /// Share of the logical bytes per storage kind, e.g. "82% LZX, 15% uncompressed, 3% LZNT1".
+    /// Empty files only count when there are no bytes at all.
+    pub fn summary(&self) -> String {
+        let total = self.total();
+        let share = |t: &AlgoTally| if total.logical > 0 {
+            t.logical as f64 * 100.0 / total.logical as f64
+        } else {
+            t.files as f64 * 100.0 / total.files as f64
+        };
+        self.entries().iter()
+            .map(|(algo, t)| {
+                let name = algo.map_or("uncompressed".to_string(), |a| a.name().to_uppercase());
+                let pct = share(t);
+                if pct > 0.0 && pct < 1.0 { ["<1% ", &name].concat() } else { [&(pct.round() as u64).to_string(), "% ", &name].concat() }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakdown_state_and_summary() {
+        let mut b = AlgoBreakdown::default();
+        assert_eq!(b.state(), CompressionState::None);
+        assert_eq!(b.summary(), "");
+
+        b.add(None, 150, 150);
+        assert_eq!(b.state(), CompressionState::None);
+        assert!(b.is_uniform());
+        b.add(Some(WofAlgorithm::Lzx), 500, 200);
+        b.add(Some(WofAlgorithm::Lzx), 320, 100);
+        // Uncompressed files alongside one algorithm are a partial run, not `Mixed`
+        assert_eq!(b.state(), CompressionState::Specific(WofAlgorithm::Lzx));
+        assert!(!b.is_uniform());
+
+        let mut other = AlgoBreakdown::default();
+        other.add(Some(WofAlgorithm::Lznt1), 30, 20);
+        other.add(None, 0, 0);
+        b.merge(&other);
+        assert_eq!(b.state(), CompressionState::Mixed);
+        assert_eq!(b.get(None), AlgoTally { files: 2, logical: 150, disk: 150 });
+        assert_eq!(b.total(), AlgoTally { files: 5, logical: 1000, disk: 470 });
+        assert_eq!(b.summary(), "82% LZX, 15% uncompressed, 3% LZNT1");
+
+        b.add(Some(WofAlgorithm::Xpress4K), 1, 1);
+        assert!(b.summary().ends_with(", <1% XPRESS4K"));
+    }
+
+    #[test]
+    fn test_algo_choice_codes_and_names() {
+        for code in 0..=5 {
+            let choice = AlgoChoice::from_code(code);
+            assert_eq!(choice.code(), code);
+            assert_eq!(AlgoChoice::from_name(choice.name()), Some(choice));
+        }
+        assert_eq!(AlgoChoice::from_code(99), AlgoChoice::default());
+        assert_eq!(AlgoChoice::from_name("AUTO"), Some(AlgoChoice::Auto));
+        assert_eq!(WofAlgorithm::from_name("auto"), None);
+    }
+}
@@ -0,0 +1,271 @@
+/* --- src/engine/analyzer.rs --- */
+//! Whole-volume savings analyzer: which folders are worth adding to the batch.
+//!
+//! One `scanner::scan_folder_totals` pass gives every directory's own files. Those are
+//! rolled up per subtree and the tree is cut top-down into candidate folders: a folder is
+//! split into its subfolders while it is the root, holds more than `max_share` of the
+//! uncompressed bytes, or is mostly (90%) one subfolder. Files directly inside a split
+//! folder are not recommended on their own, since adding the folder would take its
+//! subfolders too.
+//!
+//! Only the largest candidates are estimated (that is the expensive part) and the results
+//! are ranked by projected savings against their current on-disk size, so folders that
+//! are already compressed fall to the bottom.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::engine::estimator::{self, AllocModel, Estimate, EstimateMode, EstimatorParams};
+use crate::engine::scanner::{self, FolderTotals, is_critical_path};
+use crate::engine::wof::AlgoChoice;
+use crate::ui::state::ProcessingState;
+
+/// Share of a folder's uncompressed bytes in one subfolder above which it is split anyway.
+const DOMINANT_CHILD: f64 = 0.9;
+
+#[derive(Clone, Copy, Debug)]
+pub struct AnalyzerOptions {
+    /// Algorithm the savings are projected for.
+    pub algo: AlgoChoice,
+    /// Number of recommendations returned.
+    pub top: usize,
+    /// Candidates estimated per recommendation returned.
+    pub estimate_factor: usize,
+    /// Smallest uncompressed size worth recommending.
+    pub min_bytes: u64,
+    /// Largest share of the root's uncompressed bytes a single recommendation may hold.
+    pub max_share: f64,
+    /// Never recommend paths `scanner::is_critical_path` protects.
+    pub skip_critical: bool,
+    pub process_hidden_files: bool,
+}
+
+impl Default for AnalyzerOptions {
+    fn default() -> Self {
+        Self {
+            algo: AlgoChoice::default(),
+            top: 15,
+            estimate_factor: 3,
+            min_bytes: 64 * 1024 * 1024,
+            max_share: 0.25,
+            skip_critical: true,
+            process_hidden_files: true,
+        }
+    }
+}
+
+/// Totals of a whole subtree.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FolderSummary {
+    pub path: String,
+    pub files: u64,
+    pub logical: u64,
+    pub disk: u64,
+    /// Logical size of the files not compressed yet.
+    pub uncompressed: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recommendation {
+    pub folder: FolderSummary,
+    /// Projected on-disk size once compressed with `AnalyzerOptions::algo`.
+    pub estimate: Estimate,
+}
+
+impl Recommendation {
+    pub fn savings(&self) -> u64 {
+        self.folder.disk.saturating_sub(self.estimate.size)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VolumeAnalysis {
+    /// Totals of the analyzed root.
+    pub root: FolderSummary,
+    /// Largest projected savings first.
+    pub recommendations: Vec<Recommendation>,
+}
+
+/// Analysis stage reported to `on_progress`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnalyzeProgress {
+    Scanning,
+    /// Candidates estimated so far, of the total.
+    Estimating(usize, usize),
+}
+
+/// Analyzes the tree under `root`. `None` once `state` is set to `ProcessingState::Stopped`.
+pub fn analyze(
+    root: &str,
+    opts: AnalyzerOptions,
+    state: &Arc<AtomicU8>,
+    on_progress: &mut dyn FnMut(AnalyzeProgress),
+) -> Option<VolumeAnalysis> {
+    let stopped = || state.load(Ordering::Relaxed) == ProcessingState::Stopped as u8;
+    on_progress(AnalyzeProgress::Scanning);
+    let dirs = scanner::scan_folder_totals(root, Some(state), opts.process_hidden_files);
+    if stopped() { return None; }
+
+    let model = AllocModel::for_path(root);
+    analyze_folders(dirs, root, opts, &mut |path| {
+        estimator::estimate_path_progressive(path, opts.algo, EstimateMode::Fast, model, EstimatorParams::default(), &stopped, &mut |_| {})
+    }, on_progress)
+}
+
+/// `analyze` over scanned `dirs`, projecting each candidate with `estimate` (`None` cancels).
+pub fn analyze_folders(
+    dirs: Vec<FolderTotals>,
+    root: &str,
+    opts: AnalyzerOptions,
+    estimate: &mut dyn FnMut(&str) -> Option<Estimate>,
+    on_progress: &mut dyn FnMut(AnalyzeProgress),
+) -> Option<VolumeAnalysis> {
+    let tree = Tree::new(dirs);
+    let Some(&root_idx) = tree.index.get(root) else { return Some(VolumeAnalysis::default()) };
+
+    let mut candidates = tree.candidates(root_idx, &opts);
+    candidates.sort_by_key(|c| Reverse(c.uncompressed));
+    candidates.truncate(opts.top.saturating_mul(opts.estimate_factor));
+
+    let total = candidates.len();
+    let mut recommendations = Vec::with_capacity(total);
+    for (i, folder) in candidates.into_iter().enumerate() {
+        on_progress(AnalyzeProgress::Estimating(i, total));
+        let estimate = estimate(&folder.path)?;
+        recommendations.push(Recommendation { folder, estimate });
+    }
+    recommendations.retain(|r| r.savings() > 0);
+    recommendations.sort_by_key(|r| Reverse(r.savings()));
+    recommendations.truncate(opts.top);
+
+    Some(VolumeAnalysis { root: tree.subtree[root_idx].clone(), recommendations })
+}
+
+/// Scanned directories with their subtree totals.
+struct Tree {
+    children: Vec<Vec<usize>>,
+    subtree: Vec<FolderSummary>,
+    index: HashMap<String, usize>,
+}
+
+impl Tree {
+    fn new(dirs: Vec<FolderTotals>) -> Self {
+        let index: HashMap<String, usize> = dirs.iter().enumerate().map(|(i, d)| (d.path.clone(), i)).collect();
+        let children: Vec<Vec<usize>> = dirs.iter()
+            .map(|d| d.children.iter().filter_map(|c| index.get(c).copied()).collect())
+            .collect();
+        let mut subtree: Vec<FolderSummary> = dirs.into_iter()
+            .map(|d| FolderSummary { path: d.path, files: d.files, logical: d.logical, disk: d.disk, uncompressed: d.uncompressed })
+            .collect();
+
+        // Children before parents: the reverse of a pre-order walk from the top-level folders
+        let mut has_parent = vec![false; subtree.len()];
+        children.iter().flatten().for_each(|&c| has_parent[c] = true);
+        let mut order = Vec::with_capacity(subtree.len());
+        let mut stack: Vec<usize> = (0..subtree.len()).filter(|&i| !has_parent[i]).collect();
+        while let Some(i) = stack.pop() {
+            order.push(i);
+            stack.extend(children[i].iter().copied());
+        }
+        for &i in order.iter().rev() {
+            let (mut files, mut logical, mut disk, mut uncompressed) = (0, 0, 0, 0);
+            for &c in &children[i] {
+                let s = &subtree[c];
+                files += s.files;
+                logical += s.logical;
+                disk += s.disk;
+                uncompressed += s.uncompressed;
+            }
+            let s = &mut subtree[i];
+            s.files += files;
+            s.logical += logical;
+            s.disk += disk;
+            s.uncompressed += uncompressed;
+        }
+
+        Self { children, subtree, index }
+    }
+
+    /// Top-down partition of the subtree at `root` into candidate folders.
+    fn candidates(&self, root: usize, opts: &AnalyzerOptions) -> Vec<FolderSummary> {
+        let limit = self.subtree[root].uncompressed as f64 * opts.max_share;
+        let mut out = Vec::new();
+        let mut stack = vec![root];
+        while let Some(i) = stack.pop() {
+            let s = &self.subtree[i];
+            if s.uncompressed < opts.min_bytes { continue; }
+            if opts.skip_critical && is_critical_path(&s.path) { continue; }
+
+            let largest = self.children[i].iter().map(|&c| self.subtree[c].uncompressed).max();
+            let split = match largest {
+                Some(l) => i == root || s.uncompressed as f64 > limit || l as f64 >= s.uncompressed as f64 * DOMINANT_CHILD,
+                None => false,
+            };
+            if split {
+                stack.extend(self.children[i].iter().copied());
+            } else {
+                out.push(s.clone());
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(path: &str, logical: u64, uncompressed: u64, children: &[&str]) -> FolderTotals {
+        FolderTotals {
+            path: path.to_string(),
+            files: 1,
+            logical,
+            disk: logical,
+            uncompressed,
+            children: children.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_analyze_folders_partition_and_rank() {
+        let dirs = vec![
+            // Games holds more than a quarter, so its subfolders are ranked instead
+            dir("C:\\Games", 0, 0, &["C:\\Games\\A", "C:\\Games\\B"]),
+            dir("C:\\Games\\A", 400, 400, &[]),
+            dir("C:\\Games\\B", 150, 150, &[]),
+            // Docs is mostly one subfolder, so it is narrowed down to it
+            dir("C:\\Docs", 5, 5, &["C:\\Docs\\Work"]),
+            dir("C:\\Docs\\Work", 200, 200, &["C:\\Docs\\Work\\Old"]),
+            dir("C:\\Docs\\Work\\Old", 50, 0, &[]),
+            dir("C:\\Small", 5, 5, &[]),
+            dir("C:\\Windows", 0, 0, &["C:\\Windows\\System32"]),
+            dir("C:\\Windows\\System32", 200, 200, &[]),
+            // Scan order is arbitrary
+            dir("C:", 10, 10, &["C:\\Games", "C:\\Docs", "C:\\Small", "C:\\Windows"]),
+        ];
+        let opts = AnalyzerOptions { top: 2, estimate_factor: 1, min_bytes: 100, ..AnalyzerOptions::default() };
+
+        let mut estimated = Vec::new();
+        let mut stages = Vec::new();
+        let analysis = analyze_folders(dirs.clone(), "C:", opts, &mut |p| {
+            estimated.push(p.to_string());
+            // Work does not compress at all
+            Some(Estimate { size: if p.ends_with("Work") { 250 } else { 100 }, margin: 0 })
+        }, &mut |p| stages.push(p)).unwrap();
+
+        assert_eq!(analysis.root.logical, 1020);
+        assert_eq!(analysis.root.uncompressed, 970);
+        estimated.sort();
+        // System32 is protected and Small is under min_bytes; only the two largest are estimated
+        assert_eq!(estimated, vec!["C:\\Docs\\Work", "C:\\Games\\A"]);
+        assert_eq!(stages, vec![AnalyzeProgress::Estimating(0, 2), AnalyzeProgress::Estimating(1, 2)]);
+
+        let paths: Vec<&str> = analysis.recommendations.iter().map(|r| r.folder.path.as_str()).collect();
+        assert_eq!(paths, vec!["C:\\Games\\A"]);
+        assert_eq!(analysis.recommendations[0].savings(), 300);
+
+        assert_eq!(analyze_folders(dirs, "C:", opts, &mut |_| None, &mut |_| {}), None);
+    }
+}
@@ -0,0 +1,155 @@
+/* --- src/engine/auto.rs --- */
+//! Per-file algorithm selection for `AlgoChoice::Auto`.
+//!
+//! Up to three blocks (head, middle, tail) are compressed with every WOF codec and the
+//! ratios compared:
+//! - Nothing saves `MIN_GAIN` of the file: skip.
+//! - LZX beats the best XPRESS by at least `LZX_GAIN`: LZX (slow to compress, worth it).
+//! - Otherwise the smallest XPRESS chunk size within `XPRESS_SLACK` of XPRESS16K, since
+//!   smaller chunks decompress faster on random reads.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::engine::codec::{lzx, xpress};
+use crate::engine::sniff;
+use crate::engine::wof::WofAlgorithm;
+
+/// Bytes per sample block (one LZX chunk).
+const SAMPLE_BLOCK: usize = lzx::CHUNK_SIZE;
+const SAMPLE_BLOCKS: u64 = 3;
+
+/// Minimum fraction of the file that must be saved to compress at all.
+pub const MIN_GAIN: f64 = 0.05;
+/// LZX must save this fraction of the file beyond XPRESS16K to be chosen.
+pub const LZX_GAIN: f64 = 0.05;
+/// Smaller XPRESS chunk sizes are preferred while within this ratio of XPRESS16K.
+pub const XPRESS_SLACK: f64 = 0.02;
+
+/// Sampled compressed/original ratio per WOF algorithm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoSample {
+    pub xpress4k: f64,
+    pub xpress8k: f64,
+    pub xpress16k: f64,
+    pub lzx: f64,
+}
+
+impl AutoSample {
+    /// All algorithms at the same ratio (incompressible or a single known ratio).
+    pub fn uniform(ratio: f64) -> Self {
+        Self { xpress4k: ratio, xpress8k: ratio, xpress16k: ratio, lzx: ratio }
+    }
+
+    pub fn ratio(&self, algo: WofAlgorithm) -> f64 {
+        match algo {
+            WofAlgorithm::Xpress4K => self.xpress4k,
+            WofAlgorithm::Xpress8K => self.xpress8k,
+            WofAlgorithm::Lzx => self.lzx,
+            _ => self.xpress16k,
+        }
+    }
+
+    /// Ratios of `data` compressed with each codec.
+    pub fn measure(data: &[u8]) -> Self {
+        if data.is_empty() { return Self::uniform(1.0); }
+        let len = data.len() as f64;
+        Self {
+            xpress4k: xpress::compressed_size_chunked(data, 4 * 1024) as f64 / len,
+            xpress8k: xpress::compressed_size_chunked(data, 8 * 1024) as f64 / len,
+            xpress16k: xpress::compressed_size_chunked(data, 16 * 1024) as f64 / len,
+            lzx: lzx::compressed_size_chunked(data) as f64 / len,
+        }
+    }
+}
+
+/// Algorithm to use for a file with the sampled ratios, `None` to leave it uncompressed.
+pub fn choose(s: &AutoSample) -> Option<WofAlgorithm> {
+    let best_xpress = s.xpress16k.min(s.xpress8k).min(s.xpress4k);
+    if 1.0 - best_xpress.min(s.lzx) < MIN_GAIN { return None; }
+    if best_xpress - s.lzx >= LZX_GAIN { return Some(WofAlgorithm::Lzx); }
+
+    [WofAlgorithm::Xpress4K, WofAlgorithm::Xpress8K]
+        .into_iter()
+        .find(|&a| s.ratio(a) - best_xpress <= XPRESS_SLACK)
+        .or(Some(WofAlgorithm::Xpress16K))
+}
+
+/// Samples an open file of `size` bytes. Known compressed containers are not sampled.
+pub fn sample_file(f: &mut File, size: u64) -> Option<AutoSample> {
+    if sniff::sniff_file(f).is_some() { return Some(AutoSample::uniform(1.0)); }
+
+    let count = size.div_ceil(SAMPLE_BLOCK as u64).min(SAMPLE_BLOCKS);
+    let last = size.saturating_sub(SAMPLE_BLOCK as u64);
+    let mut data = Vec::with_capacity(count as usize * SAMPLE_BLOCK);
+    let mut buf = vec![0u8; SAMPLE_BLOCK];
+
+    for i in 0..count {
+        let pos = if count > 1 { last * i / (count - 1) } else { 0 };
+        f.seek(SeekFrom::Start(pos)).ok()?;
+        let n = f.read(&mut buf).ok()?;
+        data.extend_from_slice(&buf[..n]);
+    }
+    if data.is_empty() { return None; }
+
+    // Blocks are measured separately so no codec sees history across sample boundaries
+    let (mut acc, mut total) = (AutoSample::uniform(0.0), 0.0);
+    for block in data.chunks(SAMPLE_BLOCK) {
+        let s = AutoSample::measure(block);
+        let w = block.len() as f64;
+        acc.xpress4k += s.xpress4k * w;
+        acc.xpress8k += s.xpress8k * w;
+        acc.xpress16k += s.xpress16k * w;
+        acc.lzx += s.lzx * w;
+        total += w;
+    }
+    Some(AutoSample {
+        xpress4k: acc.xpress4k / total,
+        xpress8k: acc.xpress8k / total,
+        xpress16k: acc.xpress16k / total,
+        lzx: acc.lzx / total,
+    })
+}
+
+/// Samples the file at `path`; unreadable or empty files are `None`.
+pub fn sample_path(path: &str) -> Option<AutoSample> {
+    let mut f = File::open(path).ok()?;
+    let size = f.metadata().ok()?.len();
+    sample_file(&mut f, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose() {
+        // Incompressible
+        assert_eq!(choose(&AutoSample::uniform(0.98)), None);
+
+        // Large LZX advantage
+        let s = AutoSample { xpress4k: 0.50, xpress8k: 0.47, xpress16k: 0.45, lzx: 0.35 };
+        assert_eq!(choose(&s), Some(WofAlgorithm::Lzx));
+
+        // LZX barely better: smallest XPRESS chunk within slack of the best
+        let s = AutoSample { xpress4k: 0.46, xpress8k: 0.455, xpress16k: 0.45, lzx: 0.43 };
+        assert_eq!(choose(&s), Some(WofAlgorithm::Xpress4K));
+        let s = AutoSample { xpress4k: 0.50, xpress8k: 0.46, xpress16k: 0.45, lzx: 0.43 };
+        assert_eq!(choose(&s), Some(WofAlgorithm::Xpress8K));
+        let s = AutoSample { xpress4k: 0.55, xpress8k: 0.50, xpress16k: 0.45, lzx: 0.43 };
+        assert_eq!(choose(&s), Some(WofAlgorithm::Xpress16K));
+    }
+
+    #[test]
+    fn test_measure() {
+        let text = b"fn main() { println!(\"hello\"); }\r\n".repeat(2000);
+        let s = AutoSample::measure(&text);
+        assert!(s.xpress16k <= s.xpress4k);
+        assert!(s.lzx < 0.2);
+        assert!(choose(&s).is_some());
+
+        let mut seed = 7u32;
+        let noise: Vec<u8> = (0..SAMPLE_BLOCK).map(|_| { seed = seed.wrapping_mul(1664525).wrapping_add(1013904223); (seed >> 24) as u8 }).collect();
+        assert_eq!(choose(&AutoSample::measure(&noise)), None);
+    }
+}
@@ -0,0 +1,529 @@
+/* --- src/engine/backend.rs --- */
+//! Pluggable compression backends.
+//!
+//! The batch pipeline (`worker::process_file_core`) and the scanner only need a handful of
+//! per-file operations: compress, uncompress, query the current algorithm, read the
+//! logical / on-disk sizes and the facts rules match on (all at once as a `FileSnapshot`),
+//! sniff or entropy-probe the content, project a dry run's result, hash the content to
+//! verify it, and put back the attributes a forced run or an undo restores. `CompressionBackend` captures exactly that
+//! surface so the skip/force/lock logic can run against something other than a live NTFS
+//! volume.
+//!
+//! - `WofBackend`: forwards to the Win32 WOF/LZNT1 calls in `engine::wof`.
+//! - `MemoryBackend`: deterministic in-memory simulation of WOF state per path.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::engine::auto::{self, AutoSample};
+use crate::engine::entropy;
+use crate::engine::error::CompressError;
+use crate::engine::estimator::{self, EstimateMode};
+use crate::engine::verify;
+use crate::engine::wof_stream::WofStreamError;
+use crate::engine::rules::FileFacts;
+use crate::engine::sniff::{self, ContentKind};
+use crate::engine::wof::{self, CompressionState, WofAlgorithm};
+use crate::engine::enumerator::DirEntry;
+use crate::types::{ERROR_ACCESS_DENIED, FILE_ATTRIBUTE_COMPRESSED, FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_REPARSE_POINT, FILE_ATTRIBUTE_SPARSE_FILE};
+
+/// What the batch pipeline knows about a file before (and after) changing it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FileSnapshot {
+    pub facts: FileFacts,
+    /// Current WOF or LZNT1 algorithm, `None` if uncompressed.
+    pub algorithm: Option<WofAlgorithm>,
+    /// Allocated size on disk (what `GetCompressedFileSizeW` reports).
+    pub disk: u64,
+}
+
+impl FileSnapshot {
+    /// Snapshot of a file from its directory listing alone, when that says enough: a file
+    /// that is neither a reparse point (WOF), compressed (LZNT1) nor sparse is stored as
+    /// is and takes exactly its size on disk.
+    pub fn from_listing(entry: &DirEntry) -> Option<Self> {
+        let stored = FILE_ATTRIBUTE_REPARSE_POINT | FILE_ATTRIBUTE_COMPRESSED | FILE_ATTRIBUTE_SPARSE_FILE;
+        if entry.is_dir() || entry.attributes & stored != 0 { return None; }
+        let facts = FileFacts { size: entry.size, modified: entry.modified, attributes: entry.attributes };
+        Some(Self { facts, algorithm: None, disk: entry.size })
+    }
+}
+
+/// Per-file compression operations used by the batch pipeline.
+///
+/// Errors are Win32 codes mapped to `CompressError`, so callers behave identically regardless
+/// of backend.
+pub trait CompressionBackend: Send + Sync {
+    /// Compress a file, reusing the existing state when it already matches `algo`
+    /// (unless `force` is set).
+    ///
+    /// # Returns
+    /// - `Ok(true)` if compression succeeded or the file was already optimal
+    /// - `Ok(false)` if compression was not beneficial (ERROR_COMPRESSION_NOT_BENEFICIAL)
+    /// - `Err(error)` on failure
+    fn compress(&self, path: &str, algo: WofAlgorithm, force: bool) -> Result<bool, CompressError>;
+
+    /// Remove WOF and LZNT1 compression from a file.
+    fn uncompress(&self, path: &str) -> Result<(), CompressError>;
+
+    /// Current WOF or LZNT1 algorithm, `None` if uncompressed.
+    fn query_algorithm(&self, path: &str) -> Option<WofAlgorithm>;
+
+    /// Compression state of a single file.
+    fn detect_state(&self, path: &str) -> CompressionState {
+        match self.query_algorithm(path) {
+            Some(algo) => CompressionState::Specific(algo),
+            None => CompressionState::None,
+        }
+    }
+
+    /// Allocated size on disk (what `GetCompressedFileSizeW` reports).
+    fn disk_size(&self, path: &str) -> u64;
+
+    /// Logical (uncompressed) file size.
+    fn logical_size(&self, path: &str) -> u64;
+
+    /// Size, last write time and attributes for rule evaluation.
+    fn file_facts(&self, path: &str) -> Option<FileFacts> {
+        std::fs::metadata(path).ok().map(|m| FileFacts::from_metadata(&m))
+    }
+
+    /// Facts, algorithm and size on disk together, `None` if the file cannot be opened.
+    fn snapshot(&self, path: &str) -> Option<FileSnapshot> {
+        Some(FileSnapshot { facts: self.file_facts(path)?, algorithm: self.query_algorithm(path), disk: self.disk_size(path) })
+    }
+
+    /// Already-compressed container detected from the file header, if any.
+    fn sniff_content(&self, path: &str) -> Option<ContentKind> {
+        sniff::sniff_path(path)
+    }
+
+    /// Mean byte entropy of sampled blocks (bits per byte), `None` if unreadable.
+    fn entropy_probe(&self, path: &str) -> Option<f64> {
+        entropy::probe_path(path)
+    }
+
+    /// Sampled compression ratio per algorithm, used to resolve `AlgoChoice::Auto`.
+    fn sample_ratios(&self, path: &str) -> Option<AutoSample> {
+        auto::sample_path(path)
+    }
+
+    /// Projected size on disk once compressed with `algo`, for dry runs.
+    fn estimate_size(&self, path: &str, algo: WofAlgorithm) -> u64 {
+        estimator::estimate_path_with_mode(path, algo, EstimateMode::Fast)
+    }
+
+    /// Hash of the content as applications read it, `None` if it cannot be read to the end.
+    fn content_hash(&self, path: &str) -> Option<u64> {
+        verify::hash_path(path)
+    }
+
+    /// Hash of the content decoded from the stored `WofCompressedData` stream with our own
+    /// codecs. `None` where the stream cannot be opened (LZNT1 files, or a WOF filter that
+    /// hides it), `Some(Err)` if the stored chunks are damaged.
+    fn stored_hash(&self, path: &str, algo: WofAlgorithm) -> Option<Result<u64, WofStreamError>> {
+        let logical = self.logical_size(path);
+        let stream = std::fs::File::open([path, ":WofCompressedData"].concat()).ok()?;
+        Some(verify::hash_wof_stream(&mut std::io::BufReader::new(stream), logical, algo))
+    }
+
+    /// Replace a file's attributes (read-only, hidden, system...).
+    fn set_attributes(&self, path: &str, attributes: u32) -> Result<(), CompressError> {
+        wof::set_file_attributes(path, attributes).map_err(CompressError::from_os)
+    }
+
+    /// Set or clear the read-only attribute.
+    fn set_readonly(&self, path: &str, readonly: bool) -> Result<(), CompressError> {
+        let to_code = |e: std::io::Error| CompressError::from_os(e.raw_os_error().unwrap_or(0) as u32);
+        let mut perms = std::fs::metadata(path).map_err(to_code)?.permissions();
+        perms.set_readonly(readonly);
+        std::fs::set_permissions(path, perms).map_err(to_code)
+    }
+
+    /// Whether a folder has the NTFS compressed attribute (new files in it get LZNT1).
+    fn folder_compressed(&self, path: &str) -> bool {
+        wof::has_compressed_attribute(path)
+    }
+
+    /// Set or clear a folder's compressed attribute.
+    fn set_folder_compressed(&self, path: &str, enable: bool) {
+        wof::set_compressed_attribute(path, enable);
+    }
+}
+
+/// Shared handle to the backend used by the live pipeline.
+pub fn default_backend() -> Arc<dyn CompressionBackend> {
+    Arc::new(WofBackend)
+}
+
+// ===== WIN32 / WOF =====
+
+/// Backend performing real WOF/LZNT1 operations through `engine::wof`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WofBackend;
+
+impl CompressionBackend for WofBackend {
+    fn compress(&self, path: &str, algo: WofAlgorithm, force: bool) -> Result<bool, CompressError> {
+        wof::smart_compress(path, algo, force).map_err(CompressError::from_os)
+    }
+
+    fn uncompress(&self, path: &str) -> Result<(), CompressError> {
+        wof::uncompress_file(path).map_err(CompressError::from_os)
+    }
+
+    fn query_algorithm(&self, path: &str) -> Option<WofAlgorithm> {
+        wof::get_wof_algorithm(path)
+    }
+
+    fn detect_state(&self, path: &str) -> CompressionState {
+        // Includes the attribute fallback for files that cannot be opened
+        wof::detect_compression_state(path)
+    }
+
+    fn disk_size(&self, path: &str) -> u64 {
+        wof::get_real_file_size(path)
+    }
+
+    fn logical_size(&self, path: &str) -> u64 {
+        std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    fn snapshot(&self, path: &str) -> Option<FileSnapshot> {
+        // One handle instead of a metadata call, an algorithm query and a size query
+        let (meta, algorithm, disk) = wof::query_file(path)?;
+        Some(FileSnapshot { facts: FileFacts::from_metadata(&meta), algorithm, disk })
+    }
+}
+
+// ===== IN-MEMORY SIMULATION =====
+
+/// Cluster granularity used when simulating compressed allocation.
+const SIM_CLUSTER: u64 = 4096;
+
+#[derive(Clone, Debug)]
+struct MemoryFile {
+    logical_size: u64,
+    /// Compressed/logical ratio achieved by XPRESS4K; other algorithms are scaled from it.
+    ratio: f64,
+    state: Option<WofAlgorithm>,
+    /// Simulated open failure (e.g. 32 = locked, 5 = access denied).
+    error: Option<u32>,
+    /// First bytes of the file, for content sniffing and entropy probes.
+    header: Vec<u8>,
+    /// Last write time (Unix seconds) and attributes reported to rules.
+    modified: u64,
+    attributes: u32,
+    /// Simulated bad compression: reads return different bytes while compressed.
+    corrupt: bool,
+}
+
+/// Deterministic stand-in for the WOF driver.
+///
+/// Each registered path carries a logical size and a compressibility ratio. Compression
+/// follows the same rules as `wof::compress_file_handle`:
+/// - A result that does not save at least one cluster is "not beneficial" (`Ok(false)`),
+///   unless `force` is set, in which case the file falls back to LZNT1.
+/// - `ERROR_ACCESS_DENIED` is bypassed when `force` is set (backup semantics).
+/// - Any other injected error is returned as-is.
+/// - A read-only file cannot be opened for writing (`ERROR_ACCESS_DENIED`); `force` clears
+///   the attribute first, like `force_remove_readonly`.
+/// - Attribute changes fail with any injected error except `ERROR_ACCESS_DENIED`, which
+///   only guards the file content.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    files: Mutex<HashMap<String, MemoryFile>>,
+    /// Folders carrying the compressed attribute.
+    folders: Mutex<HashMap<String, bool>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an uncompressed file.
+    pub fn insert(&self, path: &str, logical_size: u64, ratio: f64) {
+        let file = MemoryFile { logical_size, ratio, state: None, error: None, header: Vec::new(), modified: 0, attributes: 0, corrupt: false };
+        self.files.lock().unwrap().insert(path.to_string(), file);
+    }
+
+    /// Override the current compression state of a registered file.
+    pub fn set_state(&self, path: &str, state: Option<WofAlgorithm>) {
+        if let Some(f) = self.files.lock().unwrap().get_mut(path) {
+            f.state = state;
+        }
+    }
+
+    /// Make every subsequent open of `path` fail with `error` (`None` clears it).
+    pub fn set_error(&self, path: &str, error: Option<u32>) {
+        if let Some(f) = self.files.lock().unwrap().get_mut(path) {
+            f.error = error;
+        }
+    }
+
+    /// Set the header bytes seen by content sniffing and entropy probes.
+    pub fn set_header(&self, path: &str, header: &[u8]) {
+        if let Some(f) = self.files.lock().unwrap().get_mut(path) {
+            f.header = header.to_vec();
+        }
+    }
+
+    /// Make reads of `path` return damaged content whenever it is compressed.
+    pub fn set_corrupt(&self, path: &str, corrupt: bool) {
+        if let Some(f) = self.files.lock().unwrap().get_mut(path) {
+            f.corrupt = corrupt;
+        }
+    }
+
+    /// Stand-in for the hash of the file's original content.
+    fn original_hash(file: &MemoryFile) -> u64 {
+        let mut h = verify::ContentHasher::new();
+        h.update(&file.logical_size.to_le_bytes());
+        h.update(&file.header);
+        h.finish()
+    }
+
+    /// Set the last write time and attributes seen by rules.
+    pub fn set_facts(&self, path: &str, modified: u64, attributes: u32) {
+        if let Some(f) = self.files.lock().unwrap().get_mut(path) {
+            f.modified = modified;
+            f.attributes = attributes;
+        }
+    }
+
+    fn algorithm_factor(algo: WofAlgorithm) -> f64 {
+        match algo {
+            WofAlgorithm::Xpress4K => 1.0,
+            WofAlgorithm::Xpress8K => 0.97,
+            WofAlgorithm::Xpress16K => 0.95,
+            WofAlgorithm::Lzx => 0.85,
+            WofAlgorithm::Lznt1 => 1.05,
+        }
+    }
+
+    /// Simulated allocation for a file compressed with `algo`, rounded to whole clusters.
+    fn compressed_size(file: &MemoryFile, algo: WofAlgorithm) -> u64 {
+        let raw = (file.logical_size as f64 * file.ratio * Self::algorithm_factor(algo)).ceil() as u64;
+        raw.div_ceil(SIM_CLUSTER) * SIM_CLUSTER
+    }
+}
+
+impl CompressionBackend for MemoryBackend {
+    fn compress(&self, path: &str, algo: WofAlgorithm, force: bool) -> Result<bool, CompressError> {
+        let mut files = self.files.lock().unwrap();
+        let file = files.get_mut(path).ok_or(CompressError::NotFound)?;
+
+        match file.error {
+            Some(ERROR_ACCESS_DENIED) if force => {}
+            Some(err) => return Err(CompressError::from_os(err)),
+            None => {}
+        }
+        if file.attributes & FILE_ATTRIBUTE_READONLY != 0 {
+            if !force { return Err(CompressError::AccessDenied); }
+            file.attributes &= !FILE_ATTRIBUTE_READONLY;
+        }
+
+        if !force && file.state == Some(algo) {
+            return Ok(true);
+        }
+
+        if algo == WofAlgorithm::Lznt1 {
+            file.state = Some(WofAlgorithm::Lznt1);
+            return Ok(true);
+        }
+
+        // compress_file_handle drops LZNT1 before asking the driver, whatever it answers
+        if file.state == Some(WofAlgorithm::Lznt1) {
+            file.state = None;
+        }
+        if Self::compressed_size(file, algo) >= file.logical_size {
+            if force {
+                // Mirrors the FSCTL_SET_COMPRESSION fallback in compress_file_handle
+                file.state = Some(WofAlgorithm::Lznt1);
+                return Ok(true);
+            }
+            return Ok(false);
+        }
+
+        file.state = Some(algo);
+        Ok(true)
+    }
+
+    fn uncompress(&self, path: &str) -> Result<(), CompressError> {
+        let mut files = self.files.lock().unwrap();
+        let file = files.get_mut(path).ok_or(CompressError::NotFound)?;
+        if let Some(err) = file.error {
+            return Err(CompressError::from_os(err));
+        }
+        if file.attributes & FILE_ATTRIBUTE_READONLY != 0 {
+            return Err(CompressError::AccessDenied);
+        }
+        file.state = None;
+        Ok(())
+    }
+
+    fn query_algorithm(&self, path: &str) -> Option<WofAlgorithm> {
+        self.files.lock().unwrap().get(path).and_then(|f| f.state)
+    }
+
+    fn disk_size(&self, path: &str) -> u64 {
+        let files = self.files.lock().unwrap();
+        match files.get(path) {
+            Some(f) => match f.state {
+                Some(algo) => Self::compressed_size(f, algo).min(f.logical_size),
+                None => f.logical_size,
+            },
+            None => 0,
+        }
+    }
+
+    fn logical_size(&self, path: &str) -> u64 {
+        self.files.lock().unwrap().get(path).map(|f| f.logical_size).unwrap_or(0)
+    }
+
+    fn file_facts(&self, path: &str) -> Option<FileFacts> {
+        let files = self.files.lock().unwrap();
+        files.get(path).map(|f| FileFacts { size: f.logical_size, modified: f.modified, attributes: f.attributes })
+    }
+
+    fn sniff_content(&self, path: &str) -> Option<ContentKind> {
+        self.files.lock().unwrap().get(path).and_then(|f| sniff::sniff(&f.header))
+    }
+
+    fn entropy_probe(&self, path: &str) -> Option<f64> {
+        let files = self.files.lock().unwrap();
+        files.get(path).filter(|f| !f.header.is_empty()).map(|f| entropy::shannon_entropy(&f.header))
+    }
+
+    fn sample_ratios(&self, path: &str) -> Option<AutoSample> {
+        let files = self.files.lock().unwrap();
+        let f = files.get(path)?;
+        if sniff::sniff(&f.header).is_some() { return Some(AutoSample::uniform(1.0)); }
+        let r = |algo| (f.ratio * Self::algorithm_factor(algo)).min(1.0);
+        Some(AutoSample {
+            xpress4k: r(WofAlgorithm::Xpress4K),
+            xpress8k: r(WofAlgorithm::Xpress8K),
+            xpress16k: r(WofAlgorithm::Xpress16K),
+            lzx: r(WofAlgorithm::Lzx),
+        })
+    }
+
+    fn estimate_size(&self, path: &str, algo: WofAlgorithm) -> u64 {
+        let files = self.files.lock().unwrap();
+        files.get(path).map(|f| Self::compressed_size(f, algo).min(f.logical_size)).unwrap_or(0)
+    }
+
+    fn content_hash(&self, path: &str) -> Option<u64> {
+        let files = self.files.lock().unwrap();
+        let f = files.get(path).filter(|f| f.error.is_none())?;
+        let damaged = f.corrupt && f.state.is_some();
+        Some(Self::original_hash(f) ^ damaged as u64)
+    }
+
+    fn stored_hash(&self, path: &str, _algo: WofAlgorithm) -> Option<Result<u64, WofStreamError>> {
+        let files = self.files.lock().unwrap();
+        let f = files.get(path)?;
+        // The stored chunks hold the original; only the read path is damaged
+        matches!(f.state, Some(a) if a != WofAlgorithm::Lznt1).then(|| Ok(Self::original_hash(f)))
+    }
+
+    fn set_attributes(&self, path: &str, attributes: u32) -> Result<(), CompressError> {
+        let mut files = self.files.lock().unwrap();
+        let file = files.get_mut(path).ok_or(CompressError::NotFound)?;
+        if let Some(err) = file.error.filter(|&e| e != ERROR_ACCESS_DENIED) {
+            return Err(CompressError::from_os(err));
+        }
+        file.attributes = attributes;
+        Ok(())
+    }
+
+    fn set_readonly(&self, path: &str, readonly: bool) -> Result<(), CompressError> {
+        let mut files = self.files.lock().unwrap();
+        let file = files.get_mut(path).ok_or(CompressError::NotFound)?;
+        if readonly {
+            file.attributes |= FILE_ATTRIBUTE_READONLY;
+        } else {
+            file.attributes &= !FILE_ATTRIBUTE_READONLY;
+        }
+        Ok(())
+    }
+
+    fn folder_compressed(&self, path: &str) -> bool {
+        self.folders.lock().unwrap().get(path).copied().unwrap_or(false)
+    }
+
+    fn set_folder_compressed(&self, path: &str, enable: bool) {
+        self.folders.lock().unwrap().insert(path.to_string(), enable);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::error::ERROR_SHARING_VIOLATION;
+
+    #[test]
+    fn test_memory_backend_compress_cycle() {
+        let b = MemoryBackend::new();
+        b.insert("a.txt", 100_000, 0.4);
+
+        assert_eq!(b.compress("a.txt", WofAlgorithm::Xpress8K, false), Ok(true));
+        assert_eq!(b.query_algorithm("a.txt"), Some(WofAlgorithm::Xpress8K));
+        assert!(b.disk_size("a.txt") < b.logical_size("a.txt"));
+
+        assert_eq!(b.uncompress("a.txt"), Ok(()));
+        assert_eq!(b.detect_state("a.txt"), CompressionState::None);
+        assert_eq!(b.disk_size("a.txt"), 100_000);
+    }
+
+    #[test]
+    fn test_memory_backend_not_beneficial_and_force() {
+        let b = MemoryBackend::new();
+        b.insert("a.zip", 100_000, 1.0);
+
+        assert_eq!(b.compress("a.zip", WofAlgorithm::Xpress4K, false), Ok(false));
+        assert_eq!(b.query_algorithm("a.zip"), None);
+
+        // Forced: falls back to LZNT1 like the real driver path
+        assert_eq!(b.compress("a.zip", WofAlgorithm::Xpress4K, true), Ok(true));
+        assert_eq!(b.query_algorithm("a.zip"), Some(WofAlgorithm::Lznt1));
+
+        // Declined without force: the LZNT1 compression is already gone, as on a real volume
+        assert_eq!(b.compress("a.zip", WofAlgorithm::Xpress4K, false), Ok(false));
+        assert_eq!(b.query_algorithm("a.zip"), None);
+    }
+
+    #[test]
+    fn test_memory_backend_errors() {
+        let b = MemoryBackend::new();
+        assert_eq!(b.compress("missing", WofAlgorithm::Lzx, false), Err(CompressError::NotFound));
+
+        b.insert("locked.bin", 10_000, 0.5);
+        b.set_error("locked.bin", Some(ERROR_SHARING_VIOLATION));
+        assert_eq!(b.compress("locked.bin", WofAlgorithm::Lzx, true), Err(CompressError::SharingViolation));
+
+        b.set_error("locked.bin", Some(ERROR_ACCESS_DENIED));
+        assert_eq!(b.compress("locked.bin", WofAlgorithm::Lzx, false), Err(CompressError::AccessDenied));
+        assert_eq!(b.compress("locked.bin", WofAlgorithm::Lzx, true), Ok(true));
+    }
+
+    #[test]
+    fn test_snapshot_from_listing() {
+        let entry = |attributes| DirEntry { name: "a.bin".into(), size: 5000, attributes, modified: 1_700_000_000 };
+        let listed = FileSnapshot::from_listing(&entry(FILE_ATTRIBUTE_READONLY)).unwrap();
+        assert_eq!(listed.facts, FileFacts { size: 5000, modified: 1_700_000_000, attributes: FILE_ATTRIBUTE_READONLY });
+        assert_eq!((listed.algorithm, listed.disk), (None, 5000));
+
+        // WOF files are reparse points; LZNT1 and sparse files are allocated differently
+        assert!(FileSnapshot::from_listing(&entry(FILE_ATTRIBUTE_REPARSE_POINT)).is_none());
+        assert!(FileSnapshot::from_listing(&entry(FILE_ATTRIBUTE_COMPRESSED)).is_none());
+        assert!(FileSnapshot::from_listing(&entry(FILE_ATTRIBUTE_SPARSE_FILE)).is_none());
+
+        let b = MemoryBackend::new();
+        b.insert("a.txt", 100_000, 0.4);
+        b.set_state("a.txt", Some(WofAlgorithm::Lzx));
+        let snap = b.snapshot("a.txt").unwrap();
+        assert_eq!((snap.facts.size, snap.algorithm, snap.disk), (100_000, Some(WofAlgorithm::Lzx), b.disk_size("a.txt")));
+        assert!(b.snapshot("missing").is_none());
+    }
+}
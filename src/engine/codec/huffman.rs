@@ -0,0 +1,165 @@
+/* --- src/engine/codec/huffman.rs --- */
+//! Canonical Huffman helpers shared by the XPRESS and LZX codecs.
+//!
+//! Both formats transmit only code lengths; codes are assigned canonically
+//! (shorter codes first, ties broken by symbol value) and written MSB-first.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Code lengths for `freqs`, limited to `max_len` bits. Unused symbols get length 0.
+///
+/// A single used symbol is paired with a dummy so the resulting code is always complete.
+pub fn build_lengths(freqs: &[u32], max_len: u8) -> Vec<u8> {
+    let mut lens = vec![0u8; freqs.len()];
+    let used: Vec<usize> = (0..freqs.len()).filter(|&i| freqs[i] > 0).collect();
+
+    match used.len() {
+        0 => return lens,
+        1 => {
+            lens[used[0]] = 1;
+            lens[if used[0] == 0 { 1 } else { 0 }] = 1;
+            return lens;
+        }
+        _ => {}
+    }
+
+    // Halve frequencies until the tree fits; rare for block-sized inputs
+    let mut weights: Vec<u32> = used.iter().map(|&i| freqs[i]).collect();
+    loop {
+        let depths = tree_depths(&weights);
+        if depths.iter().all(|&d| d <= max_len as u32) {
+            for (k, &sym) in used.iter().enumerate() {
+                lens[sym] = depths[k] as u8;
+            }
+            return lens;
+        }
+        for w in weights.iter_mut() {
+            *w = (*w >> 1).max(1);
+        }
+    }
+}
+
+/// Leaf depths of an (unbounded) Huffman tree over `weights`.
+fn tree_depths(weights: &[u32]) -> Vec<u32> {
+    let n = weights.len();
+    let mut parent = vec![usize::MAX; 2 * n - 1];
+    let mut heap = BinaryHeap::with_capacity(n);
+    for (i, &w) in weights.iter().enumerate() {
+        heap.push(Reverse((w as u64, i)));
+    }
+
+    let mut next = n;
+    while heap.len() > 1 {
+        let Reverse((w1, a)) = heap.pop().unwrap();
+        let Reverse((w2, b)) = heap.pop().unwrap();
+        parent[a] = next;
+        parent[b] = next;
+        heap.push(Reverse((w1 + w2, next)));
+        next += 1;
+    }
+
+    // Internal nodes are created after their children, so walk from the root downwards
+    let mut depth = vec![0u32; 2 * n - 1];
+    for node in (0..next - 1).rev() {
+        depth[node] = depth[parent[node]] + 1;
+    }
+    depth.truncate(n);
+    depth
+}
+
+/// Canonical codewords for `lens` (value is the MSB-first code of `lens[i]` bits).
+pub fn canonical_codes(lens: &[u8]) -> Vec<u16> {
+    let max = lens.iter().copied().max().unwrap_or(0) as usize;
+    let mut count = vec![0u32; max + 1];
+    for &l in lens {
+        if l > 0 { count[l as usize] += 1; }
+    }
+
+    let mut next = vec![0u32; max + 2];
+    let mut code = 0u32;
+    for len in 1..=max {
+        code = (code + count[len - 1]) << 1;
+        next[len] = code;
+    }
+
+    lens.iter()
+        .map(|&l| {
+            if l == 0 { return 0; }
+            let c = next[l as usize];
+            next[l as usize] += 1;
+            c as u16
+        })
+        .collect()
+}
+
+/// Direct lookup table indexed by the next `bits` bits of the stream.
+pub struct DecodeTable {
+    bits: u32,
+    /// `(symbol << 5) | len`, 0 for codes that are not assigned.
+    entries: Vec<u32>,
+}
+
+impl DecodeTable {
+    /// Builds the table for `lens`. Returns `None` if the lengths over-subscribe the code space.
+    /// Incomplete codes are accepted; decoding an unassigned code fails.
+    pub fn new(lens: &[u8], bits: u32) -> Option<Self> {
+        let mut entries = vec![0u32; 1 << bits];
+        let mut pos = 0usize;
+        for len in 1..=bits {
+            for (sym, &l) in lens.iter().enumerate() {
+                if l as u32 != len { continue; }
+                let span = 1usize << (bits - len);
+                if pos + span > entries.len() { return None; }
+                entries[pos..pos + span].fill(((sym as u32) << 5) | len);
+                pos += span;
+            }
+        }
+        if lens.iter().any(|&l| l as u32 > bits) { return None; }
+        Some(Self { bits, entries })
+    }
+
+    /// Resolves `(symbol, length)` from the next `bits` bits, MSB-aligned in `peek`.
+    pub fn decode(&self, peek: u32) -> Option<(usize, u32)> {
+        let e = self.entries[(peek >> (32 - self.bits)) as usize];
+        if e == 0 { None } else { Some(((e >> 5) as usize, e & 31)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lengths_are_limited_and_complete() {
+        // Fibonacci weights force a deep tree
+        let mut freqs = vec![0u32; 40];
+        let (mut a, mut b) = (1u32, 1u32);
+        for f in freqs.iter_mut() {
+            *f = a;
+            let c = a.saturating_add(b);
+            a = b;
+            b = c;
+        }
+        let lens = build_lengths(&freqs, 15);
+        assert!(lens.iter().all(|&l| (1..=15).contains(&l)));
+
+        // Kraft sum of a complete code is exactly 1
+        let kraft: u64 = lens.iter().map(|&l| 1u64 << (15 - l)).sum();
+        assert_eq!(kraft, 1 << 15);
+        assert!(DecodeTable::new(&lens, 15).is_some());
+    }
+
+    #[test]
+    fn test_canonical_codes_round_trip() {
+        let lens = [2u8, 1, 3, 3, 0];
+        let codes = canonical_codes(&lens);
+        assert_eq!(&codes[..4], &[0b10, 0b0, 0b110, 0b111]);
+
+        let table = DecodeTable::new(&lens, 4).unwrap();
+        for sym in 0..4 {
+            let peek = (codes[sym] as u32) << (32 - lens[sym] as u32);
+            assert_eq!(table.decode(peek), Some((sym, lens[sym] as u32)));
+        }
+    }
+}
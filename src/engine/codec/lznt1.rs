@@ -0,0 +1,233 @@
+/* --- src/engine/codec/lznt1.rs --- */
+//! LZNT1 codec (classic NTFS compression, MS-XCA 2.5).
+//!
+//! # Format
+//! - Data is split into 4 KiB chunks, each with a 2-byte header:
+//!   `0xB000 | (chunk_size - 3)` when compressed, `0x3000 | (len - 1)` when stored raw.
+//! - A compressed chunk is groups of one flag byte (LSB first) and up to 8 tokens:
+//!   a literal byte (flag 0) or a 16-bit copy token (flag 1).
+//! - Copy tokens split their bits by position: with `d` the smallest value >= 4 such that
+//!   `1 << d >= pos`, the top `d` bits hold `offset - 1` and the rest `length - 3`.
+//!
+//! # NTFS Allocation
+//! NTFS compresses files in 64 KiB units. A unit whose chunks do not save at least one
+//! cluster is stored uncompressed.
+
+use super::matchfinder::MatchFinder;
+
+/// LZNT1 chunk size.
+pub const CHUNK_SIZE: usize = 4096;
+/// NTFS compression unit (16 clusters of 4 KiB).
+pub const UNIT_SIZE: usize = 65536;
+
+const MIN_MATCH: usize = 3;
+const HEADER_COMPRESSED: u16 = 0xB000;
+const HEADER_RAW: u16 = 0x3000;
+
+const SEARCH_DEPTH: usize = 16;
+const NICE_MATCH: usize = 64;
+
+/// Number of offset bits in a copy token emitted at chunk position `pos`.
+fn displacement_bits(pos: usize) -> u32 {
+    let mut d = 4;
+    while (1usize << d) < pos && d < 12 {
+        d += 1;
+    }
+    d
+}
+
+fn compress_chunk(mf: &mut MatchFinder, chunk: &[u8], out: &mut Vec<u8>) {
+    let start = out.len();
+    out.extend_from_slice(&[0, 0]);
+
+    mf.reset(chunk.len());
+    let mut pos = 0;
+
+    while pos < chunk.len() {
+        let flag_at = out.len();
+        out.push(0);
+
+        for bit in 0..8 {
+            if pos >= chunk.len() { break; }
+
+            let d = displacement_bits(pos);
+            let max_len = (1usize << (16 - d)) - 1 + MIN_MATCH;
+            match mf.find(chunk, pos, MIN_MATCH, max_len) {
+                Some((len, dist)) => {
+                    let token = (((dist - 1) as u32) << (16 - d)) | (len - MIN_MATCH) as u32;
+                    out.extend_from_slice(&(token as u16).to_le_bytes());
+                    out[flag_at] |= 1 << bit;
+                    for p in pos + 1..pos + len {
+                        mf.insert(chunk, p);
+                    }
+                    pos += len;
+                }
+                None => {
+                    out.push(chunk[pos]);
+                    pos += 1;
+                }
+            }
+        }
+    }
+
+    let data_len = out.len() - start - 2;
+    let header = if data_len >= chunk.len() {
+        out.truncate(start + 2);
+        out.extend_from_slice(chunk);
+        HEADER_RAW | (chunk.len() - 1) as u16
+    } else {
+        HEADER_COMPRESSED | (data_len + 2 - 3) as u16
+    };
+    out[start..start + 2].copy_from_slice(&header.to_le_bytes());
+}
+
+fn compress_with(mf: &mut MatchFinder, input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 2 + 16);
+    for chunk in input.chunks(CHUNK_SIZE) {
+        compress_chunk(mf, chunk, &mut out);
+    }
+    out
+}
+
+/// Compresses `input` as a sequence of LZNT1 chunks (no end marker).
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    compress_with(&mut MatchFinder::new(CHUNK_SIZE, SEARCH_DEPTH, NICE_MATCH), input)
+}
+
+/// Bytes NTFS allocates for `input` with LZNT1, unit by unit, on a volume with
+/// `cluster`-byte clusters. Units that do not save a cluster count at their logical size.
+pub fn compressed_size_units(input: &[u8], cluster: usize) -> usize {
+    let mut mf = MatchFinder::new(CHUNK_SIZE, SEARCH_DEPTH, NICE_MATCH);
+    input.chunks(UNIT_SIZE)
+        .map(|unit| {
+            let clusters = compress_with(&mut mf, unit).len().div_ceil(cluster);
+            if clusters < unit.len().div_ceil(cluster) { clusters * cluster } else { unit.len() }
+        })
+        .sum()
+}
+
+fn decompress_chunk(data: &[u8], out: &mut Vec<u8>) -> Option<()> {
+    let base = out.len();
+    let mut i = 0;
+
+    while i < data.len() {
+        let flags = data[i];
+        i += 1;
+
+        for bit in 0..8 {
+            if i >= data.len() { break; }
+
+            if flags & (1 << bit) == 0 {
+                out.push(data[i]);
+                i += 1;
+                continue;
+            }
+
+            let token = u16::from_le_bytes([data[i], *data.get(i + 1)?]) as usize;
+            i += 2;
+
+            let pos = out.len() - base;
+            let d = displacement_bits(pos);
+            let dist = (token >> (16 - d)) + 1;
+            let len = (token & ((1 << (16 - d)) - 1)) + MIN_MATCH;
+            if dist > pos || pos + len > CHUNK_SIZE { return None; }
+
+            let start = out.len() - dist;
+            for k in 0..len {
+                out.push(out[start + k]);
+            }
+        }
+    }
+    Some(())
+}
+
+/// Decompresses a sequence of LZNT1 chunks, stopping at the end of input or a zero header.
+pub fn decompress(input: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 2);
+    let mut i = 0;
+
+    while i + 2 <= input.len() {
+        let header = u16::from_le_bytes([input[i], input[i + 1]]);
+        i += 2;
+        if header == 0 { break; }
+
+        let size = (header & 0x0FFF) as usize + 1;
+        let data = input.get(i..i + size)?;
+        i += size;
+
+        if header & 0x8000 == 0 {
+            out.extend_from_slice(data);
+        } else {
+            decompress_chunk(data, &mut out)?;
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Example of MS-XCA 3.3: the opening of "Ode to Joy" as note names.
+    const SPEC_TEXT: &[u8] = b"F# F# G A A G F# E D D E F# F# E E F# F# G A A G F# E D D E F# E D D E E F# D E F# G F# D E F# G F# E D E A F# F# G A A G F# E D D E F# E D D\0";
+    const SPEC_COMPRESSED: [u8; 59] = [
+        0x38, 0xb0, 0x88, 0x46, 0x23, 0x20, 0x00, 0x20, 0x47, 0x20, 0x41, 0x00, 0x10, 0xa2, 0x47, 0x01, 0xa0, 0x45, 0x20, 0x44,
+        0x00, 0x08, 0x45, 0x01, 0x50, 0x79, 0x00, 0xc0, 0x45, 0x20, 0x05, 0x24, 0x13, 0x88, 0x05, 0xb4, 0x02, 0x4a, 0x44, 0xef,
+        0x03, 0x58, 0x02, 0x8c, 0x09, 0x16, 0x01, 0x48, 0x45, 0x00, 0xbe, 0x00, 0x9e, 0x00, 0x04, 0x01, 0x18, 0x90, 0x00,
+    ];
+
+    #[test]
+    fn test_known_vectors() {
+        assert_eq!(decompress(&SPEC_COMPRESSED).as_deref(), Some(SPEC_TEXT));
+        // Matches are the encoder's choice; ours finds longer ones than the example, so only
+        // the first flag group and the decoded text are fixed
+        let ours = compress(SPEC_TEXT);
+        assert_eq!(ours[2..14], SPEC_COMPRESSED[2..14]);
+        assert!(ours.len() <= SPEC_COMPRESSED.len());
+        assert_eq!(decompress(&ours).as_deref(), Some(SPEC_TEXT));
+
+        // Past position 16 a copy token's displacement takes 5 bits: 32 distinct bytes, then
+        // offset 32 / length 8 = (31 << 11) | 5 = 0xF805
+        let mut data: Vec<u8> = (0..32).collect();
+        data.extend(0..8);
+        let mut expected = vec![0x26, 0xB0];
+        for group in 0..4u8 {
+            expected.push(0x00);
+            expected.extend(group * 8..group * 8 + 8);
+        }
+        expected.extend_from_slice(&[0x01, 0x05, 0xF8]);
+        assert_eq!(decompress(&expected), Some(data));
+    }
+
+    #[test]
+    fn test_raw_chunk_and_round_trip() {
+        let mut seed = 42u32;
+        let noise: Vec<u8> = (0..5000).map(|_| { seed = seed.wrapping_mul(1664525).wrapping_add(1013904223); (seed >> 24) as u8 }).collect();
+
+        let c = compress(&noise);
+        assert_eq!(&c[..2], &[0xFF, 0x3F]);
+        assert_eq!(&c[2..4098], &noise[..4096]);
+        assert_eq!(decompress(&c).unwrap(), noise);
+
+        let text = b"The quick brown fox jumps over the lazy dog. ".repeat(300);
+        let c = compress(&text);
+        assert!(c.len() < text.len() / 4);
+        assert_eq!(decompress(&c).unwrap(), text);
+
+        // Zero header terminates the stream
+        let mut terminated = compress(b"abcabcabcabcabc");
+        terminated.extend_from_slice(&[0, 0, 0xFF]);
+        assert_eq!(decompress(&terminated).unwrap(), b"abcabcabcabcabc");
+    }
+
+    #[test]
+    fn test_unit_allocation() {
+        // Highly compressible unit: one cluster
+        assert_eq!(compressed_size_units(&[0u8; UNIT_SIZE], 4096), 4096);
+
+        // Incompressible unit stays at its logical size
+        let mut seed = 1u32;
+        let noise: Vec<u8> = (0..UNIT_SIZE + 100).map(|_| { seed = seed.wrapping_mul(1664525).wrapping_add(1013904223); (seed >> 24) as u8 }).collect();
+        assert_eq!(compressed_size_units(&noise, 4096), UNIT_SIZE + 100);
+    }
+}
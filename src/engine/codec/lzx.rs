@@ -0,0 +1,661 @@
+/* --- src/engine/codec/lzx.rs --- */
+//! LZX codec, WIM/WOF variant.
+//!
+//! WOF compresses LZX files in independent 32 KiB chunks, each starting with fresh Huffman
+//! codes and repeat offsets. Differences from the CAB flavour of LZX:
+//! - No stream header; E8 call translation is always enabled with a fixed "file size" of
+//!   12000000 and positions relative to the chunk.
+//! - The block size is one bit (1 = 32768) or a zero bit followed by 16 bits.
+//! - The window is 32 KiB, i.e. 30 position slots and 496 main symbols.
+//!
+//! The encoder emits one verbatim or aligned-offset block per chunk (whichever is smaller).
+//! The decoder also accepts multiple blocks and uncompressed blocks.
+
+use super::huffman::{self, DecodeTable};
+use super::matchfinder::{self, MatchFinder};
+
+/// WOF LZX chunk size (also the LZX window size).
+pub const CHUNK_SIZE: usize = 32768;
+
+const NUM_CHARS: usize = 256;
+const NUM_POSITION_SLOTS: usize = 30;
+const NUM_MAIN_SYMBOLS: usize = NUM_CHARS + NUM_POSITION_SLOTS * 8;
+const NUM_LEN_SYMBOLS: usize = 249;
+const NUM_ALIGNED_SYMBOLS: usize = 8;
+const NUM_PRE_SYMBOLS: usize = 20;
+
+const NUM_PRIMARY_LENS: usize = 7;
+const MIN_MATCH: usize = 2;
+const MAX_MATCH: usize = 257;
+const OFFSET_ADJUSTMENT: usize = 2;
+const NUM_REPS: usize = 3;
+
+const MAX_MAIN_CODE_LEN: u8 = 16;
+const MAX_LEN_CODE_LEN: u8 = 16;
+const MAX_ALIGNED_CODE_LEN: u8 = 7;
+const MAX_PRE_CODE_LEN: u8 = 15;
+
+const BLOCKTYPE_VERBATIM: u32 = 1;
+const BLOCKTYPE_ALIGNED: u32 = 2;
+const BLOCKTYPE_UNCOMPRESSED: u32 = 3;
+
+const E8_MAGIC_FILESIZE: i32 = 12_000_000;
+
+const SEARCH_DEPTH: usize = 48;
+const NICE_MATCH: usize = 128;
+
+const SLOT_BASE: [u32; NUM_POSITION_SLOTS] = [
+    0, 1, 2, 3, 4, 6, 8, 12, 16, 24, 32, 48, 64, 96, 128, 192,
+    256, 384, 512, 768, 1024, 1536, 2048, 3072, 4096, 6144, 8192, 12288, 16384, 24576,
+];
+const SLOT_EXTRA_BITS: [u32; NUM_POSITION_SLOTS] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+/// Position slot of a formatted offset (`distance + 2`, or 0..3 for repeat offsets).
+fn offset_slot(formatted: usize) -> usize {
+    if formatted < 4 { return formatted; }
+    let hb = formatted.ilog2() as usize;
+    2 * hb + ((formatted >> (hb - 1)) & 1)
+}
+
+// ===== E8 TRANSLATION =====
+
+fn e8_filter(data: &mut [u8], translate: fn(i32, i32) -> i32) {
+    if data.len() <= 10 { return; }
+    let tail = data.len() - 10;
+    let mut i = 0;
+    while i < tail {
+        if data[i] == 0xE8 {
+            let t = &mut data[i + 1..i + 5];
+            let v = i32::from_le_bytes([t[0], t[1], t[2], t[3]]);
+            t.copy_from_slice(&translate(v, i as i32).to_le_bytes());
+            i += 5;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Relative call target -> absolute (compression side).
+fn do_translate(rel: i32, pos: i32) -> i32 {
+    if rel >= -pos && rel < E8_MAGIC_FILESIZE {
+        if rel < E8_MAGIC_FILESIZE - pos { rel + pos } else { rel - E8_MAGIC_FILESIZE }
+    } else {
+        rel
+    }
+}
+
+/// Absolute call target -> relative (decompression side).
+fn undo_translate(abs: i32, pos: i32) -> i32 {
+    if abs >= 0 {
+        if abs < E8_MAGIC_FILESIZE { abs - pos } else { abs }
+    } else if abs >= -pos {
+        abs + E8_MAGIC_FILESIZE
+    } else {
+        abs
+    }
+}
+
+// ===== ENCODER =====
+
+#[derive(Clone, Copy)]
+enum Item {
+    Literal(u8),
+    /// `slot` 0..3 are repeat offsets; `extra` holds the offset bits below the slot base.
+    Match { len: usize, slot: usize, extra: u32 },
+}
+
+impl Item {
+    fn main_symbol(&self) -> usize {
+        match *self {
+            Item::Literal(b) => b as usize,
+            Item::Match { len, slot, .. } => {
+                NUM_CHARS + slot * 8 + (len - MIN_MATCH).min(NUM_PRIMARY_LENS)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Candidate {
+    len: usize,
+    /// Repeat offset index, or `None` for an explicit distance.
+    rep: Option<usize>,
+    dist: usize,
+}
+
+/// Best match at `pos`: repeat offsets are preferred unless an explicit match is at least
+/// two bytes longer, since they cost no offset bits.
+fn best_match(mf: &mut MatchFinder, data: &[u8], pos: usize, reps: &[usize; NUM_REPS], min_len: usize) -> Option<Candidate> {
+    let max_len = MAX_MATCH.min(data.len() - pos);
+    let mut best: Option<Candidate> = None;
+
+    for (i, &r) in reps.iter().enumerate() {
+        if r > pos || max_len < MIN_MATCH { continue; }
+        let len = matchfinder::match_len(data, pos - r, pos, max_len);
+        if len >= MIN_MATCH.max(min_len) && best.is_none_or(|b| len > b.len) {
+            best = Some(Candidate { len, rep: Some(i), dist: r });
+        }
+    }
+
+    let explicit = mf.find(data, pos, 3.max(min_len), max_len);
+    match (best, explicit) {
+        (Some(b), Some((len, dist))) if len >= b.len + 2 => Some(Candidate { len, rep: None, dist }),
+        (None, Some((len, dist))) => Some(Candidate { len, rep: None, dist }),
+        (b, _) => b,
+    }
+}
+
+/// Greedy parse with one step of lazy evaluation, tracking the repeat offset queue.
+fn parse(mf: &mut MatchFinder, data: &[u8]) -> Vec<Item> {
+    let mut items = Vec::with_capacity(data.len() / 2);
+    mf.reset(data.len());
+    let mut reps = [1usize; NUM_REPS];
+    let mut pos = 0;
+    let mut pending = None;
+
+    while pos < data.len() {
+        let cur = match pending.take() {
+            Some(c) => Some(c),
+            None => best_match(mf, data, pos, &reps, MIN_MATCH),
+        };
+        let Some(cur) = cur else {
+            items.push(Item::Literal(data[pos]));
+            pos += 1;
+            continue;
+        };
+
+        let lazy = cur.len < NICE_MATCH && pos + 1 < data.len();
+        let better = if lazy { best_match(mf, data, pos + 1, &reps, cur.len + 1) } else { None };
+        if better.is_some() {
+            items.push(Item::Literal(data[pos]));
+            pos += 1;
+            pending = better;
+            continue;
+        }
+
+        let item = match cur.rep {
+            Some(i) => {
+                reps.swap(0, i);
+                Item::Match { len: cur.len, slot: i, extra: 0 }
+            }
+            None => {
+                let formatted = cur.dist + OFFSET_ADJUSTMENT;
+                let slot = offset_slot(formatted);
+                reps = [cur.dist, reps[0], reps[1]];
+                Item::Match { len: cur.len, slot, extra: formatted as u32 - SLOT_BASE[slot] }
+            }
+        };
+        items.push(item);
+
+        for p in (pos + 1 + lazy as usize)..pos + cur.len {
+            mf.insert(data, p);
+        }
+        pos += cur.len;
+    }
+    items
+}
+
+/// MSB-first writer over 16-bit little-endian words.
+struct BitWriter {
+    out: Vec<u8>,
+    bitbuf: u32,
+    bitcount: u32,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, bits: u32, n: u32) {
+        if n == 0 { return; }
+        self.bitbuf = (self.bitbuf << n) | bits;
+        self.bitcount += n;
+        while self.bitcount >= 16 {
+            self.bitcount -= 16;
+            self.out.extend_from_slice(&((self.bitbuf >> self.bitcount) as u16).to_le_bytes());
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bitcount > 0 {
+            self.out.extend_from_slice(&((self.bitbuf << (16 - self.bitcount)) as u16).to_le_bytes());
+        }
+        self.out
+    }
+}
+
+/// Writes `lens` as pretree-coded deltas against `prev`, with run-length items.
+fn write_code_lens(w: &mut BitWriter, lens: &[u8], prev: &[u8]) {
+    let delta = |old: u8, new: u8| ((old as usize + 17 - new as usize) % 17) as u8;
+
+    // (presymbol, extra bits); symbol 19 is followed by its delta presymbol
+    let mut items: Vec<(u8, u32)> = Vec::new();
+    let mut i = 0;
+    while i < lens.len() {
+        let len = lens[i];
+        let mut run_end = i + 1;
+        while run_end < lens.len() && lens[run_end] == len {
+            run_end += 1;
+        }
+
+        if len == 0 {
+            while run_end - i >= 20 {
+                let extra = (run_end - i - 20).min(31);
+                items.push((18, extra as u32));
+                i += 20 + extra;
+            }
+            if run_end - i >= 4 {
+                let extra = (run_end - i - 4).min(15);
+                items.push((17, extra as u32));
+                i += 4 + extra;
+            }
+        } else {
+            while run_end - i >= 4 {
+                let extra = (run_end - i > 4) as usize;
+                items.push((19, extra as u32));
+                items.push((delta(prev[i], len), 0));
+                i += 4 + extra;
+            }
+        }
+        while i < run_end {
+            items.push((delta(prev[i], len), 0));
+            i += 1;
+        }
+    }
+
+    let mut freqs = [0u32; NUM_PRE_SYMBOLS];
+    for &(sym, _) in &items {
+        freqs[sym as usize] += 1;
+    }
+    let pre_lens = huffman::build_lengths(&freqs, MAX_PRE_CODE_LEN);
+    let pre_codes = huffman::canonical_codes(&pre_lens);
+
+    for &l in &pre_lens {
+        w.write_bits(l as u32, 4);
+    }
+    for &(sym, extra) in &items {
+        let s = sym as usize;
+        w.write_bits(pre_codes[s] as u32, pre_lens[s] as u32);
+        match sym {
+            17 => w.write_bits(extra, 4),
+            18 => w.write_bits(extra, 5),
+            19 => w.write_bits(extra, 1),
+            _ => {}
+        }
+    }
+}
+
+/// LZX chunk encoder that reuses its match finder across chunks.
+pub struct Encoder {
+    mf: MatchFinder,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self { mf: MatchFinder::new(CHUNK_SIZE - OFFSET_ADJUSTMENT - 1, SEARCH_DEPTH, NICE_MATCH) }
+    }
+
+    /// Compresses one chunk of at most [`CHUNK_SIZE`] bytes.
+    ///
+    /// Returns `None` if `input` is larger than a chunk. The result may be larger than the
+    /// input; WOF stores such chunks uncompressed.
+    pub fn compress(&mut self, input: &[u8]) -> Option<Vec<u8>> {
+        if input.len() > CHUNK_SIZE { return None; }
+        if input.is_empty() { return Some(Vec::new()); }
+
+        let mut data = input.to_vec();
+        e8_filter(&mut data, do_translate);
+        Some(encode_chunk(input.len(), &parse(&mut self.mf, &data)))
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compresses one chunk of at most [`CHUNK_SIZE`] bytes (see [`Encoder::compress`]).
+pub fn compress(input: &[u8]) -> Option<Vec<u8>> {
+    Encoder::new().compress(input)
+}
+
+fn encode_chunk(input_len: usize, items: &[Item]) -> Vec<u8> {
+
+    let mut main_freqs = [0u32; NUM_MAIN_SYMBOLS];
+    let mut len_freqs = [0u32; NUM_LEN_SYMBOLS];
+    let mut aligned_freqs = [0u32; NUM_ALIGNED_SYMBOLS];
+    for item in items {
+        main_freqs[item.main_symbol()] += 1;
+        if let Item::Match { len, slot, extra } = *item {
+            if len - MIN_MATCH >= NUM_PRIMARY_LENS {
+                len_freqs[len - MIN_MATCH - NUM_PRIMARY_LENS] += 1;
+            }
+            if SLOT_EXTRA_BITS[slot] >= 3 {
+                aligned_freqs[(extra & 7) as usize] += 1;
+            }
+        }
+    }
+
+    let main_lens = huffman::build_lengths(&main_freqs, MAX_MAIN_CODE_LEN);
+    let len_lens = huffman::build_lengths(&len_freqs, MAX_LEN_CODE_LEN);
+    let aligned_lens = huffman::build_lengths(&aligned_freqs, MAX_ALIGNED_CODE_LEN);
+    let main_codes = huffman::canonical_codes(&main_lens);
+    let len_codes = huffman::canonical_codes(&len_lens);
+    let aligned_codes = huffman::canonical_codes(&aligned_lens);
+
+    // Aligned blocks pay 24 bits of tree up front to Huffman-code the low 3 offset bits
+    let verbatim_cost: u32 = aligned_freqs.iter().sum::<u32>() * 3;
+    let aligned_cost: u32 = 24 + aligned_freqs.iter().zip(&aligned_lens).map(|(&f, &l)| f * l as u32).sum::<u32>();
+    let aligned = aligned_cost < verbatim_cost;
+
+    let mut w = BitWriter { out: Vec::with_capacity(input_len / 2 + 64), bitbuf: 0, bitcount: 0 };
+    w.write_bits(if aligned { BLOCKTYPE_ALIGNED } else { BLOCKTYPE_VERBATIM }, 3);
+    if input_len == CHUNK_SIZE {
+        w.write_bits(1, 1);
+    } else {
+        w.write_bits(0, 1);
+        w.write_bits(input_len as u32, 16);
+    }
+    if aligned {
+        for &l in &aligned_lens {
+            w.write_bits(l as u32, 3);
+        }
+    }
+
+    let zeros = [0u8; NUM_MAIN_SYMBOLS];
+    write_code_lens(&mut w, &main_lens[..NUM_CHARS], &zeros[..NUM_CHARS]);
+    write_code_lens(&mut w, &main_lens[NUM_CHARS..], &zeros[NUM_CHARS..]);
+    write_code_lens(&mut w, &len_lens, &zeros[..NUM_LEN_SYMBOLS]);
+
+    for item in items {
+        let sym = item.main_symbol();
+        w.write_bits(main_codes[sym] as u32, main_lens[sym] as u32);
+
+        if let Item::Match { len, slot, extra } = *item {
+            if len - MIN_MATCH >= NUM_PRIMARY_LENS {
+                let ls = len - MIN_MATCH - NUM_PRIMARY_LENS;
+                w.write_bits(len_codes[ls] as u32, len_lens[ls] as u32);
+            }
+            let nbits = SLOT_EXTRA_BITS[slot];
+            if aligned && nbits >= 3 {
+                w.write_bits(extra >> 3, nbits - 3);
+                let a = (extra & 7) as usize;
+                w.write_bits(aligned_codes[a] as u32, aligned_lens[a] as u32);
+            } else {
+                w.write_bits(extra, nbits);
+            }
+        }
+    }
+
+    w.finish()
+}
+
+/// Compressed size of `input` in independent WOF LZX chunks.
+pub fn compressed_size_chunked(input: &[u8]) -> usize {
+    let mut enc = Encoder::new();
+    super::chunked_size(input, CHUNK_SIZE, |c| enc.compress(c))
+}
+
+// ===== DECODER =====
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bitbuf: u32,
+    bitsleft: u32,
+}
+
+impl<'a> BitReader<'a> {
+    /// Loads one word if fewer than `n` (<= 16) bits are buffered; past the end reads zeros.
+    fn ensure(&mut self, n: u32) {
+        if self.bitsleft < n {
+            let word = match self.data.get(self.pos..self.pos + 2) {
+                Some(b) => u16::from_le_bytes([b[0], b[1]]) as u32,
+                None => 0,
+            };
+            self.pos += 2;
+            self.bitbuf |= word << (16 - self.bitsleft);
+            self.bitsleft += 16;
+        }
+    }
+
+    fn read_bits(&mut self, n: u32) -> u32 {
+        if n == 0 { return 0; }
+        self.ensure(n);
+        let v = self.bitbuf >> (32 - n);
+        self.bitbuf <<= n;
+        self.bitsleft -= n;
+        v
+    }
+
+    fn read_symbol(&mut self, table: &DecodeTable, max_len: u32) -> Option<usize> {
+        self.ensure(max_len);
+        let (sym, len) = table.decode(self.bitbuf)?;
+        self.bitbuf <<= len;
+        self.bitsleft -= len;
+        Some(sym)
+    }
+
+    /// Drops buffered bits so the next read starts at a word boundary.
+    fn align(&mut self) {
+        self.bitbuf = 0;
+        self.bitsleft = 0;
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        let b = self.data.get(self.pos..self.pos + 4)?;
+        self.pos += 4;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn read_bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let b = self.data.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(b)
+    }
+}
+
+/// Reads pretree-coded deltas into `lens` (which holds the previous block's lengths).
+fn read_code_lens(r: &mut BitReader, lens: &mut [u8]) -> Option<()> {
+    let mut pre_lens = [0u8; NUM_PRE_SYMBOLS];
+    for l in pre_lens.iter_mut() {
+        *l = r.read_bits(4) as u8;
+    }
+    let table = DecodeTable::new(&pre_lens, MAX_PRE_CODE_LEN as u32)?;
+    let apply = |old: u8, presym: usize| ((old as usize + 17 - presym) % 17) as u8;
+
+    let mut i = 0;
+    while i < lens.len() {
+        let sym = r.read_symbol(&table, MAX_PRE_CODE_LEN as u32)?;
+        let (run, value) = match sym {
+            0..=16 => (1, apply(lens[i], sym)),
+            17 => (4 + r.read_bits(4) as usize, 0),
+            18 => (20 + r.read_bits(5) as usize, 0),
+            _ => {
+                let run = 4 + r.read_bits(1) as usize;
+                let presym = r.read_symbol(&table, MAX_PRE_CODE_LEN as u32)?;
+                if presym > 17 { return None; }
+                (run, apply(lens[i], presym))
+            }
+        };
+        if i + run > lens.len() { return None; }
+        lens[i..i + run].fill(value);
+        i += run;
+    }
+    Some(())
+}
+
+/// Decompresses one WOF LZX chunk into exactly `out_len` bytes.
+pub fn decompress(input: &[u8], out_len: usize) -> Option<Vec<u8>> {
+    if out_len > CHUNK_SIZE { return None; }
+
+    let mut r = BitReader { data: input, pos: 0, bitbuf: 0, bitsleft: 0 };
+    let mut out: Vec<u8> = Vec::with_capacity(out_len);
+    let mut reps = [1usize; NUM_REPS];
+    let mut main_lens = [0u8; NUM_MAIN_SYMBOLS];
+    let mut len_lens = [0u8; NUM_LEN_SYMBOLS];
+
+    while out.len() < out_len {
+        let block_type = r.read_bits(3);
+        let block_size = if r.read_bits(1) == 1 { CHUNK_SIZE } else { r.read_bits(16) as usize };
+        if block_size == 0 || out.len() + block_size > out_len { return None; }
+        let block_end = out.len() + block_size;
+
+        if block_type == BLOCKTYPE_UNCOMPRESSED {
+            r.ensure(1);
+            r.align();
+            for rep in reps.iter_mut() {
+                *rep = r.read_u32()? as usize;
+                if *rep == 0 { return None; }
+            }
+            out.extend_from_slice(r.read_bytes(block_size)?);
+            if block_size & 1 == 1 {
+                r.read_bytes(1)?;
+            }
+            continue;
+        }
+        if block_type != BLOCKTYPE_VERBATIM && block_type != BLOCKTYPE_ALIGNED { return None; }
+
+        let mut aligned_lens = [0u8; NUM_ALIGNED_SYMBOLS];
+        let aligned_table = if block_type == BLOCKTYPE_ALIGNED {
+            for l in aligned_lens.iter_mut() {
+                *l = r.read_bits(3) as u8;
+            }
+            Some(DecodeTable::new(&aligned_lens, MAX_ALIGNED_CODE_LEN as u32)?)
+        } else {
+            None
+        };
+
+        read_code_lens(&mut r, &mut main_lens[..NUM_CHARS])?;
+        read_code_lens(&mut r, &mut main_lens[NUM_CHARS..])?;
+        read_code_lens(&mut r, &mut len_lens)?;
+        let main_table = DecodeTable::new(&main_lens, MAX_MAIN_CODE_LEN as u32)?;
+        let len_table = DecodeTable::new(&len_lens, MAX_LEN_CODE_LEN as u32)?;
+
+        while out.len() < block_end {
+            let sym = r.read_symbol(&main_table, MAX_MAIN_CODE_LEN as u32)?;
+            if sym < NUM_CHARS {
+                out.push(sym as u8);
+                continue;
+            }
+
+            let slot = (sym - NUM_CHARS) >> 3;
+            let mut len = (sym - NUM_CHARS) & 7;
+            if len == NUM_PRIMARY_LENS {
+                len += r.read_symbol(&len_table, MAX_LEN_CODE_LEN as u32)?;
+            }
+            len += MIN_MATCH;
+
+            let dist = if slot < NUM_REPS {
+                reps.swap(0, slot);
+                reps[0]
+            } else {
+                let nbits = SLOT_EXTRA_BITS[slot];
+                let mut formatted = SLOT_BASE[slot] as usize;
+                match &aligned_table {
+                    Some(t) if nbits >= 3 => {
+                        formatted += (r.read_bits(nbits - 3) as usize) << 3;
+                        formatted += r.read_symbol(t, MAX_ALIGNED_CODE_LEN as u32)?;
+                    }
+                    _ => formatted += r.read_bits(nbits) as usize,
+                }
+                let dist = formatted - OFFSET_ADJUSTMENT;
+                reps = [dist, reps[0], reps[1]];
+                dist
+            };
+
+            if dist > out.len() || out.len() + len > block_end { return None; }
+            let start = out.len() - dist;
+            for i in 0..len {
+                out.push(out[start + i]);
+            }
+        }
+    }
+
+    e8_filter(&mut out, undo_translate);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize) -> Vec<u8> {
+        // Text mixed with x86-like call instructions to exercise E8 translation
+        let parts: [&[u8]; 5] = [b"mov eax, ", b"\xE8\x10\x00\x00\x00", b"push ebp\n", b"\xE8\xF0\xFF\xFF\xFF", b"ret 0x0004 "];
+        let mut out = Vec::with_capacity(len);
+        let mut seed = 7u32;
+        while out.len() < len {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            out.extend_from_slice(parts[(seed >> 16) as usize % parts.len()]);
+        }
+        out.truncate(len);
+        out
+    }
+
+    fn round_trip(data: &[u8]) -> usize {
+        let c = compress(data).unwrap();
+        assert_eq!(decompress(&c, data.len()).as_deref(), Some(data));
+        c.len()
+    }
+
+    #[test]
+    fn test_offset_slots() {
+        assert_eq!(offset_slot(3), 3);
+        assert_eq!(offset_slot(4), 4);
+        assert_eq!(offset_slot(6), 5);
+        assert_eq!(offset_slot(32767), 29);
+        for (slot, &base) in SLOT_BASE.iter().enumerate().skip(3) {
+            assert_eq!(offset_slot(base as usize), slot);
+        }
+    }
+
+    #[test]
+    fn test_e8_translation_is_reversible() {
+        let mut data = sample(5000);
+        let orig = data.clone();
+        e8_filter(&mut data, do_translate);
+        assert_ne!(data, orig);
+        e8_filter(&mut data, undo_translate);
+        assert_eq!(data, orig);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(b"x");
+        round_trip(b"abcabcabcabcabc");
+        round_trip(&vec![0u8; CHUNK_SIZE]);
+        round_trip(&[0xE8u8; 100]);
+
+        let mut seed = 99u32;
+        let noise: Vec<u8> = (0..CHUNK_SIZE).map(|_| { seed = seed.wrapping_mul(1664525).wrapping_add(1013904223); (seed >> 24) as u8 }).collect();
+        round_trip(&noise);
+
+        let data = sample(CHUNK_SIZE);
+        assert!(round_trip(&data) < data.len() / 4);
+        round_trip(&data[..12345]);
+    }
+
+    #[test]
+    fn test_uncompressed_block() {
+        // Hand-built block: type 3, non-default size 5, aligned, R0-R2, data, pad byte
+        let mut s = vec![0x00, 0x60, 0x00, 0x50];
+        for _ in 0..3 { s.extend_from_slice(&1u32.to_le_bytes()); }
+        s.extend_from_slice(b"hello\0");
+        assert_eq!(decompress(&s, 5).as_deref(), Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn test_beats_xpress_on_long_range_data() {
+        // Repeats 20 KiB apart are outside the 16K XPRESS chunk but inside the LZX window
+        let mut seed = 5u32;
+        let block: Vec<u8> = (0..20000).map(|_| { seed = seed.wrapping_mul(1664525).wrapping_add(1013904223); (seed >> 24) as u8 }).collect();
+        let mut data = block.clone();
+        data.extend_from_slice(&block[..CHUNK_SIZE - block.len()]);
+
+        let lzx = compressed_size_chunked(&data);
+        let xp = super::super::xpress::compressed_size_chunked(&data, 16384);
+        assert!(lzx < xp * 3 / 4);
+    }
+}
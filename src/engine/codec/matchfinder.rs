@@ -0,0 +1,100 @@
+/* --- src/engine/codec/matchfinder.rs --- */
+//! Hash-chain LZ77 match finder shared by the codecs.
+//!
+//! Sized per input buffer (WOF chunks are at most 64 KiB), so positions are plain indices.
+//! Encoders keep one finder and [`MatchFinder::reset`] it between chunks instead of
+//! reallocating the 128 KiB hash table each time.
+
+const HASH_BITS: u32 = 15;
+const NIL: u32 = u32::MAX;
+
+pub struct MatchFinder {
+    head: Vec<u32>,
+    prev: Vec<u32>,
+    max_dist: usize,
+    max_depth: usize,
+    nice_len: usize,
+}
+
+impl MatchFinder {
+    /// `max_dist`: largest distance the format can encode.
+    /// `max_depth`: chain links followed per search. `nice_len`: stop searching at this length.
+    pub fn new(max_dist: usize, max_depth: usize, nice_len: usize) -> Self {
+        Self {
+            head: vec![NIL; 1 << HASH_BITS],
+            prev: Vec::new(),
+            max_dist,
+            max_depth,
+            nice_len,
+        }
+    }
+
+    /// Empties the chains and sizes them for a new `len`-byte buffer.
+    pub fn reset(&mut self, len: usize) {
+        self.head.fill(NIL);
+        self.prev.clear();
+        self.prev.resize(len, NIL);
+    }
+
+    #[inline]
+    fn hash(data: &[u8], pos: usize) -> usize {
+        let v = (data[pos] as u32) | ((data[pos + 1] as u32) << 8) | ((data[pos + 2] as u32) << 16);
+        (v.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+    }
+
+    /// Adds `pos` to the chains without searching.
+    pub fn insert(&mut self, data: &[u8], pos: usize) {
+        if pos + 3 > data.len() { return; }
+        let h = Self::hash(data, pos);
+        self.prev[pos] = self.head[h];
+        self.head[h] = pos as u32;
+    }
+
+    /// Longest match at `pos` of at least `min_len` (>= 3) and at most `max_len` bytes,
+    /// as `(length, distance)`. `pos` is inserted into the chains.
+    pub fn find(&mut self, data: &[u8], pos: usize, min_len: usize, max_len: usize) -> Option<(usize, usize)> {
+        if pos + 3 > data.len() { return None; }
+        let max_len = max_len.min(data.len() - pos);
+        let h = Self::hash(data, pos);
+        let mut cand = self.head[h];
+        self.prev[pos] = cand;
+        self.head[h] = pos as u32;
+
+        if max_len < min_len { return None; }
+
+        let nice = self.nice_len.min(max_len);
+        let mut best_len = min_len - 1;
+        let mut best_dist = 0;
+        let mut depth = self.max_depth;
+
+        while cand != NIL && depth > 0 {
+            let c = cand as usize;
+            let dist = pos - c;
+            if dist > self.max_dist { break; }
+
+            // Cheap reject: the byte that would extend the current best must match
+            if data[c + best_len] == data[pos + best_len] {
+                let len = match_len(data, c, pos, max_len);
+                if len > best_len {
+                    best_len = len;
+                    best_dist = dist;
+                    if len >= nice { break; }
+                }
+            }
+            cand = self.prev[c];
+            depth -= 1;
+        }
+
+        if best_len >= min_len { Some((best_len, best_dist)) } else { None }
+    }
+}
+
+/// Length of the common prefix of `data[a..]` and `data[b..]`, capped at `max_len`.
+#[inline]
+pub fn match_len(data: &[u8], a: usize, b: usize, max_len: usize) -> usize {
+    let mut len = 0;
+    while len < max_len && data[a + len] == data[b + len] {
+        len += 1;
+    }
+    len
+}
@@ -0,0 +1,18 @@
+//! Pure-Rust implementations of the compression formats used by WOF.
+//!
+//! These let the estimator measure real per-algorithm output without calling into
+//! `cabinet.dll` or touching the files being analysed.
+
+pub mod huffman;
+pub mod matchfinder;
+pub mod xpress;
+pub mod lzx;
+pub mod lznt1;
+
+/// Size of `input` compressed in independent `chunk`-byte pieces, counting pieces that do
+/// not shrink at their raw size (WOF stores those uncompressed).
+pub fn chunked_size(input: &[u8], chunk: usize, mut compress: impl FnMut(&[u8]) -> Option<Vec<u8>>) -> usize {
+    input.chunks(chunk)
+        .map(|c| compress(c).map(|v| v.len().min(c.len())).unwrap_or(c.len()))
+        .sum()
+}
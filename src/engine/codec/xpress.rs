@@ -0,0 +1,369 @@
+/* --- src/engine/codec/xpress.rs --- */
+//! XPRESS Huffman ("LZ77+Huffman", MS-XCA 2.1) codec.
+//!
+//! This is the format WOF uses for XPRESS4K/8K/16K: every chunk is compressed independently
+//! as a single block of at most 64 KiB.
+//!
+//! # Block Layout
+//! - 256 bytes: 512 four-bit code lengths (low nibble = even symbol).
+//! - Bitstream of 16-bit little-endian words read MSB-first. Extra match-length bytes are
+//!   interleaved with the words at the position the decoder reaches them.
+//!
+//! # Symbols
+//! - `0..256`: literal byte.
+//! - `256..512`: match, `256 + (min(len - 3, 15) | (floor(log2(dist)) << 4))`, followed by
+//!   `floor(log2(dist))` low offset bits. Length nibble 15 is extended by a byte, and byte 255
+//!   by a u16 holding `len - 3`.
+//! - Symbol 256 is additionally emitted once as end-of-data for compatibility with the
+//!   Windows decoder.
+
+use super::huffman::{self, DecodeTable};
+use super::matchfinder::MatchFinder;
+
+/// Largest input a single XPRESS Huffman block can describe.
+pub const MAX_BLOCK_SIZE: usize = 65536;
+
+const NUM_SYMBOLS: usize = 512;
+const NUM_CHARS: usize = 256;
+const END_OF_DATA: usize = 256;
+const MAX_CODE_LEN: u8 = 15;
+const TABLE_SIZE: usize = NUM_SYMBOLS / 2;
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 0xFFFF + MIN_MATCH;
+const MAX_OFFSET: usize = 0xFFFF;
+
+/// Chain links followed per position; matches the speed of a "normal" compression level.
+const SEARCH_DEPTH: usize = 24;
+const NICE_MATCH: usize = 64;
+
+enum Item {
+    Literal(u8),
+    Match { len: usize, dist: usize },
+}
+
+impl Item {
+    fn symbol(&self) -> usize {
+        match *self {
+            Item::Literal(b) => b as usize,
+            Item::Match { len, dist } => {
+                let log2 = dist.ilog2() as usize;
+                NUM_CHARS + ((len - MIN_MATCH).min(15) | (log2 << 4))
+            }
+        }
+    }
+}
+
+// ===== ENCODER =====
+
+/// Bit writer with the two reserved word slots XPRESS needs.
+///
+/// The decoder always holds the next two 16-bit words in its bit buffer, so literal bytes
+/// written now must land after the slots for the words it has already pre-fetched.
+struct BitWriter {
+    out: Vec<u8>,
+    bitbuf: u32,
+    bitcount: u32,
+    next_seq1: usize,
+    next_seq2: usize,
+}
+
+impl BitWriter {
+    fn new(mut out: Vec<u8>) -> Self {
+        let start = out.len();
+        out.extend_from_slice(&[0; 4]);
+        Self { out, bitbuf: 0, bitcount: 0, next_seq1: start, next_seq2: start + 2 }
+    }
+
+    fn put_word(&mut self, at: usize, v: u16) {
+        self.out[at..at + 2].copy_from_slice(&v.to_le_bytes());
+    }
+
+    /// Writes the low `n` (<= 16) bits of `bits`.
+    fn write_bits(&mut self, bits: u32, n: u32) {
+        if n == 0 { return; }
+        self.bitbuf = (self.bitbuf << n) | bits;
+        self.bitcount += n;
+        if self.bitcount > 16 {
+            self.bitcount -= 16;
+            let word = (self.bitbuf >> self.bitcount) as u16;
+            self.put_word(self.next_seq1, word);
+            self.next_seq1 = self.next_seq2;
+            self.next_seq2 = self.out.len();
+            self.out.extend_from_slice(&[0; 2]);
+        }
+    }
+
+    fn write_byte(&mut self, b: u8) {
+        self.out.push(b);
+    }
+
+    fn write_u16(&mut self, v: u16) {
+        self.out.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let word = (self.bitbuf << (16 - self.bitcount)) as u16;
+        self.put_word(self.next_seq1, word);
+        self.put_word(self.next_seq2, 0);
+        self.out
+    }
+}
+
+/// Greedy parse with one step of lazy evaluation.
+fn parse(mf: &mut MatchFinder, input: &[u8]) -> Vec<Item> {
+    let mut items = Vec::with_capacity(input.len() / 2);
+    mf.reset(input.len());
+    let mut pos = 0;
+    let mut pending = None;
+
+    while pos < input.len() {
+        let cur = pending.take().or_else(|| mf.find(input, pos, MIN_MATCH, MAX_MATCH));
+        let Some((len, dist)) = cur else {
+            items.push(Item::Literal(input[pos]));
+            pos += 1;
+            continue;
+        };
+
+        let lazy = len < NICE_MATCH && pos + 1 < input.len();
+        let better = if lazy { mf.find(input, pos + 1, len + 1, MAX_MATCH) } else { None };
+        if better.is_some() {
+            items.push(Item::Literal(input[pos]));
+            pos += 1;
+            pending = better;
+            continue;
+        }
+
+        items.push(Item::Match { len, dist });
+        // Position `pos + 1` is already inserted by the lazy search
+        for p in (pos + 1 + lazy as usize)..pos + len {
+            mf.insert(input, p);
+        }
+        pos += len;
+    }
+    items
+}
+
+/// XPRESS Huffman encoder that reuses its match finder across blocks.
+pub struct Encoder {
+    mf: MatchFinder,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self { mf: MatchFinder::new(MAX_OFFSET, SEARCH_DEPTH, NICE_MATCH) }
+    }
+
+    /// Compresses `input` as one XPRESS Huffman block.
+    ///
+    /// Returns `None` if `input` exceeds [`MAX_BLOCK_SIZE`]. The result may be larger than the
+    /// input; WOF stores such chunks uncompressed.
+    pub fn compress(&mut self, input: &[u8]) -> Option<Vec<u8>> {
+        if input.len() > MAX_BLOCK_SIZE { return None; }
+        Some(encode_block(input, &parse(&mut self.mf, input)))
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compresses `input` as one XPRESS Huffman block (see [`Encoder::compress`]).
+pub fn compress(input: &[u8]) -> Option<Vec<u8>> {
+    Encoder::new().compress(input)
+}
+
+fn encode_block(input: &[u8], items: &[Item]) -> Vec<u8> {
+
+    let mut freqs = [0u32; NUM_SYMBOLS];
+    for item in items {
+        freqs[item.symbol()] += 1;
+    }
+    freqs[END_OF_DATA] += 1;
+
+    let lens = huffman::build_lengths(&freqs, MAX_CODE_LEN);
+    let codes = huffman::canonical_codes(&lens);
+
+    let mut out = Vec::with_capacity(TABLE_SIZE + input.len() / 2 + 16);
+    for pair in lens.chunks(2) {
+        out.push(pair[0] | (pair[1] << 4));
+    }
+
+    let mut w = BitWriter::new(out);
+    for item in items {
+        let sym = item.symbol();
+        w.write_bits(codes[sym] as u32, lens[sym] as u32);
+
+        if let Item::Match { len, dist } = *item {
+            let adj = len - MIN_MATCH;
+            if adj >= 15 {
+                if adj - 15 >= 0xFF {
+                    w.write_byte(0xFF);
+                    w.write_u16(adj as u16);
+                } else {
+                    w.write_byte((adj - 15) as u8);
+                }
+            }
+            let log2 = dist.ilog2();
+            w.write_bits((dist - (1 << log2)) as u32, log2);
+        }
+    }
+    w.write_bits(codes[END_OF_DATA] as u32, lens[END_OF_DATA] as u32);
+
+    w.finish()
+}
+
+/// Compressed size of `input` in independent `chunk`-byte XPRESS chunks.
+pub fn compressed_size_chunked(input: &[u8], chunk: usize) -> usize {
+    let mut enc = Encoder::new();
+    super::chunked_size(input, chunk, |c| enc.compress(c))
+}
+
+// ===== DECODER =====
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bitbuf: u32,
+    bitsleft: u32,
+}
+
+impl<'a> BitReader<'a> {
+    /// Keeps at least 16 bits buffered. Reading past the end yields zero bits.
+    fn ensure(&mut self) {
+        if self.bitsleft < 16 {
+            let word = match self.data.get(self.pos..self.pos + 2) {
+                Some(b) => u16::from_le_bytes([b[0], b[1]]) as u32,
+                None => 0,
+            };
+            self.pos += 2;
+            self.bitbuf |= word << (16 - self.bitsleft);
+            self.bitsleft += 16;
+        }
+    }
+
+    fn pop(&mut self, n: u32) -> u32 {
+        if n == 0 { return 0; }
+        let v = self.bitbuf >> (32 - n);
+        self.bitbuf <<= n;
+        self.bitsleft -= n;
+        v
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        let b = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn read_u16(&mut self) -> Option<u16> {
+        let b = self.data.get(self.pos..self.pos + 2)?;
+        self.pos += 2;
+        Some(u16::from_le_bytes([b[0], b[1]]))
+    }
+}
+
+/// Decompresses one XPRESS Huffman block into exactly `out_len` bytes.
+pub fn decompress(input: &[u8], out_len: usize) -> Option<Vec<u8>> {
+    if out_len > MAX_BLOCK_SIZE || input.len() < TABLE_SIZE { return None; }
+
+    let mut lens = [0u8; NUM_SYMBOLS];
+    for (i, &b) in input[..TABLE_SIZE].iter().enumerate() {
+        lens[2 * i] = b & 0x0F;
+        lens[2 * i + 1] = b >> 4;
+    }
+    let table = DecodeTable::new(&lens, MAX_CODE_LEN as u32)?;
+
+    let mut r = BitReader { data: input, pos: TABLE_SIZE, bitbuf: 0, bitsleft: 0 };
+    let mut out = Vec::with_capacity(out_len);
+
+    while out.len() < out_len {
+        r.ensure();
+        let (sym, len) = table.decode(r.bitbuf)?;
+        r.pop(len);
+
+        if sym < NUM_CHARS {
+            out.push(sym as u8);
+            continue;
+        }
+
+        let sym = sym - NUM_CHARS;
+        let log2 = (sym >> 4) as u32;
+        let mut len = sym & 0x0F;
+
+        r.ensure();
+        if len == 15 {
+            len += r.read_byte()? as usize;
+            if len == 15 + 0xFF {
+                len = r.read_u16()? as usize;
+            }
+        }
+        len += MIN_MATCH;
+        let dist = (1usize << log2) | r.pop(log2) as usize;
+
+        if dist > out.len() || out.len() + len > out_len { return None; }
+        let start = out.len() - dist;
+        for i in 0..len {
+            out.push(out[start + i]);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_text(len: usize) -> Vec<u8> {
+        let words = ["compact", "wof ", "xpress", " chunk", "lzx\r\n", "0123", "huffman "];
+        let mut out = Vec::with_capacity(len);
+        let mut seed = 12345u32;
+        while out.len() < len {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            out.extend_from_slice(words[(seed >> 16) as usize % words.len()].as_bytes());
+        }
+        out.truncate(len);
+        out
+    }
+
+    fn noise(len: usize) -> Vec<u8> {
+        let mut seed = 0xC0FFEEu32;
+        (0..len).map(|_| { seed = seed.wrapping_mul(1664525).wrapping_add(1013904223); (seed >> 24) as u8 }).collect()
+    }
+
+    fn round_trip(data: &[u8]) -> usize {
+        let c = compress(data).unwrap();
+        assert_eq!(decompress(&c, data.len()).as_deref(), Some(data));
+        c.len()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(b"");
+        round_trip(b"a");
+        round_trip(b"abcabcabcabcabc");
+        round_trip(&noise(4096));
+        // Long runs exercise the byte and u16 length extensions
+        round_trip(&vec![7u8; 300]);
+        round_trip(&vec![0u8; MAX_BLOCK_SIZE]);
+
+        let text = sample_text(16384);
+        assert!(round_trip(&text) < text.len() / 2);
+    }
+
+    #[test]
+    fn test_chunked_size() {
+        let text = sample_text(16384);
+        let c4 = compressed_size_chunked(&text, 4096);
+        let c16 = compressed_size_chunked(&text, 16384);
+        // Larger chunks keep more history
+        assert!(c16 < c4);
+
+        // Incompressible chunks are counted at their raw size
+        let n = noise(8192);
+        assert_eq!(compressed_size_chunked(&n, 4096), 8192);
+        assert!(compress(&vec![0u8; MAX_BLOCK_SIZE + 1]).is_none());
+    }
+}
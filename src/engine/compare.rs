@@ -0,0 +1,128 @@
+/* --- src/engine/compare.rs --- */
+//! "What-if" comparison of every WOF algorithm for one path.
+//!
+//! `estimator::estimate_path_shared` samples the path once for all algorithms, so a
+//! comparison reads about as much as a single estimate. Besides the projected size and
+//! savings, each algorithm gets a relative CPU cost: the time the built-in encoders spent
+//! per sampled byte, against XPRESS4K. It ranks compression effort; decompression is cheap
+//! for all of them.
+//!
+//! Savings are measured against what the path takes on disk now, like the analyzer's, so
+//! an already compressed folder does not look like it would gain its compression again.
+
+use crate::engine::estimator::{self, AllocModel, Estimate, EstimatorParams};
+use crate::engine::scanner;
+use crate::engine::wof::WofAlgorithm;
+
+/// Algorithms compared, fastest first.
+pub const ALGORITHMS: [WofAlgorithm; 5] = [
+    WofAlgorithm::Xpress4K,
+    WofAlgorithm::Xpress8K,
+    WofAlgorithm::Xpress16K,
+    WofAlgorithm::Lznt1,
+    WofAlgorithm::Lzx,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AlgoComparison {
+    pub algo: WofAlgorithm,
+    pub estimate: Estimate,
+    /// Compression time per byte relative to XPRESS4K; `None` if nothing was sampled.
+    pub cpu_cost: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    /// Logical size of the path.
+    pub logical: u64,
+    /// Current size on disk of the path.
+    pub disk: u64,
+    /// One row per entry of `ALGORITHMS`, in that order.
+    pub rows: Vec<AlgoComparison>,
+}
+
+impl Comparison {
+    /// Bytes freed on disk by compressing with `row`'s algorithm.
+    pub fn savings(&self, row: &AlgoComparison) -> u64 {
+        self.disk.saturating_sub(row.estimate.size)
+    }
+
+    /// `savings` as a share of the current size on disk.
+    pub fn savings_percent(&self, row: &AlgoComparison) -> f64 {
+        if self.disk == 0 { 0.0 } else { self.savings(row) as f64 * 100.0 / self.disk as f64 }
+    }
+
+    /// Row with the smallest projected size (the faster one on ties).
+    pub fn smallest(&self) -> Option<&AlgoComparison> {
+        self.rows.iter().min_by_key(|r| r.estimate.size)
+    }
+
+    pub fn row(&self, algo: WofAlgorithm) -> Option<&AlgoComparison> {
+        self.rows.iter().find(|r| r.algo == algo)
+    }
+}
+
+/// Compares all algorithms for `path` on the volume holding it. `None` if cancelled.
+pub fn compare_path(path: &str, cancelled: &dyn Fn() -> bool) -> Option<Comparison> {
+    let disk = scanner::calculate_path_disk_size(path, true);
+    compare_path_with(path, disk, AllocModel::for_path(path), EstimatorParams::default(), cancelled)
+}
+
+/// `compare_path` for a path taking `disk` bytes on disk now.
+pub fn compare_path_with(path: &str, disk: u64, model: AllocModel, params: EstimatorParams, cancelled: &dyn Fn() -> bool) -> Option<Comparison> {
+    let (logical, shared) = estimator::estimate_path_shared(path, &ALGORITHMS, model, params, cancelled)?;
+
+    let per_byte = |s: &estimator::SharedEstimate| {
+        (s.sampled_bytes > 0).then(|| s.codec_time.as_secs_f64() / s.sampled_bytes as f64)
+    };
+    let base = shared.iter().find(|s| s.algo == WofAlgorithm::Xpress4K).and_then(per_byte).filter(|&b| b > 0.0);
+    let rows = shared.iter()
+        .map(|s| AlgoComparison {
+            algo: s.algo,
+            estimate: s.estimate,
+            cpu_cost: base.and_then(|b| per_byte(s).map(|t| t / b)),
+        })
+        .collect();
+    Some(Comparison { logical, disk, rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::estimator::EstimateMode;
+
+    #[test]
+    fn test_compare_matches_single_estimates() {
+        let dir = std::env::temp_dir().join(["compactrs_compare_", &std::process::id().to_string()].concat());
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let data: Vec<u8> = (0..196_608u32).map(|i| (i % 251) as u8 ^ (i / 4096) as u8).collect();
+        std::fs::write(dir.join("a.dat"), &data).unwrap();
+        std::fs::write(dir.join("b.bin"), b"compare ".repeat(8192)).unwrap();
+        let path = dir.to_str().unwrap();
+
+        // A budget of the whole file reads it whole, so the shared pass sees the same bytes
+        let params = EstimatorParams { use_learned: false, budget_divisor: 1, ..EstimatorParams::default() };
+        let model = AllocModel::default();
+        let cmp = compare_path_with(path, 4 * 65_536, model, params, &|| false).unwrap();
+        assert_eq!(cmp.logical, 4 * 65_536);
+        assert_eq!(cmp.rows.iter().map(|r| r.algo).collect::<Vec<_>>(), ALGORITHMS.to_vec());
+
+        for row in &cmp.rows {
+            let single = estimator::estimate_path_range(path, row.algo, EstimateMode::Accurate, model, params);
+            assert_eq!(row.estimate, single, "{:?}", row.algo);
+            assert!(row.cpu_cost.is_some_and(|c| c > 0.0));
+        }
+        assert_eq!(cmp.row(WofAlgorithm::Xpress4K).unwrap().cpu_cost, Some(1.0));
+        let best = cmp.smallest().unwrap();
+        assert!(cmp.rows.iter().all(|r| r.estimate.size >= best.estimate.size));
+        assert!(cmp.savings_percent(best) > 0.0);
+
+        // Savings count from what is on disk now: an already compressed path gains less
+        let compressed = compare_path_with(path, best.estimate.size, model, params, &|| false).unwrap();
+        assert_eq!(compressed.savings(compressed.smallest().unwrap()), 0);
+
+        assert_eq!(compare_path_with(path, 4 * 65_536, model, params, &|| true), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
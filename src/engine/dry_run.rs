@@ -0,0 +1,134 @@
+/* --- src/engine/dry_run.rs --- */
+//! Dry-run plans: what a compress batch would do to each file, without doing it.
+//!
+//! `worker::dry_run_file` goes through the same critical-path guard, rules and skip
+//! heuristics as a real compress and reports the outcome as a `FileDecision` instead of
+//! calling the driver. Files that would be compressed are projected with the estimator, so
+//! a row's `DryRunPlan` also gives the savings to expect.
+
+use std::cmp::Reverse;
+
+use crate::engine::wof::{AlgoBreakdown, WofAlgorithm};
+
+/// What compressing one file would do.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Decision {
+    Compress(WofAlgorithm),
+    /// Left as is, with the reason a real run reports ("Already optimal", "System Path", ...).
+    Skip(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileDecision {
+    pub decision: Decision,
+    pub logical: u64,
+    /// Current size on disk.
+    pub disk: u64,
+    /// Projected size on disk after the run (`disk` when skipped).
+    pub projected: u64,
+}
+
+impl FileDecision {
+    /// e.g. "would compress with LZX" or "skip (Filtered extension)".
+    pub fn describe(&self) -> String {
+        match &self.decision {
+            Decision::Compress(algo) => ["would compress with ", &algo.name().to_uppercase()].concat(),
+            Decision::Skip(reason) => ["skip (", reason, ")"].concat(),
+        }
+    }
+}
+
+/// Dry-run outcome of a batch row.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DryRunPlan {
+    /// Files that would be compressed, per algorithm; `disk` is their projected size.
+    pub compress: AlgoBreakdown,
+    /// Files left alone: (reason, files, bytes on disk), in the order first seen.
+    pub skipped: Vec<(String, u64, u64)>,
+    /// On-disk size of every planned file before the run.
+    pub current: u64,
+}
+
+impl DryRunPlan {
+    pub fn record(&mut self, d: &FileDecision) {
+        self.current += d.disk;
+        match &d.decision {
+            Decision::Compress(algo) => self.compress.add(Some(*algo), d.logical, d.projected),
+            Decision::Skip(reason) => match self.skipped.iter_mut().find(|(r, _, _)| r == reason) {
+                Some((_, files, bytes)) => {
+                    *files += 1;
+                    *bytes += d.disk;
+                }
+                None => self.skipped.push((reason.clone(), 1, d.disk)),
+            },
+        }
+    }
+
+    pub fn files_to_compress(&self) -> u64 {
+        self.compress.total().files
+    }
+
+    pub fn files_skipped(&self) -> u64 {
+        self.skipped.iter().map(|(_, files, _)| files).sum()
+    }
+
+    /// On-disk size of every planned file after the run.
+    pub fn projected(&self) -> u64 {
+        self.compress.total().disk + self.skipped.iter().map(|(_, _, bytes)| bytes).sum::<u64>()
+    }
+
+    pub fn savings(&self) -> u64 {
+        self.current.saturating_sub(self.projected())
+    }
+
+    /// Skip reasons, most files first.
+    pub fn skip_reasons(&self) -> Vec<(String, u64, u64)> {
+        let mut reasons = self.skipped.clone();
+        reasons.sort_by_key(|(_, files, _)| Reverse(*files));
+        reasons
+    }
+
+    /// e.g. "Compress 120 (100 LZX, 20 XPRESS4K), skip 30".
+    pub fn summary(&self) -> String {
+        let algos: Vec<String> = self.compress.entries().iter()
+            .filter_map(|(algo, t)| algo.map(|a| [&t.files.to_string(), " ", &a.name().to_uppercase()].concat()))
+            .collect();
+        let mut text = ["Compress ", &self.files_to_compress().to_string()].concat();
+        if algos.len() > 1 {
+            text = [&text, " (", &algos.join(", "), ")"].concat();
+        } else if let Some(only) = self.compress.entries().first().and_then(|(a, _)| *a) {
+            text = [&text, " with ", &only.name().to_uppercase()].concat();
+        }
+        [&text, ", skip ", &self.files_skipped().to_string()].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(decision: Decision, disk: u64, projected: u64) -> FileDecision {
+        FileDecision { decision, logical: disk, disk, projected }
+    }
+
+    #[test]
+    fn test_plan_totals_and_summary() {
+        let mut plan = DryRunPlan::default();
+        plan.record(&decision(Decision::Compress(WofAlgorithm::Lzx), 1000, 400));
+        plan.record(&decision(Decision::Skip("Filtered extension".to_string()), 300, 300));
+        assert_eq!(plan.summary(), "Compress 1 with LZX, skip 1");
+
+        plan.record(&decision(Decision::Compress(WofAlgorithm::Xpress4K), 200, 150));
+        plan.record(&decision(Decision::Compress(WofAlgorithm::Lzx), 500, 100));
+        plan.record(&decision(Decision::Skip("Already optimal".to_string()), 50, 50));
+        plan.record(&decision(Decision::Skip("Already optimal".to_string()), 60, 60));
+
+        assert_eq!(plan.current, 2110);
+        assert_eq!(plan.projected(), 1060);
+        assert_eq!(plan.savings(), 1050);
+        assert_eq!(plan.files_skipped(), 3);
+        assert_eq!(plan.skip_reasons()[0], ("Already optimal".to_string(), 2, 110));
+        assert_eq!(plan.summary(), "Compress 3 (2 LZX, 1 XPRESS4K), skip 3");
+        assert_eq!(plan.skipped[0].0, "Filtered extension");
+    }
+}
@@ -2,7 +2,7 @@
 use crate::types::*;
 use std::{mem::{size_of as sz, zeroed}, ptr::{null, null_mut}};
 
-#[link(name = "kernel32")] unsafe extern "system" { fn Sleep(ms: u32); }
+#[cfg_attr(windows, link(name = "kernel32"))] unsafe extern "system" { fn Sleep(ms: u32); }
 
 unsafe fn priv_ok(n: LPCWSTR) -> bool {
     let (mut t, mut l): (HANDLE, LUID) = (null_mut(), zeroed());
@@ -0,0 +1,101 @@
+/* --- src/engine/entropy.rs --- */
+//! Byte-entropy probe for skipping incompressible files.
+//!
+//! A few blocks spread across the file are read and their Shannon entropy (bits per byte,
+//! 0.0..=8.0) averaged. Encrypted, compressed or encoded media sits just below 8.0, so a
+//! threshold around 7.9 skips such files without asking the WOF driver to try first.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+/// Bytes read per probe block.
+pub const PROBE_BLOCK: usize = 16 * 1024;
+/// Blocks read per file (head, two body points, tail).
+pub const PROBE_BLOCKS: u64 = 4;
+
+/// Shannon entropy of `data` in bits per byte.
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() { return 0.0; }
+
+    let mut hist = [0u32; 256];
+    for &b in data {
+        hist[b as usize] += 1;
+    }
+
+    let n = data.len() as f64;
+    hist.iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / n;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Mean entropy of up to `PROBE_BLOCKS` blocks of an open file of `size` bytes.
+pub fn probe_file(f: &mut File, size: u64) -> Option<f64> {
+    if size == 0 { return None; }
+
+    // Small files get fewer blocks so they do not overlap
+    let count = size.div_ceil(PROBE_BLOCK as u64).min(PROBE_BLOCKS);
+    let last = size.saturating_sub(PROBE_BLOCK as u64);
+    let mut buf = vec![0u8; PROBE_BLOCK];
+    let (mut sum, mut blocks) = (0.0, 0u32);
+
+    for i in 0..count {
+        let pos = if count > 1 { last * i / (count - 1) } else { 0 };
+        if f.seek(SeekFrom::Start(pos)).is_err() { break; }
+        let n = match f.read(&mut buf) { Ok(n) if n > 0 => n, _ => break };
+        sum += shannon_entropy(&buf[..n]);
+        blocks += 1;
+    }
+
+    if blocks == 0 { None } else { Some(sum / blocks as f64) }
+}
+
+/// Probes the file at `path`; unreadable or empty files are `None`.
+pub fn probe_path(path: &str) -> Option<f64> {
+    let mut f = File::open(path).ok()?;
+    let size = f.metadata().ok()?.len();
+    probe_file(&mut f, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shannon_entropy() {
+        assert_eq!(shannon_entropy(b""), 0.0);
+        assert_eq!(shannon_entropy(&[7u8; 1000]), 0.0);
+        assert!((shannon_entropy(b"abab") - 1.0).abs() < 1e-9);
+
+        let all: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        assert!((shannon_entropy(&all) - 8.0).abs() < 1e-9);
+
+        let text = b"The quick brown fox jumps over the lazy dog. ".repeat(100);
+        assert!(shannon_entropy(&text) < 5.0);
+    }
+
+    #[test]
+    fn test_probe_path() {
+        let root = std::env::temp_dir().join(["compactrs_entropy_", &std::process::id().to_string()].concat());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+
+        let mut seed = 99u32;
+        let noise: Vec<u8> = (0..100_000).map(|_| { seed = seed.wrapping_mul(1664525).wrapping_add(1013904223); (seed >> 24) as u8 }).collect();
+        let noise_path = root.join("noise.bin");
+        std::fs::write(&noise_path, &noise).unwrap();
+        let text_path = root.join("small.txt");
+        std::fs::write(&text_path, b"key = value\r\n".repeat(40)).unwrap();
+        let empty_path = root.join("empty");
+        std::fs::write(&empty_path, b"").unwrap();
+
+        assert!(probe_path(noise_path.to_str().unwrap()).unwrap() > 7.9);
+        assert!(probe_path(text_path.to_str().unwrap()).unwrap() < 4.0);
+        assert_eq!(probe_path(empty_path.to_str().unwrap()), None);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
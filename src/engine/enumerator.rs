@@ -0,0 +1,203 @@
+/* --- src/engine/enumerator.rs --- */
+//! Directory enumeration abstraction used by the scanner.
+//!
+//! The walkers in `engine::scanner` only need "list the children of this directory" plus a
+//! way to build child paths. `DirEnumerator` provides both so traversal, hidden-file
+//! filtering, reparse-point skipping and stop/pause handling are shared between hosts.
+//!
+//! - `Win32Enumerator`: `FindFirstFileExW` with large fetch (native path).
+//! - `StdEnumerator`: `std::fs::read_dir`; attributes are synthesized off Windows.
+//!
+//! Names and directory paths stay `OsString`/`PathBuf` while walking: Windows allows names
+//! with unpaired surrogates, which a `String` would rewrite into a path that does not exist.
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::types::*;
+use crate::utils::PathBuffer;
+
+/// A single child of an enumerated directory (never `.` or `..`).
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    /// File name only, without the parent path, as the OS returned it.
+    pub name: OsString,
+    /// Logical size in bytes (0 for directories).
+    pub size: u64,
+    /// Win32 `FILE_ATTRIBUTE_*` flags.
+    pub attributes: u32,
+    /// Last write time, seconds since the Unix epoch (0 if unknown).
+    pub modified: u64,
+}
+
+impl DirEntry {
+    pub fn is_dir(&self) -> bool {
+        (self.attributes & FILE_ATTRIBUTE_DIRECTORY) != 0
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        (self.attributes & FILE_ATTRIBUTE_HIDDEN) != 0
+    }
+
+    pub fn is_system(&self) -> bool {
+        (self.attributes & FILE_ATTRIBUTE_SYSTEM) != 0
+    }
+
+    pub fn is_reparse(&self) -> bool {
+        (self.attributes & FILE_ATTRIBUTE_REPARSE_POINT) != 0
+    }
+
+    /// NTFS (LZNT1) compressed.
+    pub fn is_compressed(&self) -> bool {
+        (self.attributes & FILE_ATTRIBUTE_COMPRESSED) != 0
+    }
+}
+
+/// Lists directory children for the scanner.
+pub trait DirEnumerator: Send + Sync {
+    /// Calls `visit` for every child of `dir`. Returning `false` from `visit` stops the listing.
+    /// Directories that cannot be opened are silently treated as empty.
+    fn for_each_entry(&self, dir: &Path, visit: &mut dyn FnMut(&DirEntry) -> bool);
+
+    /// Builds the full path of `name` inside `dir`.
+    fn join(&self, dir: &Path, name: &OsStr) -> PathBuf {
+        dir.join(name)
+    }
+}
+
+/// Enumerator used by the live pipeline on this host.
+pub fn default_enumerator() -> Arc<dyn DirEnumerator> {
+    if cfg!(windows) {
+        Arc::new(Win32Enumerator)
+    } else {
+        Arc::new(StdEnumerator)
+    }
+}
+
+fn is_dot_or_dot_dot(name: &[u16]) -> bool {
+    matches!(name, [46] | [46, 46])
+}
+
+#[cfg(windows)]
+fn os_from_wide(wide: &[u16]) -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+    OsString::from_wide(wide)
+}
+
+/// Only reached on Windows; other hosts have no UTF-16 names to keep.
+#[cfg(not(windows))]
+fn os_from_wide(wide: &[u16]) -> OsString {
+    OsString::from(String::from_utf16_lossy(wide))
+}
+
+/// FILETIME (100 ns ticks since 1601) as seconds since the Unix epoch, 0 before it.
+fn unix_seconds(ft: &FILETIME) -> u64 {
+    let ticks = ((ft.dwHighDateTime as u64) << 32) | ft.dwLowDateTime as u64;
+    (ticks / 10_000_000).saturating_sub(11_644_473_600)
+}
+
+// ===== WIN32 =====
+
+/// `FindFirstFileExW` / `FindNextFileW` enumeration.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Win32Enumerator;
+
+impl DirEnumerator for Win32Enumerator {
+    fn for_each_entry(&self, dir: &Path, visit: &mut dyn FnMut(&DirEntry) -> bool) {
+        let mut buffer = PathBuffer::from_os(dir.as_os_str());
+        buffer.push("*");
+
+        let mut find_data: WIN32_FIND_DATAW = unsafe { std::mem::zeroed() };
+
+        unsafe {
+            let handle = FindFirstFileExW(
+                buffer.as_ptr(),
+                FindExInfoBasic,
+                &mut find_data as *mut _ as *mut _,
+                FindExSearchNameMatch,
+                std::ptr::null_mut(),
+                FIND_FIRST_EX_LARGE_FETCH,
+            );
+
+            if handle == INVALID_HANDLE_VALUE {
+                return;
+            }
+
+            loop {
+                let filename_len = find_data.cFileName.iter().position(|&c| c == 0).unwrap_or(find_data.cFileName.len());
+                let name = &find_data.cFileName[..filename_len];
+
+                if !is_dot_or_dot_dot(name) {
+                    let entry = DirEntry {
+                        name: os_from_wide(name),
+                        size: ((find_data.nFileSizeHigh as u64) << 32) | (find_data.nFileSizeLow as u64),
+                        attributes: find_data.dwFileAttributes,
+                        modified: unix_seconds(&find_data.ftLastWriteTime),
+                    };
+                    if !visit(&entry) {
+                        break;
+                    }
+                }
+
+                if FindNextFileW(handle, &mut find_data) == 0 {
+                    break;
+                }
+            }
+            FindClose(handle);
+        }
+    }
+}
+
+// ===== STD::FS =====
+
+/// `std::fs::read_dir` enumeration. Entries are not followed through symlinks, which are
+/// reported as reparse points.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdEnumerator;
+
+impl StdEnumerator {
+    #[cfg(windows)]
+    fn attributes(_name: &OsStr, meta: &std::fs::Metadata) -> u32 {
+        use std::os::windows::fs::MetadataExt;
+        meta.file_attributes()
+    }
+
+    /// Maps POSIX metadata onto the Win32 flags the scanner understands.
+    /// Dot-files are treated as hidden, symlinks as reparse points.
+    #[cfg(not(windows))]
+    fn attributes(name: &OsStr, meta: &std::fs::Metadata) -> u32 {
+        let ft = meta.file_type();
+        let mut attrs = 0;
+        if ft.is_dir() { attrs |= FILE_ATTRIBUTE_DIRECTORY; }
+        if ft.is_symlink() { attrs |= FILE_ATTRIBUTE_REPARSE_POINT; }
+        if name.as_encoded_bytes().starts_with(b".") { attrs |= FILE_ATTRIBUTE_HIDDEN; }
+        if meta.permissions().readonly() { attrs |= FILE_ATTRIBUTE_READONLY; }
+        if attrs == 0 { attrs = FILE_ATTRIBUTE_NORMAL; }
+        attrs
+    }
+}
+
+impl DirEnumerator for StdEnumerator {
+    fn for_each_entry(&self, dir: &Path, visit: &mut dyn FnMut(&DirEntry) -> bool) {
+        let Ok(read_dir) = std::fs::read_dir(dir) else { return; };
+
+        for item in read_dir.flatten() {
+            let Ok(meta) = std::fs::symlink_metadata(item.path()) else { continue; };
+            let name = item.file_name();
+            let attributes = Self::attributes(&name, &meta);
+
+            let entry = DirEntry {
+                size: if meta.is_dir() { 0 } else { meta.len() },
+                attributes,
+                modified: meta.modified().ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map_or(0, |d| d.as_secs()),
+                name,
+            };
+            if !visit(&entry) {
+                break;
+            }
+        }
+    }
+}
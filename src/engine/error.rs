@@ -0,0 +1,196 @@
+/* --- src/engine/error.rs --- */
+//! Why a file could not be processed.
+//!
+//! The Win32 layer (`engine::wof`) reports raw error codes. `CompressionBackend` maps them
+//! to a `CompressError` so the pipeline, the log, the status column and reports can say
+//! "Locked by another process" instead of "32", and tell failures worth another attempt
+//! (a lock, a full disk) from ones that will fail the same way again.
+
+use std::fmt;
+
+use crate::types::ERROR_ACCESS_DENIED;
+
+/// ERROR_INVALID_FUNCTION, returned by file systems without WOF support
+pub const ERROR_INVALID_FUNCTION: u32 = 1;
+/// ERROR_FILE_NOT_FOUND
+pub const ERROR_FILE_NOT_FOUND: u32 = 2;
+/// ERROR_PATH_NOT_FOUND
+pub const ERROR_PATH_NOT_FOUND: u32 = 3;
+/// ERROR_WRITE_PROTECT
+pub const ERROR_WRITE_PROTECT: u32 = 19;
+/// ERROR_NOT_READY
+pub const ERROR_NOT_READY: u32 = 21;
+/// ERROR_SHARING_VIOLATION
+pub const ERROR_SHARING_VIOLATION: u32 = 32;
+/// ERROR_LOCK_VIOLATION, a byte range locked by another process
+pub const ERROR_LOCK_VIOLATION: u32 = 33;
+/// ERROR_HANDLE_DISK_FULL
+pub const ERROR_HANDLE_DISK_FULL: u32 = 39;
+/// ERROR_NOT_SUPPORTED
+pub const ERROR_NOT_SUPPORTED: u32 = 50;
+/// ERROR_DISK_FULL
+pub const ERROR_DISK_FULL: u32 = 112;
+/// ERROR_FILENAME_EXCED_RANGE
+pub const ERROR_FILENAME_EXCED_RANGE: u32 = 206;
+/// ERROR_COMPRESSION_NOT_BENEFICIAL, returned by FSCTL_SET_EXTERNAL_BACKING
+pub const ERROR_COMPRESSION_NOT_BENEFICIAL: u32 = 344;
+/// ERROR_USER_MAPPED_FILE, a running executable or mapped DLL
+pub const ERROR_USER_MAPPED_FILE: u32 = 1224;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressError {
+    /// Open or byte-range locked by another process
+    SharingViolation,
+    /// Mapped into memory by a running program (its executable or a loaded DLL). Ending
+    /// the program is not offered: it is usually one the user is running on purpose.
+    MappedFile,
+    AccessDenied,
+    NotFound,
+    PathTooLong,
+    DiskFull,
+    WriteProtected,
+    DeviceNotReady,
+    /// The file system does not support WOF compression
+    NotSupported,
+    NotBeneficial,
+    /// Content could not be read to the end to verify it
+    Unreadable,
+    /// Read back differently after compressing; decompressed again
+    VerifyFailed,
+    /// Read back differently, and decompressing it again failed too
+    VerifyFailedStillCompressed,
+    /// Any other Win32 error code
+    Os(u32),
+}
+
+impl CompressError {
+    pub fn from_os(code: u32) -> Self {
+        match code {
+            ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION => CompressError::SharingViolation,
+            ERROR_USER_MAPPED_FILE => CompressError::MappedFile,
+            ERROR_ACCESS_DENIED => CompressError::AccessDenied,
+            ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND => CompressError::NotFound,
+            ERROR_FILENAME_EXCED_RANGE => CompressError::PathTooLong,
+            ERROR_DISK_FULL | ERROR_HANDLE_DISK_FULL => CompressError::DiskFull,
+            ERROR_WRITE_PROTECT => CompressError::WriteProtected,
+            ERROR_NOT_READY => CompressError::DeviceNotReady,
+            ERROR_INVALID_FUNCTION | ERROR_NOT_SUPPORTED => CompressError::NotSupported,
+            ERROR_COMPRESSION_NOT_BENEFICIAL => CompressError::NotBeneficial,
+            code => CompressError::Os(code),
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            CompressError::SharingViolation => "Locked by another process",
+            CompressError::MappedFile => "In use by a running program",
+            CompressError::AccessDenied => "Access denied",
+            CompressError::NotFound => "File not found",
+            CompressError::PathTooLong => "Path too long",
+            CompressError::DiskFull => "Disk full",
+            CompressError::WriteProtected => "Drive is write-protected",
+            CompressError::DeviceNotReady => "Drive not ready",
+            CompressError::NotSupported => "Not supported by the file system",
+            CompressError::NotBeneficial => "Not beneficial",
+            CompressError::Unreadable => "Unreadable",
+            CompressError::VerifyFailed => "Verify failed",
+            CompressError::VerifyFailedStillCompressed => "Verify failed, still compressed",
+            CompressError::Os(_) => "Windows error",
+        }
+    }
+
+    /// Whether the same operation may succeed later without changing anything else.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, CompressError::SharingViolation | CompressError::MappedFile | CompressError::DiskFull | CompressError::DeviceNotReady)
+    }
+}
+
+impl fmt::Display for CompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressError::Os(code) => write!(f, "{} {}", self.message(), code),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+/// Failures of one batch row, counted per error.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FailureSummary {
+    pub counts: Vec<(CompressError, u64)>,
+}
+
+impl FailureSummary {
+    pub fn add(&mut self, error: CompressError) {
+        match self.counts.iter_mut().find(|(e, _)| *e == error) {
+            Some((_, n)) => *n += 1,
+            None => self.counts.push((error, 1)),
+        }
+    }
+
+    pub fn merge(&mut self, other: &FailureSummary) {
+        for &(error, n) in &other.counts {
+            match self.counts.iter_mut().find(|(e, _)| *e == error) {
+                Some((_, total)) => *total += n,
+                None => self.counts.push((error, n)),
+            }
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|(_, n)| n).sum()
+    }
+
+    pub fn retryable(&self) -> u64 {
+        self.counts.iter().filter(|(e, _)| e.is_retryable()).map(|(_, n)| n).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// "3 failed: Locked by another process", or "5 failed, mostly Access denied" when the
+    /// errors differ.
+    pub fn summary(&self) -> String {
+        let Some((error, _)) = self.counts.iter().max_by_key(|(_, n)| *n) else { return String::new() };
+        let sep = if self.counts.len() == 1 { " failed: " } else { " failed, mostly " };
+        let mut text = [&self.total().to_string(), sep, &error.to_string()].concat();
+        let retryable = self.retryable();
+        if retryable > 0 {
+            text = [&text, " (", &retryable.to_string(), " may succeed if retried)"].concat();
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_and_summary() {
+        assert_eq!(CompressError::from_os(32), CompressError::SharingViolation);
+        assert_eq!(CompressError::from_os(33), CompressError::SharingViolation);
+        // Mapped files are not offered to end the process holding them
+        assert_eq!(CompressError::from_os(1224), CompressError::MappedFile);
+        assert!(CompressError::MappedFile.is_retryable());
+        assert_eq!(CompressError::from_os(112), CompressError::DiskFull);
+        assert_eq!(CompressError::from_os(206), CompressError::PathTooLong);
+        assert_eq!(CompressError::from_os(1), CompressError::NotSupported);
+        assert_eq!(CompressError::from_os(1117).to_string(), "Windows error 1117");
+        assert!(CompressError::from_os(32).is_retryable());
+        assert!(!CompressError::from_os(5).is_retryable());
+
+        let mut s = FailureSummary::default();
+        assert_eq!(s.summary(), "");
+        s.add(CompressError::SharingViolation);
+        s.add(CompressError::SharingViolation);
+        assert_eq!(s.summary(), "2 failed: Locked by another process (2 may succeed if retried)");
+
+        let mut other = FailureSummary::default();
+        for _ in 0..3 { other.add(CompressError::AccessDenied); }
+        s.merge(&other);
+        assert_eq!((s.total(), s.retryable()), (5, 2));
+        assert_eq!(s.summary(), "5 failed, mostly Access denied (2 may succeed if retried)");
+    }
+}
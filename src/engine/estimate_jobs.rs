@@ -0,0 +1,294 @@
+/* --- src/engine/estimate_jobs.rs --- */
+//! Background estimation jobs for the batch list.
+//!
+//! A small pool of threads runs `estimator::estimate_path_progressive` for `(id, algorithm)`
+//! jobs. The next job is always the one with the highest `Priority` (selected rows, then
+//! visible rows, then the rest), oldest first. Each job streams `EstimateEvent::Partial`
+//! projections while it walks, so a large folder shows a rough size within seconds that
+//! tightens as more bytes are sampled.
+//!
+//! Jobs end early when cancelled by id, when superseded by a job for another algorithm on
+//! the same row before they started, or when Stop is pressed after they were submitted.
+//! Stops are counted, so a job queued while an earlier Stop is still in effect ignores that
+//! one but not the next.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::engine::estimator::{self, AllocModel, EstimateMode, EstimatorParams};
+use crate::engine::wof::AlgoChoice;
+
+/// Minimum time between two partial reports of one job.
+const PARTIAL_INTERVAL: Duration = Duration::from_millis(250);
+/// Upper bound on estimation threads; sampling is CPU bound but shares the disk with scans.
+const MAX_THREADS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Background = 0,
+    Visible = 1,
+    Selected = 2,
+}
+
+/// Progress of a job, delivered from the pool threads.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EstimateEvent {
+    /// Running projection; `confidence` is the share of bytes sampled so far (0.0..1.0).
+    Partial { id: u32, algo: AlgoChoice, estimated: u64, confidence: f64 },
+    /// Final size with its 95% sampling margin.
+    Done { id: u32, algo: AlgoChoice, estimated: u64, margin: u64 },
+    /// Ended by a Stop before finishing.
+    Cancelled { id: u32, algo: AlgoChoice },
+}
+
+struct Job {
+    id: u32,
+    algo: AlgoChoice,
+    path: String,
+    priority: Priority,
+    seq: u64,
+    cancel: Arc<AtomicBool>,
+    /// Stop count at submission; any later Stop cancels this job.
+    stops: u64,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: Vec<Job>,
+    running: Vec<(u32, AlgoChoice, Arc<AtomicBool>)>,
+    selected: HashSet<u32>,
+    visible: HashSet<u32>,
+    next_seq: u64,
+    shutdown: bool,
+}
+
+impl Queue {
+    fn priority_of(&self, id: u32) -> Priority {
+        rank(&self.selected, &self.visible, id)
+    }
+
+    /// Removes the most urgent pending job.
+    fn take_next(&mut self) -> Option<Job> {
+        let best = self.pending.iter().enumerate()
+            .max_by(|(_, a), (_, b)| a.priority.cmp(&b.priority).then(b.seq.cmp(&a.seq)))
+            .map(|(i, _)| i)?;
+        Some(self.pending.swap_remove(best))
+    }
+}
+
+fn rank(selected: &HashSet<u32>, visible: &HashSet<u32>, id: u32) -> Priority {
+    if selected.contains(&id) {
+        Priority::Selected
+    } else if visible.contains(&id) {
+        Priority::Visible
+    } else {
+        Priority::Background
+    }
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+    stops: Box<dyn Fn() -> u64 + Send + Sync>,
+    sink: Box<dyn Fn(EstimateEvent) + Send + Sync>,
+}
+
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub struct EstimateScheduler {
+    shared: Arc<Shared>,
+}
+
+impl EstimateScheduler {
+    /// Starts a pool sized to the machine. `stops` reads how many times Stop was pressed;
+    /// `sink` receives every event and must not block.
+    pub fn new(stops: impl Fn() -> u64 + Send + Sync + 'static, sink: impl Fn(EstimateEvent) + Send + Sync + 'static) -> Self {
+        let threads = std::thread::available_parallelism().map_or(2, |n| n.get() / 2).clamp(1, MAX_THREADS);
+        Self::with_threads(threads, stops, sink)
+    }
+
+    pub fn with_threads(threads: usize, stops: impl Fn() -> u64 + Send + Sync + 'static, sink: impl Fn(EstimateEvent) + Send + Sync + 'static) -> Self {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue::default()),
+            ready: Condvar::new(),
+            stops: Box::new(stops),
+            sink: Box::new(sink),
+        });
+        for _ in 0..threads.max(1) {
+            let shared = shared.clone();
+            std::thread::spawn(move || worker_loop(&shared));
+        }
+        Self { shared }
+    }
+
+    /// Queues an estimate of `path` with `algo` for row `id`. A job already queued or running
+    /// for the same row and algorithm is kept; queued jobs of the row for other algorithms
+    /// are dropped, their result would no longer be shown.
+    pub fn submit(&self, id: u32, path: String, algo: AlgoChoice) {
+        let mut q = self.shared.lock();
+        let active = |i: u32, a: AlgoChoice| i == id && a == algo;
+        if q.pending.iter().any(|j| active(j.id, j.algo)) || q.running.iter().any(|r| active(r.0, r.1) && !r.2.load(Ordering::Relaxed)) {
+            return;
+        }
+        q.pending.retain(|j| j.id != id);
+
+        let job = Job {
+            id,
+            algo,
+            path,
+            priority: q.priority_of(id),
+            seq: q.next_seq,
+            cancel: Arc::new(AtomicBool::new(false)),
+            stops: (self.shared.stops)(),
+        };
+        q.next_seq += 1;
+        q.pending.push(job);
+        drop(q);
+        self.shared.ready.notify_one();
+    }
+
+    /// Rows currently selected and on screen; queued jobs are re-ranked accordingly.
+    pub fn set_focus(&self, selected: &[u32], visible: &[u32]) {
+        let mut guard = self.shared.lock();
+        let q = &mut *guard;
+        q.selected = selected.iter().copied().collect();
+        q.visible = visible.iter().copied().collect();
+        for job in &mut q.pending {
+            job.priority = rank(&q.selected, &q.visible, job.id);
+        }
+    }
+
+    /// Drops every job of row `id` without reporting it (the row is gone).
+    pub fn cancel(&self, id: u32) {
+        let mut q = self.shared.lock();
+        q.pending.retain(|j| j.id != id);
+        q.running.iter().filter(|r| r.0 == id).for_each(|r| r.2.store(true, Ordering::Relaxed));
+    }
+
+    /// Drops all jobs without reporting them.
+    pub fn cancel_all(&self) {
+        let mut q = self.shared.lock();
+        q.pending.clear();
+        q.running.iter().for_each(|r| r.2.store(true, Ordering::Relaxed));
+    }
+}
+
+impl Drop for EstimateScheduler {
+    fn drop(&mut self) {
+        self.cancel_all();
+        self.shared.lock().shutdown = true;
+        self.shared.ready.notify_all();
+    }
+}
+
+fn worker_loop(shared: &Shared) {
+    loop {
+        let job = {
+            let mut q = shared.lock();
+            loop {
+                if q.shutdown { return; }
+                if let Some(job) = q.take_next() {
+                    q.running.push((job.id, job.algo, job.cancel.clone()));
+                    break job;
+                }
+                q = shared.ready.wait(q).unwrap_or_else(|e| e.into_inner());
+            }
+        };
+
+        run_job(shared, &job);
+
+        let mut q = shared.lock();
+        if let Some(i) = q.running.iter().position(|r| Arc::ptr_eq(&r.2, &job.cancel)) {
+            q.running.swap_remove(i);
+        }
+    }
+}
+
+fn run_job(shared: &Shared, job: &Job) {
+    let (id, algo) = (job.id, job.algo);
+    let stopped = || (shared.stops)() != job.stops;
+    let cancelled = || job.cancel.load(Ordering::Relaxed) || stopped();
+
+    let mut last = Instant::now();
+    let mut on_progress = |p: estimator::Progress| {
+        if p.done_bytes < p.total_bytes && last.elapsed() >= PARTIAL_INTERVAL {
+            last = Instant::now();
+            (shared.sink)(EstimateEvent::Partial { id, algo, estimated: p.projected(), confidence: p.confidence() });
+        }
+    };
+
+    let model = AllocModel::for_path(&job.path);
+    let result = estimator::estimate_path_progressive(&job.path, algo, EstimateMode::Accurate, model, EstimatorParams::default(), &cancelled, &mut on_progress);
+    match result {
+        Some(e) => (shared.sink)(EstimateEvent::Done { id, algo, estimated: e.size, margin: e.margin }),
+        // Rows that were removed are not reported
+        None if !job.cancel.load(Ordering::Relaxed) && stopped() => (shared.sink)(EstimateEvent::Cancelled { id, algo }),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::wof::WofAlgorithm;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_priority_order() {
+        let mut q = Queue::default();
+        q.visible.insert(2);
+        q.selected.insert(3);
+        for (seq, id) in [1u32, 2, 3, 4].into_iter().enumerate() {
+            let priority = q.priority_of(id);
+            q.pending.push(Job { id, algo: AlgoChoice::Fixed(WofAlgorithm::Lzx), path: String::new(), priority, seq: seq as u64, cancel: Arc::default(), stops: 0 });
+        }
+        let order: Vec<u32> = std::iter::from_fn(|| q.take_next()).map(|j| j.id).collect();
+        assert_eq!(order, vec![3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn test_jobs_report_and_stop() {
+        let dir = std::env::temp_dir().join(["compactrs_jobs_", &std::process::id().to_string()].concat());
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), b"hello estimate ".repeat(4000)).unwrap();
+        let path = dir.to_string_lossy().into_owned();
+
+        let stops = Arc::new(AtomicU64::new(0));
+        let (tx, rx) = channel();
+        let count = stops.clone();
+        let jobs = EstimateScheduler::with_threads(2, move || count.load(Ordering::Relaxed), move |e| { let _ = tx.send(e); });
+
+        jobs.submit(1, path.clone(), AlgoChoice::Fixed(WofAlgorithm::Xpress4K));
+        let expected = estimator::estimate_path_range(&path, WofAlgorithm::Xpress4K, EstimateMode::Accurate, AllocModel::for_path(&path), EstimatorParams::default());
+        let done = rx.iter().find(|e| !matches!(e, EstimateEvent::Partial { .. })).unwrap();
+        assert_eq!(done, EstimateEvent::Done { id: 1, algo: AlgoChoice::Fixed(WofAlgorithm::Xpress4K), estimated: expected.size, margin: expected.margin });
+
+        // A Stop cancels jobs submitted before it; jobs queued after it still run
+        stops.store(1, Ordering::Relaxed);
+        let job = |id, stops| Job { id, algo: AlgoChoice::Fixed(WofAlgorithm::Xpress8K), path: path.clone(), priority: Priority::Background, seq: 0, cancel: Arc::default(), stops };
+        run_job(&jobs.shared, &job(2, 0));
+        assert_eq!(rx.recv().unwrap(), EstimateEvent::Cancelled { id: 2, algo: AlgoChoice::Fixed(WofAlgorithm::Xpress8K) });
+        run_job(&jobs.shared, &job(3, 1));
+        assert!(matches!(rx.recv().unwrap(), EstimateEvent::Done { id: 3, .. }));
+        // ...until the next Stop
+        stops.store(2, Ordering::Relaxed);
+        run_job(&jobs.shared, &job(5, 1));
+        assert_eq!(rx.recv().unwrap(), EstimateEvent::Cancelled { id: 5, algo: AlgoChoice::Fixed(WofAlgorithm::Xpress8K) });
+
+        // Dropped rows are not reported
+        let removed = job(4, 2);
+        removed.cancel.store(true, Ordering::Relaxed);
+        run_job(&jobs.shared, &removed);
+        assert!(rx.try_recv().is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,13 +1,22 @@
 /* --- src/engine/estimator.rs --- */
-//! EXPERT ESTIMATOR: Volumetric Weighting & Continuous Calibration
+//! EXPERT ESTIMATOR: Adaptive Sampling & Continuous Calibration
 //!
-//! # Mathematical Correction: Volumetric Sampling
-//! Previous Flaw: `Mean(p1...p5)` assigns 20% weight to `p1` (Header) and `p5` (Footer).
-//! Impact: For a 2GB ISO, Header is <1MB. 20% weight is statistically wrong.
-//! Correction:
-//! - P1 (Header), P5 (Tail): Use small constant weight (5%).
-//! - P2, P3, P4 (Body): Represent 90% of file volume.
-//! - Formula: `0.05*P1 + 0.3*P2 + 0.3*P3 + 0.3*P4 + 0.05*P5`.
+//! # Adaptive Sampling
+//! Previous Flaw: 3 or 5 samples at fixed offsets (0, 1/4, 1/2, 3/4, end) whatever the
+//! file. Pack files mixing compressed and raw regions land on one kind or the other.
+//! Correction: stratified, variance-driven sampling per file.
+//! - Samples are drawn in rounds (`initial_samples`, then doubling the total). A round of
+//!   `m` samples splits the file into `m` equal strata and takes one block at a jittered
+//!   offset inside each. The jitter comes from a hash seeded by the file size, so reruns
+//!   hit the same blocks; unlike a fixed grid or low-discrepancy sequence it cannot alias
+//!   with regularly repeating regions.
+//! - After `initial_samples` (per tier), sampling continues while the 95% confidence
+//!   interval of the mean block ratio is wider than `target_half_width`, up to the
+//!   file's byte budget (`sample_budget`).
+//! - Files that fit in the budget are read whole, so their ratio is exact.
+//! - Each file's interval is carried through to the on-disk projection (`Estimate`);
+//!   files are independent, so a folder's margin is the root of the summed squares.
+//!   Ratios that were not sampled (tables, cache, learned) carry no sampling margin.
 //!
 //! # Mathematical Correction: Continuous LZX Curve
 //! Previous Flaw: Step-function at 0.75 ratio caused jumps (0.76x -> 0.95x).
@@ -15,130 +24,641 @@
 //! - if XPRESS < 0.4: LZX = XPRESS * 0.82
 //! - if XPRESS > 0.9: LZX = XPRESS * 0.98
 //! - Mid-range lerp to smooth transitions.
+//!
+//! # Algorithm-Accurate XPRESS
+//! Samples are compressed with the built-in XPRESS Huffman encoder (`engine::codec::xpress`)
+//! in the real WOF chunk size of the target algorithm (4K/8K/16K), so the smaller history
+//! window of XPRESS4K is measured rather than approximated.
+//!
+//! # Native LZX / LZNT1
+//! In `EstimateMode::Accurate` (default), LZX targets are sampled in 32K chunks with the
+//! built-in LZX encoder (`engine::codec::lzx`), and LZNT1 targets in whole 64K NTFS
+//! compression units (`engine::codec::lznt1`), including the "must save a cluster" rule.
+//! The curve above and the LZNT1 multiplier remain as `EstimateMode::Fast`, which maps the
+//! cheaper XPRESS16K ratio instead.
+//!
+//! # Content Sniffing
+//! Files whose header matches a compressed container (`engine::sniff`) get the same ratio
+//! as the static archive/media extensions. Sniffing needs the file open, so it runs only
+//! once the static table and the per-run extension cache have no ratio for the file.
+//!
+//! # On-Disk Allocation
+//! Projected sizes are what `GetCompressedFileSizeW` reports afterwards, not `sz * ratio`:
+//! - Files up to `resident_limit` live in their MFT record and are never compressed.
+//! - WOF streams carry a chunk table ahead of the data (`wof_stream::chunk_table_size`).
+//! - The result is rounded up to whole clusters of the target volume; if that saves no
+//!   cluster the driver leaves the file uncompressed, so its logical size is reported.
+//!
+//! # Learned Ratios
+//! Extensions with enough real results in `engine::knowledge` (fed by the worker after each
+//! batch) use the learned ratio instead of the static table, sniffing and sampling for files
+//! below `TIER_L`. The learned ratio is already on the target algorithm's basis.
+//!
+//! # Auto
+//! `AlgoChoice::Auto` samples each file with every codec (`engine::auto`), the same way
+//! the worker resolves it, and projects the chosen algorithm (or the logical size when the
+//! file would be skipped).
+//!
+//! # Progressive Estimates
+//! `estimate_path_progressive` reports a running projection after every file and can be
+//! cancelled between files; `engine::estimate_jobs` runs it on a thread pool for the UI.
+//!
+//! # Shared Sampling
+//! `estimate_path_shared` estimates several algorithms in one pass (`engine::compare`).
+//! Each sampled block is read once, in the largest codec unit, and compressed by every
+//! codec still in need of samples; the round loop runs until all their intervals are
+//! narrow enough. The time each codec spends is reported as a measure of its CPU cost.
 
-#![allow(non_snake_case)]
-use std::{fs::{self, File}, io::{Read, Seek, SeekFrom}, path::Path, ptr::null_mut, ffi::c_void, collections::HashMap};
-use crate::engine::wof::WofAlgorithm;
-
-#[link(name = "cabinet")]
-unsafe extern "system" {
-    fn CreateCompressor(alg: u32, alloc: *const c_void, h: *mut *mut c_void) -> i32;
-    fn Compress(h: *mut c_void, src: *const c_void, src_len: usize, dst: *mut c_void, dst_len: usize, out_len: *mut usize) -> i32;
-    fn CloseCompressor(h: *mut c_void) -> i32;
-}
+use std::{fs::{self, File}, io::{Read, Seek, SeekFrom}, path::{Path, PathBuf}, collections::HashMap, time::{Duration, Instant}};
+use crate::engine::codec::{lznt1, lzx, xpress};
+use crate::engine::wof::{self, AlgoChoice, WofAlgorithm};
+use crate::engine::{auto, knowledge, sniff, wof_stream};
 
 const BLK: usize = 16 * 1024;
 const CACHE_LIMIT: usize = 7;
 const TIER_L: u64 = 10 * 1024 * 1024; // 10MB
 const TIER_XL: u64 = 50 * 1024 * 1024; // 50MB
+const DEFAULT_CLUSTER: u64 = 4096;
+/// Largest file that typically stays resident in a 1 KiB MFT record.
+const RESIDENT_LIMIT: u64 = 700;
+
+/// Volume parameters used to turn compressed bytes into allocated bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocModel {
+    pub cluster_size: u64,
+    /// Files of at most this many bytes are MFT-resident and stay uncompressed.
+    pub resident_limit: u64,
+}
+
+impl Default for AllocModel {
+    fn default() -> Self {
+        Self { cluster_size: DEFAULT_CLUSTER, resident_limit: RESIDENT_LIMIT }
+    }
+}
+
+impl AllocModel {
+    /// Model for the volume holding `path`, falling back to 4 KiB clusters.
+    pub fn for_path(path: &str) -> Self {
+        let cluster_size = wof::get_cluster_size(path).unwrap_or(DEFAULT_CLUSTER);
+        Self { cluster_size, ..Self::default() }
+    }
+
+    fn round_up(&self, n: u64) -> u64 {
+        n.div_ceil(self.cluster_size) * self.cluster_size
+    }
+
+    /// Predicted `GetCompressedFileSizeW` result for a file of `sz` bytes whose chunks
+    /// compress to `data` bytes in total.
+    pub fn disk_size(&self, sz: u64, data: u64, algo: WofAlgorithm) -> u64 {
+        if sz <= self.resident_limit { return sz; }
+
+        let table = if algo == WofAlgorithm::Lznt1 { 0 } else { wof_stream::chunk_table_size(sz, algo) };
+        let allocated = self.round_up(data.min(sz) + table);
+        if allocated >= self.round_up(sz) { sz } else { allocated }
+    }
+}
+
+/// Accuracy/speed trade-off for LZX and LZNT1 estimates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EstimateMode {
+    /// Compress samples with the target algorithm's own encoder.
+    #[default]
+    Accurate,
+    /// Map an XPRESS16K sample through the LZX curve / LZNT1 multiplier.
+    Fast,
+}
+
+/// Size class of a file; each tier starts from a different number of samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Tier {
+    /// Below `tier_l`: static tables, the extension cache and learned ratios apply first.
+    Small,
+    /// `tier_l..=tier_xl`: always sampled.
+    Large,
+    /// Above `tier_xl`: always sampled, from more initial points.
+    Giant,
+}
+
+/// XPRESS ratio -> LZX ratio mapping used by fast mode and the static tables.
+/// The multiplier is `low_mult` below `low`, `high_mult` above `high`, linear in between.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LzxCurve {
+    pub low: f64,
+    pub high: f64,
+    pub low_mult: f64,
+    pub high_mult: f64,
+}
+
+impl Default for LzxCurve {
+    fn default() -> Self {
+        Self { low: 0.4, high: 0.9, low_mult: 0.82, high_mult: 0.98 }
+    }
+}
+
+impl LzxCurve {
+    pub fn apply(&self, ratio: f64) -> f64 {
+        if ratio < self.low {
+            ratio * self.low_mult
+        } else if ratio > self.high {
+            ratio * self.high_mult
+        } else {
+            let t = (ratio - self.low) / (self.high - self.low);
+            ratio * (self.low_mult + t * (self.high_mult - self.low_mult))
+        }
+    }
+}
+
+/// Tunable constants of the estimator. `Default` is what the app ships with; the
+/// `calibrate` tool measures and refits them against a ground-truth corpus.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EstimatorParams {
+    pub tier_l: u64,
+    pub tier_xl: u64,
+    /// Stratified samples taken before the variance is looked at, indexed by `Tier`.
+    pub initial_samples: [usize; 3],
+    /// Sampling stops once the 95% interval of a file's ratio is within +/- this.
+    pub target_half_width: f64,
+    /// At most `1 / budget_divisor` of a file is sampled (beyond the initial samples)...
+    pub budget_divisor: u64,
+    /// ...and never more than this many bytes.
+    pub max_sample_bytes: u64,
+    pub lzx_curve: LzxCurve,
+    /// Prefer ratios learned from real results (`engine::knowledge`).
+    pub use_learned: bool,
+}
+
+impl Default for EstimatorParams {
+    fn default() -> Self {
+        Self {
+            tier_l: TIER_L,
+            tier_xl: TIER_XL,
+            initial_samples: [3, 8, 16],
+            target_half_width: 0.02,
+            budget_divisor: 32,
+            max_sample_bytes: 8 * 1024 * 1024,
+            lzx_curve: LzxCurve::default(),
+            use_learned: true,
+        }
+    }
+}
+
+impl EstimatorParams {
+    pub fn tier(&self, sz: u64) -> Tier {
+        if sz < self.tier_l { Tier::Small } else if sz > self.tier_xl { Tier::Giant } else { Tier::Large }
+    }
+
+    pub fn initial_samples(&self, sz: u64) -> usize {
+        self.initial_samples[self.tier(sz) as usize]
+    }
+
+    /// Bytes that may be sampled from a file of `sz` bytes.
+    pub fn sample_budget(&self, sz: u64) -> u64 {
+        (sz / self.budget_divisor.max(1)).min(self.max_sample_bytes)
+    }
+
+    /// XPRESS ratio -> `algo` ratio for fast mode and static tables.
+    fn curve_ratio(&self, ratio: f64, algo: WofAlgorithm) -> f64 {
+        match algo {
+            // Measured directly at the algorithm's chunk size
+            WofAlgorithm::Xpress4K | WofAlgorithm::Xpress8K | WofAlgorithm::Xpress16K => ratio,
+            WofAlgorithm::Lznt1 => ratio * 1.05, // LZNT1 generally ~5% larger than XPRESS
+            WofAlgorithm::Lzx => self.lzx_curve.apply(ratio),
+        }
+    }
+}
+
+/// Ratio of one file with the half-width of its 95% confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampledRatio {
+    pub ratio: f64,
+    /// 0.0 when the ratio is exact or did not come from sampling.
+    pub half_width: f64,
+    pub samples: usize,
+}
+
+impl SampledRatio {
+    /// A ratio taken from a table, the cache or learned results.
+    fn fixed(ratio: f64) -> Self {
+        Self { ratio, half_width: 0.0, samples: 0 }
+    }
+}
+
+/// Projected on-disk size and the half-width of its 95% sampling interval.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Estimate {
+    pub size: u64,
+    pub margin: u64,
+}
+
+impl Estimate {
+    pub fn low(&self) -> u64 {
+        self.size.saturating_sub(self.margin)
+    }
+
+    pub fn high(&self) -> u64 {
+        self.size + self.margin
+    }
+}
+
+/// Two-sided 95% normal quantile.
+const Z_95: f64 = 1.96;
+
+/// Running mean and variance of block ratios (Welford).
+#[derive(Default)]
+struct RunningStats {
+    n: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    fn push(&mut self, x: f64) {
+        self.n += 1;
+        let d = x - self.mean;
+        self.mean += d / self.n as f64;
+        self.m2 += d * (x - self.mean);
+    }
+
+    /// 95% half-width of the mean when `n` of `population` blocks were drawn.
+    fn half_width(&self, population: u64) -> f64 {
+        if self.n < 2 { return f64::INFINITY; }
+        let var = self.m2 / (self.n - 1) as f64;
+        let fpc = (1.0 - self.n as f64 / population as f64).max(0.0);
+        Z_95 * (var / self.n as f64 * fpc).sqrt()
+    }
+}
+
+/// SplitMix64 step: a well-mixed hash of `x`.
+fn mix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Jitter in `[0, 1)` for stratum `k` of sampling round `round`.
+fn jitter(seed: u64, round: u64, k: u64) -> f64 {
+    (mix64(seed ^ mix64(round << 32 | k)) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Encoder applied to each sample.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SampleCodec {
+    /// XPRESS Huffman in chunks of the given size.
+    Xpress(usize),
+    Lzx,
+    Lznt1,
+}
+
+impl SampleCodec {
+    /// Compressed/original ratio of `sample`.
+    fn ratio(self, sample: &[u8], cluster_size: u64) -> f64 {
+        if sample.is_empty() { return 1.0; }
+        let c_sz = match self {
+            SampleCodec::Xpress(chunk) => xpress::compressed_size_chunked(sample, chunk),
+            SampleCodec::Lzx => lzx::compressed_size_chunked(sample),
+            SampleCodec::Lznt1 => lznt1::compressed_size_units(sample, cluster_size as usize),
+        };
+        c_sz as f64 / sample.len() as f64
+    }
+}
+
+/// Reads up to `buf.len()` bytes at `pos`; empty if the read fails.
+fn read_block<'a>(f: &mut File, pos: u64, buf: &'a mut [u8]) -> &'a [u8] {
+    if f.seek(SeekFrom::Start(pos)).is_err() { return &[]; }
+    match f.read(buf) { Ok(n) => &buf[..n], _ => &[] }
+}
+
+/// Samples `f` in blocks of `buf.len()` bytes at stratified, seeded offsets until the
+/// interval of every codec's mean block ratio is narrow enough or the budget is spent.
+/// `measure` fills one ratio per codec for each block read. A file within the budget is
+/// read whole.
+fn sample_blocks(f: &mut File, sz: u64, params: &EstimatorParams, buf: &mut [u8], codecs: usize, measure: &mut dyn FnMut(&[u8], &mut [f64])) -> Vec<SampledRatio> {
+    let blk = buf.len() as u64;
+    let blocks = sz.div_ceil(blk).max(1);
+    let initial = params.initial_samples(sz);
+    let max = (params.sample_budget(sz) / blk).max(initial as u64);
+    let mut ratios = vec![1.0; codecs];
+    let mut read_at = |f: &mut File, pos: u64, ratios: &mut [f64]| {
+        let block = read_block(f, pos, buf);
+        if block.is_empty() { ratios.fill(1.0) } else { measure(block, ratios) }
+    };
+
+    if blocks <= max {
+        let mut compressed = vec![0.0; codecs];
+        for b in 0..blocks {
+            let len = blk.min(sz - b * blk);
+            read_at(f, b * blk, &mut ratios);
+            compressed.iter_mut().zip(&ratios).for_each(|(c, r)| *c += r * len as f64);
+        }
+        return compressed.iter().map(|c| SampledRatio { ratio: c / sz.max(1) as f64, half_width: 0.0, samples: blocks as usize }).collect();
+    }
+
+    let seed = mix64(sz);
+    let tail = sz.saturating_sub(blk);
+    let mut stats: Vec<RunningStats> = (0..codecs).map(|_| RunningStats::default()).collect();
+    let mut n = 0u64;
+    let mut round_len = initial as u64;
+    for round in 0.. {
+        let m = round_len.min(max - n);
+        for k in 0..m {
+            let u = (k as f64 + jitter(seed, round, k)) / m as f64;
+            let block = ((u * blocks as f64) as u64).min(blocks - 1);
+            read_at(f, (block * blk).min(tail), &mut ratios);
+            stats.iter_mut().zip(&ratios).for_each(|(s, &r)| s.push(r));
+        }
+        n += m;
+        if n >= max || stats.iter().all(|s| s.half_width(blocks) <= params.target_half_width) { break; }
+        round_len = n;
+    }
+    stats.iter().map(|s| SampledRatio { ratio: s.mean, half_width: s.half_width(blocks), samples: s.n }).collect()
+}
+
+/// Lowercase extension of `path`, empty if it has none.
+fn extension_of(path: &Path) -> String {
+    path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase()
+}
 
 struct Estimator {
-    h: *mut c_void,
+    algo: WofAlgorithm,
+    codec: SampleCodec,
+    model: AllocModel,
+    params: EstimatorParams,
     in_b: Vec<u8>,
-    out_b: Vec<u8>,
     cache: HashMap<String, (f64, usize)>,
+    /// Trusted ratios from `knowledge` for `algo`, snapshotted at construction.
+    learned: HashMap<String, f64>,
 }
 
 impl Estimator {
-    fn new() -> Option<Self> {
-        let mut h = null_mut();
-        if unsafe { CreateCompressor(4, null_mut(), &mut h) } == 0 { return None; }
-        Some(Self { 
-            h, 
-            in_b: vec![0; BLK], 
-            out_b: vec![0; BLK],
-            cache: HashMap::new()
-        })
-    }
-
-    fn sample_at(&mut self, f: &mut File, pos: u64) -> f64 {
-        if f.seek(SeekFrom::Start(pos)).is_err() { return 1.0; }
-        let len = match f.read(&mut self.in_b) { Ok(n) if n > 0 => n, _ => return 1.0 };
-        unsafe {
-            let mut c_sz = 0;
-            if Compress(self.h, self.in_b.as_ptr() as _, len, self.out_b.as_mut_ptr() as _, BLK, &mut c_sz) == 0 || c_sz >= len {
-                return 1.0;
-            }
-            c_sz as f64 / len as f64
+    fn new(algo: WofAlgorithm, mode: EstimateMode, model: AllocModel, params: EstimatorParams) -> Self {
+        let (codec, sample_len) = match (algo, mode) {
+            (WofAlgorithm::Lzx, EstimateMode::Accurate) => (SampleCodec::Lzx, lzx::CHUNK_SIZE),
+            (WofAlgorithm::Lznt1, EstimateMode::Accurate) => (SampleCodec::Lznt1, lznt1::UNIT_SIZE),
+            _ => (SampleCodec::Xpress(sample_chunk_size(algo)), BLK),
+        };
+        Self { 
+            algo,
+            codec,
+            model,
+            params,
+            in_b: vec![0; BLK.max(sample_len)], 
+            cache: HashMap::new(),
+            learned: if params.use_learned { knowledge::global().ratios_for(algo) } else { HashMap::new() },
         }
     }
 
-    fn est_file_ratio(&mut self, path: &Path, sz: u64) -> f64 {
-        // --- TIER 1: CACHED (Small/Medium Files) ---
-        if sz < TIER_L {
-            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
-            if !ext.is_empty() {
-                // Static
-                match ext.as_str() {
-                    "txt" | "xml" | "json" | "csv" | "log" | "md" | "c" | "cpp" | "h" | "rs" | "js" | "css" | "html" | "svg" | "xaml" => return 0.35,
-                    "zip" | "7z" | "rar" | "jpg" | "png" | "mp4" | "mkv" | "mp3" | "ogg" | "docx" | "xlsx" | "pptx" | "kbs" | "apk" | "msi" | "cab" | "pdf" | "sys" => return 1.0,
-                    _ => {}
-                }
-                // Learned Cache
-                if let Some(&(sum, count)) = self.cache.get(&ext) {
-                    if count >= CACHE_LIMIT { return sum / count as f64; }
-                }
-            }
-            
-            let mut f = match File::open(path) { Ok(f) => f, _ => return 1.0 };
-            
-            // Tier 1 Sampling: 3-Point Fast
-            let p1 = self.sample_at(&mut f, 0);
-            let p2 = self.sample_at(&mut f, sz / 2);
-            let p3 = self.sample_at(&mut f, sz.saturating_sub(BLK as u64));
-            
-            let ratio = (p1 + p2 + p3) / 3.0; // Mean is acceptable for small files
+    /// Sampled ratios are already on the target algorithm's basis.
+    fn is_native(&self) -> bool {
+        !matches!(self.codec, SampleCodec::Xpress(_))
+    }
 
-            if !ext.is_empty() {
-                let entry = self.cache.entry(ext).or_insert((0.0, 0));
-                entry.0 += ratio;
-                entry.1 += 1;
-            }
-            return ratio;
+    /// Static table ratios are on the XPRESS basis.
+    fn static_ratio(&self, xpress_ratio: f64) -> f64 {
+        if self.is_native() { self.params.curve_ratio(xpress_ratio, self.algo) } else { xpress_ratio }
+    }
+
+    /// Projected on-disk size for `sz` bytes at the sampled `ratio`.
+    fn project(&self, sz: u64, ratio: f64) -> u64 {
+        let data = if self.is_native() { (sz as f64 * ratio) as u64 } else { (sz as f64 * self.params.curve_ratio(ratio, self.algo)) as u64 };
+        self.model.disk_size(sz, data, self.algo)
+    }
+
+    /// Projected on-disk size and margin of a file of `sz` bytes at ratio `r`.
+    fn to_estimate(&self, sz: u64, r: SampledRatio) -> Estimate {
+        let size = self.project(sz, r.ratio);
+        if r.half_width == 0.0 { return Estimate { size, margin: 0 }; }
+
+        let high = self.project(sz, r.ratio + r.half_width);
+        let low = self.project(sz, (r.ratio - r.half_width).max(0.0));
+        Estimate { size, margin: (high - low) / 2 }
+    }
+
+    /// Projection from learned results, for files below `tier_l`.
+    fn learned_estimate(&self, ext: &str, sz: u64) -> Option<Estimate> {
+        if sz >= self.params.tier_l { return None; }
+        let &r = self.learned.get(ext)?;
+        Some(Estimate { size: self.model.disk_size(sz, (sz as f64 * r) as u64, self.algo), margin: 0 })
+    }
+
+    /// Projected on-disk size of one file, from learned results when available.
+    fn estimate_file(&mut self, path: &Path, sz: u64) -> Estimate {
+        if let Some(e) = self.learned_estimate(&extension_of(path), sz) { return e; }
+        let r = self.est_file_ratio(path, sz);
+        self.to_estimate(sz, r)
+    }
+
+    /// Samples `f` on this estimator's codec (see `sample_blocks`).
+    fn sample_adaptive(&mut self, f: &mut File, sz: u64) -> SampledRatio {
+        let (codec, cluster) = (self.codec, self.model.cluster_size);
+        sample_blocks(f, sz, &self.params, &mut self.in_b, 1, &mut |block, out| out[0] = codec.ratio(block, cluster))[0]
+    }
+
+    /// Ratio from the static extension table (small files only).
+    fn table_ratio(&self, ext: &str, sz: u64) -> Option<SampledRatio> {
+        if self.params.tier(sz) != Tier::Small { return None; }
+        match ext {
+            "txt" | "xml" | "json" | "csv" | "log" | "md" | "c" | "cpp" | "h" | "rs" | "js" | "css" | "html" | "svg" | "xaml" => Some(SampledRatio::fixed(self.static_ratio(0.35))),
+            "zip" | "7z" | "rar" | "jpg" | "png" | "mp4" | "mkv" | "mp3" | "ogg" | "docx" | "xlsx" | "pptx" | "kbs" | "apk" | "msi" | "cab" | "pdf" | "sys" => Some(SampledRatio::fixed(self.static_ratio(1.0))),
+            _ => None,
         }
+    }
 
-        // --- TIER 2 & 3: HEAVYWEIGHTS (Always Sampled) ---
-        let mut f = match File::open(path) { Ok(f) => f, _ => return 1.0 };
-        
-        if sz > TIER_XL {
-            // TIER 3: Giant Files uses VOLUMETRIC WEIGHTING
-            let p1 = self.sample_at(&mut f, 0);
-            let p2 = self.sample_at(&mut f, sz / 4);
-            let p3 = self.sample_at(&mut f, sz / 2);
-            let p4 = self.sample_at(&mut f, (sz * 3) / 4);
-            let p5 = self.sample_at(&mut f, sz.saturating_sub(BLK as u64));
-            
-            // Volumetric Mean: Body (p2,p3,p4) matters 90%. Edges (p1,p5) matter 10%.
-            (0.05 * p1) + (0.3 * p2) + (0.3 * p3) + (0.3 * p4) + (0.05 * p5)
-        } else {
-            // TIER 2: Large Files (10-50MB)
-            let p1 = self.sample_at(&mut f, 0);
-            let p2 = self.sample_at(&mut f, sz / 2);
-            let p3 = self.sample_at(&mut f, sz.saturating_sub(BLK as u64));
-            
-            // Weighted: Body (p2) matters 80%. Edges matter 20%.
-            (0.1 * p1) + (0.8 * p2) + (0.1 * p3)
+    /// Mean ratio of earlier small files with this extension, once there are enough.
+    fn cached_ratio(&self, ext: &str, sz: u64) -> Option<SampledRatio> {
+        if self.params.tier(sz) != Tier::Small { return None; }
+        match self.cache.get(ext) {
+            Some(&(sum, count)) if count >= CACHE_LIMIT => Some(SampledRatio::fixed(sum / count as f64)),
+            _ => None,
         }
     }
+
+    /// Adds a sampled small file to the extension cache.
+    fn remember(&mut self, ext: &str, sz: u64, ratio: f64) {
+        if self.params.tier(sz) != Tier::Small || ext.is_empty() { return; }
+        let entry = self.cache.entry(ext.to_string()).or_insert((0.0, 0));
+        entry.0 += ratio;
+        entry.1 += 1;
+    }
+
+    /// Tier 1 (small files): static table, extension cache, sniffing, then sampling.
+    /// Tiers 2 and 3 (heavyweights): sniffing, then always sampled.
+    fn est_file_ratio(&mut self, path: &Path, sz: u64) -> SampledRatio {
+        let ext = extension_of(path);
+        if let Some(r) = self.table_ratio(&ext, sz) { return r; }
+        if let Some(r) = self.cached_ratio(&ext, sz) { return r; }
+
+        // Renamed archives/media are incompressible whatever their extension
+        let mut f = match File::open(path) { Ok(f) => f, _ => return SampledRatio::fixed(1.0) };
+        if sniff::sniff_file(&mut f).is_some() { return SampledRatio::fixed(self.static_ratio(1.0)); }
+
+        let sampled = self.sample_adaptive(&mut f, sz);
+        self.remember(&ext, sz, sampled.ratio);
+        sampled
+    }
 }
 
-impl Drop for Estimator {
-    fn drop(&mut self) { unsafe { CloseCompressor(self.h); } }
+/// `Estimator::estimate_file` for several estimators at once. Blocks are read once into
+/// `buf` and compressed by every estimator that has to sample; codec time and bytes are
+/// added to `results`.
+fn estimate_file_shared(ests: &mut [Estimator], path: &Path, sz: u64, buf: &mut [u8], results: &mut [SharedEstimate]) -> Vec<Estimate> {
+    let ext = extension_of(path);
+    let mut known: Vec<Option<Estimate>> = ests.iter()
+        .map(|e| e.learned_estimate(&ext, sz)
+            .or_else(|| e.table_ratio(&ext, sz).or_else(|| e.cached_ratio(&ext, sz)).map(|r| e.to_estimate(sz, r))))
+        .collect();
+    if known.iter().all(Option::is_some) { return known.into_iter().flatten().collect(); }
+
+    let Ok(mut f) = File::open(path) else {
+        return known.into_iter().zip(ests.iter()).map(|(k, e)| k.unwrap_or_else(|| e.to_estimate(sz, SampledRatio::fixed(1.0)))).collect();
+    };
+    if sniff::sniff_file(&mut f).is_some() {
+        for (k, e) in known.iter_mut().zip(ests.iter()).filter(|(k, _)| k.is_none()) {
+            *k = Some(e.to_estimate(sz, SampledRatio::fixed(e.static_ratio(1.0))));
+        }
+    }
+
+    let todo: Vec<usize> = (0..ests.len()).filter(|&i| known[i].is_none()).collect();
+    if !todo.is_empty() {
+        let codecs: Vec<SampleCodec> = todo.iter().map(|&i| ests[i].codec).collect();
+        let (params, cluster) = (ests[0].params, ests[0].model.cluster_size);
+        let mut spent = vec![(Duration::ZERO, 0u64); todo.len()];
+        let sampled = sample_blocks(&mut f, sz, &params, buf, todo.len(), &mut |block, out| {
+            for ((codec, o), s) in codecs.iter().zip(out.iter_mut()).zip(spent.iter_mut()) {
+                let t = Instant::now();
+                *o = codec.ratio(block, cluster);
+                s.0 += t.elapsed();
+                s.1 += block.len() as u64;
+            }
+        });
+        for ((&i, r), (time, bytes)) in todo.iter().zip(sampled).zip(spent) {
+            ests[i].remember(&ext, sz, r.ratio);
+            known[i] = Some(ests[i].to_estimate(sz, r));
+            results[i].codec_time += time;
+            results[i].sampled_bytes += bytes;
+        }
+    }
+    known.into_iter().map(Option::unwrap_or_default).collect()
+}
+
+/// XPRESS chunk size used for sampling `algo`.
+fn sample_chunk_size(algo: WofAlgorithm) -> usize {
+    match algo {
+        WofAlgorithm::Xpress4K => 4 * 1024,
+        WofAlgorithm::Xpress8K => 8 * 1024,
+        WofAlgorithm::Xpress16K | WofAlgorithm::Lzx | WofAlgorithm::Lznt1 => 16 * 1024,
+    }
 }
 
 pub fn estimate_path(path: &str, algo: WofAlgorithm) -> u64 {
+    estimate_path_with_mode(path, algo, EstimateMode::Accurate)
+}
+
+pub fn estimate_path_with_mode(path: &str, algo: WofAlgorithm, mode: EstimateMode) -> u64 {
+    estimate_path_with_model(path, algo, mode, AllocModel::for_path(path))
+}
+
+/// Estimate for a volume described by `model` instead of the one holding `path`.
+pub fn estimate_path_with_model(path: &str, algo: WofAlgorithm, mode: EstimateMode, model: AllocModel) -> u64 {
+    estimate_path_with_params(path, algo, mode, model, EstimatorParams::default())
+}
+
+/// Estimate with explicit tuning constants (used by the `calibrate` tool).
+pub fn estimate_path_with_params(path: &str, algo: WofAlgorithm, mode: EstimateMode, model: AllocModel, params: EstimatorParams) -> u64 {
+    estimate_path_range(path, algo, mode, model, params).size
+}
+
+/// Estimate together with its 95% sampling margin.
+pub fn estimate_path_range(path: &str, algo: WofAlgorithm, mode: EstimateMode, model: AllocModel, params: EstimatorParams) -> Estimate {
+    estimate_path_progressive(path, AlgoChoice::Fixed(algo), mode, model, params, &|| false, &mut |_| {}).unwrap_or_default()
+}
+
+/// Running totals of an estimate in progress.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// Projected on-disk size of the files estimated so far.
+    pub estimated: u64,
+    /// Logical size of the files estimated so far.
+    pub done_bytes: u64,
+    /// Logical size of every file under the path.
+    pub total_bytes: u64,
+    /// Sum of the squared margins of the files estimated so far.
+    pub variance: f64,
+}
+
+impl Progress {
+    /// Projection for the whole path: the ratio seen so far applied to the bytes not yet
+    /// estimated. Exact once `done_bytes == total_bytes`.
+    pub fn projected(&self) -> u64 {
+        if self.done_bytes == 0 { return self.total_bytes; }
+        let rest = self.total_bytes.saturating_sub(self.done_bytes);
+        self.estimated + (rest as f64 * self.estimated as f64 / self.done_bytes as f64) as u64
+    }
+
+    /// Sampling margin of the files estimated so far.
+    pub fn margin(&self) -> u64 {
+        self.variance.sqrt() as u64
+    }
+
+    /// Share of the bytes backed by samples, from 0.0 to 1.0.
+    pub fn confidence(&self) -> f64 {
+        if self.total_bytes == 0 { 1.0 } else { self.done_bytes as f64 / self.total_bytes as f64 }
+    }
+}
+
+/// Like `estimate_path_with_params`, reporting `on_progress` after every file and giving up
+/// (`None`) as soon as `cancelled` returns true.
+///
+/// Files are listed first so the total is known. Files from `tier_l` upwards are estimated
+/// before the rest: they carry most of the bytes, so the projection settles early, and they
+/// never touch the extension cache, so the result is the same as estimating in walk order.
+pub fn estimate_path_progressive(
+    path: &str,
+    algo: AlgoChoice,
+    mode: EstimateMode,
+    model: AllocModel,
+    params: EstimatorParams,
+    cancelled: &dyn Fn() -> bool,
+    on_progress: &mut dyn FnMut(Progress),
+) -> Option<Estimate> {
     let p = Path::new(path);
-    let mut est = match Estimator::new() { Some(e) => e, None => return 0 };
+    let mut est = match algo {
+        AlgoChoice::Fixed(a) => Some(Estimator::new(a, mode, model, params)),
+        AlgoChoice::Auto => None,
+    };
+    let mut file_est = |path: &Path, sz: u64| match est.as_mut() {
+        Some(est) => est.estimate_file(path, sz),
+        None => Estimate { size: project_auto(&model, path, sz), margin: 0 },
+    };
     
     if p.is_file() {
         let sz = p.metadata().map(|m| m.len()).unwrap_or(0);
-        if sz == 0 { return 0; }
-        let raw = est.est_file_ratio(p, sz);
-        return apply_lzx_curve(sz, raw, algo);
+        if sz == 0 { return Some(Estimate::default()); }
+        let e = file_est(p, sz);
+        on_progress(Progress { estimated: e.size, done_bytes: sz, total_bytes: sz, variance: (e.margin as f64).powi(2) });
+        return Some(e);
     } 
     
-    let (mut est_sz, mut stack) = (0u64, vec![p.to_path_buf()]);
+    let files = list_files(p, cancelled)?;
+    let mut progress = Progress { total_bytes: files.iter().map(|f| f.1).sum(), ..Progress::default() };
+    let (large, small): (Vec<_>, Vec<_>) = files.into_iter().partition(|f| params.tier(f.1) != Tier::Small);
+    for (path, sz) in large.into_iter().chain(small) {
+        if cancelled() { return None; }
+        let e = file_est(&path, sz);
+        progress.estimated += e.size;
+        progress.variance += (e.margin as f64).powi(2);
+        progress.done_bytes += sz;
+        on_progress(progress);
+    }
+    Some(Estimate { size: progress.estimated, margin: progress.margin() })
+}
+
+/// Non-empty files under the directory `p` with their sizes, in a stable order. `None` if
+/// cancelled while walking.
+fn list_files(p: &Path, cancelled: &dyn Fn() -> bool) -> Option<Vec<(PathBuf, u64)>> {
+    let (mut files, mut stack) = (Vec::new(), vec![p.to_path_buf()]);
     while let Some(dir) = stack.pop() {
+        if cancelled() { return None; }
         if let Ok(entries) = fs::read_dir(&dir) {
             // CRITICAL FIX: Sort entries for deterministic cache population
             // fs::read_dir() order is non-deterministic, causing variance between runs
@@ -150,47 +670,168 @@ pub fn estimate_path(path: &str, algo: WofAlgorithm) -> u64 {
                 if path.is_dir() { stack.push(path); } 
                 else if let Ok(m) = path.metadata() {
                     let sz = m.len();
-                    if sz > 0 {
-                        let r = est.est_file_ratio(&path, sz);
-                        est_sz += apply_lzx_curve(sz, r, algo);
-                    }
+                    if sz > 0 { files.push((path, sz)); }
                 }
             }
         }
     }
-    est_sz
-}
-
-fn apply_lzx_curve(sz: u64, ratio: f64, algo: WofAlgorithm) -> u64 {
-    let adj = match algo {
-        WofAlgorithm::Xpress4K => ratio,
-        WofAlgorithm::Xpress8K => ratio * 0.99,
-        WofAlgorithm::Xpress16K => ratio * 0.98,
-        WofAlgorithm::Lznt1 => ratio * 1.05, // LZNT1 generally ~5% larger than XPRESS4K
-        WofAlgorithm::Lzx => {
-            // ═══════════════════════════════════════════════════════════════
-            // BASELINE LZX CURVE (Proven 92% Accuracy)
-            // ═══════════════════════════════════════════════════════════════
-            // 
-            // This curve was empirically validated to give 92% accuracy.
-            // Combined with sorted enumeration, results should be consistent.
-            //
-            // XPRESS Ratio → LZX Multiplier mapping:
-            // - ratio < 0.4: 0.82 (highly compressible)
-            // - ratio > 0.9: 0.98 (nearly incompressible)
-            // - mid-range: linear interpolation
-            // ═══════════════════════════════════════════════════════════════
-            
-            if ratio < 0.4 { 
-                ratio * 0.82
-            } else if ratio > 0.9 { 
-                ratio * 0.98
-            } else {
-                let t = (ratio - 0.4) / 0.5;
-                let mult = 0.82 + (t * (0.98 - 0.82));
-                ratio * mult
-            }
-        },
+    Some(files)
+}
+
+/// One algorithm's result from `estimate_path_shared`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SharedEstimate {
+    pub algo: WofAlgorithm,
+    pub estimate: Estimate,
+    /// Bytes this algorithm's codec compressed while sampling...
+    pub sampled_bytes: u64,
+    /// ...and the time it took.
+    pub codec_time: Duration,
+}
+
+/// Estimates `path` for each of `algos` reading every sampled block
+/// once, in `EstimateMode::Accurate`. Returns the logical size and one result per
+/// algorithm, or `None` if cancelled.
+pub fn estimate_path_shared(
+    path: &str,
+    algos: &[WofAlgorithm],
+    model: AllocModel,
+    params: EstimatorParams,
+    cancelled: &dyn Fn() -> bool,
+) -> Option<(u64, Vec<SharedEstimate>)> {
+    let mut ests: Vec<Estimator> = algos.iter().map(|&a| Estimator::new(a, EstimateMode::Accurate, model, params)).collect();
+    let mut results: Vec<SharedEstimate> = algos.iter()
+        .map(|&algo| SharedEstimate { algo, estimate: Estimate::default(), sampled_bytes: 0, codec_time: Duration::ZERO })
+        .collect();
+    let mut buf = vec![0u8; ests.iter().map(|e| e.in_b.len()).max().unwrap_or(BLK)];
+
+    let p = Path::new(path);
+    let files = if p.is_file() {
+        let sz = p.metadata().map(|m| m.len()).unwrap_or(0);
+        if sz > 0 { vec![(p.to_path_buf(), sz)] } else { Vec::new() }
+    } else {
+        list_files(p, cancelled)?
     };
-    (sz as f64 * adj) as u64
-}
\ No newline at end of file
+
+    let mut logical = 0;
+    let mut variance = vec![0.0f64; algos.len()];
+    for (file, sz) in files {
+        if cancelled() { return None; }
+        logical += sz;
+        let estimates = estimate_file_shared(&mut ests, &file, sz, &mut buf, &mut results);
+        for ((r, e), v) in results.iter_mut().zip(estimates).zip(variance.iter_mut()) {
+            r.estimate.size += e.size;
+            *v += (e.margin as f64).powi(2);
+        }
+    }
+    results.iter_mut().zip(variance).for_each(|(r, v)| r.estimate.margin = v.sqrt() as u64);
+    Some((logical, results))
+}
+
+/// Projected size of one file under `Auto`: the per-file choice, or `sz` when skipped.
+fn project_auto(model: &AllocModel, path: &Path, sz: u64) -> u64 {
+    let Some(sample) = path.to_str().and_then(auto::sample_path) else { return sz };
+    match auto::choose(&sample) {
+        Some(a) => model.disk_size(sz, (sz as f64 * sample.ratio(a)) as u64, a),
+        None => sz,
+    }
+}
+
+/// Adaptively sampled ratio of the file at `path`, on the basis of the codec `algo` and
+/// `mode` sample with (no tables, cache or sniffing). `None` if empty or unreadable.
+pub fn sample_ratio(path: &Path, algo: WofAlgorithm, mode: EstimateMode, model: AllocModel, params: EstimatorParams) -> Option<SampledRatio> {
+    let mut f = File::open(path).ok()?;
+    let sz = f.metadata().ok()?.len();
+    if sz == 0 { return None; }
+    Some(Estimator::new(algo, mode, model, EstimatorParams { use_learned: false, ..params }).sample_adaptive(&mut f, sz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_size_model() {
+        let m = AllocModel::default();
+
+        // MFT-resident files are never compressed
+        assert_eq!(m.disk_size(500, 100, WofAlgorithm::Xpress4K), 500);
+
+        // 100 KiB in 25 XPRESS4K chunks: 24 table entries, rounded to clusters
+        assert_eq!(m.disk_size(100 * 1024, 30_000, WofAlgorithm::Xpress4K), 32 * 1024);
+        assert_eq!(m.disk_size(100 * 1024, 30_000, WofAlgorithm::Lznt1), 32 * 1024);
+        assert_eq!(m.disk_size(100 * 1024, 32_700, WofAlgorithm::Xpress4K), 36 * 1024);
+
+        // Small files that cannot save a cluster stay at their logical size
+        assert_eq!(m.disk_size(3000, 1000, WofAlgorithm::Xpress4K), 3000);
+        assert_eq!(m.disk_size(6000, 3000, WofAlgorithm::Lzx), 4096);
+
+        let big = AllocModel { cluster_size: 64 * 1024, ..m };
+        assert_eq!(big.disk_size(100 * 1024, 30_000, WofAlgorithm::Xpress4K), 64 * 1024);
+        assert_eq!(big.disk_size(60 * 1024, 30_000, WofAlgorithm::Xpress4K), 60 * 1024);
+    }
+
+    #[test]
+    fn test_progressive_estimate() {
+        let dir = std::env::temp_dir().join(["compactrs_progressive_", &std::process::id().to_string()].concat());
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.dat"), b"0123456789abcdef".repeat(8192)).unwrap();
+        fs::write(dir.join("sub").join("b.dat"), vec![7u8; 300_000]).unwrap();
+        let path = dir.to_str().unwrap();
+        let params = EstimatorParams { use_learned: false, tier_l: 200_000, ..EstimatorParams::default() };
+
+        let mut seen = Vec::new();
+        let total = estimate_path_progressive(path, WofAlgorithm::Xpress8K.into(), EstimateMode::Fast, AllocModel::default(), params, &|| false, &mut |p| seen.push(p)).unwrap();
+        assert_eq!(total.size, estimate_path_with_params(path, WofAlgorithm::Xpress8K, EstimateMode::Fast, AllocModel::default(), params));
+
+        // The large file is estimated first; the last report is exact
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].done_bytes, 300_000);
+        assert!(seen[0].confidence() > 0.6 && seen[0].confidence() < 1.0);
+        assert_eq!(seen[1].confidence(), 1.0);
+        assert_eq!(seen[1].projected(), total.size);
+        assert_eq!(seen[1].margin(), total.margin);
+
+        assert_eq!(estimate_path_progressive(path, WofAlgorithm::Xpress8K.into(), EstimateMode::Fast, AllocModel::default(), params, &|| true, &mut |_| {}), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_adaptive_sampling() {
+        let dir = std::env::temp_dir().join(["compactrs_adaptive_", &std::process::id().to_string()].concat());
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // 16 MiB pack: 1 MiB random regions alternating with 1 MiB of zeros
+        let mut seed = 7u32;
+        let mut pack = Vec::with_capacity(16 << 20);
+        for region in 0..16 {
+            for _ in 0..1 << 20 {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                pack.push(if region % 2 == 0 { (seed >> 24) as u8 } else { 0 });
+            }
+        }
+        fs::write(dir.join("mixed.pak"), &pack).unwrap();
+        fs::write(dir.join("flat.pak"), vec![0u8; 16 << 20]).unwrap();
+
+        let params = EstimatorParams { use_learned: false, ..EstimatorParams::default() };
+        let sample = |name: &str| sample_ratio(&dir.join(name), WofAlgorithm::Xpress16K, EstimateMode::Fast, AllocModel::default(), params).unwrap();
+
+        // Homogeneous content stops at the initial samples with a tight interval
+        let flat = sample("flat.pak");
+        assert_eq!(flat.samples, params.initial_samples(16 << 20));
+        assert!(flat.half_width < 0.001);
+
+        // Mixed content samples up to the budget and the interval covers the true ratio (~0.5)
+        let mixed = sample("mixed.pak");
+        assert_eq!(mixed.samples as u64, params.sample_budget(16 << 20) / (16 * 1024));
+        assert!(mixed.half_width > 0.0 && mixed.half_width < 0.25, "{:?}", mixed);
+        let truth = 0.5 * (xpress::compressed_size_chunked(&pack[..1 << 20], 16 * 1024) as f64 / (1 << 20) as f64) + 0.5 * (xpress::compressed_size_chunked(&pack[1 << 20..2 << 20], 16 * 1024) as f64 / (1 << 20) as f64);
+        assert!((mixed.ratio - truth).abs() <= mixed.half_width, "{:?} vs {}", mixed, truth);
+
+        // Same file, same offsets
+        assert_eq!(sample("mixed.pak"), mixed);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,442 @@
+/* --- src/engine/journal.rs --- */
+//! On-disk progress journal that lets an interrupted batch resume.
+//!
+//! Every `batch_process_worker` run writes a journal to the `compactrs_journal` folder next
+//! to the executable: the batch rows first, then one line per finished file, appended by the
+//! workers as they go. A batch that completes deletes its journal. One that was stopped, or
+//! never finished because the app crashed or the machine rebooted, leaves it behind; the next
+//! start offers to resume it, and a batch that runs one of its rows again takes over the
+//! files already done instead of reopening them.
+//!
+//! The format is line based so a crash mid-write only loses the torn last line. Fields are
+//! separated by tabs, which Windows paths cannot contain:
+//!
+//! ```text
+//! CRJ 1
+//! I <action> <algorithm> <row path>
+//! D <row index> <S|K|F> <size on disk> <file path>
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::engine::wof::AlgoChoice;
+
+/// Format version written after the header tag.
+pub const JOURNAL_VERSION: u32 = 1;
+
+const HEADER: &str = "CRJ";
+const JOURNAL_DIR: &str = "compactrs_journal";
+const EXTENSION: &str = "log";
+/// Results buffered before the workers flush them to disk.
+const FLUSH_EVERY: u32 = 256;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Serializes taking over journals between batches started at the same time.
+static CLAIM_LOCK: Mutex<()> = Mutex::new(());
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+/// A batch row as the worker received it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalItem {
+    pub path: String,
+    /// `BatchAction` name ("compress", "verify", ...).
+    pub action: String,
+    pub algo: AlgoChoice,
+}
+
+impl JournalItem {
+    fn same_row(&self, other: &JournalItem) -> bool {
+        self.action == other.action && self.algo == other.algo && self.path.eq_ignore_ascii_case(&other.path)
+    }
+}
+
+/// How a file ended, without the reason text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Success,
+    Skipped,
+    Failed,
+}
+
+impl Outcome {
+    fn code(self) -> &'static str {
+        match self {
+            Outcome::Success => "S",
+            Outcome::Skipped => "K",
+            Outcome::Failed => "F",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "S" => Some(Outcome::Success),
+            "K" => Some(Outcome::Skipped),
+            "F" => Some(Outcome::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Finished files of one row: path -> (outcome, size on disk afterwards).
+pub type DoneFiles = HashMap<String, (Outcome, u64)>;
+
+/// Files a journal recorded, by outcome.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JournalTotals {
+    pub succeeded: u64,
+    pub skipped: u64,
+    pub failed: u64,
+}
+
+impl JournalTotals {
+    pub fn files(&self) -> u64 {
+        self.succeeded + self.skipped + self.failed
+    }
+}
+
+/// Contents of a journal file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Journal {
+    pub items: Vec<JournalItem>,
+    /// Finished files, indexed like `items`.
+    pub done: Vec<DoneFiles>,
+}
+
+impl Journal {
+    /// Parses journal text. Another header or version is `None`; malformed lines (a write
+    /// torn by a crash) are ignored.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let version = lines.next()?.strip_prefix(HEADER)?.trim().parse::<u32>().ok()?;
+        if version != JOURNAL_VERSION { return None; }
+
+        let mut journal = Journal::default();
+        for line in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields.as_slice() {
+                ["I", action, algo, path] => {
+                    let Some(algo) = AlgoChoice::from_name(algo) else { continue };
+                    journal.items.push(JournalItem { path: path.to_string(), action: action.to_string(), algo });
+                    journal.done.push(DoneFiles::new());
+                }
+                ["D", item, outcome, disk, path] if !path.is_empty() => {
+                    let (Ok(item), Some(outcome), Ok(disk)) = (item.parse::<usize>(), Outcome::from_code(outcome), disk.parse::<u64>()) else { continue };
+                    if let Some(done) = journal.done.get_mut(item) {
+                        done.insert(path.to_string(), (outcome, disk));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(journal)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = header_text(&self.items);
+        for (i, done) in self.done.iter().enumerate() {
+            for (path, (outcome, disk)) in done {
+                out.push_str(&done_line(i, path, *outcome, *disk));
+            }
+        }
+        out
+    }
+
+    pub fn totals(&self) -> JournalTotals {
+        let mut totals = JournalTotals::default();
+        for (outcome, _) in self.done.iter().flat_map(|d| d.values()) {
+            match outcome {
+                Outcome::Success => totals.succeeded += 1,
+                Outcome::Skipped => totals.skipped += 1,
+                Outcome::Failed => totals.failed += 1,
+            }
+        }
+        totals
+    }
+
+    pub fn load_from(path: &Path) -> Option<Self> {
+        Self::parse(&std::fs::read_to_string(path).ok()?)
+    }
+}
+
+fn header_text(items: &[JournalItem]) -> String {
+    let mut out = [HEADER, " ", &JOURNAL_VERSION.to_string(), "\n"].concat();
+    for item in items {
+        out.push_str(&["I\t", &item.action, "\t", item.algo.name(), "\t", &item.path, "\n"].concat());
+    }
+    out
+}
+
+fn done_line(item: usize, path: &str, outcome: Outcome, disk: u64) -> String {
+    ["D\t", &item.to_string(), "\t", outcome.code(), "\t", &disk.to_string(), "\t", path, "\n"].concat()
+}
+
+struct WriterState {
+    out: BufWriter<File>,
+    unflushed: u32,
+    last_flush: Instant,
+}
+
+/// Journal of a running batch, shared by its workers. Dropping it keeps the file for a
+/// later resume; `finish` deletes it.
+pub struct JournalWriter {
+    path: PathBuf,
+    state: Mutex<WriterState>,
+}
+
+impl JournalWriter {
+    /// Starts a journal for `items` in the default folder. See `start_in`.
+    pub fn start(items: &[JournalItem]) -> std::io::Result<(Self, Vec<DoneFiles>)> {
+        Self::start_in(&default_dir(), items)
+    }
+
+    /// Starts a journal for `items` in `dir`, taking over every pending journal row that
+    /// matches one of them. Returns the files those rows already finished, indexed like
+    /// `items`; failed files are left out so they are tried again.
+    pub fn start_in(dir: &Path, items: &[JournalItem]) -> std::io::Result<(Self, Vec<DoneFiles>)> {
+        let _claim = CLAIM_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::fs::create_dir_all(dir)?;
+
+        let mut done = vec![DoneFiles::new(); items.len()];
+        let mut sources = Vec::new();
+        for (path, mut journal) in pending_in(dir) {
+            let mut claimed = false;
+            for (i, item) in items.iter().enumerate() {
+                let Some(j) = journal.items.iter().position(|old| old.same_row(item)) else { continue };
+                journal.items.remove(j);
+                let prior = journal.done.remove(j);
+                done[i].extend(prior.into_iter().filter(|(_, (outcome, _))| *outcome != Outcome::Failed));
+                claimed = true;
+            }
+            if claimed { sources.push((path, journal)); }
+        }
+
+        let writer = Self::create(&new_path(dir), items)?;
+        {
+            let mut st = writer.lock();
+            for (i, files) in done.iter().enumerate() {
+                for (path, (outcome, disk)) in files {
+                    st.out.write_all(done_line(i, path, *outcome, *disk).as_bytes())?;
+                }
+            }
+            st.out.flush()?;
+        }
+
+        // The carried-over files are safe in the new journal; drop them from the old ones
+        for (path, rest) in sources {
+            if rest.items.is_empty() {
+                let _ = std::fs::remove_file(&path);
+            } else {
+                let _ = std::fs::write(&path, rest.to_text());
+            }
+        }
+        Ok((writer, done))
+    }
+
+    fn create(path: &Path, items: &[JournalItem]) -> std::io::Result<Self> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        // Other instances may read the journal but not take it over while it is in use
+        #[cfg(windows)]
+        std::os::windows::fs::OpenOptionsExt::share_mode(&mut options, 1); // FILE_SHARE_READ
+        let mut out = BufWriter::new(options.open(path)?);
+        out.write_all(header_text(items).as_bytes())?;
+        out.flush()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            state: Mutex::new(WriterState { out, unflushed: 0, last_flush: Instant::now() }),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, WriterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Appends the result of one file of row `item`.
+    pub fn record(&self, item: usize, path: &str, outcome: Outcome, disk: u64) {
+        let mut st = self.lock();
+        if st.out.write_all(done_line(item, path, outcome, disk).as_bytes()).is_err() { return; }
+        st.unflushed += 1;
+        if st.unflushed >= FLUSH_EVERY || st.last_flush.elapsed() >= FLUSH_INTERVAL {
+            let _ = st.out.flush();
+            st.unflushed = 0;
+            st.last_flush = Instant::now();
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The batch completed: there is nothing to resume.
+    pub fn finish(self) {
+        let path = self.path.clone();
+        drop(self);
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+fn default_dir() -> PathBuf {
+    let mut path = std::env::current_exe().unwrap_or_default();
+    path.set_file_name(JOURNAL_DIR);
+    path
+}
+
+fn new_path(dir: &Path) -> PathBuf {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let seq = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let name = [&std::process::id().to_string(), "-", &nanos.to_string(), "-", &seq.to_string(), ".", EXTENSION].concat();
+    dir.join(name)
+}
+
+/// Journals left by batches that did not complete, skipping any still being written.
+pub fn pending() -> Vec<(PathBuf, Journal)> {
+    pending_in(&default_dir())
+}
+
+pub fn pending_in(dir: &Path) -> Vec<(PathBuf, Journal)> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut journals = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) { continue; }
+        // A running batch holds its journal open without write sharing
+        if std::fs::OpenOptions::new().append(true).open(&path).is_err() { continue; }
+        match Journal::load_from(&path) {
+            Some(journal) => journals.push((path, journal)),
+            None => {
+                crate::log_info!("Discarded a batch journal from an older version");
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+    journals.sort_by(|a, b| a.0.cmp(&b.0));
+    journals
+}
+
+/// Deletes every pending journal (the user chose not to resume).
+pub fn discard_pending() {
+    for (path, _) in pending() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Drops every pending journal row covering one of `paths` (see `utils::path_covers`),
+/// deleting journals left without rows.
+pub fn discard_covering(paths: &[&str]) {
+    discard_covering_in(&default_dir(), paths)
+}
+
+pub fn discard_covering_in(dir: &Path, paths: &[&str]) {
+    for (path, mut journal) in pending_in(dir) {
+        let before = journal.items.len();
+        for i in (0..journal.items.len()).rev() {
+            if paths.iter().any(|p| crate::utils::path_covers(&journal.items[i].path, p)) {
+                journal.items.remove(i);
+                journal.done.remove(i);
+            }
+        }
+        if journal.items.len() == before { continue; }
+        crate::log_info!(&["Discarded ", &(before - journal.items.len()).to_string(), " undone row(s) of a batch journal"].concat());
+        if journal.items.is_empty() {
+            let _ = std::fs::remove_file(&path);
+        } else {
+            let _ = std::fs::write(&path, journal.to_text());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::wof::WofAlgorithm;
+
+    fn item(path: &str, action: &str, algo: WofAlgorithm) -> JournalItem {
+        JournalItem { path: path.to_string(), action: action.to_string(), algo: algo.into() }
+    }
+
+    #[test]
+    fn test_parse_ignores_torn_lines() {
+        let text = "CRJ 1\nI\tcompress\tlzx\tD:\\games\nD\t0\tS\t4096\tD:\\games\\a.exe\nD\t0\tF\t100\tD:\\games\\b.dll\nD\t3\tS\t1\tD:\\x\nD\t0\tS\t12";
+        let j = Journal::parse(text).unwrap();
+        assert_eq!(j.items, vec![item("D:\\games", "compress", WofAlgorithm::Lzx)]);
+        assert_eq!(j.done[0].len(), 2);
+        assert_eq!(j.totals(), JournalTotals { succeeded: 1, skipped: 0, failed: 1 });
+        assert_eq!(Journal::parse(&j.to_text()), Some(j));
+
+        assert_eq!(Journal::parse("CRJ 2\n"), None);
+        assert_eq!(Journal::parse(""), None);
+    }
+
+    #[test]
+    fn test_stopped_batch_is_taken_over() {
+        let dir = std::env::temp_dir().join(["compactrs_journal_test_", &std::process::id().to_string()].concat());
+        let _ = std::fs::remove_dir_all(&dir);
+        let games = item("D:\\games", "compress", WofAlgorithm::Lzx);
+        let docs = item("D:\\docs", "compress", WofAlgorithm::Xpress8K);
+
+        let (w, done) = JournalWriter::start_in(&dir, &[games.clone(), docs.clone()]).unwrap();
+        assert!(done.iter().all(|d| d.is_empty()));
+        w.record(0, "D:\\games\\a.exe", Outcome::Success, 4096);
+        w.record(0, "D:\\games\\b.dll", Outcome::Failed, 8192);
+        w.record(1, "D:\\docs\\c.txt", Outcome::Skipped, 100);
+        drop(w);
+
+        let pending = pending_in(&dir);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].1.totals().files(), 3);
+
+        // Same games row again (decompressing docs is a different row): failures are retried
+        let (w, done) = JournalWriter::start_in(&dir, &[games.clone(), item("D:\\docs", "decompress", WofAlgorithm::Xpress8K)]).unwrap();
+        assert_eq!(done[0].get("D:\\games\\a.exe"), Some(&(Outcome::Success, 4096)));
+        assert_eq!(done[0].len(), 1);
+        assert!(done[1].is_empty());
+
+        // The old journal keeps only the unclaimed docs row; the new one has the carried file
+        let new_path = w.path().to_path_buf();
+        drop(w);
+        let pending = pending_in(&dir);
+        assert_eq!(pending.len(), 2);
+        let old = pending.iter().find(|(p, _)| *p != new_path).unwrap();
+        assert_eq!(old.1.items, vec![docs]);
+        assert_eq!(Journal::load_from(&new_path).unwrap().done[0].len(), 1);
+
+        let (w, _) = JournalWriter::start_in(&dir, &[games]).unwrap();
+        w.finish();
+        // Only the two docs rows nobody ran again are left
+        let pending = pending_in(&dir);
+        assert_eq!(pending.len(), 2);
+        assert!(pending.iter().all(|(_, j)| j.items.len() == 1 && j.items[0].path == "D:\\docs"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_discard_rows_covering_undone_paths() {
+        let dir = std::env::temp_dir().join(["compactrs_journal_discard_test_", &std::process::id().to_string()].concat());
+        let _ = std::fs::remove_dir_all(&dir);
+        let rows = [
+            item("D:\\Games", "compress", WofAlgorithm::Lzx),
+            item("D:\\Games2", "compress", WofAlgorithm::Lzx),
+            item("D:\\docs", "compress", WofAlgorithm::Xpress8K),
+        ];
+        let (w, _) = JournalWriter::start_in(&dir, &rows).unwrap();
+        w.record(0, "D:\\Games\\a.exe", Outcome::Success, 4096);
+        drop(w);
+
+        // Only whole segments match: "D:\Games2" does not hold "D:\games\a.exe"
+        discard_covering_in(&dir, &["D:\\games\\a.exe"]);
+        let pending = pending_in(&dir);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].1.items, rows[1..].to_vec());
+        assert!(pending[0].1.done.iter().all(|d| d.is_empty()));
+
+        discard_covering_in(&dir, &["D:\\Games2", "D:\\docs\\sub\\c.txt"]);
+        assert!(pending_in(&dir).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
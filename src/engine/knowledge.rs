@@ -0,0 +1,297 @@
+/* --- src/engine/knowledge.rs --- */
+//! Persistent per-extension, per-algorithm ratios learned from real compression results.
+//!
+//! The estimator's in-memory extension cache only lives for one `estimate_path` call and
+//! is built from samples. This store is fed by the worker with the actual `disk_size` /
+//! `logical_size` of every file it compressed, merged after each batch and saved to
+//! `compactrs_knowledge.dat` next to the executable. The estimator prefers a learned ratio
+//! over sampling once an extension has `MIN_SAMPLES` observations for the target algorithm.
+//!
+//! The file carries `KNOWLEDGE_VERSION`; bump it whenever the meaning of a stored ratio
+//! changes so stale data is discarded on load instead of skewing estimates.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof_stream;
+
+/// Format and model version of the knowledge file.
+pub const KNOWLEDGE_VERSION: u32 = 1;
+/// Observations needed before a learned ratio is trusted.
+pub const MIN_SAMPLES: u32 = 5;
+/// Files below this size are dominated by cluster rounding and are not learned from.
+pub const LEARN_MIN_SIZE: u64 = 64 * 1024;
+/// Once an entry reaches this weight, older observations are halved so it keeps adapting.
+const MAX_WEIGHT: u32 = 1000;
+/// Expected cluster round-up included in an observed `disk_size` (half a 4K cluster).
+const ROUNDING_SLACK: u64 = 2048;
+
+const MAGIC: u32 = 0x424B_5243; // "CRKB"
+const KNOWLEDGE_FILE: &str = "compactrs_knowledge.dat";
+
+/// Accumulated ratios for one extension and algorithm.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ExtStat {
+    pub ratio_sum: f64,
+    pub count: u32,
+}
+
+impl ExtStat {
+    pub fn ratio(&self) -> f64 {
+        if self.count == 0 { 1.0 } else { self.ratio_sum / self.count as f64 }
+    }
+}
+
+/// Learned ratios keyed by lowercase extension and algorithm.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Knowledge {
+    entries: HashMap<(String, u32), ExtStat>,
+}
+
+impl Knowledge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, ext: &str, algo: WofAlgorithm) -> Option<ExtStat> {
+        self.entries.get(&(ext.to_string(), algo as u32)).copied()
+    }
+
+    /// Learned ratio once `ext` has enough observations for `algo`.
+    pub fn ratio(&self, ext: &str, algo: WofAlgorithm) -> Option<f64> {
+        self.get(ext, algo).filter(|s| s.count >= MIN_SAMPLES).map(|s| s.ratio())
+    }
+
+    /// Trusted ratios for `algo`, keyed by extension (snapshot for one estimate run).
+    pub fn ratios_for(&self, algo: WofAlgorithm) -> HashMap<String, f64> {
+        self.entries.iter()
+            .filter(|((_, a), s)| *a == algo as u32 && s.count >= MIN_SAMPLES)
+            .map(|((ext, _), s)| (ext.clone(), s.ratio()))
+            .collect()
+    }
+
+    /// Adds one observed data ratio.
+    pub fn observe(&mut self, ext: &str, algo: WofAlgorithm, ratio: f64) {
+        let stat = self.entries.entry((ext.to_string(), algo as u32)).or_default();
+        stat.ratio_sum += ratio.clamp(0.0, 1.0);
+        stat.count += 1;
+        if stat.count >= MAX_WEIGHT {
+            stat.ratio_sum /= 2.0;
+            stat.count /= 2;
+        }
+    }
+
+    /// Records the outcome of compressing `path`. Small, extensionless and unsupported
+    /// files are ignored; returns whether the observation was kept.
+    pub fn learn(&mut self, path: &str, algo: WofAlgorithm, logical: u64, disk: u64) -> bool {
+        if logical < LEARN_MIN_SIZE || algo == WofAlgorithm::Lznt1 { return false; }
+        let Some(ext) = extension_of(path) else { return false };
+        self.observe(&ext, algo, observed_ratio(logical, disk, algo));
+        true
+    }
+
+    /// Folds a batch worth of observations into this store.
+    pub fn merge(&mut self, other: &Knowledge) {
+        for (key, s) in &other.entries {
+            let stat = self.entries.entry(key.clone()).or_default();
+            stat.ratio_sum += s.ratio_sum;
+            stat.count += s.count;
+            if stat.count >= MAX_WEIGHT {
+                let scale = (MAX_WEIGHT / 2) as f64 / stat.count as f64;
+                stat.ratio_sum *= scale;
+                stat.count = MAX_WEIGHT / 2;
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(12 + self.entries.len() * 24);
+        out.extend_from_slice(&MAGIC.to_le_bytes());
+        out.extend_from_slice(&KNOWLEDGE_VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+
+        let mut keys: Vec<_> = self.entries.keys().collect();
+        keys.sort();
+        for key in keys {
+            let s = &self.entries[key];
+            let ext = key.0.as_bytes();
+            out.push(ext.len().min(255) as u8);
+            out.extend_from_slice(&ext[..ext.len().min(255)]);
+            out.push(key.1 as u8);
+            out.extend_from_slice(&s.count.to_le_bytes());
+            out.extend_from_slice(&s.ratio_sum.to_le_bytes());
+        }
+        out
+    }
+
+    /// Parses `to_bytes` output. Unknown magic, another version or truncation is `None`.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let mut r = Reader { data, pos: 0 };
+        if r.u32()? != MAGIC || r.u32()? != KNOWLEDGE_VERSION { return None; }
+
+        let n = r.u32()?;
+        let mut entries = HashMap::new();
+        for _ in 0..n {
+            let len = r.take(1)?[0] as usize;
+            let ext = String::from_utf8(r.take(len)?.to_vec()).ok()?;
+            let algo = r.take(1)?[0] as u32;
+            let count = r.u32()?;
+            let ratio_sum = f64::from_le_bytes(r.take(8)?.try_into().ok()?);
+            entries.insert((ext, algo), ExtStat { ratio_sum, count });
+        }
+        Some(Self { entries })
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        match std::fs::read(path) {
+            Ok(data) => Self::from_bytes(&data).unwrap_or_else(|| {
+                match stored_version(&data) {
+                    Some(v) if v != KNOWLEDGE_VERSION => crate::log_info!("Discarded estimator knowledge from an older version"),
+                    _ => crate::log_warn!(&["Discarded corrupt estimator knowledge: ", &path.to_string_lossy()].concat()),
+                }
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    fn default_path() -> PathBuf {
+        let mut path = std::env::current_exe().unwrap_or_default();
+        path.set_file_name(KNOWLEDGE_FILE);
+        path
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let s = self.data.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(s)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+}
+
+/// Version field of a knowledge file, `None` if it does not start like one.
+fn stored_version(data: &[u8]) -> Option<u32> {
+    let mut r = Reader { data, pos: 0 };
+    if r.u32()? != MAGIC { return None; }
+    r.u32()
+}
+
+/// Lowercase extension of `path`, `None` if it has none.
+pub fn extension_of(path: &str) -> Option<String> {
+    Path::new(path).extension().and_then(|e| e.to_str()).filter(|e| !e.is_empty()).map(|e| e.to_lowercase())
+}
+
+/// Compressed data ratio behind an observed allocation: the chunk table and the expected
+/// cluster round-up are removed so it matches what the estimator projects from.
+pub fn observed_ratio(logical: u64, disk: u64, algo: WofAlgorithm) -> f64 {
+    if logical == 0 || disk >= logical { return 1.0; }
+    let data = disk.saturating_sub(wof_stream::chunk_table_size(logical, algo) + ROUNDING_SLACK);
+    data as f64 / logical as f64
+}
+
+static GLOBAL: OnceLock<Mutex<Knowledge>> = OnceLock::new();
+
+/// Process-wide store, loaded from disk on first use.
+pub fn global() -> MutexGuard<'static, Knowledge> {
+    let m = GLOBAL.get_or_init(|| Mutex::new(Knowledge::load_from(&Knowledge::default_path())));
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Merges a finished batch into the global store and saves it.
+pub fn commit(batch: &Knowledge) {
+    if batch.is_empty() { return; }
+    let mut k = global();
+    k.merge(batch);
+    if let Err(e) = k.save_to(&Knowledge::default_path()) {
+        crate::log_error!(&["Failed to save estimator knowledge: ", &e.to_string()].concat());
+    }
+}
+
+/// Forgets everything learned and deletes the knowledge file.
+pub fn reset() {
+    *global() = Knowledge::default();
+    let _ = std::fs::remove_file(Knowledge::default_path());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_learn_and_trust() {
+        let mut k = Knowledge::new();
+        assert!(!k.learn("C:\\a\\small.dll", WofAlgorithm::Lzx, 10_000, 4096));
+        assert!(!k.learn("C:\\a\\README", WofAlgorithm::Lzx, 1 << 20, 1 << 19));
+
+        for _ in 0..MIN_SAMPLES - 1 {
+            assert!(k.learn("C:\\a\\Core.DLL", WofAlgorithm::Lzx, 1 << 20, 1 << 19));
+        }
+        assert_eq!(k.ratio("dll", WofAlgorithm::Lzx), None);
+        k.learn("C:\\b\\x.dll", WofAlgorithm::Lzx, 1 << 20, 1 << 19);
+
+        let r = k.ratio("dll", WofAlgorithm::Lzx).unwrap();
+        assert!(r > 0.49 && r < 0.5, "{}", r);
+        assert_eq!(k.ratio("dll", WofAlgorithm::Xpress4K), None);
+        assert_eq!(k.ratios_for(WofAlgorithm::Lzx).len(), 1);
+
+        // Uncompressed outcome counts as incompressible
+        assert_eq!(observed_ratio(1 << 20, 1 << 20, WofAlgorithm::Xpress8K), 1.0);
+    }
+
+    #[test]
+    fn test_weight_cap() {
+        let mut k = Knowledge::new();
+        for _ in 0..MAX_WEIGHT { k.observe("bin", WofAlgorithm::Xpress8K, 0.8); }
+        let s = k.get("bin", WofAlgorithm::Xpress8K).unwrap();
+        assert!(s.count < MAX_WEIGHT);
+        assert!((s.ratio() - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_roundtrip_and_version() {
+        let mut k = Knowledge::new();
+        k.observe("exe", WofAlgorithm::Lzx, 0.42);
+        k.observe("txt", WofAlgorithm::Xpress4K, 0.3);
+
+        let mut batch = Knowledge::new();
+        batch.observe("exe", WofAlgorithm::Lzx, 0.5);
+        k.merge(&batch);
+        assert_eq!(k.get("exe", WofAlgorithm::Lzx).unwrap().count, 2);
+
+        let bytes = k.to_bytes();
+        assert_eq!(Knowledge::from_bytes(&bytes), Some(k.clone()));
+        assert_eq!(Knowledge::from_bytes(&bytes[..bytes.len() - 1]), None);
+
+        let mut stale = bytes.clone();
+        stale[4..8].copy_from_slice(&(KNOWLEDGE_VERSION + 1).to_le_bytes());
+        assert_eq!(Knowledge::from_bytes(&stale), None);
+        // Only a different version field counts as an older store; the rest is corrupt
+        assert_eq!(stored_version(&stale), Some(KNOWLEDGE_VERSION + 1));
+        assert_eq!(stored_version(&bytes[..bytes.len() - 1]), Some(KNOWLEDGE_VERSION));
+        assert_eq!(stored_version(&bytes[..6]), None);
+    }
+}
@@ -1,9 +1,28 @@
+pub mod algorithm;
 pub mod wof;
+pub mod wof_stream;
+pub mod error;
+pub mod backend;
 pub mod estimator;
+pub mod estimate_jobs;
+pub mod compare;
+pub mod analyzer;
+pub mod dry_run;
+pub mod verify;
+pub mod journal;
+pub mod undo;
+pub mod report;
+pub mod codec;
 pub mod worker;
 pub mod power;
 pub mod elevation;
 pub mod watcher;
 pub mod scanner;
+pub mod sniff;
+pub mod entropy;
+pub mod rules;
+pub mod auto;
+pub mod knowledge;
+pub mod enumerator;
 pub mod process;
 // pub mod dynamic_import; // Removed
@@ -4,7 +4,7 @@ use std::mem::zeroed;
 
 // --- Manual Bindings & Structs ---
 
-#[link(name = "kernel32")]
+#[cfg_attr(windows, link(name = "kernel32"))]
 unsafe extern "system" {
     fn GetCurrentThread() -> HANDLE;
     fn GetCurrentProcess() -> HANDLE;
@@ -0,0 +1,518 @@
+/* --- src/engine/report.rs --- */
+//! Batch reports: what one run did to every row and file.
+//!
+//! Each worker thread records a `FileRecord` per processed file in its own `FileLog`, merged
+//! into the `RunReport` it hands to the UI when the run ends; queued rows of the same Process
+//! click are merged into one report. Totals count every file, but only the first
+//! `MAX_LISTED_FILES` are listed so a run over millions of files stays small in memory.
+//! "Export Report" writes it as:
+//!
+//! - CSV: one line per run, volume, row (`item`) and file, in that order. Volume lines hold
+//!   the free space before and after in the `disk_before` / `disk_after` columns.
+//! - JSON: the same data nested as run > items > files.
+//! - HTML: a self-contained summary (totals, volumes, rows and failed files) to share.
+//!
+//! For dry-run rows nothing changes on disk: `disk_after` is the projected size and the
+//! algorithm the one a real run would use.
+
+use std::collections::HashMap;
+
+use crate::engine::journal::Outcome;
+use crate::engine::wof::{AlgoChoice, WofAlgorithm};
+use crate::utils::{align_right, fixed_point, size_text};
+
+/// Most files a report lists; past that files only count in the totals.
+pub const MAX_LISTED_FILES: usize = 100_000;
+
+/// One file processed by the run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileRecord {
+    /// Batch row the file belongs to.
+    pub item: u32,
+    pub path: String,
+    /// WOF or LZNT1 algorithm on disk after the run, `None` if uncompressed.
+    pub algorithm: Option<WofAlgorithm>,
+    pub logical: u64,
+    pub disk_before: u64,
+    pub disk_after: u64,
+    pub outcome: Outcome,
+    /// Skip or failure reason, empty on success.
+    pub reason: String,
+    /// Failed for a reason that may pass on another try (`CompressError::is_retryable`).
+    pub retryable: bool,
+}
+
+/// One batch row, as it was started.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemRecord {
+    pub id: u32,
+    pub path: String,
+    /// `BatchAction::name()`
+    pub action: String,
+    /// Requested algorithm.
+    pub algorithm: AlgoChoice,
+}
+
+/// Free space of a volume the run wrote to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VolumeSpace {
+    pub root: String,
+    pub free_before: u64,
+    pub free_after: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Totals {
+    pub files: u64,
+    pub succeeded: u64,
+    pub skipped: u64,
+    pub failed: u64,
+    pub logical: u64,
+    pub disk_before: u64,
+    pub disk_after: u64,
+}
+
+impl Totals {
+    pub fn add(&mut self, f: &FileRecord) {
+        self.files += 1;
+        match f.outcome {
+            Outcome::Success => self.succeeded += 1,
+            Outcome::Skipped => self.skipped += 1,
+            Outcome::Failed => self.failed += 1,
+        }
+        self.logical += f.logical;
+        self.disk_before += f.disk_before;
+        self.disk_after += f.disk_after;
+    }
+
+    pub fn merge(&mut self, other: &Totals) {
+        self.files += other.files;
+        self.succeeded += other.succeeded;
+        self.skipped += other.skipped;
+        self.failed += other.failed;
+        self.logical += other.logical;
+        self.disk_before += other.disk_before;
+        self.disk_after += other.disk_after;
+    }
+
+    /// Bytes freed on disk; negative when decompressing.
+    pub fn saved(&self) -> i64 {
+        self.disk_before as i64 - self.disk_after as i64
+    }
+}
+
+/// Files of a run: running totals per row for every file, and the records themselves up to
+/// a limit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileLog {
+    /// Listed files, at most `limit`.
+    pub records: Vec<FileRecord>,
+    /// Files counted in the totals but not listed.
+    pub omitted: u64,
+    limit: usize,
+    item_totals: HashMap<u32, Totals>,
+}
+
+impl Default for FileLog {
+    fn default() -> Self {
+        Self::with_limit(MAX_LISTED_FILES)
+    }
+}
+
+impl FileLog {
+    pub fn with_limit(limit: usize) -> Self {
+        FileLog { records: Vec::new(), omitted: 0, limit, item_totals: HashMap::new() }
+    }
+
+    pub fn add(&mut self, f: FileRecord) {
+        self.item_totals.entry(f.item).or_default().add(&f);
+        if self.records.len() < self.limit {
+            self.records.push(f);
+        } else {
+            self.omitted += 1;
+        }
+    }
+
+    /// Takes over `other`'s files; records past this log's limit are counted as omitted.
+    pub fn merge(&mut self, other: FileLog) {
+        for (id, t) in &other.item_totals {
+            self.item_totals.entry(*id).or_default().merge(t);
+        }
+        let room = self.limit.saturating_sub(self.records.len());
+        self.omitted += other.omitted + other.records.len().saturating_sub(room) as u64;
+        self.records.extend(other.records.into_iter().take(room));
+    }
+
+    pub fn totals(&self) -> Totals {
+        let mut t = Totals::default();
+        self.item_totals.values().for_each(|i| t.merge(i));
+        t
+    }
+
+    pub fn item_totals(&self, id: u32) -> Totals {
+        self.item_totals.get(&id).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunReport {
+    /// Start time, Unix seconds (UTC).
+    pub started: u64,
+    pub duration_ms: u64,
+    pub threads: u32,
+    /// Cancelled before every file was processed.
+    pub stopped: bool,
+    pub items: Vec<ItemRecord>,
+    pub files: FileLog,
+    pub volumes: Vec<VolumeSpace>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+    Html,
+}
+
+impl ReportFormat {
+    /// Format for a file name's extension.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(ReportFormat::Csv),
+            "json" => Some(ReportFormat::Json),
+            "html" | "htm" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+impl RunReport {
+    /// Appends a later run of the same batch (rows queued behind `max_concurrent_items`).
+    pub fn merge(&mut self, other: RunReport) {
+        self.duration_ms += other.duration_ms;
+        self.threads = self.threads.max(other.threads);
+        self.stopped |= other.stopped;
+        self.items.extend(other.items);
+        self.files.merge(other.files);
+        for v in other.volumes {
+            match self.volumes.iter_mut().find(|own| own.root.eq_ignore_ascii_case(&v.root)) {
+                Some(own) => own.free_after = v.free_after,
+                None => self.volumes.push(v),
+            }
+        }
+    }
+
+    pub fn totals(&self) -> Totals {
+        self.files.totals()
+    }
+
+    pub fn item_totals(&self, id: u32) -> Totals {
+        self.files.item_totals(id)
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Csv => self.to_csv(),
+            ReportFormat::Json => self.to_json(),
+            ReportFormat::Html => self.to_html(),
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("type,item,path,action,algorithm,result,reason,retryable,files,succeeded,skipped,failed,logical_bytes,disk_before_bytes,disk_after_bytes\r\n");
+        let counts = |t: &Totals| [t.files, t.succeeded, t.skipped, t.failed].map(|n| n.to_string()).join(",");
+        let sizes = |logical: u64, before: u64, after: u64| [logical, before, after].map(|n| n.to_string()).join(",");
+
+        let t = self.totals();
+        let run_result = if self.stopped { "stopped" } else { "finished" };
+        let mut run_info = [&utc_timestamp(self.started), ", ", &duration_text(self.duration_ms), ", ", &self.threads.to_string(), " threads"].concat();
+        if self.files.omitted > 0 {
+            run_info.push_str(&[", ", &self.files.omitted.to_string(), " files not listed"].concat());
+        }
+        out.push_str(&["run,,,,,", run_result, ",", &csv_field(&run_info), ",,", &counts(&t), ",", &sizes(t.logical, t.disk_before, t.disk_after), "\r\n"].concat());
+        for v in &self.volumes {
+            out.push_str(&["volume,,", &csv_field(&v.root), ",,,,,,,,,,,", &v.free_before.to_string(), ",", &v.free_after.to_string(), "\r\n"].concat());
+        }
+        for item in &self.items {
+            let t = self.item_totals(item.id);
+            out.push_str(&["item,", &item.id.to_string(), ",", &csv_field(&item.path), ",", &item.action, ",", item.algorithm.name(), ",,,,",
+                &counts(&t), ",", &sizes(t.logical, t.disk_before, t.disk_after), "\r\n"].concat());
+        }
+        for f in &self.files.records {
+            out.push_str(&["file,", &f.item.to_string(), ",", &csv_field(&f.path), ",,", f.algorithm.map_or("", |a| a.name()), ",",
+                outcome_name(f.outcome), ",", &csv_field(&f.reason), ",", bool_text(f.retryable), ",,,,,", &sizes(f.logical, f.disk_before, f.disk_after), "\r\n"].concat());
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let totals = |t: &Totals| {
+            ["{\"files\":", &t.files.to_string(), ",\"succeeded\":", &t.succeeded.to_string(), ",\"skipped\":", &t.skipped.to_string(),
+                ",\"failed\":", &t.failed.to_string(), ",\"logical\":", &t.logical.to_string(), ",\"disk_before\":", &t.disk_before.to_string(),
+                ",\"disk_after\":", &t.disk_after.to_string(), ",\"saved\":", &t.saved().to_string(), "}"].concat()
+        };
+
+        let volumes: Vec<String> = self.volumes.iter()
+            .map(|v| ["{\"root\":", &json_string(&v.root), ",\"free_before\":", &v.free_before.to_string(), ",\"free_after\":", &v.free_after.to_string(), "}"].concat())
+            .collect();
+        let mut files: HashMap<u32, Vec<String>> = HashMap::new();
+        for f in &self.files.records {
+            files.entry(f.item).or_default().push(["{\"path\":", &json_string(&f.path), ",\"algorithm\":", &f.algorithm.map_or("null".to_string(), |a| json_string(a.name())),
+                ",\"logical\":", &f.logical.to_string(), ",\"disk_before\":", &f.disk_before.to_string(), ",\"disk_after\":", &f.disk_after.to_string(),
+                ",\"result\":\"", outcome_name(f.outcome), "\",\"reason\":", &json_string(&f.reason), ",\"retryable\":", bool_text(f.retryable), "}"].concat());
+        }
+        let items: Vec<String> = self.items.iter().map(|item| {
+            let files = files.get(&item.id).map_or(String::new(), |f| f.join(","));
+            ["{\"id\":", &item.id.to_string(), ",\"path\":", &json_string(&item.path), ",\"action\":", &json_string(&item.action),
+                ",\"algorithm\":", &json_string(item.algorithm.name()), ",\"totals\":", &totals(&self.item_totals(item.id)),
+                ",\"files\":[", &files, "]}"].concat()
+        }).collect();
+
+        ["{\"started\":", &json_string(&utc_timestamp(self.started)), ",\"duration_ms\":", &self.duration_ms.to_string(),
+            ",\"threads\":", &self.threads.to_string(), ",\"stopped\":", bool_text(self.stopped),
+            ",\"totals\":", &totals(&self.totals()), ",\"unlisted_files\":", &self.files.omitted.to_string(), ",\"volumes\":[", &volumes.join(","), "],\"items\":[", &items.join(","), "]}\n"].concat()
+    }
+
+    pub fn to_html(&self) -> String {
+        let t = self.totals();
+        let mut out = String::from("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>CompactRS batch report</title><style>\
+            body{font-family:'Segoe UI',sans-serif;margin:24px;color:#222}h1{font-size:20px}h2{font-size:16px;margin-top:28px}\
+            table{border-collapse:collapse}th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}td.n{text-align:right}\
+            th{background:#f0f0f0}.failed{color:#b00020}</style></head><body>\n<h1>CompactRS batch report</h1>\n");
+
+        let status = if self.stopped { "Stopped" } else { "Finished" };
+        out.push_str(&["<p>", status, " &middot; started ", &utc_timestamp(self.started), " &middot; ", &duration_text(self.duration_ms),
+            " &middot; ", &self.threads.to_string(), " threads</p>\n"].concat());
+        out.push_str("<table><tr><th>Files</th><th>Succeeded</th><th>Skipped</th><th>Failed</th><th>Size</th><th>On disk before</th><th>On disk after</th><th>Saved</th></tr>\n");
+        out.push_str(&totals_row(&t, ""));
+        out.push_str("</table>\n");
+        if self.files.omitted > 0 {
+            out.push_str(&["<p>", &self.files.omitted.to_string(), " more files are counted above but not listed in this report.</p>\n"].concat());
+        }
+
+        if !self.volumes.is_empty() {
+            out.push_str("<h2>Volumes</h2>\n<table><tr><th>Volume</th><th>Free before</th><th>Free after</th><th>Change</th></tr>\n");
+            for v in &self.volumes {
+                out.push_str(&["<tr><td>", &html_escape(&v.root), "</td><td class=\"n\">", &size_text(v.free_before), "</td><td class=\"n\">",
+                    &size_text(v.free_after), "</td><td class=\"n\">", &signed_size_text(v.free_after as i64 - v.free_before as i64), "</td></tr>\n"].concat());
+            }
+            out.push_str("</table>\n");
+        }
+
+        out.push_str("<h2>Items</h2>\n<table><tr><th>Path</th><th>Action</th><th>Algorithm</th><th>Files</th><th>Succeeded</th><th>Skipped</th><th>Failed</th><th>Size</th><th>On disk before</th><th>On disk after</th><th>Saved</th></tr>\n");
+        for item in &self.items {
+            let lead = ["<td>", &html_escape(&item.path), "</td><td>", &item.action, "</td><td>", &item.algorithm.name().to_uppercase(), "</td>"].concat();
+            out.push_str(&totals_row(&self.item_totals(item.id), &lead));
+        }
+        out.push_str("</table>\n");
+
+        let failed: Vec<&FileRecord> = self.files.records.iter().filter(|f| f.outcome == Outcome::Failed).collect();
+        if !failed.is_empty() {
+            out.push_str("<h2 class=\"failed\">Failed files</h2>\n<table><tr><th>Path</th><th>Reason</th><th>May pass on retry</th></tr>\n");
+            for f in failed {
+                out.push_str(&["<tr><td>", &html_escape(&f.path), "</td><td>", &html_escape(&f.reason), "</td><td>",
+                    if f.retryable { "Yes" } else { "No" }, "</td></tr>\n"].concat());
+            }
+            out.push_str("</table>\n");
+        }
+        out.push_str("</body></html>\n");
+        out
+    }
+}
+
+fn totals_row(t: &Totals, lead: &str) -> String {
+    let failed_class = if t.failed > 0 { "n failed" } else { "n" };
+    ["<tr>", lead, "<td class=\"n\">", &t.files.to_string(), "</td><td class=\"n\">", &t.succeeded.to_string(), "</td><td class=\"n\">",
+        &t.skipped.to_string(), "</td><td class=\"", failed_class, "\">", &t.failed.to_string(), "</td><td class=\"n\">", &size_text(t.logical),
+        "</td><td class=\"n\">", &size_text(t.disk_before), "</td><td class=\"n\">", &size_text(t.disk_after), "</td><td class=\"n\">",
+        &signed_size_text(t.saved()), "</td></tr>\n"].concat()
+}
+
+fn bool_text(b: bool) -> &'static str {
+    if b { "true" } else { "false" }
+}
+
+fn outcome_name(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Success => "success",
+        Outcome::Skipped => "skipped",
+        Outcome::Failed => "failed",
+    }
+}
+
+/// Quotes a CSV field when it holds a separator, quote or line break.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        ["\"", &s.replace('"', "\"\""), "\""].concat()
+    } else {
+        s.to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let hex = |d: u32| char::from_digit(d, 16).unwrap_or('0').to_string();
+                out.push_str(&["\\u00", &hex(c as u32 >> 4), &hex(c as u32 & 0xf)].concat());
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn signed_size_text(bytes: i64) -> String {
+    let text = size_text(bytes.unsigned_abs());
+    if bytes < 0 { ["-", &text].concat() } else { text }
+}
+
+fn duration_text(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs < 60 {
+        [&fixed_point(ms / 100, 1), " s"].concat()
+    } else {
+        [&(secs / 3600).to_string(), ":", &two_digits(secs / 60 % 60), ":", &two_digits(secs % 60)].concat()
+    }
+}
+
+/// "2026-10-16 14:03:09 UTC" for Unix seconds.
+pub fn utc_timestamp(secs: u64) -> String {
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    let t = secs % 86_400;
+    [&align_right(&y.to_string(), 4).replace(' ', "0"), "-", &two_digits(m as u64), "-", &two_digits(d as u64), " ",
+        &two_digits(t / 3600), ":", &two_digits(t / 60 % 60), ":", &two_digits(t % 60), " UTC"].concat()
+}
+
+fn two_digits(n: u64) -> String {
+    align_right(&n.to_string(), 2).replace(' ', "0")
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + (m <= 2) as i64, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RunReport {
+        let file = |item, path: &str, outcome, before, after, reason: &str| FileRecord {
+            item, path: path.to_string(), algorithm: (outcome == Outcome::Success).then_some(WofAlgorithm::Lzx),
+            logical: before, disk_before: before, disk_after: after, outcome, reason: reason.to_string(), retryable: outcome == Outcome::Failed,
+        };
+        let mut files = FileLog::default();
+        files.add(file(1, "D:\\Games, \"Old\"\\a.pak", Outcome::Success, 1_000_000, 400_000, ""));
+        files.add(file(1, "D:\\Games, \"Old\"\\b<1>.dll", Outcome::Failed, 50_000, 50_000, "Locked by another process"));
+        RunReport {
+            started: 1_792_159_389,
+            duration_ms: 12_345,
+            threads: 8,
+            stopped: false,
+            items: vec![ItemRecord { id: 1, path: "D:\\Games, \"Old\"".to_string(), action: "compress".to_string(), algorithm: AlgoChoice::Fixed(WofAlgorithm::Lzx) }],
+            files,
+            volumes: vec![VolumeSpace { root: "D:\\".to_string(), free_before: 5_000_000, free_after: 5_600_000 }],
+        }
+    }
+
+    #[test]
+    fn test_totals_and_formats() {
+        let r = sample();
+        let t = r.totals();
+        assert_eq!((t.files, t.succeeded, t.failed, t.saved()), (2, 1, 1, 600_000));
+        assert_eq!(r.item_totals(1), t);
+        assert_eq!(utc_timestamp(r.started), "2026-10-16 14:03:09 UTC");
+        assert_eq!(utc_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!((duration_text(900), duration_text(3_725_000)), ("0.9 s".to_string(), "1:02:05".to_string()));
+
+        let csv = r.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[1], "run,,,,,finished,\"2026-10-16 14:03:09 UTC, 12.3 s, 8 threads\",,2,1,0,1,1050000,1050000,450000");
+        assert_eq!(lines[2], "volume,,D:\\,,,,,,,,,,,5000000,5600000");
+        assert_eq!(lines[3], "item,1,\"D:\\Games, \"\"Old\"\"\",compress,lzx,,,,2,1,0,1,1050000,1050000,450000");
+        assert_eq!(lines[5], "file,1,\"D:\\Games, \"\"Old\"\"\\b<1>.dll\",,,failed,Locked by another process,true,,,,,50000,50000,50000");
+
+        let json = r.to_json();
+        assert!(json.contains("\"path\":\"D:\\\\Games, \\\"Old\\\"\\\\a.pak\",\"algorithm\":\"lzx\""));
+        assert!(json.contains("\"saved\":600000"));
+        assert!(json.contains("\"reason\":\"Locked by another process\",\"retryable\":true}"));
+        assert_eq!(json_string("a\u{1}\u{1f}"), "\"a\\u0001\\u001f\"");
+
+        let html = r.to_html();
+        assert!(html.contains("b&lt;1&gt;.dll</td><td>Locked by another process</td><td>Yes"));
+        assert!(html.contains("<td class=\"n failed\">1</td>"));
+        assert!(html.contains("585 KB"));
+    }
+
+    #[test]
+    fn test_merge_queued_runs() {
+        let mut r = sample();
+        let mut later = sample();
+        later.items[0].id = 2;
+        let mut files = FileLog::default();
+        for mut f in later.files.records.drain(..) {
+            f.item = 2;
+            files.add(f);
+        }
+        later.files = files;
+        later.volumes[0].free_after = 6_000_000;
+        later.volumes.push(VolumeSpace { root: "E:\\".to_string(), free_before: 1, free_after: 2 });
+        r.merge(later);
+
+        assert_eq!((r.items.len(), r.files.records.len(), r.duration_ms), (2, 4, 24_690));
+        assert_eq!(r.item_totals(2), r.item_totals(1));
+        assert_eq!(r.totals().files, 4);
+        assert_eq!(r.volumes[0].free_before, 5_000_000);
+        assert_eq!(r.volumes[0].free_after, 6_000_000);
+        assert_eq!(r.volumes.len(), 2);
+        assert_eq!(ReportFormat::from_path("C:\\out\\run.HTM"), Some(ReportFormat::Html));
+        assert_eq!(ReportFormat::from_path("C:\\out\\run"), None);
+    }
+
+    #[test]
+    fn test_file_log_keeps_totals_past_limit() {
+        let file = |item, path: &str| FileRecord {
+            item, path: path.to_string(), algorithm: None, logical: 10, disk_before: 10, disk_after: 4,
+            outcome: Outcome::Success, reason: String::new(), retryable: false,
+        };
+        let mut log = FileLog::with_limit(2);
+        log.add(file(1, "a"));
+        log.add(file(1, "b"));
+        log.add(file(2, "c"));
+        assert_eq!((log.records.len(), log.omitted), (2, 1));
+        assert_eq!((log.item_totals(1).files, log.item_totals(2).disk_after), (2, 4));
+
+        let mut other = FileLog::with_limit(2);
+        other.add(file(2, "d"));
+        let mut report = RunReport { files: FileLog::with_limit(3), ..RunReport::default() };
+        report.files.merge(log);
+        report.files.merge(other);
+        assert_eq!((report.files.records.len(), report.files.omitted), (3, 1));
+        assert_eq!((report.totals().files, report.totals().saved()), (4, 24));
+        assert!(report.to_csv().contains("1 files not listed"));
+        assert!(report.to_json().contains("\"unlisted_files\":1"));
+    }
+}
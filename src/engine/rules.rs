@@ -0,0 +1,491 @@
+/* --- src/engine/rules.rs --- */
+//! Per-file rules: ordered conditions mapped to skip / algorithm / force actions.
+//!
+//! Rules are read from `compactrs_rules.txt` next to the executable, one per line, and the
+//! first rule whose conditions all match decides for the file:
+//!
+//! ```text
+//! # conditions                     -> action
+//! size:<8K                         -> skip Too small
+//! ext:dll,exe                      -> lzx
+//! **\node_modules\**  age:>30d     -> xpress16k
+//! content:any                      -> skip
+//! attr:hidden,!system              -> force
+//! *                                -> xpress8k
+//! ```
+//!
+//! # Conditions (space separated, all must match)
+//! - `glob:PAT[,PAT]` or a bare pattern: `*`, `**` and `?` wildcards, case-insensitive.
+//!   Patterns without a backslash match the file name only.
+//! - `ext:a,b,c`: extension set.
+//! - `size:<N`, `size:>N`, `size:A-B`: byte sizes with optional K/M/G suffix.
+//! - `age:<Nd`, `age:>Nd`: days since last write (`h` hours also accepted).
+//! - `attr:name[,!name]`: readonly, hidden, system, archive, sparse, compressed.
+//! - `content:kind[,kind]`, `content:any`, `content:none`: sniffed header (`engine::sniff`).
+//!
+//! # Actions
+//! `skip [reason]`, an algorithm name (`xpress4k` ... `lznt1`), or `force`.
+//!
+//! # Built-in Rules
+//! The system path guard and the Smart Skip extension list are rules too, placed ahead of
+//! the file's rules by [`RuleSet::with_defaults`] unless the batch is forced:
+//!
+//! ```text
+//! **windows\system32** **windows\syswow64** ...  -> skip System Path
+//! ext:<Excluded Extensions setting>             -> skip Filtered extension
+//! ```
+//!
+//! Matches are counted per rule and logged once per batch (`RuleSet::log_summary`).
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::engine::sniff::ContentKind;
+use crate::engine::wof::WofAlgorithm;
+
+const RULES_FILE: &str = "compactrs_rules.txt";
+
+const FILE_ATTRIBUTE_READONLY: u32 = 0x1;
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+const FILE_ATTRIBUTE_ARCHIVE: u32 = 0x20;
+const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x200;
+const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x800;
+
+/// Paths the system guard protects (`scanner::is_critical_path`), matched on the full path.
+pub const CRITICAL_PATTERNS: [&str; 4] = [
+    "**windows\\system32**",
+    "**windows\\syswow64**",
+    "**windows\\winsxs**",
+    "**\\**boot**",
+];
+
+/// File properties rules are evaluated against.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileFacts {
+    pub size: u64,
+    /// Last write time, seconds since the Unix epoch.
+    pub modified: u64,
+    pub attributes: u32,
+}
+
+impl FileFacts {
+    pub fn from_metadata(m: &std::fs::Metadata) -> Self {
+        let modified = m.modified().ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self { size: m.len(), modified, attributes: attributes_of(m) }
+    }
+}
+
+#[cfg(windows)]
+fn attributes_of(m: &std::fs::Metadata) -> u32 {
+    use std::os::windows::fs::MetadataExt;
+    m.file_attributes()
+}
+
+/// Only the read-only bit has a portable equivalent.
+#[cfg(not(windows))]
+fn attributes_of(m: &std::fs::Metadata) -> u32 {
+    if m.permissions().readonly() { FILE_ATTRIBUTE_READONLY } else { 0 }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    Glob(Vec<String>),
+    Extensions(Vec<String>),
+    /// Inclusive byte range.
+    Size { min: u64, max: u64 },
+    /// Inclusive range of seconds since last write.
+    Age { min: u64, max: u64 },
+    Attributes { set: u32, clear: u32 },
+    /// `None` in the list stands for "not a known compressed format".
+    Content(Vec<Option<ContentKind>>),
+    AnyContent,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuleAction {
+    Skip(String),
+    Algorithm(WofAlgorithm),
+    Force,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    /// Source line, used when logging the match.
+    pub name: String,
+    pub conditions: Vec<Condition>,
+    pub action: RuleAction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Ordered rule list; the first full match wins.
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    /// Files decided by each rule, for the batch summary.
+    hits: Vec<AtomicU64>,
+}
+
+// ===== MATCHING =====
+
+/// Case-insensitive glob with `*` (within a component), `**` (across components) and `?`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().replace('/', "\\").chars().collect();
+    let t: Vec<char> = text.to_lowercase().replace('/', "\\").chars().collect();
+    glob_at(&p, &t)
+}
+
+fn glob_at(p: &[char], t: &[char]) -> bool {
+    match p.first() {
+        None => t.is_empty(),
+        Some('*') if p.get(1) == Some(&'*') => {
+            let rest = &p[2..];
+            (0..=t.len()).any(|i| glob_at(rest, &t[i..]))
+        }
+        Some('*') => {
+            let rest = &p[1..];
+            for i in 0..=t.len() {
+                if glob_at(rest, &t[i..]) { return true; }
+                if t.get(i) == Some(&'\\') { break; }
+            }
+            false
+        }
+        Some('?') => !t.is_empty() && t[0] != '\\' && glob_at(&p[1..], &t[1..]),
+        Some(&c) => t.first() == Some(&c) && glob_at(&p[1..], &t[1..]),
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
+
+impl Condition {
+    fn matches(&self, path: &str, facts: &FileFacts, now: u64, content: &mut dyn FnMut() -> Option<ContentKind>) -> bool {
+        match self {
+            Condition::Glob(patterns) => patterns.iter().any(|pat| {
+                if pat.contains(['\\', '/']) { glob_match(pat, path) } else { glob_match(pat, file_name(path)) }
+            }),
+            Condition::Extensions(exts) => {
+                let name = file_name(path);
+                match name.rfind('.') {
+                    Some(i) => exts.iter().any(|e| e.eq_ignore_ascii_case(&name[i + 1..])),
+                    None => false,
+                }
+            }
+            Condition::Size { min, max } => (*min..=*max).contains(&facts.size),
+            Condition::Age { min, max } => (*min..=*max).contains(&now.saturating_sub(facts.modified)),
+            Condition::Attributes { set, clear } => facts.attributes & set == *set && facts.attributes & clear == 0,
+            Condition::Content(kinds) => kinds.contains(&content()),
+            Condition::AnyContent => content().is_some(),
+        }
+    }
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        let hits = rules.iter().map(|_| AtomicU64::new(0)).collect();
+        Self { rules, hits }
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// First rule matching the file at `path`. `content` is only called (at most once) if a
+    /// rule needs the sniffed content type.
+    pub fn evaluate(&self, path: &str, facts: &FileFacts, now: u64, content: impl FnOnce() -> Option<ContentKind>) -> Option<&Rule> {
+        let mut content = Some(content);
+        let mut sniffed = None;
+        let mut lazy = || *sniffed.get_or_insert_with(|| content.take().and_then(|f| f()));
+        let i = self.rules.iter().position(|r| r.conditions.iter().all(|c| c.matches(path, facts, now, &mut lazy)))?;
+        self.hits[i].fetch_add(1, Ordering::Relaxed);
+        Some(&self.rules[i])
+    }
+
+    /// Logs how many files each rule decided, one line per rule that matched.
+    pub fn log_summary(&self) {
+        for (rule, hits) in self.rules.iter().zip(&self.hits) {
+            let n = hits.load(Ordering::Relaxed);
+            if n > 0 {
+                crate::log_info!(&["Rule [", &rule.name, "]: ", &n.to_string(), " files"].concat());
+            }
+        }
+    }
+
+    /// Puts the built-in skips ahead of these rules: the system path guard when `guard` is
+    /// set, and `skip_exts` (comma separated) when not empty. Forced batches pass neither.
+    pub fn with_defaults(self, guard: bool, skip_exts: &str) -> Self {
+        let mut rules = Vec::with_capacity(self.rules.len() + 2);
+        if guard {
+            rules.push(Rule {
+                name: "built-in: system path guard".to_string(),
+                conditions: vec![Condition::Glob(CRITICAL_PATTERNS.iter().map(|p| p.to_string()).collect())],
+                action: RuleAction::Skip("System Path".to_string()),
+            });
+        }
+        let exts: Vec<String> = list(skip_exts).map(|e| e.trim_start_matches('.').to_string()).collect();
+        if !exts.is_empty() {
+            rules.push(Rule {
+                name: "built-in: excluded extensions".to_string(),
+                conditions: vec![Condition::Extensions(exts)],
+                action: RuleAction::Skip("Filtered extension".to_string()),
+            });
+        }
+        rules.extend(self.rules);
+        Self::new(rules)
+    }
+
+    // ===== PARSING =====
+
+    /// Parses the rule text format described in the module docs.
+    pub fn parse(text: &str) -> Result<Self, RuleError> {
+        let mut rules = Vec::new();
+        for (i, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+
+            let err = |message: String| RuleError { line: i + 1, message };
+            let (lhs, rhs) = line.split_once("->").ok_or_else(|| err("missing '->'".to_string()))?;
+
+            let conditions = lhs.split_whitespace()
+                .filter(|tok| *tok != "*")
+                .map(parse_condition)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(err)?;
+            let action = parse_action(rhs.trim()).map_err(err)?;
+
+            rules.push(Rule { name: line.to_string(), conditions, action });
+        }
+        Ok(Self::new(rules))
+    }
+
+    fn default_path() -> PathBuf {
+        let mut path = std::env::current_exe().unwrap_or_default();
+        path.set_file_name(RULES_FILE);
+        path
+    }
+
+    /// Loads `compactrs_rules.txt`. A missing file is an empty rule set; a malformed one is
+    /// logged and ignored so a typo never changes what gets compressed.
+    pub fn load() -> Self {
+        let Ok(text) = std::fs::read_to_string(Self::default_path()) else { return Self::default() };
+        match Self::parse(&text) {
+            Ok(set) => set,
+            Err(e) => {
+                crate::log_error!(&["Rules ignored, line ", &e.line.to_string(), ": ", &e.message].concat());
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Parses "8K", "1.5M", "100" into bytes.
+fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim().to_ascii_uppercase();
+    let s = s.strip_suffix("IB").or_else(|| s.strip_suffix('B')).unwrap_or(&s);
+    let (num, mult) = match s.chars().last()? {
+        'K' => (&s[..s.len() - 1], 1u64 << 10),
+        'M' => (&s[..s.len() - 1], 1 << 20),
+        'G' => (&s[..s.len() - 1], 1 << 30),
+        'T' => (&s[..s.len() - 1], 1 << 40),
+        _ => (s, 1),
+    };
+    let v: f64 = num.parse().ok()?;
+    if v < 0.0 { return None; }
+    Some((v * mult as f64) as u64)
+}
+
+/// Parses "30d", "12h" into seconds.
+fn parse_age(s: &str) -> Option<u64> {
+    let (num, mult) = match s.chars().last()? {
+        'd' | 'D' => (&s[..s.len() - 1], 86_400),
+        'h' | 'H' => (&s[..s.len() - 1], 3_600),
+        _ => (s, 86_400),
+    };
+    num.parse::<u64>().ok().map(|v| v * mult)
+}
+
+/// `<N`, `>N` or `A-B` into an inclusive range.
+fn parse_range(spec: &str, unit: fn(&str) -> Option<u64>) -> Option<(u64, u64)> {
+    if let Some(v) = spec.strip_prefix("<=") {
+        Some((0, unit(v)?))
+    } else if let Some(v) = spec.strip_prefix('<') {
+        Some((0, unit(v)?.checked_sub(1)?))
+    } else if let Some(v) = spec.strip_prefix(">=") {
+        Some((unit(v)?, u64::MAX))
+    } else if let Some(v) = spec.strip_prefix('>') {
+        Some((unit(v)?.checked_add(1)?, u64::MAX))
+    } else {
+        let (a, b) = spec.split_once('-')?;
+        Some((unit(a)?, unit(b)?))
+    }
+}
+
+fn parse_attribute(name: &str) -> Option<u32> {
+    match name.to_ascii_lowercase().as_str() {
+        "readonly" => Some(FILE_ATTRIBUTE_READONLY),
+        "hidden" => Some(FILE_ATTRIBUTE_HIDDEN),
+        "system" => Some(FILE_ATTRIBUTE_SYSTEM),
+        "archive" => Some(FILE_ATTRIBUTE_ARCHIVE),
+        "sparse" => Some(FILE_ATTRIBUTE_SPARSE_FILE),
+        "compressed" => Some(FILE_ATTRIBUTE_COMPRESSED),
+        _ => None,
+    }
+}
+
+fn parse_content(name: &str) -> Option<Option<ContentKind>> {
+    use ContentKind::*;
+    if name.eq_ignore_ascii_case("none") { return Some(None); }
+    [Zip, SevenZip, Rar, Cab, Gzip, Bzip2, Xz, Zstd, Lz4, Oodle, UnityFs, Png, Jpeg, Gif, Webp, Woff2, Ogg, Flac, Mp3, Mp4, Matroska]
+        .into_iter()
+        .find(|k| k.label().eq_ignore_ascii_case(name))
+        .map(Some)
+}
+
+fn list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn parse_condition(tok: &str) -> Result<Condition, String> {
+    let bad = || ["invalid condition '", tok, "'"].concat();
+    let Some((key, value)) = tok.split_once(':').filter(|(k, _)| k.len() > 1) else {
+        // Bare token (a drive letter colon is part of the pattern)
+        return Ok(Condition::Glob(list(tok).map(str::to_string).collect()));
+    };
+
+    match key.to_ascii_lowercase().as_str() {
+        "glob" => Ok(Condition::Glob(list(value).map(str::to_string).collect())),
+        "ext" => Ok(Condition::Extensions(list(value).map(|e| e.trim_start_matches('.').to_string()).collect())),
+        "size" => parse_range(value, parse_size).map(|(min, max)| Condition::Size { min, max }).ok_or_else(bad),
+        "age" => parse_range(value, parse_age).map(|(min, max)| Condition::Age { min, max }).ok_or_else(bad),
+        "attr" => {
+            let (mut set, mut clear) = (0, 0);
+            for name in list(value) {
+                match name.strip_prefix('!') {
+                    Some(n) => clear |= parse_attribute(n).ok_or_else(bad)?,
+                    None => set |= parse_attribute(name).ok_or_else(bad)?,
+                }
+            }
+            Ok(Condition::Attributes { set, clear })
+        }
+        "content" if value.eq_ignore_ascii_case("any") => Ok(Condition::AnyContent),
+        "content" => list(value).map(parse_content).collect::<Option<Vec<_>>>().map(Condition::Content).ok_or_else(bad),
+        _ => Err(["unknown condition '", key, "'"].concat()),
+    }
+}
+
+fn parse_action(s: &str) -> Result<RuleAction, String> {
+    let (verb, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+    if verb.eq_ignore_ascii_case("skip") {
+        let reason = rest.trim();
+        return Ok(RuleAction::Skip(if reason.is_empty() { "Rule".to_string() } else { reason.to_string() }));
+    }
+    if verb.eq_ignore_ascii_case("force") && rest.trim().is_empty() {
+        return Ok(RuleAction::Force);
+    }
+    match WofAlgorithm::from_name(verb) {
+        Some(algo) if rest.trim().is_empty() => Ok(RuleAction::Algorithm(algo)),
+        _ => Err(["unknown action '", s, "'"].concat()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+    const NOW: u64 = 1_700_000_000;
+
+    fn facts(size: u64, age_days: u64, attributes: u32) -> FileFacts {
+        FileFacts { size, modified: NOW - age_days * DAY, attributes }
+    }
+
+    fn action<'a>(set: &'a RuleSet, path: &str, f: FileFacts) -> Option<&'a RuleAction> {
+        set.evaluate(path, &f, NOW, || None).map(|r| &r.action)
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.dll", "KERNEL32.DLL"));
+        assert!(glob_match("C:\\Games\\**\\*.pak", "c:\\games\\a\\b\\x.pak"));
+        assert!(!glob_match("C:\\Games\\*\\*.pak", "c:\\games\\a\\b\\x.pak"));
+        assert!(glob_match("data?.bin", "data1.bin"));
+        assert!(!glob_match("data?.bin", "data10.bin"));
+    }
+
+    #[test]
+    fn test_first_match_wins() {
+        let set = RuleSet::parse("
+            # Team defaults
+            size:<8K          -> skip Too small
+            ext:dll,exe       -> lzx
+            *                 -> xpress8k
+        ").unwrap();
+        assert_eq!(set.rules().len(), 3);
+
+        assert_eq!(action(&set, "D:\\app\\tiny.dll", facts(4096, 0, 0)), Some(&RuleAction::Skip("Too small".to_string())));
+        assert_eq!(action(&set, "D:\\app\\core.DLL", facts(1 << 20, 0, 0)), Some(&RuleAction::Algorithm(WofAlgorithm::Lzx)));
+        assert_eq!(action(&set, "D:\\app\\readme.txt", facts(8192, 0, 0)), Some(&RuleAction::Algorithm(WofAlgorithm::Xpress8K)));
+    }
+
+    #[test]
+    fn test_age_attributes_and_content() {
+        let set = RuleSet::parse("
+            age:>30d attr:hidden,!system  -> force
+            content:zstd,oodle            -> skip Packed
+            D:\\cache\\**                 -> xpress4k
+        ").unwrap();
+
+        assert_eq!(action(&set, "D:\\x\\a.bin", facts(100, 31, 0x2)), Some(&RuleAction::Force));
+        assert_eq!(action(&set, "D:\\x\\a.bin", facts(100, 29, 0x2)), None);
+        assert_eq!(action(&set, "D:\\x\\a.bin", facts(100, 31, 0x6)), None);
+        assert_eq!(action(&set, "D:\\cache\\sub\\a.bin", facts(100, 0, 0)), Some(&RuleAction::Algorithm(WofAlgorithm::Xpress4K)));
+
+        let mut calls = 0;
+        let rule = set.evaluate("D:\\x\\level.pak", &facts(100, 0, 0), NOW, || { calls += 1; Some(ContentKind::Zstd) });
+        assert_eq!(rule.map(|r| &r.action), Some(&RuleAction::Skip("Packed".to_string())));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_defaults_come_first() {
+        let set = RuleSet::parse("ext:zip -> force\n* -> lzx").unwrap().with_defaults(true, "zip, .7z");
+        assert_eq!(set.rules().len(), 4);
+
+        assert_eq!(action(&set, "C:\\Windows\\System32\\a.dll", facts(100, 0, 0)), Some(&RuleAction::Skip("System Path".to_string())));
+        assert_eq!(action(&set, "D:\\a.ZIP", facts(100, 0, 0)), Some(&RuleAction::Skip("Filtered extension".to_string())));
+        assert_eq!(action(&set, "D:\\b.7z", facts(100, 0, 0)), Some(&RuleAction::Skip("Filtered extension".to_string())));
+        assert_eq!(action(&set, "D:\\Reboot\\c.txt", facts(100, 0, 0)), Some(&RuleAction::Skip("System Path".to_string())));
+        assert_eq!(action(&set, "D:\\c.txt", facts(100, 0, 0)), Some(&RuleAction::Algorithm(WofAlgorithm::Lzx)));
+        assert_eq!(set.hits.iter().map(|h| h.load(Ordering::Relaxed)).collect::<Vec<_>>(), vec![2, 2, 0, 1]);
+
+        // Forced batches keep only the file's rules
+        let set = RuleSet::parse("ext:zip -> force").unwrap().with_defaults(false, "");
+        assert_eq!(action(&set, "D:\\a.zip", facts(100, 0, 0)), Some(&RuleAction::Force));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(RuleSet::parse("ext:dll lzx").unwrap_err().line, 1);
+        assert!(RuleSet::parse("\nsize:<abc -> skip").unwrap_err().message.contains("size:<abc"));
+        assert!(RuleSet::parse("colour:red -> skip").is_err());
+        assert!(RuleSet::parse("* -> xpress32k").is_err());
+        assert!(RuleSet::parse("attr:shiny -> force").is_err());
+
+        assert_eq!(parse_size("1.5M"), Some(1_572_864));
+        assert_eq!(parse_size("8KiB"), Some(8192));
+        assert_eq!(parse_range("1K-2K", parse_size), Some((1024, 2048)));
+    }
+}
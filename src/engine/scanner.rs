@@ -1,11 +1,12 @@
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU8, AtomicUsize, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, Condvar};
 use std::time::Duration;
 use std::sync::mpsc::Sender;
 
-use crate::types::*;
-use crate::utils::PathBuffer;
-use crate::engine::wof::{get_real_file_size, get_wof_algorithm, WofAlgorithm, CompressionState, detect_compression_state};
+use crate::engine::wof::{AlgoBreakdown, CompressionState};
+use crate::engine::backend::{CompressionBackend, default_backend};
+use crate::engine::enumerator::{DirEntry, DirEnumerator, default_enumerator};
 use crate::ui::state::{UiMessage, ProcessingState};
 
 // ===== STRUCTS =====
@@ -26,39 +27,46 @@ pub struct PathMetrics {
     pub disk_size: u64,
     pub compression_state: CompressionState,
     pub file_count: u64,
+    /// How the files are stored, per algorithm.
+    pub breakdown: AlgoBreakdown,
+}
+
+/// Direct contents of one directory (files only, not its subdirectories).
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct FolderTotals {
+    pub path: String,
+    pub files: u64,
+    pub logical: u64,
+    pub disk: u64,
+    /// Logical size of the files that take no less on disk than their logical size.
+    pub uncompressed: u64,
+    /// Full paths of the subdirectories that were scanned.
+    pub children: Vec<String>,
 }
 
 // ===== HEURISTICS =====
 
-/// Check if a file path is considered a critical system path that should be protected.
-pub fn is_critical_path(path: &str) -> bool {
-    let lower = path.to_lowercase();
-    lower.contains("windows\\system32") || 
-    lower.contains("windows\\syswow64") ||
-    lower.contains("windows\\winsxs") ||
-    lower.contains("boot") ||
-    lower.ends_with("bootmgr")
+/// `path` as the `&str` the backends take. Names Windows allows but Unicode does not
+/// (unpaired surrogates) have none; they are logged and left alone rather than rewritten
+/// into a path that does not exist.
+pub fn path_str(path: &Path) -> Option<&str> {
+    let s = path.to_str();
+    if s.is_none() {
+        crate::log_warn!(&["Skipped (name is not valid Unicode): ", &path.to_string_lossy()].concat());
+    }
+    s
 }
 
-/// Check if a file should be skipped based on extension.
-pub fn should_skip_extension(path: &str, enabled: bool, custom_list: &str) -> bool {
-    if !enabled { return false; }
-    
-    let path_obj = std::path::Path::new(path);
-    if let Some(ext) = path_obj.extension().and_then(|s| s.to_str()) {
-        let ext_lower = ext.to_lowercase();
-        custom_list.split(',')
-            .map(|s| s.trim())
-            .any(|skip_ext| skip_ext.eq_ignore_ascii_case(&ext_lower))
-    } else {
-        false
-    }
+/// Check if a file path is considered a critical system path that should be protected.
+/// Same patterns as the built-in guard rule (`rules::CRITICAL_PATTERNS`).
+pub fn is_critical_path(path: &str) -> bool {
+    crate::engine::rules::CRITICAL_PATTERNS.iter().any(|p| crate::engine::rules::glob_match(p, path))
 }
 
 // ===== SINGLE-THREADED WALKER (PRESERVED FOR FFI COMPATIBILITY) =====
 
-/// Generic single-threaded directory walker.
-/// Preserved exactly as requested to ensure 100% compatibility with existing `worker.rs` closures.
+/// Generic single-threaded directory walker. The visitor gets each path as the OS returned
+/// it; `path_str` turns it into the `&str` the backends take.
 pub fn walk_directory<F>(
     path: &str,
     state: Option<&Arc<AtomicU8>>,
@@ -66,20 +74,34 @@ pub fn walk_directory<F>(
     visitor: &mut F,
 )
 where
-    F: FnMut(&str, bool, &WIN32_FIND_DATAW),
+    F: FnMut(&Path, bool, &DirEntry),
 {
-    let mut buffer = PathBuffer::from(path);
-    walk_recursive(&mut buffer, state, process_hidden_files, visitor);
+    walk_directory_with(default_enumerator().as_ref(), path, state, process_hidden_files, visitor);
+}
+
+/// `walk_directory` over an explicit enumerator.
+pub fn walk_directory_with<F>(
+    enumerator: &dyn DirEnumerator,
+    path: &str,
+    state: Option<&Arc<AtomicU8>>,
+    process_hidden_files: bool,
+    visitor: &mut F,
+)
+where
+    F: FnMut(&Path, bool, &DirEntry),
+{
+    walk_recursive(enumerator, Path::new(path), state, process_hidden_files, visitor);
 }
 
 fn walk_recursive<F>(
-    buffer: &mut PathBuffer,
+    enumerator: &dyn DirEnumerator,
+    dir: &Path,
     state: Option<&Arc<AtomicU8>>,
     process_hidden_files: bool,
     visitor: &mut F,
 )
 where
-    F: FnMut(&str, bool, &WIN32_FIND_DATAW),
+    F: FnMut(&Path, bool, &DirEntry),
 {
     if let Some(s) = state {
         loop {
@@ -93,76 +115,30 @@ where
         }
     }
 
-    let original_len = buffer.len();
-    buffer.push("*");
-    
-    let mut find_data: WIN32_FIND_DATAW = unsafe { std::mem::zeroed() };
-
-    unsafe {
-        let handle = FindFirstFileExW(
-            buffer.as_ptr(),
-            FindExInfoBasic,
-            &mut find_data as *mut _ as *mut _,
-            FindExSearchNameMatch,
-            std::ptr::null_mut(),
-            FIND_FIRST_EX_LARGE_FETCH,
-        );
-
-        buffer.truncate(original_len);
-
-        if handle == INVALID_HANDLE_VALUE {
-            return;
+    enumerator.for_each_entry(dir, &mut |entry| {
+        if let Some(s) = state && s.load(Ordering::Relaxed) == ProcessingState::Stopped as u8 {
+            return false;
         }
 
-        loop {
-            if let Some(s) = state {
-                 if s.load(Ordering::Relaxed) == ProcessingState::Stopped as u8 {
-                     FindClose(handle);
-                     return;
-                 }
-            }
-
-            let is_hidden_or_system = (find_data.dwFileAttributes & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM)) != 0;
-            if !process_hidden_files && is_hidden_or_system {
-                if FindNextFileW(handle, &mut find_data) == 0 {
-                    break;
-                }
-                continue;
-            }
+        if !process_hidden_files && (entry.is_hidden() || entry.is_system()) {
+            return true;
+        }
 
-            let filename_len = find_data.cFileName.iter().position(|&c| c == 0).unwrap_or(find_data.cFileName.len());
-            let is_dot = filename_len == 1 && find_data.cFileName[0] == 46;
-            let is_dot_dot = filename_len == 2 && find_data.cFileName[0] == 46 && find_data.cFileName[1] == 46;
-
-            if !is_dot && !is_dot_dot {
-                let len_before = buffer.len();
-                buffer.push_u16_slice(&find_data.cFileName[..filename_len]);
-                
-                let is_dir = (find_data.dwFileAttributes & FILE_ATTRIBUTE_DIRECTORY) != 0;
-                let is_reparse = (find_data.dwFileAttributes & FILE_ATTRIBUTE_REPARSE_POINT) != 0;
-                
-                let full_path_str = buffer.to_string_lossy();
-                visitor(&full_path_str, is_dir, &find_data);
-
-                if is_dir && !is_reparse {
-                    walk_recursive(buffer, state, process_hidden_files, visitor);
-                }
-                
-                buffer.truncate(len_before);
-            }
+        let full_path = enumerator.join(dir, &entry.name);
+        let is_dir = entry.is_dir();
+        visitor(&full_path, is_dir, entry);
 
-            if FindNextFileW(handle, &mut find_data) == 0 {
-                break;
-            }
+        if is_dir && !entry.is_reparse() {
+            walk_recursive(enumerator, &full_path, state, process_hidden_files, visitor);
         }
-        FindClose(handle);
-    }
+        true
+    });
 }
 
 // ===== MULTI-THREADED NVMe WORK-STEALING SCANNER =====
 
 struct ScanContext {
-    queue: Mutex<Vec<String>>,
+    queue: Mutex<Vec<PathBuf>>,
     cvar: Condvar,
     active_workers: AtomicUsize,
     
@@ -170,13 +146,16 @@ struct ScanContext {
     total_logical: AtomicU64,
     total_disk: AtomicU64,
     
-    algo_scanned: AtomicUsize,
-    seen_algos: Mutex<std::collections::HashSet<u32>>,
+    breakdown: Mutex<AlgoBreakdown>,
     
     app_state: Option<Arc<AtomicU8>>,
+    backend: Arc<dyn CompressionBackend>,
+    enumerator: Arc<dyn DirEnumerator>,
     
     collect_paths: bool,
     collected_paths: Mutex<Vec<String>>,
+    collect_dirs: bool,
+    dir_totals: Mutex<Vec<FolderTotals>>,
     process_hidden_files: bool,
 }
 
@@ -244,131 +223,116 @@ fn scan_worker_thread(ctx: Arc<ScanContext>) {
             }
         }
         
-        let mut buffer = PathBuffer::from(&dir);
-        let original_len = buffer.len();
-        buffer.push("*");
-        
-        let mut find_data: WIN32_FIND_DATAW = unsafe { std::mem::zeroed() };
-        let handle = unsafe { FindFirstFileExW(
-            buffer.as_ptr(),
-            FindExInfoBasic,
-            &mut find_data as *mut _ as *mut _,
-            FindExSearchNameMatch,
-            std::ptr::null_mut(),
-            FIND_FIRST_EX_LARGE_FETCH,
-        ) };
-        
-        buffer.truncate(original_len);
-        
-        if handle != INVALID_HANDLE_VALUE {
-            loop {
-                if let Some(s) = &ctx.app_state {
+        let mut stopped = false;
+        let mut totals = FolderTotals { path: dir.to_string_lossy().into_owned(), ..FolderTotals::default() };
+        let mut breakdown = AlgoBreakdown::default();
+
+        ctx.enumerator.for_each_entry(&dir, &mut |entry| {
+            if let Some(s) = &ctx.app_state {
+                loop {
                     let st = s.load(Ordering::Relaxed);
                     if st == ProcessingState::Stopped as u8 {
-                        unsafe { FindClose(handle); }
-                        ctx.decrement_worker_and_notify();
-                        return;
+                        stopped = true;
+                        return false;
                     }
                     if st == ProcessingState::Paused as u8 {
                         std::thread::sleep(Duration::from_millis(100));
-                        continue;
-                    }
-                }
-                
-                let is_hidden_or_system = (find_data.dwFileAttributes & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM)) != 0;
-                if !ctx.process_hidden_files && is_hidden_or_system {
-                    if unsafe { FindNextFileW(handle, &mut find_data) } == 0 {
+                    } else {
                         break;
                     }
-                    continue;
                 }
+            }
 
-                let filename_len = find_data.cFileName.iter().position(|&c| c == 0).unwrap_or(find_data.cFileName.len());
-                let is_dot = filename_len == 1 && find_data.cFileName[0] == 46;
-                let is_dot_dot = filename_len == 2 && find_data.cFileName[0] == 46 && find_data.cFileName[1] == 46;
-                
-                if !is_dot && !is_dot_dot {
-                    let len_before = buffer.len();
-                    buffer.push_u16_slice(&find_data.cFileName[..filename_len]);
-                    let full_path_str = buffer.to_string_lossy();
-                    
-                    let is_dir = (find_data.dwFileAttributes & FILE_ATTRIBUTE_DIRECTORY) != 0;
-                    let is_reparse = (find_data.dwFileAttributes & FILE_ATTRIBUTE_REPARSE_POINT) != 0;
-                    
-                    if is_dir {
-                        if !is_reparse {
-                            let mut queue_lock = ctx.queue.lock().unwrap();
-                            queue_lock.push(full_path_str);
-                            ctx.cvar.notify_one();
-                        }
-                    } else {
-                        // File Processing
-                        ctx.total_files.fetch_add(1, Ordering::Relaxed);
-                        let logical_size = ((find_data.nFileSizeHigh as u64) << 32) | (find_data.nFileSizeLow as u64);
-                        ctx.total_logical.fetch_add(logical_size, Ordering::Relaxed);
-                        
-                        let disk_size = get_real_file_size(&full_path_str);
-                        ctx.total_disk.fetch_add(disk_size, Ordering::Relaxed);
-                        
-                        if ctx.collect_paths {
-                            let mut cp = ctx.collected_paths.lock().unwrap();
-                            cp.push(full_path_str.clone());
-                        }
-                        
-                        // Heuristic Algorithm Sampling
-                        let scanned = ctx.algo_scanned.load(Ordering::Relaxed);
-                        let mut check_algo = false;
-                        if scanned < 200 {
-                            check_algo = true;
-                        } else if scanned < 2000 {
-                            let seen = ctx.seen_algos.lock().unwrap();
-                            if seen.is_empty() {
-                                check_algo = true;
-                            }
-                        }
-                        
-                        if check_algo {
-                            if let Some(algo) = get_wof_algorithm(&full_path_str) {
-                                let mut seen = ctx.seen_algos.lock().unwrap();
-                                seen.insert(algo as u32);
-                            }
-                            ctx.algo_scanned.fetch_add(1, Ordering::Relaxed);
-                        }
+            if !ctx.process_hidden_files && (entry.is_hidden() || entry.is_system()) {
+                return true;
+            }
+
+            let full_path = ctx.enumerator.join(&dir, &entry.name);
+
+            if entry.is_dir() {
+                if !entry.is_reparse() {
+                    if ctx.collect_dirs {
+                        totals.children.push(full_path.to_string_lossy().into_owned());
                     }
-                    buffer.truncate(len_before);
+                    let mut queue_lock = ctx.queue.lock().unwrap();
+                    queue_lock.push(full_path);
+                    ctx.cvar.notify_one();
                 }
-                
-                if unsafe { FindNextFileW(handle, &mut find_data) } == 0 {
-                    break;
+            } else {
+                let full_path_str = full_path.to_string_lossy();
+                // File Processing
+                ctx.total_files.fetch_add(1, Ordering::Relaxed);
+                ctx.total_logical.fetch_add(entry.size, Ordering::Relaxed);
+
+                let disk_size = ctx.backend.disk_size(&full_path_str);
+                ctx.total_disk.fetch_add(disk_size, Ordering::Relaxed);
+
+                if ctx.collect_dirs {
+                    totals.files += 1;
+                    totals.logical += entry.size;
+                    totals.disk += disk_size;
+                    if disk_size >= entry.size {
+                        totals.uncompressed += entry.size;
+                    }
                 }
+
+                if ctx.collect_paths {
+                    let mut cp = ctx.collected_paths.lock().unwrap();
+                    cp.push(full_path_str.to_string());
+                }
+
+                // Only files that look compressed are opened to ask for their algorithm:
+                // WOF files are reparse points, and both kinds take less than their size
+                let looks_compressed = entry.is_reparse() || entry.is_compressed() || disk_size < entry.size;
+                let algo = if looks_compressed { ctx.backend.query_algorithm(&full_path_str) } else { None };
+                breakdown.add(algo, entry.size, disk_size);
             }
-            unsafe { FindClose(handle); }
+            true
+        });
+
+        ctx.breakdown.lock().unwrap().merge(&breakdown);
+
+        // A stopped directory is incomplete; the next pop sees the stop and exits
+        if ctx.collect_dirs && !stopped {
+            ctx.dir_totals.lock().unwrap().push(totals);
         }
         
         ctx.decrement_worker_and_notify();
     }
 }
 
+/// What a scan keeps besides the totals.
+#[derive(Clone, Copy, Default)]
+struct Collect {
+    paths: bool,
+    dirs: bool,
+}
+
 /// Core runner for the multi-threaded Work-Stealing scanner.
 fn run_multi_threaded_scan(
+    backend: Arc<dyn CompressionBackend>,
+    enumerator: Arc<dyn DirEnumerator>,
     path: &str,
     state: Option<&Arc<AtomicU8>>,
-    collect_paths: bool,
+    collect: Collect,
     tx_info: Option<(u32, Sender<UiMessage>)>,
     process_hidden_files: bool,
 ) -> ScanContext {
     let ctx = Arc::new(ScanContext {
-        queue: Mutex::new(vec![path.to_string()]),
+        queue: Mutex::new(vec![PathBuf::from(path)]),
         cvar: Condvar::new(),
         active_workers: AtomicUsize::new(0),
         total_files: AtomicU64::new(0),
         total_logical: AtomicU64::new(0),
         total_disk: AtomicU64::new(0),
-        algo_scanned: AtomicUsize::new(0),
-        seen_algos: Mutex::new(std::collections::HashSet::new()),
+        breakdown: Mutex::new(AlgoBreakdown::default()),
         app_state: state.cloned(),
-        collect_paths,
+        backend,
+        enumerator,
+        collect_paths: collect.paths,
         collected_paths: Mutex::new(Vec::new()),
+        collect_dirs: collect.dirs,
+        dir_totals: Mutex::new(Vec::new()),
         process_hidden_files,
     });
     
@@ -425,22 +389,28 @@ fn run_multi_threaded_scan(
 pub fn scan_path_metrics(path: &str) -> PathMetrics {
     let p = std::path::Path::new(path);
     
+    let backend = default_backend();
+    
     if p.is_file() {
-        let logical = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
-        let disk = get_real_file_size(path);
-        let state = detect_compression_state(path);
-        return PathMetrics { logical_size: logical, disk_size: disk, compression_state: state, file_count: 1 };
+        let logical = backend.logical_size(path);
+        let disk = backend.disk_size(path);
+        let state = backend.detect_state(path);
+        let mut breakdown = AlgoBreakdown::default();
+        let algo = match state { CompressionState::Specific(a) => Some(a), _ => None };
+        breakdown.add(algo, logical, disk);
+        return PathMetrics { logical_size: logical, disk_size: disk, compression_state: state, file_count: 1, breakdown };
     }
     
     // Fallback to true if used externally without specifying
-    let ctx = run_multi_threaded_scan(path, None, false, None, true);
-    let algos = ctx.seen_algos.into_inner().unwrap();
+    let ctx = run_multi_threaded_scan(backend, default_enumerator(), path, None, Collect::default(), None, true);
+    let breakdown = ctx.breakdown.into_inner().unwrap();
     
     PathMetrics {
         logical_size: ctx.total_logical.into_inner(),
         disk_size: ctx.total_disk.into_inner(),
-        compression_state: resolve_mixed_state(algos),
+        compression_state: breakdown.state(),
         file_count: ctx.total_files.into_inner(),
+        breakdown,
     }
 }
 
@@ -460,12 +430,12 @@ pub fn scan_path_streaming(
         return m;
     }
 
-    let ctx = run_multi_threaded_scan(path, state, false, Some((id, tx.clone())), process_hidden_files);
+    let ctx = run_multi_threaded_scan(default_backend(), default_enumerator(), path, state, Collect::default(), Some((id, tx.clone())), process_hidden_files);
     
     let files = ctx.total_files.into_inner();
     let logical = ctx.total_logical.into_inner();
     let disk = ctx.total_disk.into_inner();
-    let algos = ctx.seen_algos.into_inner().unwrap();
+    let breakdown = ctx.breakdown.into_inner().unwrap();
     
     // Final precision sync
     let _ = tx.send(UiMessage::ScanProgress(id, logical, disk, files));
@@ -473,8 +443,9 @@ pub fn scan_path_streaming(
     PathMetrics {
         logical_size: logical,
         disk_size: disk,
-        compression_state: resolve_mixed_state(algos),
+        compression_state: breakdown.state(),
         file_count: files,
+        breakdown,
     }
 }
 
@@ -484,7 +455,7 @@ pub fn scan_directory_for_processing(
     state: Option<&Arc<AtomicU8>>,
     process_hidden_files: bool,
 ) -> ScanStats {
-    let ctx = run_multi_threaded_scan(path, state, true, None, process_hidden_files);
+    let ctx = run_multi_threaded_scan(default_backend(), default_enumerator(), path, state, Collect { paths: true, dirs: false }, None, process_hidden_files);
     
     ScanStats {
         file_count: ctx.total_files.into_inner(),
@@ -494,6 +465,28 @@ pub fn scan_directory_for_processing(
     }
 }
 
+/// Per-directory totals of the tree under `path`, in no particular order. Reparse points are
+/// not followed, and a stopped scan returns only the directories it finished.
+pub fn scan_folder_totals(
+    path: &str,
+    state: Option<&Arc<AtomicU8>>,
+    process_hidden_files: bool,
+) -> Vec<FolderTotals> {
+    scan_folder_totals_with(default_backend(), default_enumerator(), path, state, process_hidden_files)
+}
+
+/// `scan_folder_totals` over an explicit backend and enumerator.
+pub fn scan_folder_totals_with(
+    backend: Arc<dyn CompressionBackend>,
+    enumerator: Arc<dyn DirEnumerator>,
+    path: &str,
+    state: Option<&Arc<AtomicU8>>,
+    process_hidden_files: bool,
+) -> Vec<FolderTotals> {
+    let ctx = run_multi_threaded_scan(backend, enumerator, path, state, Collect { paths: false, dirs: true }, None, process_hidden_files);
+    ctx.dir_totals.into_inner().unwrap()
+}
+
 // ===== UTILS =====
 
 pub fn detect_path_algorithm(path: &str) -> CompressionState {
@@ -501,49 +494,181 @@ pub fn detect_path_algorithm(path: &str) -> CompressionState {
 }
 
 pub fn calculate_path_disk_size(path: &str, process_hidden_files: bool) -> u64 {
+    let backend = default_backend();
     if std::path::Path::new(path).is_file() {
-        get_real_file_size(path)
+        backend.disk_size(path)
     } else {
         let mut sum = 0;
         walk_directory(path, None, process_hidden_files, &mut |p, is_dir, _| {
-            if !is_dir { sum += get_real_file_size(p); }
+            if !is_dir && let Some(p) = path_str(p) { sum += backend.disk_size(p); }
         });
         sum
     }
 }
 
-fn resolve_mixed_state(algos: std::collections::HashSet<u32>) -> CompressionState {
-    if algos.is_empty() { return CompressionState::None; }
-    if algos.len() > 1 { return CompressionState::Mixed; }
-    
-    match algos.into_iter().next().unwrap() {
-        0 => CompressionState::Specific(WofAlgorithm::Xpress4K),
-        1 => CompressionState::Specific(WofAlgorithm::Lzx),
-        2 => CompressionState::Specific(WofAlgorithm::Xpress8K),
-        3 => CompressionState::Specific(WofAlgorithm::Xpress16K),
-        4 => CompressionState::Specific(WofAlgorithm::Lznt1),
-        _ => CompressionState::None,
-    }
-}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::backend::MemoryBackend;
+    use crate::engine::wof::{AlgoTally, WofAlgorithm};
+    use crate::engine::enumerator::StdEnumerator;
+    use std::collections::HashMap;
+    use std::ffi::OsStr;
+
+    /// Fixed in-memory tree so attribute handling does not depend on the host filesystem.
+    struct FakeEnumerator {
+        dirs: HashMap<String, Vec<DirEntry>>,
+    }
+
+    impl FakeEnumerator {
+        fn entry(name: &str, size: u64, attributes: u32) -> DirEntry {
+            DirEntry { name: name.into(), size, attributes, modified: 0 }
+        }
+    }
+
+    impl DirEnumerator for FakeEnumerator {
+        fn for_each_entry(&self, dir: &Path, visit: &mut dyn FnMut(&DirEntry) -> bool) {
+            for e in dir.to_str().and_then(|d| self.dirs.get(d)).into_iter().flatten() {
+                if !visit(e) { break; }
+            }
+        }
+
+        fn join(&self, dir: &Path, name: &OsStr) -> PathBuf {
+            PathBuf::from([&dir.to_string_lossy(), "\\", &name.to_string_lossy()].concat())
+        }
+    }
+
+    fn fake_tree() -> FakeEnumerator {
+        use crate::types::*;
+        let mut dirs = HashMap::new();
+        dirs.insert("R".to_string(), vec![
+            FakeEnumerator::entry("a.txt", 100, FILE_ATTRIBUTE_REPARSE_POINT),
+            FakeEnumerator::entry("hidden.txt", 10, FILE_ATTRIBUTE_HIDDEN),
+            FakeEnumerator::entry("sub", 0, FILE_ATTRIBUTE_DIRECTORY),
+            FakeEnumerator::entry("link", 0, FILE_ATTRIBUTE_DIRECTORY | FILE_ATTRIBUTE_REPARSE_POINT),
+            FakeEnumerator::entry("sys", 0, FILE_ATTRIBUTE_DIRECTORY | FILE_ATTRIBUTE_SYSTEM),
+        ]);
+        dirs.insert("R\\sub".to_string(), vec![FakeEnumerator::entry("b.bin", 300, FILE_ATTRIBUTE_NORMAL)]);
+        dirs.insert("R\\link".to_string(), vec![FakeEnumerator::entry("loop.bin", 999, FILE_ATTRIBUTE_NORMAL)]);
+        dirs.insert("R\\sys".to_string(), vec![FakeEnumerator::entry("s.bin", 50, FILE_ATTRIBUTE_NORMAL)]);
+        FakeEnumerator { dirs }
+    }
+
+    fn walk_files(e: &FakeEnumerator, hidden: bool, state: Option<&Arc<AtomicU8>>) -> Vec<String> {
+        let mut files = Vec::new();
+        walk_directory_with(e, "R", state, hidden, &mut |p, is_dir, _| {
+            if !is_dir { files.push(p.to_string_lossy().into_owned()); }
+        });
+        files.sort();
+        files
+    }
 
     #[test]
-    fn test_should_skip_extension() {
-        let list = "zip,7z,rar";
-        
-        // Enabled, match
-        assert!(should_skip_extension("test.zip", true, list));
-        assert!(should_skip_extension("TEST.ZIP", true, list));
-        assert!(should_skip_extension("archive.7z", true, list));
-        
-        // Enabled, no match
-        assert!(!should_skip_extension("test.txt", true, list));
-        assert!(!should_skip_extension("image.jpg", true, list)); // Not in custom list
-        
-        // Disabled
-        assert!(!should_skip_extension("test.zip", false, list));
+    fn test_walk_hidden_and_reparse() {
+        let e = fake_tree();
+        assert_eq!(walk_files(&e, false, None), vec!["R\\a.txt", "R\\sub\\b.bin"]);
+        // Reparse points are reported but never descended into
+        assert_eq!(walk_files(&e, true, None), vec!["R\\a.txt", "R\\hidden.txt", "R\\sub\\b.bin", "R\\sys\\s.bin"]);
+
+        let stopped = Arc::new(AtomicU8::new(ProcessingState::Stopped as u8));
+        assert!(walk_files(&e, true, Some(&stopped)).is_empty());
+    }
+
+    #[test]
+    fn test_multi_threaded_scan_fake_tree() {
+        let backend = Arc::new(MemoryBackend::new());
+        backend.insert("R\\a.txt", 100, 0.5);
+        backend.insert("R\\sub\\b.bin", 300, 1.0);
+        backend.set_state("R\\a.txt", Some(WofAlgorithm::Xpress8K));
+
+        let ctx = run_multi_threaded_scan(backend, Arc::new(fake_tree()), "R", None, Collect { paths: true, dirs: false }, None, false);
+        let mut paths = ctx.collected_paths.into_inner().unwrap();
+        paths.sort();
+
+        assert_eq!(paths, vec!["R\\a.txt", "R\\sub\\b.bin"]);
+        assert_eq!(ctx.total_files.into_inner(), 2);
+        assert_eq!(ctx.total_logical.into_inner(), 400);
+        let breakdown = ctx.breakdown.into_inner().unwrap();
+        assert_eq!(breakdown.state(), CompressionState::Specific(WofAlgorithm::Xpress8K));
+        assert_eq!(breakdown.get(Some(WofAlgorithm::Xpress8K)).files, 1);
+        assert_eq!(breakdown.get(None), AlgoTally { files: 1, logical: 300, disk: 300 });
+    }
+
+    #[test]
+    fn test_folder_totals_fake_tree() {
+        let backend = Arc::new(MemoryBackend::new());
+        backend.insert("R\\a.txt", 100, 0.5);
+        backend.insert("R\\sub\\b.bin", 300, 1.0);
+        backend.insert("R\\sys\\s.bin", 50, 1.0);
+
+        let mut dirs = scan_folder_totals_with(backend, Arc::new(fake_tree()), "R", None, true);
+        dirs.sort_by(|a, b| a.path.cmp(&b.path));
+
+        assert_eq!(dirs.iter().map(|d| d.path.as_str()).collect::<Vec<_>>(), vec!["R", "R\\sub", "R\\sys"]);
+        // The hidden file has no allocation here, so it does not count as uncompressed
+        assert_eq!((dirs[0].files, dirs[0].logical, dirs[0].disk, dirs[0].uncompressed), (2, 110, 100, 100));
+        assert_eq!(dirs[0].children, vec!["R\\sub", "R\\sys"]);
+        assert_eq!((dirs[1].files, dirs[1].logical, dirs[1].uncompressed), (1, 300, 300));
+        assert!(dirs[1].children.is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_multi_threaded_scan_temp_dir() {
+        let root = std::env::temp_dir().join(["compactrs_scan_", &std::process::id().to_string()].concat());
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("d1").join("d2")).unwrap();
+        std::fs::write(root.join("top.txt"), vec![0u8; 1000]).unwrap();
+        std::fs::write(root.join("d1").join("mid.txt"), vec![0u8; 2000]).unwrap();
+        std::fs::write(root.join("d1").join("d2").join("deep.txt"), vec![0u8; 3000]).unwrap();
+
+        let root_s = root.to_string_lossy().into_owned();
+        let backend = Arc::new(MemoryBackend::new());
+        for name in [root.join("top.txt"), root.join("d1").join("mid.txt")] {
+            backend.insert(&name.to_string_lossy(), 4096, 1.0);
+        }
+
+        let ctx = run_multi_threaded_scan(backend, Arc::new(StdEnumerator), &root_s, None, Collect { paths: true, dirs: false }, None, true);
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(ctx.total_files.into_inner(), 3);
+        assert_eq!(ctx.total_logical.into_inner(), 6000);
+        // Only registered paths report an allocation
+        assert_eq!(ctx.total_disk.into_inner(), 8192);
+        assert_eq!(ctx.collected_paths.into_inner().unwrap().len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_keeps_non_unicode_names() {
+        use std::os::unix::ffi::OsStrExt;
+        let root = std::env::temp_dir().join(["compactrs_names_", &std::process::id().to_string()].concat());
+        let _ = std::fs::remove_dir_all(&root);
+        // Counterpart of an unpaired surrogate on Windows
+        let odd = root.join(OsStr::from_bytes(b"odd\xff"));
+        std::fs::create_dir_all(&odd).unwrap();
+        std::fs::write(odd.join("inner.txt"), b"x").unwrap();
+
+        let mut files = Vec::new();
+        walk_directory_with(&StdEnumerator, &root.to_string_lossy(), None, true, &mut |p, is_dir, _| {
+            if !is_dir { files.push(p.to_path_buf()); }
+        });
+        // The visitor gets the real path, not a lossy copy that does not exist
+        let found = files.len() == 1 && files[0].is_file();
+        assert!(path_str(&odd).is_none());
+        let _ = std::fs::remove_dir_all(&root);
+        assert!(found);
+        assert!(files[0].ends_with("inner.txt"));
+    }
+
+    #[test]
+    fn test_is_critical_path() {
+        assert!(is_critical_path("C:\\Windows\\System32\\kernel32.dll"));
+        assert!(is_critical_path("c:\\windows\\WinSxS\\x\\y.dll"));
+        assert!(is_critical_path("C:\\bootmgr"));
+        assert!(is_critical_path("D:\\EFI\\Boot\\bootx64.efi"));
+
+        assert!(!is_critical_path("C:\\Windows\\Fonts\\arial.ttf"));
+        assert!(!is_critical_path("D:\\Games\\data.pak"));
+    }
+}
@@ -0,0 +1,162 @@
+/* --- src/engine/sniff.rs --- */
+//! Magic-byte detection of already-compressed content.
+//!
+//! Extension lists miss renamed payloads (`.pak`, `.bundle`, `.dat`, ...) that are really
+//! archives or encoded media. `sniff` classifies the first bytes of a file so the batch
+//! pipeline can skip it and the estimator can treat it as incompressible.
+
+use std::fs::File;
+use std::io::Read;
+
+/// Bytes of the file header needed by `sniff`.
+pub const HEADER_LEN: usize = 16;
+
+/// Known compressed archive, stream or media container.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentKind {
+    Zip,
+    SevenZip,
+    Rar,
+    Cab,
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+    Lz4,
+    Oodle,
+    UnityFs,
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Woff2,
+    Ogg,
+    Flac,
+    Mp3,
+    Mp4,
+    Matroska,
+}
+
+impl ContentKind {
+    /// Short name used in skip reasons, e.g. "Compressed content (zstd)".
+    pub fn label(self) -> &'static str {
+        match self {
+            ContentKind::Zip => "zip",
+            ContentKind::SevenZip => "7z",
+            ContentKind::Rar => "rar",
+            ContentKind::Cab => "cab",
+            ContentKind::Gzip => "gzip",
+            ContentKind::Bzip2 => "bzip2",
+            ContentKind::Xz => "xz",
+            ContentKind::Zstd => "zstd",
+            ContentKind::Lz4 => "lz4",
+            ContentKind::Oodle => "oodle",
+            ContentKind::UnityFs => "unityfs",
+            ContentKind::Png => "png",
+            ContentKind::Jpeg => "jpeg",
+            ContentKind::Gif => "gif",
+            ContentKind::Webp => "webp",
+            ContentKind::Woff2 => "woff2",
+            ContentKind::Ogg => "ogg",
+            ContentKind::Flac => "flac",
+            ContentKind::Mp3 => "mp3",
+            ContentKind::Mp4 => "mp4",
+            ContentKind::Matroska => "mkv",
+        }
+    }
+}
+
+const SIGNATURES: &[(&[u8], ContentKind)] = &[
+    (b"PK\x03\x04", ContentKind::Zip),
+    (b"PK\x05\x06", ContentKind::Zip),
+    (b"PK\x07\x08", ContentKind::Zip),
+    (b"7z\xBC\xAF\x27\x1C", ContentKind::SevenZip),
+    (b"Rar!\x1A\x07", ContentKind::Rar),
+    (b"MSCF", ContentKind::Cab),
+    (b"\x1F\x8B\x08", ContentKind::Gzip),
+    (b"\xFD7zXZ\x00", ContentKind::Xz),
+    (b"\x28\xB5\x2F\xFD", ContentKind::Zstd),
+    (b"\x04\x22\x4D\x18", ContentKind::Lz4),
+    (b"UnityFS\x00", ContentKind::UnityFs),
+    (b"\x89PNG\r\n\x1A\n", ContentKind::Png),
+    (b"\xFF\xD8\xFF", ContentKind::Jpeg),
+    (b"GIF87a", ContentKind::Gif),
+    (b"GIF89a", ContentKind::Gif),
+    (b"wOF2", ContentKind::Woff2),
+    (b"OggS", ContentKind::Ogg),
+    (b"fLaC", ContentKind::Flac),
+    (b"ID3", ContentKind::Mp3),
+    (b"\x1A\x45\xDF\xA3", ContentKind::Matroska),
+];
+
+/// Classifies a file header. Returns `None` for anything not known to be compressed.
+pub fn sniff(header: &[u8]) -> Option<ContentKind> {
+    if let Some(&(_, kind)) = SIGNATURES.iter().find(|(magic, _)| header.starts_with(magic)) {
+        return Some(kind);
+    }
+
+    match header {
+        // "BZh" + block size digit
+        [b'B', b'Z', b'h', b'1'..=b'9', ..] => Some(ContentKind::Bzip2),
+        // Oodle block header: 0x8C/0xCC followed by the Kraken/Mermaid/Selkie/Leviathan id
+        [0x8C | 0xCC, 0x06 | 0x0A | 0x0C, ..] => Some(ContentKind::Oodle),
+        // MPEG audio frame sync without an ID3 tag (Layer III, MPEG-1/2)
+        [0xFF, 0xFB | 0xFA | 0xF3 | 0xF2, ..] => Some(ContentKind::Mp3),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(ContentKind::Webp),
+        // ISO base media (MP4, MOV, HEIC, AVIF)
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => Some(ContentKind::Mp4),
+        _ => None,
+    }
+}
+
+/// Reads the header of an open file (from its current position) and classifies it.
+pub fn sniff_file(f: &mut File) -> Option<ContentKind> {
+    let mut header = [0u8; HEADER_LEN];
+    let mut len = 0;
+    while len < HEADER_LEN {
+        match f.read(&mut header[len..]) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => len += n,
+        }
+    }
+    sniff(&header[..len])
+}
+
+/// Classifies the file at `path`; unreadable files are `None`.
+pub fn sniff_path(path: &str) -> Option<ContentKind> {
+    File::open(path).ok().and_then(|mut f| sniff_file(&mut f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_signatures() {
+        let cases: &[(&[u8], ContentKind)] = &[
+            (b"PK\x03\x04\x14\x00", ContentKind::Zip),
+            (b"\x28\xB5\x2F\xFD\x24\x00", ContentKind::Zstd),
+            (b"BZh91AY&SY", ContentKind::Bzip2),
+            (b"\x8C\x0A\x00\x00", ContentKind::Oodle),
+            (b"UnityFS\x00\x00\x00\x00\x08", ContentKind::UnityFs),
+            (b"RIFF\x24\x00\x00\x00WEBPVP8 ", ContentKind::Webp),
+            (b"\x00\x00\x00\x20ftypisom", ContentKind::Mp4),
+            (b"\xFF\xFB\x90\x00", ContentKind::Mp3),
+        ];
+        for &(header, kind) in cases {
+            assert_eq!(sniff(header), Some(kind), "{:?}", kind);
+        }
+        assert_eq!(ContentKind::Zstd.label(), "zstd");
+    }
+
+    #[test]
+    fn test_plain_content() {
+        assert_eq!(sniff(b""), None);
+        assert_eq!(sniff(b"MZ\x90\x00\x03\x00"), None);
+        assert_eq!(sniff(b"{\"name\": \"pkg\"}"), None);
+        // RIFF alone is WAV/AVI, which are not reliably compressed
+        assert_eq!(sniff(b"RIFF\x24\x00\x00\x00WAVEfmt "), None);
+        // Too short to match the signature
+        assert_eq!(sniff(b"PK\x03"), None);
+    }
+}
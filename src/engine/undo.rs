@@ -0,0 +1,267 @@
+/* --- src/engine/undo.rs --- */
+//! Undo log: what the last compress run found before changing each file.
+//!
+//! Decompressing strips WOF and LZNT1 alike, so a run that converted LZNT1 files to LZX
+//! cannot be reverted by decompressing. While a compress batch runs, the worker records the
+//! prior state of every file it compressed (uncompressed, LZNT1 or a WOF algorithm, and the
+//! read-only attribute a forced run may clear) and of every folder whose compressed
+//! attribute it set. "Undo Last Batch" (`worker::undo_changes`) replays the log to put each
+//! one back the way it was.
+//!
+//! The log lives in `compactrs_undo.log` next to the executable and covers one run: the
+//! first change after `begin_run` starts it over, so runs that change nothing (dry runs,
+//! verifies, already compressed folders) keep the previous run undoable. Line based, tab
+//! separated:
+//!
+//! ```text
+//! CRU 1
+//! F <prior algorithm or "none"> <read-only 0|1> <file path>
+//! D <compressed 0|1> <folder path>
+//! ```
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::engine::wof::WofAlgorithm;
+
+/// Format version written after the header tag.
+pub const UNDO_VERSION: u32 = 1;
+
+const HEADER: &str = "CRU";
+const UNDO_FILE: &str = "compactrs_undo.log";
+/// Changes buffered before they are flushed to disk.
+const FLUSH_EVERY: u32 = 256;
+
+/// State of one path before the run changed it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UndoEntry {
+    File { path: String, prior: Option<WofAlgorithm>, readonly: bool },
+    Folder { path: String, compressed: bool },
+}
+
+impl UndoEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            UndoEntry::File { path, .. } | UndoEntry::Folder { path, .. } => path,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        match self {
+            UndoEntry::File { path, prior, readonly } => {
+                let prior = prior.map_or("none", |a| a.name());
+                ["F\t", prior, "\t", if *readonly { "1" } else { "0" }, "\t", path, "\n"].concat()
+            }
+            UndoEntry::Folder { path, compressed } => {
+                ["D\t", if *compressed { "1" } else { "0" }, "\t", path, "\n"].concat()
+            }
+        }
+    }
+}
+
+/// Changes of one run, in the order they were made.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UndoLog {
+    pub entries: Vec<UndoEntry>,
+}
+
+impl UndoLog {
+    /// Parses log text. Another header or version is `None`; malformed lines are ignored,
+    /// and a path recorded twice keeps its first (original) state.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let version = lines.next()?.strip_prefix(HEADER)?.trim().parse::<u32>().ok()?;
+        if version != UNDO_VERSION { return None; }
+
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let entry = match fields.as_slice() {
+                ["F", prior, readonly @ ("0" | "1"), path] if !path.is_empty() => {
+                    let prior = match *prior {
+                        "none" => None,
+                        name => match WofAlgorithm::from_name(name) {
+                            Some(algo) => Some(algo),
+                            None => continue,
+                        },
+                    };
+                    UndoEntry::File { path: path.to_string(), prior, readonly: *readonly == "1" }
+                }
+                ["D", compressed @ ("0" | "1"), path] if !path.is_empty() => {
+                    UndoEntry::Folder { path: path.to_string(), compressed: *compressed == "1" }
+                }
+                _ => continue,
+            };
+            if seen.insert(entry.path().to_ascii_lowercase()) {
+                entries.push(entry);
+            }
+        }
+        Some(Self { entries })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = header();
+        for entry in &self.entries {
+            out.push_str(&entry.to_line());
+        }
+        out
+    }
+
+    pub fn files(&self) -> usize {
+        self.entries.iter().filter(|e| matches!(e, UndoEntry::File { .. })).count()
+    }
+
+    pub fn folders(&self) -> usize {
+        self.entries.len() - self.files()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn load_from(path: &Path) -> Option<Self> {
+        Self::parse(&std::fs::read_to_string(path).ok()?)
+    }
+}
+
+fn header() -> String {
+    [HEADER, " ", &UNDO_VERSION.to_string(), "\n"].concat()
+}
+
+/// Appends changes to a log file.
+pub struct UndoRecorder {
+    path: PathBuf,
+    out: Option<BufWriter<File>>,
+    /// The next change starts a new log.
+    fresh: bool,
+    unflushed: u32,
+}
+
+impl UndoRecorder {
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf(), out: None, fresh: true, unflushed: 0 }
+    }
+
+    pub fn begin_run(&mut self) {
+        self.flush();
+        self.out = None;
+        self.fresh = true;
+    }
+
+    pub fn record(&mut self, entry: &UndoEntry) {
+        if self.out.is_none() {
+            let opened = if self.fresh {
+                File::create(&self.path).and_then(|mut f| f.write_all(header().as_bytes()).map(|_| f))
+            } else {
+                std::fs::OpenOptions::new().append(true).open(&self.path)
+            };
+            match opened {
+                Ok(f) => self.out = Some(BufWriter::new(f)),
+                Err(e) => {
+                    crate::log_error!(&["Cannot write the undo log: ", &e.to_string()].concat());
+                    return;
+                }
+            }
+            self.fresh = false;
+        }
+        let Some(out) = self.out.as_mut() else { return };
+        if out.write_all(entry.to_line().as_bytes()).is_ok() {
+            self.unflushed += 1;
+            if self.unflushed >= FLUSH_EVERY { self.flush(); }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(out) = self.out.as_mut() { let _ = out.flush(); }
+        self.unflushed = 0;
+    }
+
+    /// The log as written so far (`None` if there is none).
+    pub fn load(&mut self) -> Option<UndoLog> {
+        self.flush();
+        UndoLog::load_from(&self.path)
+    }
+
+    /// Forgets the log once it has been undone.
+    pub fn clear(&mut self) {
+        self.out = None;
+        self.fresh = true;
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn default_path() -> PathBuf {
+    let mut path = std::env::current_exe().unwrap_or_default();
+    path.set_file_name(UNDO_FILE);
+    path
+}
+
+static GLOBAL: Mutex<Option<UndoRecorder>> = Mutex::new(None);
+
+/// Process-wide recorder for `compactrs_undo.log`.
+pub fn global() -> MutexGuard<'static, Option<UndoRecorder>> {
+    let mut g = GLOBAL.lock().unwrap_or_else(|e| e.into_inner());
+    if g.is_none() { *g = Some(UndoRecorder::new(&default_path())); }
+    g
+}
+
+/// Marks the start of a run (one Process click, queued rows included).
+pub fn begin_run() {
+    if let Some(r) = global().as_mut() { r.begin_run(); }
+}
+
+pub fn record(entry: &UndoEntry) {
+    if let Some(r) = global().as_mut() { r.record(entry); }
+}
+
+pub fn flush() {
+    if let Some(r) = global().as_mut() { r.flush(); }
+}
+
+/// Changes of the last run that changed anything.
+pub fn last() -> Option<UndoLog> {
+    global().as_mut().and_then(|r| r.load()).filter(|log| !log.is_empty())
+}
+
+pub fn clear() {
+    if let Some(r) = global().as_mut() { r.clear(); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorder_runs_and_parse() {
+        let path = std::env::temp_dir().join(["compactrs_undo_test_", &std::process::id().to_string(), ".log"].concat());
+        let mut r = UndoRecorder::new(&path);
+        r.record(&UndoEntry::File { path: "D:\\old.dll".to_string(), prior: None, readonly: false });
+
+        // A run that changes nothing keeps the previous log
+        r.begin_run();
+        assert_eq!(r.load().unwrap().files(), 1);
+
+        r.begin_run();
+        let dir = UndoEntry::Folder { path: "D:\\games".to_string(), compressed: false };
+        let lznt1 = UndoEntry::File { path: "D:\\games\\a.exe".to_string(), prior: Some(WofAlgorithm::Lznt1), readonly: true };
+        r.record(&dir);
+        r.record(&lznt1);
+        // Compressed again by an overlapping row: the original state wins
+        r.record(&UndoEntry::File { path: "D:\\Games\\A.exe".to_string(), prior: Some(WofAlgorithm::Lzx), readonly: false });
+        let log = r.load().unwrap();
+        assert_eq!(log.entries, vec![dir, lznt1]);
+        assert_eq!((log.files(), log.folders()), (1, 1));
+        assert_eq!(UndoLog::parse(&log.to_text()), Some(log));
+
+        // A torn last line is dropped
+        assert_eq!(UndoLog::parse("CRU 1\nF\tlzx\t0\tD:\\a\nF\txpress8k\t").unwrap().entries.len(), 1);
+        assert_eq!(UndoLog::parse("CRU 2\n"), None);
+
+        r.clear();
+        assert_eq!(r.load(), None);
+    }
+}
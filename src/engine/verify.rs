@@ -0,0 +1,148 @@
+/* --- src/engine/verify.rs --- */
+//! Content hashes for integrity checks of compressed files.
+//!
+//! A compress with verification on hashes the file before compressing and again after,
+//! reading through the transparent WOF layer like any application would. The `Verify`
+//! action has no "before", so it checks that a compressed file reads back completely.
+//! Where the `WofCompressedData` stream can be opened, decoding the stored chunks with our
+//! own codecs is an extra check that is only logged: the driver read decides.
+//!
+//! The hash is a fast 64-bit mix, enough to catch corruption; it is not meant to resist
+//! deliberate collisions.
+
+use std::io::Read;
+
+use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof_stream::{self, WofStreamError};
+
+const SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+const MUL: u64 = 0xBF58_476D_1CE4_E5B9;
+
+/// Incremental content hash: the result does not depend on how the input is split.
+#[derive(Clone, Debug)]
+pub struct ContentHasher {
+    state: u64,
+    len: u64,
+    tail: [u8; 8],
+    tail_len: usize,
+}
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self { state: SEED, len: 0, tail: [0; 8], tail_len: 0 }
+    }
+}
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn mix(&mut self, word: u64) {
+        self.state = (self.state ^ word).wrapping_mul(MUL).rotate_left(29);
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+        if self.tail_len > 0 {
+            let take = (8 - self.tail_len).min(data.len());
+            self.tail[self.tail_len..self.tail_len + take].copy_from_slice(&data[..take]);
+            self.tail_len += take;
+            data = &data[take..];
+            if self.tail_len < 8 { return; }
+            self.mix(u64::from_le_bytes(self.tail));
+            self.tail_len = 0;
+        }
+        let mut words = data.chunks_exact(8);
+        for w in &mut words {
+            self.mix(u64::from_le_bytes([w[0], w[1], w[2], w[3], w[4], w[5], w[6], w[7]]));
+        }
+        let rest = words.remainder();
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    pub fn finish(mut self) -> u64 {
+        if self.tail_len > 0 {
+            let mut last = [0u8; 8];
+            last[..self.tail_len].copy_from_slice(&self.tail[..self.tail_len]);
+            self.mix(u64::from_le_bytes(last));
+        }
+        let len = self.len;
+        self.mix(len);
+        let h = self.state;
+        (h ^ (h >> 31)).wrapping_mul(MUL) ^ (h >> 29)
+    }
+}
+
+pub fn hash_bytes(data: &[u8]) -> u64 {
+    let mut h = ContentHasher::new();
+    h.update(data);
+    h.finish()
+}
+
+/// Hash of everything `r` yields.
+pub fn hash_reader(r: &mut dyn Read) -> std::io::Result<u64> {
+    let mut h = ContentHasher::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        match r.read(&mut buf) {
+            Ok(0) => return Ok(h.finish()),
+            Ok(n) => h.update(&buf[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Hash of the original bytes stored in a `WofCompressedData` stream read from `r`.
+pub fn hash_wof_stream(r: &mut dyn Read, original_size: u64, algo: WofAlgorithm) -> Result<u64, WofStreamError> {
+    let mut h = ContentHasher::new();
+    wof_stream::decode_reader(r, original_size, algo, &mut |chunk| h.update(chunk))?;
+    Ok(h.finish())
+}
+
+/// Hash of a file as applications see it (WOF files are decompressed by the driver).
+/// `None` if it cannot be read to the end.
+pub fn hash_path(path: &str) -> Option<u64> {
+    let mut file = std::fs::File::open(path).ok()?;
+    hash_reader(&mut file).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_is_split_independent_and_detects_changes() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let whole = hash_bytes(&data);
+
+        let mut h = ContentHasher::new();
+        for part in data.chunks(13) {
+            h.update(part);
+        }
+        assert_eq!(h.finish(), whole);
+        assert_eq!(hash_reader(&mut &data[..]).unwrap(), whole);
+
+        let mut flipped = data.clone();
+        flipped[5000] ^= 1;
+        assert_ne!(hash_bytes(&flipped), whole);
+        // Trailing zeros change the length, so they change the hash
+        assert_ne!(hash_bytes(&[1, 2, 3]), hash_bytes(&[1, 2, 3, 0]));
+        assert_ne!(hash_bytes(&[]), hash_bytes(&[0]));
+    }
+
+    #[test]
+    fn test_hash_wof_stream() {
+        let data = b"verify the stored chunks against the original\r\n".repeat(2000);
+        let stream = wof_stream::encode(&data, WofAlgorithm::Xpress4K).unwrap();
+        assert_eq!(hash_wof_stream(&mut &stream[..], data.len() as u64, WofAlgorithm::Xpress4K), Ok(hash_bytes(&data)));
+
+        // Flip a byte in the first chunk's Huffman table
+        let mut damaged = stream.clone();
+        let first = wof_stream::WofStream::parse(&stream, data.len() as u64, WofAlgorithm::Xpress4K).unwrap().chunks()[0];
+        damaged[first.offset as usize + 40] ^= 0x55;
+        assert_ne!(hash_wof_stream(&mut &damaged[..], data.len() as u64, WofAlgorithm::Xpress4K), Ok(hash_bytes(&data)));
+    }
+}
@@ -35,7 +35,7 @@ pub fn start_watcher_thread(tasks: Arc<Mutex<Vec<WatcherTask>>>, tx: Sender<UiMe
             let mut system_time: SYSTEMTIME = unsafe { std::mem::zeroed() };
             
             // Manual binding for GetLocalTime
-            #[link(name = "kernel32")]
+            #[cfg_attr(windows, link(name = "kernel32"))]
             unsafe extern "system" {
                 fn GetLocalTime(lpsystemtime: *mut SYSTEMTIME);
             }
@@ -1,19 +1,28 @@
 /* --- src/engine/wof.rs --- */
 #![allow(non_snake_case, non_camel_case_types)]
+#[cfg(windows)]
 use std::ffi::c_void;
+#[cfg(windows)]
 use std::fs::File;
+#[cfg(windows)]
 use std::mem::size_of;
+#[cfg(windows)]
 use std::os::windows::io::AsRawHandle;
+#[cfg(windows)]
 use std::os::windows::io::FromRawHandle;
+#[cfg(windows)]
 use std::os::windows::fs::OpenOptionsExt; 
 use crate::types::*;
 
 // --- Manual Bindings & Constants ---
 
 // IOCTL Codes
+#[cfg(windows)]
 const FSCTL_SET_COMPRESSION: u32 = 0x9C040;
+#[cfg(windows)]
 const FSCTL_SET_EXTERNAL_BACKING: u32 = 0x9030C;
 const FSCTL_GET_EXTERNAL_BACKING: u32 = 0x90310;
+#[cfg(windows)]
 const FSCTL_DELETE_EXTERNAL_BACKING: u32 = 0x90314;
 
 // Security Constants
@@ -52,6 +61,55 @@ pub fn get_real_file_size(path: &str) -> u64 {
     }
 }
 
+/// Cluster size of the volume holding `path`, or `None` if it cannot be queried.
+#[cfg(windows)]
+pub fn get_cluster_size(path: &str) -> Option<u64> {
+    unsafe {
+        let wide = PathBuffer::from(path);
+        let mut root = [0u16; 512];
+        if crate::types::GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) == 0 {
+            return None;
+        }
+
+        let (mut sectors, mut bytes, mut free, mut total) = (0u32, 0u32, 0u32, 0u32);
+        if crate::types::GetDiskFreeSpaceW(root.as_ptr(), &mut sectors, &mut bytes, &mut free, &mut total) == 0 {
+            return None;
+        }
+        let cluster = sectors as u64 * bytes as u64;
+        if cluster == 0 { None } else { Some(cluster) }
+    }
+}
+
+#[cfg(not(windows))]
+pub fn get_cluster_size(_path: &str) -> Option<u64> {
+    None
+}
+
+/// Root of the volume holding `path` (e.g. `C:\` or a mount point).
+pub fn get_volume_root(path: &str) -> Option<String> {
+    unsafe {
+        let wide = PathBuffer::from(path);
+        let mut root = [0u16; 512];
+        if crate::types::GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) == 0 {
+            return None;
+        }
+        let len = root.iter().position(|&c| c == 0).unwrap_or(root.len());
+        Some(String::from_utf16_lossy(&root[..len]))
+    }
+}
+
+/// Free bytes on the volume at `root`, or `None` if it cannot be queried.
+pub fn get_free_space(root: &str) -> Option<u64> {
+    unsafe {
+        let wide = PathBuffer::from(root);
+        let mut free = 0u64;
+        if crate::types::GetDiskFreeSpaceExW(wide.as_ptr(), std::ptr::null_mut(), std::ptr::null_mut(), &mut free) == 0 {
+            return None;
+        }
+        Some(free)
+    }
+}
+
 /// Get the WOF or LZNT1 compression algorithm used for a file
 /// Returns None if file is not compressed, Some(algorithm) if it is
 pub fn get_wof_algorithm(path: &str) -> Option<WofAlgorithm> {
@@ -96,12 +154,73 @@ pub fn detect_compression_state(path: &str) -> CompressionState {
     CompressionState::None
 }
 
+/// Metadata, compression algorithm and size on disk of a file, all read through one handle
+/// opened for attributes only, so files other processes hold open can be queried too.
+/// Returns None if the file cannot be opened.
+#[cfg(windows)]
+pub fn query_file(path: &str) -> Option<(std::fs::Metadata, Option<WofAlgorithm>, u64)> {
+    let file = std::fs::OpenOptions::new()
+        .access_mode(FILE_READ_ATTRIBUTES)
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .ok()?;
+    let meta = file.metadata().ok()?;
+    let handle = file.as_raw_handle() as HANDLE;
+
+    let algorithm = get_external_backing_algorithm(handle).or_else(|| {
+        use std::os::windows::fs::MetadataExt;
+        (meta.file_attributes() & FILE_ATTRIBUTE_COMPRESSED != 0).then_some(WofAlgorithm::Lznt1)
+    });
+
+    // What GetCompressedFileSizeW reports: the compressed size, or the logical size where
+    // the file system has none
+    let disk = unsafe {
+        let mut info: FILE_COMPRESSION_INFO = std::mem::zeroed();
+        let ok = crate::types::GetFileInformationByHandleEx(
+            handle,
+            FileCompressionInfo,
+            &mut info as *mut _ as *mut c_void,
+            size_of::<FILE_COMPRESSION_INFO>() as u32,
+        );
+        if ok != 0 { info.CompressedFileSize as u64 } else { meta.len() }
+    };
+    Some((meta, algorithm, disk))
+}
+
+/// Without WOF every file is stored uncompressed.
+#[cfg(not(windows))]
+pub fn query_file(path: &str) -> Option<(std::fs::Metadata, Option<WofAlgorithm>, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    let len = meta.len();
+    Some((meta, None, len))
+}
+
 /// Get the WOF or LZNT1 compression algorithm from an already-opened file handle.
 /// Returns None if file is not compressed.
 /// 
 /// # Safety
 /// The handle must be a valid, open file handle with at least read access.
 pub fn get_wof_algorithm_from_handle(handle: HANDLE) -> Option<WofAlgorithm> {
+    if let Some(algo) = get_external_backing_algorithm(handle) {
+        return Some(algo);
+    }
+
+    unsafe {
+        // Fallback to check NTFS LZNT1 compression
+        let mut info: crate::types::BY_HANDLE_FILE_INFORMATION = std::mem::zeroed();
+        if crate::types::GetFileInformationByHandle(handle, &mut info) != 0 {
+            if (info.dwFileAttributes & crate::types::FILE_ATTRIBUTE_COMPRESSED) != 0 {
+                return Some(WofAlgorithm::Lznt1);
+            }
+        }
+        
+        None
+    }
+}
+
+/// WOF algorithm of a file from its external backing, None if it has no WOF backing.
+fn get_external_backing_algorithm(handle: HANDLE) -> Option<WofAlgorithm> {
     unsafe {
         // Buffer for WOF_EXTERNAL_INFO + FILE_PROVIDER_EXTERNAL_INFO_V1
         let mut out_buffer = [0u8; 1024];
@@ -135,15 +254,6 @@ pub fn get_wof_algorithm_from_handle(handle: HANDLE) -> Option<WofAlgorithm> {
                 };
             }
         }
-        
-        // Fallback to check NTFS LZNT1 compression
-        let mut info: crate::types::BY_HANDLE_FILE_INFORMATION = std::mem::zeroed();
-        if crate::types::GetFileInformationByHandle(handle, &mut info) != 0 {
-            if (info.dwFileAttributes & crate::types::FILE_ATTRIBUTE_COMPRESSED) != 0 {
-                return Some(WofAlgorithm::Lznt1);
-            }
-        }
-        
         None
     }
 }
@@ -162,33 +272,9 @@ pub const COMPRESSION_FORMAT_NONE: u16 = 0;
 pub const COMPRESSION_FORMAT_DEFAULT: u16 = 1;
 pub const COMPRESSION_FORMAT_LZNT1: u16 = 2;
 
-#[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum WofAlgorithm {
-    Xpress4K = 0,
-    Lzx = 1,
-    Xpress8K = 2,
-    Xpress16K = 3,
-    Lznt1 = 4, // Legacy NTFS Compression
-}
-
-impl WofAlgorithm {
-    fn to_u32(self) -> u32 {
-        self as u32
-    }
-}
-
-/// Represents the compression state of a file or folder
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum CompressionState {
-    /// Not compressed (or not WOF compressed)
-    None,
-    /// Compressed with a specific WOF or LZNT1 algorithm (all files if folder)
-    Specific(WofAlgorithm),
-    /// Contains files with different compression algorithms (folder only)
-    Mixed,
-}
+pub use crate::engine::algorithm::{AlgoBreakdown, AlgoChoice, AlgoTally, CompressionState, WofAlgorithm};
 
+#[cfg(windows)]
 pub fn compress_file(path: &str, algo: WofAlgorithm, force: bool) -> Result<bool, u32> {
     // First attempt: Normal open with permissive sharing
     let file_result = std::fs::OpenOptions::new()
@@ -241,6 +327,7 @@ pub fn compress_file(path: &str, algo: WofAlgorithm, force: bool) -> Result<bool
 /// - `Ok(true)` if compression succeeded or file was already optimally compressed
 /// - `Ok(false)` if compression was not beneficial (OS driver decision)
 /// - `Err(error_code)` on failure
+#[cfg(windows)]
 pub fn smart_compress(path: &str, target_algo: WofAlgorithm, force: bool) -> Result<bool, u32> {
     // First attempt: Normal open with permissive sharing
     let file_result = std::fs::OpenOptions::new()
@@ -279,6 +366,7 @@ pub fn smart_compress(path: &str, target_algo: WofAlgorithm, force: bool) -> Res
 }
 
 /// Internal helper for smart_compress with backup semantics
+#[cfg(windows)]
 fn smart_compress_with_backup_semantics(path: &str, algo: WofAlgorithm, force: bool) -> Result<bool, u32> {
     // Remove read-only attribute if set
     force_remove_readonly(path);
@@ -341,6 +429,7 @@ fn smart_compress_with_backup_semantics(path: &str, algo: WofAlgorithm, force: b
 
 
 /// Force remove read-only attribute from a file
+#[cfg(windows)]
 fn force_remove_readonly(path: &str) {
     unsafe {
         let wide = PathBuffer::from(path);
@@ -354,6 +443,28 @@ fn force_remove_readonly(path: &str) {
     }
 }
 
+/// Replace the attributes of a file (`SetFileAttributesW`; 0 becomes FILE_ATTRIBUTE_NORMAL).
+pub fn set_file_attributes(path: &str, attributes: u32) -> Result<(), u32> {
+    unsafe {
+        let wide = PathBuffer::from(path);
+        let attributes = if attributes == 0 { FILE_ATTRIBUTE_NORMAL } else { attributes };
+        if crate::types::SetFileAttributesW(wide.as_ptr(), attributes) == 0 {
+            return Err(GetLastError());
+        }
+        Ok(())
+    }
+}
+
+/// True if `path` has the NTFS compressed attribute (LZNT1 files, and folders whose new
+/// files are compressed).
+pub fn has_compressed_attribute(path: &str) -> bool {
+    unsafe {
+        let wide = PathBuffer::from(path);
+        let attrs = crate::types::GetFileAttributesW(wide.as_ptr());
+        attrs != u32::MAX && (attrs & FILE_ATTRIBUTE_COMPRESSED) != 0
+    }
+}
+
 /// Set or unset the compressed file attribute (visual indicator only)
 /// Set or unset the compressed file attribute (via FSCTL_SET_COMPRESSION)
 pub fn set_compressed_attribute(path: &str, enable: bool) {
@@ -449,6 +560,7 @@ pub fn enable_backup_privileges() {
 }
 
 /// Compress file using CreateFileW with FILE_FLAG_BACKUP_SEMANTICS
+#[cfg(windows)]
 fn compress_file_with_backup_semantics(path: &str, algo: WofAlgorithm, force: bool) -> Option<Result<bool, u32>> {
     unsafe {
         let wide = PathBuffer::from(path);
@@ -478,6 +590,7 @@ fn compress_file_with_backup_semantics(path: &str, algo: WofAlgorithm, force: bo
     }
 }
 
+#[cfg(windows)]
 pub fn compress_file_handle(file: &File, algo: WofAlgorithm, force: bool) -> Result<bool, u32> {
     let handle = file.as_raw_handle() as HANDLE;
     let mut bytes_returned = 0u32;
@@ -547,7 +660,7 @@ pub fn compress_file_handle(file: &File, algo: WofAlgorithm, force: bool) -> Res
     // 2. Prepare FILE_PROVIDER_EXTERNAL_INFO_V1
     let file_info = FILE_PROVIDER_EXTERNAL_INFO_V1 {
         version: FILE_PROVIDER_CURRENT_VERSION,
-        algorithm: algo.to_u32(),
+        algorithm: algo as u32,
         flags: 0,
     };
 
@@ -609,6 +722,7 @@ pub fn compress_file_handle(file: &File, algo: WofAlgorithm, force: bool) -> Res
 }
 
 
+#[cfg(windows)]
 pub fn uncompress_file(path: &str) -> Result<(), u32> {
     // Requires Write permission for FSCTL_DELETE_EXTERNAL_BACKING
     // Use permissive sharing (Read|Write|Delete = 7) to allow processing locked files
@@ -621,6 +735,7 @@ pub fn uncompress_file(path: &str) -> Result<(), u32> {
     uncompress_file_handle(&file)
 }
 
+#[cfg(windows)]
 pub fn uncompress_file_handle(file: &File) -> Result<(), u32> {
     let handle = file.as_raw_handle() as HANDLE;
     let mut bytes_returned = 0u32;
@@ -661,4 +776,15 @@ pub fn uncompress_file_handle(file: &File) -> Result<(), u32> {
         );
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// WOF exists only on Windows; elsewhere every request fails as unsupported.
+#[cfg(not(windows))]
+pub fn smart_compress(_path: &str, _target_algo: WofAlgorithm, _force: bool) -> Result<bool, u32> {
+    Err(crate::engine::error::ERROR_NOT_SUPPORTED)
+}
+
+#[cfg(not(windows))]
+pub fn uncompress_file(_path: &str) -> Result<(), u32> {
+    Err(crate::engine::error::ERROR_NOT_SUPPORTED)
+}
@@ -0,0 +1,332 @@
+/* --- src/engine/wof_stream.rs --- */
+//! Offline reader for `WofCompressedData` streams.
+//!
+//! WOF stores a compressed file in the `WofCompressedData` alternate data stream and leaves
+//! the unnamed stream sparse. This module decodes such a stream without the WOF driver,
+//! e.g. from a backup copy of the stream.
+//!
+//! # Stream Layout
+//! - Chunk table: one entry per chunk except the first, giving the chunk's start offset
+//!   relative to the end of the table. Entries are u32, or u64 if the original file is
+//!   larger than 4 GiB.
+//! - Chunk data, back to back. Each chunk covers `chunk_size` bytes of the original file
+//!   (the last one the remainder). A chunk whose stored size equals its uncompressed size
+//!   is stored raw.
+
+use std::fmt;
+use std::io::Read;
+
+use crate::engine::codec::{lzx, xpress};
+use crate::engine::wof::WofAlgorithm;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WofStreamError {
+    /// LZNT1 is not a WOF provider format.
+    UnsupportedAlgorithm,
+    /// Chunk table is shorter than the original size requires.
+    Truncated,
+    /// Offsets are decreasing, out of range, or describe a chunk larger than its output.
+    BadChunkTable,
+    /// Chunk failed to decompress to its expected size.
+    CorruptChunk(usize),
+    /// Reading the stream failed.
+    Io,
+}
+
+impl fmt::Display for WofStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WofStreamError::UnsupportedAlgorithm => f.write_str("Unsupported algorithm"),
+            WofStreamError::Truncated => f.write_str("Truncated chunk table"),
+            WofStreamError::BadChunkTable => f.write_str("Bad chunk table"),
+            WofStreamError::CorruptChunk(index) => write!(f, "Corrupt chunk {}", index),
+            WofStreamError::Io => f.write_str("Read error"),
+        }
+    }
+}
+
+/// Location and sizes of a single chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkInfo {
+    /// Absolute offset of the chunk data within the stream.
+    pub offset: u64,
+    pub compressed_size: u32,
+    pub uncompressed_size: u32,
+}
+
+impl ChunkInfo {
+    pub fn is_stored_raw(&self) -> bool {
+        self.compressed_size == self.uncompressed_size
+    }
+}
+
+/// Parsed view over a `WofCompressedData` stream.
+pub struct WofStream<'a> {
+    data: &'a [u8],
+    algo: WofAlgorithm,
+    original_size: u64,
+    chunks: Vec<ChunkInfo>,
+}
+
+fn chunk_count(original_size: u64, chunk_size: usize) -> u64 {
+    original_size.div_ceil(chunk_size as u64)
+}
+
+fn table_entry_size(original_size: u64) -> usize {
+    if original_size > u32::MAX as u64 { 8 } else { 4 }
+}
+
+/// Size of the chunk table WOF stores ahead of the data for a file of `original_size` bytes.
+pub fn chunk_table_size(original_size: u64, algo: WofAlgorithm) -> u64 {
+    chunk_count(original_size, algo.chunk_size()).saturating_sub(1) * table_entry_size(original_size) as u64
+}
+
+impl<'a> WofStream<'a> {
+    /// Parses the chunk table of `data`, the raw stream of a file of `original_size` bytes
+    /// compressed with `algo`.
+    pub fn parse(data: &'a [u8], original_size: u64, algo: WofAlgorithm) -> Result<Self, WofStreamError> {
+        if algo == WofAlgorithm::Lznt1 { return Err(WofStreamError::UnsupportedAlgorithm); }
+
+        let chunk_size = algo.chunk_size();
+        let count = chunk_count(original_size, chunk_size);
+        let entry_size = table_entry_size(original_size);
+        let table_size = count.saturating_sub(1)
+            .checked_mul(entry_size as u64)
+            .filter(|&t| t <= data.len() as u64)
+            .ok_or(WofStreamError::Truncated)? as usize;
+
+        let read_entry = |i: usize| -> u64 {
+            let e = &data[i * entry_size..(i + 1) * entry_size];
+            if entry_size == 8 {
+                u64::from_le_bytes([e[0], e[1], e[2], e[3], e[4], e[5], e[6], e[7]])
+            } else {
+                u32::from_le_bytes([e[0], e[1], e[2], e[3]]) as u64
+            }
+        };
+
+        let data_len = (data.len() - table_size) as u64;
+        let mut chunks = Vec::with_capacity(count as usize);
+        for i in 0..count as usize {
+            let start = if i == 0 { 0 } else { read_entry(i - 1) };
+            let end = if i + 1 == count as usize { data_len } else { read_entry(i) };
+            if start > end || end > data_len { return Err(WofStreamError::BadChunkTable); }
+
+            let uncompressed = (original_size - i as u64 * chunk_size as u64).min(chunk_size as u64);
+            if end - start > uncompressed { return Err(WofStreamError::BadChunkTable); }
+
+            chunks.push(ChunkInfo {
+                offset: table_size as u64 + start,
+                compressed_size: (end - start) as u32,
+                uncompressed_size: uncompressed as u32,
+            });
+        }
+
+        Ok(Self { data, algo, original_size, chunks })
+    }
+
+    pub fn algorithm(&self) -> WofAlgorithm {
+        self.algo
+    }
+
+    pub fn original_size(&self) -> u64 {
+        self.original_size
+    }
+
+    pub fn chunks(&self) -> &[ChunkInfo] {
+        &self.chunks
+    }
+
+    /// Total size of the chunk data, excluding the table.
+    pub fn compressed_size(&self) -> u64 {
+        self.chunks.iter().map(|c| c.compressed_size as u64).sum()
+    }
+
+    /// Decodes chunk `index` to its original bytes.
+    pub fn decompress_chunk(&self, index: usize) -> Result<Vec<u8>, WofStreamError> {
+        let c = self.chunks.get(index).ok_or(WofStreamError::BadChunkTable)?;
+        let start = c.offset as usize;
+        let raw = &self.data[start..start + c.compressed_size as usize];
+        decode_chunk(raw, c.uncompressed_size as usize, self.algo).ok_or(WofStreamError::CorruptChunk(index))
+    }
+
+    /// Decodes the whole stream.
+    pub fn decompress(&self) -> Result<Vec<u8>, WofStreamError> {
+        let mut out = Vec::with_capacity(self.original_size as usize);
+        for i in 0..self.chunks.len() {
+            out.extend_from_slice(&self.decompress_chunk(i)?);
+        }
+        Ok(out)
+    }
+}
+
+/// Original bytes of one chunk; a chunk as large as its output is stored raw.
+fn decode_chunk(raw: &[u8], out_len: usize, algo: WofAlgorithm) -> Option<Vec<u8>> {
+    if raw.len() == out_len {
+        return Some(raw.to_vec());
+    }
+    match algo {
+        WofAlgorithm::Lzx => lzx::decompress(raw, out_len),
+        _ => xpress::decompress(raw, out_len),
+    }
+}
+
+/// Decodes a stream read front to back from `r`, handing each chunk's original bytes to
+/// `on_chunk`. Only one chunk is held at a time, so files of any size can be checked.
+pub fn decode_reader(
+    r: &mut dyn Read,
+    original_size: u64,
+    algo: WofAlgorithm,
+    on_chunk: &mut dyn FnMut(&[u8]),
+) -> Result<(), WofStreamError> {
+    if algo == WofAlgorithm::Lznt1 { return Err(WofStreamError::UnsupportedAlgorithm); }
+
+    let chunk_size = algo.chunk_size() as u64;
+    let count = chunk_count(original_size, chunk_size as usize);
+    let entry_size = table_entry_size(original_size);
+    let mut table = vec![0u8; (count.saturating_sub(1) as usize).saturating_mul(entry_size)];
+    r.read_exact(&mut table).map_err(|_| WofStreamError::Truncated)?;
+    let entry = |i: usize| -> u64 {
+        let mut b = [0u8; 8];
+        b[..entry_size].copy_from_slice(&table[i * entry_size..(i + 1) * entry_size]);
+        u64::from_le_bytes(b)
+    };
+
+    let mut raw = Vec::new();
+    let mut start = 0u64;
+    for i in 0..count as usize {
+        let uncompressed = (original_size - i as u64 * chunk_size).min(chunk_size);
+        raw.clear();
+        if i + 1 == count as usize {
+            // The last chunk runs to the end of the stream
+            r.take(uncompressed + 1).read_to_end(&mut raw).map_err(|_| WofStreamError::Io)?;
+        } else {
+            let end = entry(i);
+            if end < start || end - start > uncompressed { return Err(WofStreamError::BadChunkTable); }
+            raw.resize((end - start) as usize, 0);
+            r.read_exact(&mut raw).map_err(|_| WofStreamError::Truncated)?;
+            start = end;
+        }
+        if raw.len() as u64 > uncompressed { return Err(WofStreamError::BadChunkTable); }
+
+        let decoded = decode_chunk(&raw, uncompressed as usize, algo).ok_or(WofStreamError::CorruptChunk(i))?;
+        on_chunk(&decoded);
+    }
+    Ok(())
+}
+
+/// Builds a `WofCompressedData` stream for `data` (used for fixtures and offline packing).
+pub fn encode(data: &[u8], algo: WofAlgorithm) -> Result<Vec<u8>, WofStreamError> {
+    if algo == WofAlgorithm::Lznt1 { return Err(WofStreamError::UnsupportedAlgorithm); }
+
+    let entry_size = table_entry_size(data.len() as u64);
+    let mut table = Vec::new();
+    let mut body = Vec::new();
+    let mut lzx_enc = lzx::Encoder::new();
+    let mut xpress_enc = xpress::Encoder::new();
+
+    for (i, chunk) in data.chunks(algo.chunk_size()).enumerate() {
+        if i > 0 {
+            let off = body.len() as u64;
+            table.extend_from_slice(&off.to_le_bytes()[..entry_size]);
+        }
+        let compressed = match algo {
+            WofAlgorithm::Lzx => lzx_enc.compress(chunk),
+            _ => xpress_enc.compress(chunk),
+        };
+        match compressed {
+            Some(c) if c.len() < chunk.len() => body.extend_from_slice(&c),
+            _ => body.extend_from_slice(chunk),
+        }
+    }
+
+    table.extend_from_slice(&body);
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(len: usize) -> Vec<u8> {
+        // Compressible text with one incompressible 4K region
+        let mut out = b"WofCompressedData fixture line\r\n".repeat(len / 32 + 1);
+        out.truncate(len);
+        let mut seed = 3u32;
+        for b in out.iter_mut().skip(4096).take(4096) {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            *b = (seed >> 24) as u8;
+        }
+        out
+    }
+
+    #[test]
+    fn test_round_trip_all_algorithms() {
+        let data = fixture(70_000);
+        for algo in [WofAlgorithm::Xpress4K, WofAlgorithm::Xpress8K, WofAlgorithm::Xpress16K, WofAlgorithm::Lzx] {
+            let stream = encode(&data, algo).unwrap();
+            let parsed = WofStream::parse(&stream, data.len() as u64, algo).unwrap();
+
+            assert_eq!(parsed.chunks().len(), data.len().div_ceil(algo.chunk_size()));
+            assert_eq!(parsed.decompress().unwrap(), data);
+            assert!((parsed.compressed_size() as usize) < data.len());
+        }
+    }
+
+    #[test]
+    fn test_decode_reader_matches_parse() {
+        let data = fixture(70_000);
+        for algo in [WofAlgorithm::Xpress4K, WofAlgorithm::Lzx] {
+            let stream = encode(&data, algo).unwrap();
+            let mut out = Vec::new();
+            decode_reader(&mut &stream[..], data.len() as u64, algo, &mut |c| out.extend_from_slice(c)).unwrap();
+            assert_eq!(out, data);
+
+            let cut = &stream[..stream.len() / 2];
+            assert!(decode_reader(&mut &cut[..], data.len() as u64, algo, &mut |_| {}).is_err());
+        }
+        // Trailing garbage makes the last chunk longer than its output
+        let mut stream = encode(&data, WofAlgorithm::Xpress8K).unwrap();
+        stream.extend_from_slice(&[0u8; 9000]);
+        assert_eq!(decode_reader(&mut &stream[..], data.len() as u64, WofAlgorithm::Xpress8K, &mut |_| {}), Err(WofStreamError::BadChunkTable));
+    }
+
+    #[test]
+    fn test_chunk_table_layout() {
+        let data = fixture(3 * 4096 + 100);
+        let stream = encode(&data, WofAlgorithm::Xpress4K).unwrap();
+        let parsed = WofStream::parse(&stream, data.len() as u64, WofAlgorithm::Xpress4K).unwrap();
+        let chunks = parsed.chunks();
+
+        // 3 table entries of 4 bytes; chunk 0 starts right after the table
+        assert_eq!(chunks[0].offset, 12);
+        let second = u32::from_le_bytes([stream[0], stream[1], stream[2], stream[3]]) as u64;
+        assert_eq!(chunks[1].offset, 12 + second);
+
+        // The noise region is stored raw, the tail chunk covers the remainder
+        assert!(!chunks[0].is_stored_raw());
+        assert!(chunks[1].is_stored_raw());
+        assert_eq!(chunks[3].uncompressed_size, 100);
+    }
+
+    #[test]
+    fn test_rejects_damaged_streams() {
+        let data = fixture(20_000);
+        let mut stream = encode(&data, WofAlgorithm::Xpress4K).unwrap();
+
+        assert_eq!(WofStream::parse(&stream[..8], data.len() as u64, WofAlgorithm::Xpress4K).err(), Some(WofStreamError::Truncated));
+        assert_eq!(WofStream::parse(&stream, 20_000, WofAlgorithm::Lznt1).err(), Some(WofStreamError::UnsupportedAlgorithm));
+
+        // Swap the first two table entries so offsets decrease
+        let (a, b) = (stream[..4].to_vec(), stream[4..8].to_vec());
+        let mut swapped = stream.clone();
+        swapped[..4].copy_from_slice(&b);
+        swapped[4..8].copy_from_slice(&a);
+        assert_eq!(WofStream::parse(&swapped, data.len() as u64, WofAlgorithm::Xpress4K).err(), Some(WofStreamError::BadChunkTable));
+
+        // Over-subscribed Huffman table in chunk 0
+        let first = WofStream::parse(&stream, data.len() as u64, WofAlgorithm::Xpress4K).unwrap().chunks()[0];
+        stream[first.offset as usize..first.offset as usize + 256].fill(0x11);
+        let parsed = WofStream::parse(&stream, data.len() as u64, WofAlgorithm::Xpress4K).unwrap();
+        assert_eq!(parsed.decompress().err(), Some(WofStreamError::CorruptChunk(0)));
+    }
+}
@@ -1,16 +1,22 @@
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex, atomic::{AtomicU8, AtomicU64, Ordering}};
 use std::sync::mpsc::{Sender, sync_channel, Receiver};
 use crate::types::*;
 
-use crate::utils::to_wstring;
+use crate::utils::{fixed_point, to_wstring};
 use crate::ui::state::{UiMessage, BatchAction, ProcessingState};
-use crate::engine::wof::{uncompress_file, WofAlgorithm, get_real_file_size, smart_compress, detect_compression_state, CompressionState};
+use crate::engine::wof::{AlgoBreakdown, AlgoChoice, WofAlgorithm, CompressionState};
+use crate::engine::backend::{CompressionBackend, FileSnapshot, default_backend};
+use crate::engine::error::{CompressError, FailureSummary};
+use crate::engine::rules::{FileFacts, RuleSet};
+use crate::engine::knowledge::{self, Knowledge};
+use crate::engine::dry_run::{Decision, DryRunPlan, FileDecision};
+use crate::engine::journal::{DoneFiles, JournalItem, JournalWriter, Outcome};
+use crate::engine::undo::{self, UndoEntry, UndoLog};
+use crate::engine::report::{FileLog, FileRecord, ItemRecord, MAX_LISTED_FILES, RunReport, VolumeSpace};
+use crate::engine::scanner::path_str;
 
 pub use crate::engine::scanner::{scan_path_metrics, scan_path_streaming};
-use crate::engine::scanner::{
-    is_critical_path, should_skip_extension, 
-    detect_path_algorithm
-};
 
 struct ExecutionStateGuard;
 
@@ -31,14 +37,29 @@ impl Drop for ExecutionStateGuard {
 pub enum ProcessResult {
     Success,
     Skipped(Vec<u16>),
-    Failed(Vec<u16>),
+    Failed(CompressError),
 }
 
+/// Skip reason when the driver reports ERROR_COMPRESSION_NOT_BENEFICIAL.
+const NOT_BENEFICIAL: &str = "Not beneficial";
+
+/// One counter per real algorithm plus one for files an `Auto` row left uncompressed.
+const AUTO_SLOTS: usize = 6;
+
+/// Skip reason for files an interrupted run of the same row had already skipped.
+const EARLIER_RUN: &str = "Done in an earlier run";
+
 struct FileTask {
     path: String,
     action: BatchAction,
     item_id: u32,
-    algorithm: WofAlgorithm,
+    algorithm: AlgoChoice,
+    /// Outcome and size on disk from an interrupted run's journal; the file is not touched
+    /// again unless it changed since (`still_done`).
+    previous: Option<(Outcome, u64)>,
+    /// The file as its directory listing showed it, where that was enough
+    /// (`FileSnapshot::from_listing`); otherwise the worker takes a snapshot.
+    listed: Option<FileSnapshot>,
 }
 
 struct SharedReceiver<T> {
@@ -56,7 +77,7 @@ impl<T> SharedReceiver<T> {
 }
 
 pub fn batch_process_worker(
-    items: Vec<(String, BatchAction, u32, WofAlgorithm)>, 
+    items: Vec<(String, BatchAction, u32, AlgoChoice)>, 
     tx: Sender<UiMessage>, 
     state: Arc<AtomicU8>,
     force: bool,
@@ -68,10 +89,21 @@ pub fn batch_process_worker(
     global_total: Arc<AtomicU64>,
     enable_skip: bool,
     skip_extensions: String,
+    entropy_threshold: f32,
+    verify: bool,
     set_compressed_attr: bool,
     process_hidden_files: bool,
 ) {
     let _sleep_guard = ExecutionStateGuard::new();
+    let started = std::time::Instant::now();
+    let mut report = RunReport {
+        started: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        items: items.iter()
+            .map(|(path, action, id, algo)| ItemRecord { id: *id, path: path.clone(), action: action.name().to_string(), algorithm: *algo })
+            .collect(),
+        volumes: volumes_of(&items),
+        ..RunReport::default()
+    };
     let _ = tx.send(UiMessage::StatusText(to_wstring("Discovering files...")));
     
     let mut item_totals = std::collections::HashMap::new();
@@ -121,46 +153,88 @@ pub fn batch_process_worker(
     let item_processed_counts = Arc::new(m1);
     let item_disk_sizes = Arc::new(m2);
 
+    // Files per resulting algorithm for Auto rows, indexed by WofAlgorithm value (last slot: left uncompressed)
+    let auto_counts: std::collections::HashMap<u32, [AtomicU64; AUTO_SLOTS]> = items.iter()
+        .filter(|(_, action, _, algo)| *action == BatchAction::Compress && *algo == AlgoChoice::Auto)
+        .map(|(_, _, id, _)| (*id, std::array::from_fn(|_| AtomicU64::new(0))))
+        .collect();
+    let auto_counts = Arc::new(auto_counts);
+    // What each dry-run row would do, handed to the UI when the row finishes
+    let row_plans: std::collections::HashMap<u32, Mutex<DryRunPlan>> = items.iter()
+        .filter(|(_, action, _, _)| *action == BatchAction::DryRun)
+        .map(|(_, _, id, _)| (*id, Mutex::new(DryRunPlan::default())))
+        .collect();
+    let row_plans = Arc::new(row_plans);
+    // Why files failed, per row, for the status column
+    let row_failures: HashMap<u32, Mutex<FailureSummary>> = items.iter()
+        .map(|(_, _, id, _)| (*id, Mutex::new(FailureSummary::default())))
+        .collect();
+    let row_failures = Arc::new(row_failures);
+    // Real outcomes for this batch, merged into the estimator knowledge at the end
+    let learned = Arc::new(Mutex::new(Knowledge::new()));
+
     let item_totals = Arc::new(item_totals);
     let item_paths = Arc::new(item_paths);
+    let backend = default_backend();
+    let rules = RuleSet::load();
+    if !rules.is_empty() {
+        crate::log_info!(&["Loaded ", &rules.rules().len().to_string(), " rules"].concat());
+    }
+    let rules = Arc::new(builtin_rules(rules, force, guard_enabled, enable_skip, &skip_extensions));
+    let (journal, journal_rows, previous_done) = start_journal(&items);
+    let previous_done = Arc::new(previous_done);
+    let resumed = Arc::new(AtomicU64::new(0));
 
     let state_producer = Arc::clone(&state);
     let items_producer = items.clone();
+    let previous_producer = Arc::clone(&previous_done);
+    let backend_producer = Arc::clone(&backend);
     let producer_handle = std::thread::spawn(move || {
         for (path, action, id, algo) in items_producer {
             if check_stop_signal(&state_producer) { break; }
             
             let enable_attr = set_compressed_attr && action == BatchAction::Compress;
             let disable_attr = action == BatchAction::Decompress;
+            let done = previous_producer.get(&id);
+            let previous = |file: &str| done.and_then(|d| d.get(file)).copied();
 
             if std::path::Path::new(&path).is_file() {
-                let _ = file_tx.send(FileTask { path, action, item_id: id, algorithm: algo });
+                let previous = previous(&path);
+                let _ = file_tx.send(FileTask { path, action, item_id: id, algorithm: algo, previous, listed: None });
             } else {
                 let msg = ["Processing dir: ", &path].concat();
                 crate::log_info!(&msg);
                 
                 if enable_attr {
-                    crate::engine::wof::set_compressed_attribute(&path, true);
+                    mark_folder_compressed(backend_producer.as_ref(), &path);
                 } else if disable_attr {
-                    crate::engine::wof::set_compressed_attribute(&path, false);
+                    backend_producer.set_folder_compressed(&path, false);
                 }
 
-                crate::engine::scanner::walk_directory(&path, Some(&state_producer), process_hidden_files, &mut |full_path, is_dir, _| {
+                crate::engine::scanner::walk_directory(&path, Some(&state_producer), process_hidden_files, &mut |full_path, is_dir, entry| {
+                    let Some(full_path) = path_str(full_path) else { return };
                     if is_dir {
                         if enable_attr {
-                            crate::engine::wof::set_compressed_attribute(full_path, true);
+                            mark_folder_compressed(backend_producer.as_ref(), full_path);
                         } else if disable_attr {
-                            crate::engine::wof::set_compressed_attribute(full_path, false);
+                            backend_producer.set_folder_compressed(full_path, false);
                         }
                     } else {
-                        let _ = file_tx.send(FileTask { path: full_path.to_string(), action, item_id: id, algorithm: algo });
+                        let _ = file_tx.send(FileTask {
+                            path: full_path.to_string(), action, item_id: id, algorithm: algo,
+                            previous: previous(full_path), listed: FileSnapshot::from_listing(entry),
+                        });
                     }
                 });
             }
         }
     });
 
-    std::thread::scope(|s| {
+    let journal_ref = journal.as_ref();
+    let journal_rows = &journal_rows;
+    // Each thread keeps its own share of the listed files, merged into the report once joined
+    let file_logs: Vec<FileLog> = std::thread::scope(|s| {
+        let mut handles = Vec::with_capacity(num_threads);
         for _ in 0..num_threads {
             let rx = Arc::clone(&shared_rx);
             let g_cur = Arc::clone(&global_current);
@@ -171,30 +245,108 @@ pub fn batch_process_worker(
             let row_size = Arc::clone(&item_disk_sizes);
             let row_tot = Arc::clone(&item_totals);
             let row_p = Arc::clone(&item_paths);
+            let row_auto = Arc::clone(&auto_counts);
+            let row_plans = Arc::clone(&row_plans);
+            let row_failures = Arc::clone(&row_failures);
+            let learned = Arc::clone(&learned);
+            let resumed = Arc::clone(&resumed);
             let tx = tx.clone();
             let st = Arc::clone(&state);
             let force = force;
             let hwnd = main_hwnd;
-            let guard = guard_enabled;
             let skip_en = enable_skip;
-            let skip_ext = skip_extensions.clone();
+            let entropy_max = entropy_threshold;
             let set_attr = set_compressed_attr;
+            let backend = Arc::clone(&backend);
+            let rules = Arc::clone(&rules);
 
-            s.spawn(move || {
+            handles.push(s.spawn(move || {
                 crate::engine::wof::enable_backup_privileges();
                 if low_power_mode { crate::engine::power::enable_eco_mode(); }
+                let mut file_log = FileLog::with_limit(MAX_LISTED_FILES.div_ceil(num_threads));
 
-                while let Some(task) = rx.recv() {
+                while let Some(mut task) = rx.recv() {
                     wait_if_paused(&st);
                     if st.load(Ordering::Relaxed) == ProcessingState::Stopped as u8 { break; }
 
-                    let (res, size) = process_file_core(
-                        &task.path, task.algorithm, task.action, force, hwnd, guard, skip_en, &skip_ext, set_attr
-                    );
+                    // Everything read about the file up front; only a file the driver changed is read again
+                    let before = task.listed.or_else(|| backend.snapshot(&task.path)).unwrap_or_default();
+                    if let Some((Outcome::Success, _)) = task.previous
+                        && !still_done(task.action, task.algorithm, before.algorithm)
+                    {
+                        crate::log_info!(&["Changed since an earlier run, processing again: ", &task.path].concat());
+                        task.previous = None;
+                    }
+
+                    let mut record = FileRecord {
+                        item: task.item_id, path: task.path.clone(), algorithm: None, logical: before.facts.size,
+                        disk_before: 0, disk_after: 0, outcome: Outcome::Success, reason: String::new(), retryable: false,
+                    };
+                    let (res, size) = if let Some((outcome, disk)) = task.previous {
+                        resumed.fetch_add(1, Ordering::Relaxed);
+                        record.algorithm = before.algorithm;
+                        (record.disk_before, record.disk_after) = (disk, disk);
+                        (resumed_result(outcome), disk)
+                    } else if task.action == BatchAction::DryRun {
+                        let d = dry_run_file(backend.as_ref(), &task.path, &before, task.algorithm, force, skip_en, entropy_max, &rules);
+                        if let Some(plan) = row_plans.get(&task.item_id) {
+                            plan.lock().unwrap_or_else(|e| e.into_inner()).record(&d);
+                        }
+                        record.algorithm = match d.decision {
+                            Decision::Compress(algo) => Some(algo),
+                            Decision::Skip(_) => before.algorithm,
+                        };
+                        (record.disk_before, record.disk_after) = (d.disk, d.projected);
+                        // The row keeps showing what is on disk now; the projection goes to its plan
+                        (dry_run_result(&d).0, d.disk)
+                    } else {
+                        record.disk_before = before.disk;
+                        let (res, after) = process_file_core(
+                            backend.as_ref(), &task.path, &before, task.algorithm, task.action, force, hwnd, skip_en, entropy_max, &rules, verify, set_attr
+                        );
+                        if task.action == BatchAction::Compress && let Some(entry) = changed_state(&task.path, &before, &after) {
+                            undo::record(&entry);
+                        }
+                        record.algorithm = after.algorithm;
+                        record.disk_after = after.disk;
+                        (res, after.disk)
+                    };
+                    let (current, logical) = (record.algorithm, record.logical);
+                    record.outcome = task.previous.map_or_else(|| journal_outcome(&res), |(outcome, _)| outcome);
+                    record.reason = match &res {
+                        ProcessResult::Success => String::new(),
+                        ProcessResult::Skipped(reason) => String::from_utf16_lossy(reason).trim_end_matches('\0').to_string(),
+                        ProcessResult::Failed(e) => e.to_string(),
+                    };
+                    record.retryable = matches!(&res, ProcessResult::Failed(e) if e.is_retryable());
+                    file_log.add(record);
+
+                    if task.previous.is_none() && let (Some(journal), Some(&row)) = (journal_ref, journal_rows.get(&task.item_id)) {
+                        journal.record(row, &task.path, journal_outcome(&res), size);
+                    }
 
                     match res {
                         ProcessResult::Success | ProcessResult::Skipped(_) => { success.fetch_add(1, Ordering::Relaxed); }
-                        ProcessResult::Failed(_) => { failed.fetch_add(1, Ordering::Relaxed); }
+                        ProcessResult::Failed(err) => {
+                            failed.fetch_add(1, Ordering::Relaxed);
+                            if let Some(failures) = row_failures.get(&task.item_id) {
+                                failures.lock().unwrap_or_else(|e| e.into_inner()).add(err);
+                            }
+                        }
+                    }
+
+                    if task.action == BatchAction::Compress {
+                        let auto_counts = row_auto.get(&task.item_id);
+                        let compressed = matches!(res, ProcessResult::Success);
+                        let applied = if compressed || auto_counts.is_some() { current } else { None };
+
+                        if let Some(counts) = auto_counts {
+                            let slot = applied.map(|a| a as usize).unwrap_or(AUTO_SLOTS - 1);
+                            counts[slot.min(AUTO_SLOTS - 1)].fetch_add(1, Ordering::Relaxed);
+                        }
+                        if task.previous.is_none() {
+                            learn_outcome(&learned, &task, &res, applied, logical, size);
+                        }
                     }
 
                     let cur = g_cur.fetch_add(1, Ordering::Relaxed) + 1;
@@ -216,37 +368,74 @@ pub fn batch_process_worker(
                               let current_bytes = row_size.get(&task.item_id).map(|a| a.load(Ordering::Relaxed)).unwrap_or(0);
                               
                               if r_cur == r_tot {
-                                  let algo_st = if let Some(p) = row_p.get(&task.item_id) {
-                                      detect_path_algorithm(p)
-                                  } else {
-                                      crate::engine::wof::CompressionState::None
-                                  };
+                                  if let (Some(counts), Some(p)) = (row_auto.get(&task.item_id), row_p.get(&task.item_id)) {
+                                      log_auto_breakdown(p, counts);
+                                  }
+                                  if let Some(plan) = row_plans.get(&task.item_id) {
+                                      send_plan(&tx, task.item_id, row_p.get(&task.item_id), plan);
+                                  }
+                                  if let Some(failures) = row_failures.get(&task.item_id) {
+                                      send_failures(&tx, task.item_id, row_p.get(&task.item_id), failures);
+                                  }
+                                  let (algo_st, breakdown) = finished_storage(row_p.get(&task.item_id));
                                   
-                                  let _ = tx.send(UiMessage::RowFinished(task.item_id, current_bytes, r_tot, algo_st));
+                                  let _ = tx.send(UiMessage::RowFinished(task.item_id, current_bytes, r_tot, algo_st, breakdown));
                               } else {
                                   let _ = tx.send(UiMessage::RowProgress(task.item_id, r_cur, r_tot, current_bytes));
                               }
                          }
                     }
                 }
-            });
+                file_log
+            }));
         }
+        handles.into_iter().map(|h| h.join().unwrap_or_default()).collect()
     });
 
     let _ = producer_handle.join();
+    undo::flush();
+
+    let stopped = state.load(Ordering::Relaxed) == ProcessingState::Stopped as u8;
+    if let Some(journal) = journal {
+        if stopped {
+            crate::log_info!(&["Batch stopped; journal kept for resuming: ", &journal.path().to_string_lossy()].concat());
+        } else {
+            journal.finish();
+        }
+    }
+
+    let learned = learned.lock().unwrap_or_else(|e| e.into_inner());
+    if !learned.is_empty() {
+        crate::log_info!(&["Estimator learned from ", &learned.len().to_string(), " extension results"].concat());
+        knowledge::commit(&learned);
+    }
 
     for (id, count) in item_totals.iter() {
         if *count == 0 {
-             let algo_st = if let Some(p) = item_paths.get(id) {
-                  detect_path_algorithm(p)
-             } else {
-                  crate::engine::wof::CompressionState::None
-             };
-             let _ = tx.send(UiMessage::RowFinished(*id, 0, 0, algo_st));
+             if let Some(plan) = row_plans.get(id) {
+                 send_plan(&tx, *id, item_paths.get(id), plan);
+             }
+             let (algo_st, breakdown) = finished_storage(item_paths.get(id));
+             let _ = tx.send(UiMessage::RowFinished(*id, 0, 0, algo_st, breakdown));
         }
     }
 
-    if state.load(Ordering::Relaxed) == ProcessingState::Stopped as u8 {
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    report.threads = num_threads as u32;
+    report.stopped = stopped;
+    for log in file_logs {
+        report.files.merge(log);
+    }
+    for v in &mut report.volumes {
+        v.free_after = crate::engine::wof::get_free_space(&v.root).unwrap_or(v.free_before);
+    }
+    rules.log_summary();
+    let totals = report.totals();
+    crate::log_info!(&["Batch report: ", &totals.files.to_string(), " files, ", &totals.saved().to_string(), " bytes saved in ",
+        &report.duration_ms.to_string(), " ms"].concat());
+    let _ = tx.send(UiMessage::RunReport(Box::new(report)));
+
+    if stopped {
         let _ = tx.send(UiMessage::StatusText(to_wstring("Cancelled.")));
         let _ = tx.send(UiMessage::Finished);
         return;
@@ -255,125 +444,609 @@ pub fn batch_process_worker(
     let g_cur = global_current.load(Ordering::Relaxed);
     let g_tot = global_total.load(Ordering::Relaxed);
     let _ = tx.send(UiMessage::Progress(g_cur, g_tot));
+    let mut failures = FailureSummary::default();
+    for row in row_failures.values() {
+        failures.merge(&row.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+    let resumed = resumed.load(Ordering::Relaxed);
+    if resumed > 0 {
+        // Totals across the interrupted run and this one
+        let succeeded = success.load(Ordering::Relaxed);
+        let failed = failed.load(Ordering::Relaxed);
+        let mut msg = ["Resumed batch finished: ", &(succeeded + failed).to_string(), " files (", &resumed.to_string(),
+            " from the earlier run), ", &succeeded.to_string(), " succeeded, ", &failed.to_string(), " failed"].concat();
+        if !failures.is_empty() {
+            msg.push_str(&[". ", &failures.summary()].concat());
+        }
+        crate::log_info!(&msg);
+        let _ = tx.send(UiMessage::StatusText(to_wstring(&msg)));
+    } else if !failures.is_empty() {
+        let msg = ["Finished: ", &failures.summary()].concat();
+        crate::log_info!(&msg);
+        let _ = tx.send(UiMessage::StatusText(to_wstring(&msg)));
+    }
     if g_cur >= g_tot {
         let _ = tx.send(UiMessage::Finished);
     }
 }
 
+/// Volumes the rows are on, with their free space before the run.
+fn volumes_of(items: &[(String, BatchAction, u32, AlgoChoice)]) -> Vec<VolumeSpace> {
+    let mut volumes: Vec<VolumeSpace> = Vec::new();
+    for (path, _, _, _) in items {
+        let Some(root) = crate::engine::wof::get_volume_root(path) else { continue };
+        if volumes.iter().any(|v| v.root.eq_ignore_ascii_case(&root)) { continue; }
+        let free = crate::engine::wof::get_free_space(&root).unwrap_or(0);
+        volumes.push(VolumeSpace { root, free_before: free, free_after: free });
+    }
+    volumes
+}
+
+/// Starts this batch's journal, taking over what interrupted runs of the same rows finished.
+/// Dry-run rows are not journaled. Returns the writer (`None` if it cannot be written), the
+/// journal row of each batch row, and the files each batch row already has done.
+fn start_journal(items: &[(String, BatchAction, u32, AlgoChoice)]) -> (Option<JournalWriter>, HashMap<u32, usize>, HashMap<u32, DoneFiles>) {
+    let rows: Vec<_> = items.iter().filter(|(_, action, _, _)| *action != BatchAction::DryRun).collect();
+    if rows.is_empty() { return (None, HashMap::new(), HashMap::new()); }
+
+    let journal_items: Vec<JournalItem> = rows.iter()
+        .map(|(path, action, _, algo)| JournalItem { path: path.clone(), action: action.name().to_string(), algo: *algo })
+        .collect();
+    match JournalWriter::start(&journal_items) {
+        Ok((writer, done)) => {
+            let index = rows.iter().enumerate().map(|(i, (_, _, id, _))| (*id, i)).collect();
+            let done: HashMap<u32, DoneFiles> = rows.iter().zip(done)
+                .filter(|(_, files)| !files.is_empty())
+                .map(|((_, _, id, _), files)| (*id, files))
+                .collect();
+            let taken: usize = done.values().map(|files| files.len()).sum();
+            if taken > 0 {
+                crate::log_info!(&["Resuming: ", &taken.to_string(), " files finished by an interrupted run are skipped"].concat());
+            }
+            (Some(writer), index, done)
+        }
+        Err(e) => {
+            crate::log_error!(&["Batch journal unavailable, this batch cannot be resumed: ", &e.to_string()].concat());
+            (None, HashMap::new(), HashMap::new())
+        }
+    }
+}
+
+/// Attributes a forced compress or decompress may clear and puts back afterwards.
+const PRESERVED_ATTRIBUTES: u32 = FILE_ATTRIBUTE_READONLY | FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM;
+
+/// Runs `op` on `path` and, when given the attributes the file had `before` (forced runs),
+/// puts back its read-only, hidden and system attributes, whether `op` succeeded or not.
+fn with_attributes_restored<T>(backend: &dyn CompressionBackend, path: &str, before: Option<u32>, op: impl FnOnce() -> T) -> T {
+    let result = op();
+    if let Some(before) = before {
+        restore_attributes(backend, path, before);
+    }
+    result
+}
+
+fn restore_attributes(backend: &dyn CompressionBackend, path: &str, before: u32) {
+    let Some(after) = backend.file_facts(path).map(|f| f.attributes) else {
+        crate::log_warn!(&["Could not restore attributes of ", path, ": file is gone"].concat());
+        return;
+    };
+    let changed = (before ^ after) & PRESERVED_ATTRIBUTES;
+    if changed == 0 { return; }
+
+    if let Err(e) = backend.set_attributes(path, (after & !PRESERVED_ATTRIBUTES) | (before & PRESERVED_ATTRIBUTES)) {
+        let names: Vec<&str> = [(FILE_ATTRIBUTE_READONLY, "read-only"), (FILE_ATTRIBUTE_HIDDEN, "hidden"), (FILE_ATTRIBUTE_SYSTEM, "system")]
+            .iter()
+            .filter(|(bit, _)| changed & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        crate::log_error!(&["Could not restore ", &names.join(", "), " attribute of ", path, ": ", &e.to_string()].concat());
+    }
+}
+
+/// What undoing a compress of `path` has to restore.
+fn prior_state(path: &str, before: &FileSnapshot) -> UndoEntry {
+    let readonly = before.facts.attributes & FILE_ATTRIBUTE_READONLY != 0;
+    UndoEntry::File { path: path.to_string(), prior: before.algorithm, readonly }
+}
+
+/// Undo entry for a file a compress run left stored differently than it found it, whatever
+/// the result: a declined or failed call can already have removed LZNT1 or the WOF backing,
+/// and a "No savings" skip leaves the file compressed.
+fn changed_state(path: &str, before: &FileSnapshot, after: &FileSnapshot) -> Option<UndoEntry> {
+    (after.algorithm != before.algorithm).then(|| prior_state(path, before))
+}
+
+/// Sets a folder's compressed attribute for a compress run, noting it for undo if it was clear.
+fn mark_folder_compressed(backend: &dyn CompressionBackend, path: &str) {
+    if !backend.folder_compressed(path) {
+        undo::record(&UndoEntry::Folder { path: path.to_string(), compressed: false });
+    }
+    backend.set_folder_compressed(path, true);
+}
+
+/// Puts every path in `log` back the way the run found it, newest change first. Stops early
+/// when `state` is set to Stopped. Returns the number restored and the paths that failed
+/// with their error.
+pub fn undo_changes(backend: &dyn CompressionBackend, log: &UndoLog, state: &AtomicU8, on_progress: &mut dyn FnMut(u64, u64)) -> (u64, Vec<(String, CompressError)>) {
+    let total = log.entries.len() as u64;
+    let mut restored = 0;
+    let mut failed = Vec::new();
+    for (i, entry) in log.entries.iter().rev().enumerate() {
+        if state.load(Ordering::Relaxed) == ProcessingState::Stopped as u8 { break; }
+        let res = match entry {
+            UndoEntry::File { path, prior, readonly } => restore_file(backend, path, *prior, *readonly),
+            UndoEntry::Folder { path, compressed } => {
+                backend.set_folder_compressed(path, *compressed);
+                Ok(())
+            }
+        };
+        match res {
+            Ok(()) => restored += 1,
+            Err(e) => {
+                crate::log_error!(&["Undo failed: ", entry.path(), ": ", &e.to_string()].concat());
+                failed.push((entry.path().to_string(), e));
+            }
+        }
+        on_progress(i as u64 + 1, total);
+    }
+    (restored, failed)
+}
+
+fn restore_file(backend: &dyn CompressionBackend, path: &str, prior: Option<WofAlgorithm>, readonly: bool) -> Result<(), CompressError> {
+    let facts = backend.file_facts(path).ok_or(CompressError::NotFound)?;
+    // Read-only files cannot be opened for writing
+    let is_readonly = facts.attributes & FILE_ATTRIBUTE_READONLY != 0;
+    if is_readonly { backend.set_readonly(path, false)?; }
+
+    let res = if backend.query_algorithm(path) == prior {
+        Ok(())
+    } else {
+        match prior {
+            None => backend.uncompress(path),
+            Some(algo) => match backend.compress(path, algo, false) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(CompressError::NotBeneficial),
+                Err(e) => Err(e),
+            },
+        }
+    };
+    if is_readonly || readonly {
+        return res.and(backend.set_readonly(path, readonly));
+    }
+    res
+}
+
+fn journal_outcome(res: &ProcessResult) -> Outcome {
+    match res {
+        ProcessResult::Success => Outcome::Success,
+        ProcessResult::Skipped(_) => Outcome::Skipped,
+        ProcessResult::Failed(_) => Outcome::Failed,
+    }
+}
+
+/// Whether a file a journal recorded as done is still stored the way that run left it
+/// (`current` is its algorithm now). One changed since is processed again.
+fn still_done(action: BatchAction, algo: AlgoChoice, current: Option<WofAlgorithm>) -> bool {
+    match (action, algo) {
+        (BatchAction::Compress, AlgoChoice::Fixed(algo)) => current == Some(algo),
+        (BatchAction::Compress, AlgoChoice::Auto) => current.is_some(),
+        (BatchAction::Decompress, _) => current.is_none(),
+        _ => true,
+    }
+}
+
+/// Result counted for a file taken over from a journal (failed files are never taken over).
+fn resumed_result(outcome: Outcome) -> ProcessResult {
+    match outcome {
+        Outcome::Success => ProcessResult::Success,
+        Outcome::Skipped | Outcome::Failed => ProcessResult::Skipped(to_wstring(EARLIER_RUN)),
+    }
+}
+
+/// Runs `action` on one file. `before` is what is known about the file going in (from its
+/// directory listing or `CompressionBackend::snapshot`); the returned snapshot is the file
+/// afterwards, read again only when the driver was asked to change it.
+#[allow(clippy::too_many_arguments)]
 fn process_file_core(
+    backend: &dyn CompressionBackend,
     path: &str,
-    algo: WofAlgorithm,
+    before: &FileSnapshot,
+    algo: AlgoChoice,
     action: BatchAction,
     force: bool,
     main_hwnd: usize,
-    guard_enabled: bool,
     enable_skip: bool,
-    skip_ext_list: &str,
+    entropy_threshold: f32,
+    rules: &RuleSet,
+    verify: bool,
     _set_compressed_attr: bool,
-) -> (ProcessResult, u64) {
+) -> (ProcessResult, FileSnapshot) {
+    let read_after = || backend.snapshot(path).unwrap_or(*before);
+    // compress_file_handle drops LZNT1 or the WOF backing before the call that may decline or fail
+    let read_if_compressed = || if before.algorithm.is_some() { read_after() } else { *before };
     match action {
         BatchAction::Compress => {
-            if guard_enabled && !force && is_critical_path(path) {
-                crate::log_info!(&["Skipped (Critical): ", path].concat());
-                return (ProcessResult::Skipped(crate::utils::to_wstring("System Path")), get_real_file_size(path));
-            }
-            if !force {
-                 if let Some(curr) = crate::engine::wof::get_wof_algorithm(path) {
-                     if curr == algo {
-                         crate::log_info!(&["Skipped (Optimal): ", path].concat());
-                         return (ProcessResult::Skipped(crate::utils::to_wstring("Already optimal")), get_real_file_size(path));
-                     }
-                 }
-                     if should_skip_extension(path, enable_skip, skip_ext_list) {
-                         crate::log_info!(&["Skipped (Ext): ", path].concat());
-                         return (ProcessResult::Skipped(crate::utils::to_wstring("Filtered extension")), get_real_file_size(path));
-                     }
-            }
+            let (algo, force) = match plan_compress(backend, path, before, algo, force, enable_skip, entropy_threshold, rules) {
+                Ok(plan) => plan,
+                Err(reason) => return (ProcessResult::Skipped(crate::utils::to_wstring(&reason)), *before),
+            };
+            let expected = if verify {
+                match backend.content_hash(path) {
+                    Some(h) => Some(h),
+                    None => {
+                        crate::log_error!(&["Failed (unreadable, not verified): ", path].concat());
+                        return (ProcessResult::Failed(CompressError::Unreadable), *before);
+                    }
+                }
+            } else {
+                None
+            };
+
+            let compressed = with_attributes_restored(backend, path, force.then_some(before.facts.attributes), || {
+                let compressed = try_compress_with_lock_handling(backend, path, algo, force, main_hwnd);
+                if let (Ok(true), Some(expected)) = (compressed, expected) && let Err(e) = verify_compressed(backend, path, expected) {
+                    return Err(e);
+                }
+                Ok(compressed)
+            });
+            let compressed = match compressed {
+                Ok(compressed) => compressed,
+                // Decompressed again (or left compressed) after a failed verify
+                Err(e) => return (ProcessResult::Failed(e), read_after()),
+            };
 
-            match try_compress_with_lock_handling(path, algo, force, main_hwnd) {
+            match compressed {
                 Ok(true) => {
-                    let disk_size = get_real_file_size(path);
-                    let logical_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+                    let after = read_after();
+                    let logical_size = before.facts.size;
                     
-                    if logical_size > 0 && disk_size < logical_size {
+                    if logical_size > 0 && after.disk < logical_size {
                         crate::log_trace!(&["Compressed: ", path].concat());
-                        (ProcessResult::Success, disk_size)
-                    } else if force {
-                        let state = detect_compression_state(path);
-                        match state {
-                            CompressionState::Specific(_) => {
-                                crate::log_trace!(&["Compressed (forced, no savings): ", path].concat());
-                                (ProcessResult::Success, disk_size)
-                            },
-                            _ => {
-                                crate::log_info!(&["Skipped (No savings): ", path].concat());
-                                (ProcessResult::Skipped(crate::utils::to_wstring("No savings")), disk_size)
-                            }
-                        }
+                        (ProcessResult::Success, after)
+                    } else if force && after.algorithm.is_some() {
+                        crate::log_trace!(&["Compressed (forced, no savings): ", path].concat());
+                        (ProcessResult::Success, after)
                     } else {
                         crate::log_info!(&["Skipped (No savings): ", path].concat());
-                        (ProcessResult::Skipped(crate::utils::to_wstring("No savings")), disk_size)
+                        (ProcessResult::Skipped(crate::utils::to_wstring("No savings")), after)
                     }
                 },
                 Ok(false) => {
                     crate::log_info!(&["Skipped (Not beneficial): ", path].concat());
-                    (ProcessResult::Skipped(crate::utils::to_wstring("Not beneficial")), get_real_file_size(path))
+                    (ProcessResult::Skipped(crate::utils::to_wstring(NOT_BENEFICIAL)), read_if_compressed())
                 },
                 Err(e) => {
-                    let err_w = crate::utils::u64_to_wstring(e as u64);
-                    let err_s = String::from_utf16_lossy(&err_w);
-                    crate::log_error!(&["Failed ", path, ": ", err_s.trim_end_matches('\0')].concat());
-                    (ProcessResult::Failed(err_w), get_real_file_size(path))
+                    crate::log_error!(&["Failed ", path, ": ", &e.to_string()].concat());
+                    (ProcessResult::Failed(e), read_if_compressed())
                 }
             }
         },
         BatchAction::Decompress => {
-            match uncompress_file(path) {
+            let uncompressed = with_attributes_restored(backend, path, force.then_some(before.facts.attributes), || {
+                // Read-only files cannot be opened for writing; force clears the attribute like compress does
+                if force && before.facts.attributes & FILE_ATTRIBUTE_READONLY != 0 {
+                    let _ = backend.set_readonly(path, false);
+                }
+                backend.uncompress(path)
+            });
+            match uncompressed {
                 Ok(_) => {
                     crate::log_trace!(&["Decompressed: ", path].concat());
-                    (ProcessResult::Success, get_real_file_size(path))
+                    (ProcessResult::Success, read_after())
                 },
                 Err(e) => {
-                    let err_w = crate::utils::u64_to_wstring(e as u64);
-                    let prefix = crate::w!("Error ");
-                    let msg = crate::utils::concat_wstrings(&[prefix, &err_w]);
-                    let err_s = String::from_utf16_lossy(&err_w);
-                    crate::log_error!(&["Failed ", path, ": ", err_s.trim_end_matches('\0')].concat());
-                    (ProcessResult::Failed(msg), get_real_file_size(path))
+                    crate::log_error!(&["Failed ", path, ": ", &e.to_string()].concat());
+                    (ProcessResult::Failed(e), *before)
                 }
             }
+        },
+        BatchAction::DryRun => {
+            let (res, _) = dry_run_result(&dry_run_file(backend, path, before, algo, force, enable_skip, entropy_threshold, rules));
+            (res, *before)
+        },
+        BatchAction::Verify => (verify_file(backend, path, before), *before),
+    }
+}
+
+/// Reads a just-compressed file back through the driver and compares it with its hash
+/// from before. On a mismatch the file is decompressed again and the failure returned.
+fn verify_compressed(backend: &dyn CompressionBackend, path: &str, expected: u64) -> Result<(), CompressError> {
+    if backend.content_hash(path) == Some(expected) {
+        check_stored(backend, path, expected);
+        crate::log_trace!(&["Verified: ", path].concat());
+        return Ok(());
+    }
+
+    crate::log_error!(&["Verify failed, decompressing: ", path].concat());
+    match backend.uncompress(path) {
+        Ok(_) => Err(CompressError::VerifyFailed),
+        Err(e) => {
+            crate::log_error!(&["Failed to decompress ", path, ": ", &e.to_string()].concat());
+            Err(CompressError::VerifyFailedStillCompressed)
+        }
+    }
+}
+
+/// Extra check of the stored `WofCompressedData` chunks against what the driver returned.
+/// Only logged: the driver read is what counts, and a disagreement may as well be our
+/// decoder's fault as the file's.
+fn check_stored(backend: &dyn CompressionBackend, path: &str, read: u64) {
+    let Some(stored) = backend.query_algorithm(path).and_then(|a| backend.stored_hash(path, a)) else { return };
+    match stored {
+        Ok(hash) if hash == read => {},
+        Ok(_) => crate::log_warn!(&["Stored data decodes differently than the driver reads it: ", path].concat()),
+        Err(e) => crate::log_warn!(&["Could not decode stored data (", &e.to_string(), "): ", path].concat()),
+    }
+}
+
+/// `Verify` action: a compressed file must read back through the driver to the end. There
+/// is no original to compare against, so a file that fails is left as is rather than
+/// decompressed from possibly damaged data.
+fn verify_file(backend: &dyn CompressionBackend, path: &str, before: &FileSnapshot) -> ProcessResult {
+    if before.algorithm.is_none() {
+        return ProcessResult::Skipped(to_wstring("Not compressed"));
+    }
+    let Some(read) = backend.content_hash(path) else {
+        crate::log_error!(&["Verify failed (unreadable): ", path].concat());
+        return ProcessResult::Failed(CompressError::Unreadable);
+    };
+    check_stored(backend, path, read);
+    crate::log_trace!(&["Verified: ", path].concat());
+    ProcessResult::Success
+}
+
+/// Rules (built-in guard and extension skips included) and skip heuristics for compressing
+/// `path`: `Err(reason)` to skip, otherwise the algorithm (`Auto` resolved) and force flag
+/// to compress with. Only reads the file, so dry runs go through exactly the same checks.
+#[allow(clippy::too_many_arguments)]
+fn plan_compress(
+    backend: &dyn CompressionBackend,
+    path: &str,
+    before: &FileSnapshot,
+    algo: AlgoChoice,
+    force: bool,
+    enable_skip: bool,
+    entropy_threshold: f32,
+    rules: &RuleSet,
+) -> Result<(WofAlgorithm, bool), String> {
+    let (algo, force) = apply_rules(backend, rules, path, &before.facts, algo, force)?;
+    if !force {
+        if let AlgoChoice::Fixed(fixed) = algo && before.algorithm == Some(fixed) {
+            crate::log_info!(&["Skipped (Optimal): ", path].concat());
+            return Err("Already optimal".to_string());
+        }
+        if enable_skip && let Some(kind) = backend.sniff_content(path) {
+            crate::log_info!(&["Skipped (Content ", kind.label(), "): ", path].concat());
+            return Err(["Compressed content (", kind.label(), ")"].concat());
+        }
+        // 0.0 disables the probe
+        if entropy_threshold > 0.0 && let Some(score) = backend.entropy_probe(path) {
+            let score_s = fixed_point((score * 100.0).round() as u64, 2);
+            if score >= entropy_threshold as f64 {
+                crate::log_info!(&["Skipped (Entropy ", &score_s, "): ", path].concat());
+                return Err(["High entropy (", &score_s, ")"].concat());
+            }
+            crate::log_trace!(&["Entropy ", &score_s, ": ", path].concat());
+        }
+    }
+    let algo = match algo {
+        AlgoChoice::Fixed(algo) => algo,
+        AlgoChoice::Auto => resolve_auto(backend, path, before.algorithm, force)?,
+    };
+    Ok((algo, force))
+}
+
+/// What compressing `path` would do: the checks of a real compress, then the estimator's
+/// projection instead of the driver call. The file and its attributes are left untouched.
+#[allow(clippy::too_many_arguments)]
+fn dry_run_file(
+    backend: &dyn CompressionBackend,
+    path: &str,
+    before: &FileSnapshot,
+    algo: AlgoChoice,
+    force: bool,
+    enable_skip: bool,
+    entropy_threshold: f32,
+    rules: &RuleSet,
+) -> FileDecision {
+    let (logical, disk) = (before.facts.size, before.disk);
+    let (decision, projected) = match plan_compress(backend, path, before, algo, force, enable_skip, entropy_threshold, rules) {
+        // The driver declines files that would not shrink, so a plan never grows one
+        Ok((algo, _)) => (Decision::Compress(algo), backend.estimate_size(path, algo).min(disk)),
+        Err(reason) => (Decision::Skip(reason), disk),
+    };
+    let d = FileDecision { decision, logical, disk, projected };
+    crate::log_info!(&["Dry run, ", &d.describe(), ": ", path].concat());
+    d
+}
+
+/// A dry-run decision as the batch counts it: planned files succeed at their projected size.
+fn dry_run_result(d: &FileDecision) -> (ProcessResult, u64) {
+    match &d.decision {
+        Decision::Compress(_) => (ProcessResult::Success, d.projected),
+        Decision::Skip(reason) => (ProcessResult::Skipped(to_wstring(reason)), d.disk),
+    }
+}
+
+/// Dry run of `path` outside the batch (the `--dry-run` command line), single-threaded,
+/// with the rules file and the given heuristics. `on_file` sees every decision.
+#[allow(clippy::too_many_arguments)]
+pub fn dry_run_path(
+    path: &str,
+    algo: AlgoChoice,
+    force: bool,
+    guard_enabled: bool,
+    enable_skip: bool,
+    skip_ext_list: &str,
+    entropy_threshold: f32,
+    process_hidden_files: bool,
+    on_file: &mut dyn FnMut(&str, &FileDecision),
+) -> DryRunPlan {
+    let backend = default_backend();
+    let rules = builtin_rules(RuleSet::load(), force, guard_enabled, enable_skip, skip_ext_list);
+    let mut plan = DryRunPlan::default();
+    let mut visit = |file: &str, listed: Option<FileSnapshot>| {
+        let before = listed.or_else(|| backend.snapshot(file)).unwrap_or_default();
+        let d = dry_run_file(backend.as_ref(), file, &before, algo, force, enable_skip, entropy_threshold, &rules);
+        plan.record(&d);
+        on_file(file, &d);
+    };
+    if std::path::Path::new(path).is_file() {
+        visit(path, None);
+    } else {
+        crate::engine::scanner::walk_directory(path, None, process_hidden_files, &mut |full_path, is_dir, entry| {
+            if !is_dir && let Some(full_path) = path_str(full_path) { visit(full_path, FileSnapshot::from_listing(entry)); }
+        });
+    }
+    rules.log_summary();
+    plan
+}
+
+/// The rules file behind the built-in guard and extension skips the settings enable.
+/// Forced batches bypass both, as they always have.
+fn builtin_rules(rules: RuleSet, force: bool, guard_enabled: bool, enable_skip: bool, skip_ext_list: &str) -> RuleSet {
+    let skip_exts = if enable_skip && !force { skip_ext_list } else { "" };
+    rules.with_defaults(guard_enabled && !force, skip_exts)
+}
+
+/// Applies the first matching rule: `Err(reason)` to skip, otherwise the algorithm and
+/// force flag to compress with. The built-in guard comes first and cannot be overridden.
+fn apply_rules(backend: &dyn CompressionBackend, rules: &RuleSet, path: &str, facts: &FileFacts, algo: AlgoChoice, force: bool) -> Result<(AlgoChoice, bool), String> {
+    use crate::engine::rules::RuleAction;
+    if rules.is_empty() { return Ok((algo, force)); }
+
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let Some(rule) = rules.evaluate(path, facts, now, || backend.sniff_content(path)) else {
+        return Ok((algo, force));
+    };
+
+    crate::log_trace!(&["Rule [", &rule.name, "]: ", path].concat());
+    match &rule.action {
+        RuleAction::Skip(reason) => Err(reason.clone()),
+        RuleAction::Algorithm(a) => Ok((AlgoChoice::Fixed(*a), force)),
+        RuleAction::Force => Ok((algo, true)),
+    }
+}
+
+/// Feeds one compression outcome to the batch knowledge. Files the driver declined count
+/// as incompressible for the requested algorithm.
+fn learn_outcome(learned: &Mutex<Knowledge>, task: &FileTask, res: &ProcessResult, applied: Option<WofAlgorithm>, logical: u64, disk: u64) {
+    let (algo, disk) = match res {
+        ProcessResult::Success => match applied { Some(a) => (a, disk), None => return },
+        // Left uncompressed: the whole file stays allocated
+        ProcessResult::Skipped(reason) if *reason == to_wstring(NOT_BENEFICIAL) => match task.algorithm {
+            AlgoChoice::Fixed(a) => (a, u64::MAX),
+            AlgoChoice::Auto => return,
+        },
+        _ => return,
+    };
+    if let Ok(mut k) = learned.lock() {
+        k.learn(&task.path, algo, logical, disk.min(logical));
+    }
+}
+
+/// Picks the algorithm for an `Auto` task from sampled ratios: `Err(reason)` to skip.
+/// Forced tasks fall back to XPRESS4K when sampling finds nothing worth compressing.
+fn resolve_auto(backend: &dyn CompressionBackend, path: &str, current: Option<WofAlgorithm>, force: bool) -> Result<WofAlgorithm, String> {
+    let choice = backend.sample_ratios(path).and_then(|s| crate::engine::auto::choose(&s));
+    let algo = match choice {
+        Some(a) => a,
+        None if force => WofAlgorithm::Xpress4K,
+        None => {
+            crate::log_info!(&["Skipped (Auto, no gain): ", path].concat());
+            return Err("Auto: no gain".to_string());
         }
+    };
+
+    crate::log_trace!(&["Auto ", &algo.name().to_uppercase(), ": ", path].concat());
+    if !force && current == Some(algo) {
+        crate::log_info!(&["Skipped (Optimal): ", path].concat());
+        return Err("Already optimal".to_string());
+    }
+    Ok(algo)
+}
+
+/// Logs how an `Auto` row was split across algorithms, e.g. "LZX 12, XPRESS4K 30, none 5".
+fn log_auto_breakdown(path: &str, counts: &[AtomicU64; AUTO_SLOTS]) {
+    const ALGOS: [WofAlgorithm; 5] = [WofAlgorithm::Xpress4K, WofAlgorithm::Lzx, WofAlgorithm::Xpress8K, WofAlgorithm::Xpress16K, WofAlgorithm::Lznt1];
+    let mut parts = Vec::new();
+    for (i, c) in counts.iter().enumerate() {
+        let n = c.load(Ordering::Relaxed);
+        if n == 0 { continue; }
+        let label = ALGOS.get(i).map_or("none".to_string(), |a| a.name().to_uppercase());
+        parts.push([&label, " ", &n.to_string()].concat());
+    }
+    crate::log_info!(&["Auto breakdown for ", path, ": ", &parts.join(", ")].concat());
+}
+
+/// State and per-algorithm breakdown of a finished row, logged when its files are not all
+/// stored the same way (a partial run, or drift from an earlier one).
+fn finished_storage(path: Option<&String>) -> (CompressionState, AlgoBreakdown) {
+    let Some(p) = path else { return (CompressionState::None, AlgoBreakdown::default()) };
+    let metrics = scan_path_metrics(p);
+    if !metrics.breakdown.is_uniform() {
+        crate::log_info!(&["Storage of ", p, ": ", &metrics.breakdown.summary()].concat());
+    }
+    (metrics.compression_state, metrics.breakdown)
+}
+
+/// Hands a finished dry-run row's plan to the UI and logs its summary.
+fn send_plan(tx: &Sender<UiMessage>, id: u32, path: Option<&String>, plan: &Mutex<DryRunPlan>) {
+    let plan = plan.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(p) = path {
+        crate::log_info!(&["Dry run of ", p, ": ", &plan.summary(), ", saves ", &crate::utils::size_text(plan.savings())].concat());
+    }
+    let _ = tx.send(UiMessage::RowPlanned(id, plan));
+}
+
+/// Hands a row's failures to the UI; rows without failures send nothing.
+fn send_failures(tx: &Sender<UiMessage>, id: u32, path: Option<&String>, failures: &Mutex<FailureSummary>) {
+    let failures = failures.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if failures.is_empty() { return; }
+    if let Some(p) = path {
+        crate::log_warn!(&[p, ": ", &failures.summary()].concat());
     }
+    let _ = tx.send(UiMessage::RowFailed(id, failures));
 }
 
 fn try_compress_with_lock_handling(
+    backend: &dyn CompressionBackend,
     path: &str, 
     algo: WofAlgorithm, 
     force: bool, 
     main_hwnd: usize
-) -> Result<bool, u32> {
-    match smart_compress(path, algo, force) {
+) -> Result<bool, CompressError> {
+    match backend.compress(path, algo, force) {
         Ok(res) => Ok(res),
         Err(e) => {
-             if force && e == 32 {
-                 if let Ok(blockers) = std::panic::catch_unwind(|| crate::engine::process::get_file_blockers(path)) {
-                     if !blockers.is_empty() {
-                         let name_w = to_wstring(&blockers[0].name);
-                         let res = unsafe { SendMessageW(main_hwnd as HWND, 0x8004, name_w.as_ptr() as usize, 0) };
-                         
-                         if res == 1 {
-                             for b in blockers { let _ = crate::engine::process::kill_process(b.pid); }
-                             std::thread::sleep(std::time::Duration::from_millis(100));
-                             return smart_compress(path, algo, force);
-                         }
-                     }
-                 }
+             if force && e == CompressError::SharingViolation && stop_blockers(path, main_hwnd) {
+                 std::thread::sleep(std::time::Duration::from_millis(100));
+                 return backend.compress(path, algo, force);
              }
              Err(e)
         }
     }
 }
 
+/// Asks the main window whether to end the processes locking `path` and ends them.
+/// Returns true if they were ended.
+#[cfg(windows)]
+fn stop_blockers(path: &str, main_hwnd: usize) -> bool {
+    let Ok(blockers) = std::panic::catch_unwind(|| crate::engine::process::get_file_blockers(path)) else { return false };
+    let Some(first) = blockers.first() else { return false };
+    let name_w = to_wstring(&first.name);
+    let res = unsafe { SendMessageW(main_hwnd as HWND, 0x8004, name_w.as_ptr() as usize, 0) };
+    if res != 1 { return false; }
+    for b in blockers { let _ = crate::engine::process::kill_process(b.pid); }
+    true
+}
+
+/// No Restart Manager off Windows: locks are reported as failures.
+#[cfg(not(windows))]
+fn stop_blockers(_path: &str, _main_hwnd: usize) -> bool {
+    false
+}
+
 fn check_stop_signal(state: &Arc<AtomicU8>) -> bool {
     state.load(Ordering::Relaxed) == ProcessingState::Stopped as u8
 }
@@ -382,4 +1055,383 @@ fn wait_if_paused(state: &Arc<AtomicU8>) {
     while state.load(Ordering::Relaxed) == ProcessingState::Paused as u8 {
         std::thread::sleep(std::time::Duration::from_millis(100));
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::backend::MemoryBackend;
+
+    fn snap(backend: &MemoryBackend, path: &str) -> FileSnapshot {
+        backend.snapshot(path).unwrap_or_default()
+    }
+
+    fn compress(backend: &MemoryBackend, path: &str, algo: impl Into<AlgoChoice>, force: bool) -> (ProcessResult, u64) {
+        let (res, after) = process_file_core(backend, path, &snap(backend, path), algo.into(), BatchAction::Compress, force, 0, true, 0.0, &builtin_rules(RuleSet::default(), force, true, true, "zip,7z"), false, false);
+        (res, after.disk)
+    }
+
+    fn skip_reason(res: &ProcessResult) -> Option<String> {
+        match res {
+            ProcessResult::Skipped(w) => Some(String::from_utf16_lossy(w).trim_end_matches('\0').to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_compress_then_already_optimal() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\data\\a.txt", 200_000, 0.3);
+
+        let (res, size) = compress(&b, "D:\\data\\a.txt", WofAlgorithm::Lzx, false);
+        assert!(matches!(res, ProcessResult::Success));
+        assert!(size < 200_000);
+
+        let (res, _) = compress(&b, "D:\\data\\a.txt", WofAlgorithm::Lzx, false);
+        assert_eq!(skip_reason(&res).as_deref(), Some("Already optimal"));
+    }
+
+    #[test]
+    fn test_skip_heuristics_and_guard() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\data\\a.zip", 200_000, 0.3);
+        b.insert("C:\\Windows\\System32\\kernel32.dll", 200_000, 0.3);
+
+        let (res, _) = compress(&b, "D:\\data\\a.zip", WofAlgorithm::Xpress8K, false);
+        assert_eq!(skip_reason(&res).as_deref(), Some("Filtered extension"));
+
+        let (res, _) = compress(&b, "C:\\Windows\\System32\\kernel32.dll", WofAlgorithm::Xpress8K, false);
+        assert_eq!(skip_reason(&res).as_deref(), Some("System Path"));
+
+        // Force bypasses both the guard and the extension filter
+        let (res, _) = compress(&b, "D:\\data\\a.zip", WofAlgorithm::Xpress8K, true);
+        assert!(matches!(res, ProcessResult::Success));
+    }
+
+    #[test]
+    fn test_skip_sniffed_content() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\game\\level0.pak", 200_000, 0.3);
+        b.set_header("D:\\game\\level0.pak", b"\x28\xB5\x2F\xFD\x24\x00");
+
+        let (res, _) = compress(&b, "D:\\game\\level0.pak", WofAlgorithm::Xpress8K, false);
+        assert_eq!(skip_reason(&res).as_deref(), Some("Compressed content (zstd)"));
+
+        // Disabled heuristics compress it anyway
+        let (res, _) = process_file_core(&b, "D:\\game\\level0.pak", &snap(&b, "D:\\game\\level0.pak"), WofAlgorithm::Xpress8K.into(), BatchAction::Compress, false, 0, false, 0.0, &RuleSet::default(), false, false);
+        assert!(matches!(res, ProcessResult::Success));
+    }
+
+    #[test]
+    fn test_entropy_precheck() {
+        let b = MemoryBackend::new();
+        let mut seed = 5u32;
+        let noise: Vec<u8> = (0..4096).map(|_| { seed = seed.wrapping_mul(1664525).wrapping_add(1013904223); (seed >> 24) as u8 }).collect();
+        b.insert("D:\\game\\data.bin", 200_000, 0.3);
+        b.set_header("D:\\game\\data.bin", &noise);
+        b.insert("D:\\game\\config.ini", 200_000, 0.3);
+        b.set_header("D:\\game\\config.ini", &b"[video]\r\nwidth=1920\r\n".repeat(100));
+
+        let run = |path: &str, threshold: f32| {
+            process_file_core(&b, path, &snap(&b, path), WofAlgorithm::Xpress8K.into(), BatchAction::Compress, false, 0, true, threshold, &RuleSet::default(), false, false).0
+        };
+
+        let reason = skip_reason(&run("D:\\game\\data.bin", 7.9)).unwrap();
+        assert!(reason.starts_with("High entropy (7.9"), "{}", reason);
+        assert!(matches!(run("D:\\game\\config.ini", 7.9), ProcessResult::Success));
+
+        // Disabled probe leaves the decision to the driver
+        assert!(matches!(run("D:\\game\\data.bin", 0.0), ProcessResult::Success));
+    }
+
+    #[test]
+    fn test_rules_choose_algorithm_and_skip() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\app\\core.dll", 400_000, 0.5);
+        b.insert("D:\\app\\tiny.ini", 2_000, 0.3);
+        b.insert("D:\\app\\data.bin", 400_000, 0.5);
+        b.insert("D:\\app\\video.zip", 400_000, 1.0);
+        let text = "
+            size:<8K     -> skip Too small
+            ext:dll,exe  -> lzx
+            ext:zip      -> force
+            *            -> xpress8k
+        ";
+        let rules = builtin_rules(RuleSet::parse(text).unwrap(), false, true, true, "");
+
+        let run = |path: &str| {
+            process_file_core(&b, path, &snap(&b, path), WofAlgorithm::Xpress4K.into(), BatchAction::Compress, false, 0, true, 0.0, &rules, false, false).0
+        };
+
+        assert!(matches!(run("D:\\app\\core.dll"), ProcessResult::Success));
+        assert_eq!(b.query_algorithm("D:\\app\\core.dll"), Some(WofAlgorithm::Lzx));
+        assert_eq!(skip_reason(&run("D:\\app\\tiny.ini")).as_deref(), Some("Too small"));
+        assert!(matches!(run("D:\\app\\data.bin"), ProcessResult::Success));
+        assert_eq!(b.query_algorithm("D:\\app\\data.bin"), Some(WofAlgorithm::Xpress8K));
+
+        // The built-in extension skip comes before the file's rules
+        let with_skip = builtin_rules(RuleSet::parse(text).unwrap(), false, true, true, "zip");
+        let (res, _) = process_file_core(&b, "D:\\app\\video.zip", &snap(&b, "D:\\app\\video.zip"), WofAlgorithm::Xpress4K.into(), BatchAction::Compress, false, 0, true, 0.0, &with_skip, false, false);
+        assert_eq!(skip_reason(&res).as_deref(), Some("Filtered extension"));
+
+        // Force rule falls back to LZNT1 like a forced batch
+        assert!(matches!(run("D:\\app\\video.zip"), ProcessResult::Success));
+        assert_eq!(b.query_algorithm("D:\\app\\video.zip"), Some(WofAlgorithm::Lznt1));
+
+        // Second pass: the rule-chosen algorithm is already applied
+        assert_eq!(skip_reason(&run("D:\\app\\core.dll")).as_deref(), Some("Already optimal"));
+    }
+
+    #[test]
+    fn test_auto_picks_per_file() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\game\\config.txt", 200_000, 0.3);
+        b.insert("D:\\game\\level.dat", 200_000, 0.8);
+        b.insert("D:\\game\\noise.bin", 200_000, 1.2);
+
+        let (res, _) = compress(&b, "D:\\game\\config.txt", AlgoChoice::Auto, false);
+        assert!(matches!(res, ProcessResult::Success));
+        assert_eq!(b.query_algorithm("D:\\game\\config.txt"), Some(WofAlgorithm::Xpress4K));
+
+        // LZX saves far more than XPRESS here
+        let (res, _) = compress(&b, "D:\\game\\level.dat", AlgoChoice::Auto, false);
+        assert!(matches!(res, ProcessResult::Success));
+        assert_eq!(b.query_algorithm("D:\\game\\level.dat"), Some(WofAlgorithm::Lzx));
+
+        let (res, size) = compress(&b, "D:\\game\\noise.bin", AlgoChoice::Auto, false);
+        assert_eq!(skip_reason(&res).as_deref(), Some("Auto: no gain"));
+        assert_eq!(size, 200_000);
+        assert_eq!(b.query_algorithm("D:\\game\\noise.bin"), None);
+
+        // Second pass resolves to the same choice
+        let (res, _) = compress(&b, "D:\\game\\level.dat", AlgoChoice::Auto, false);
+        assert_eq!(skip_reason(&res).as_deref(), Some("Already optimal"));
+    }
+
+    #[test]
+    fn test_learn_outcome() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\lib\\core.dll", 1 << 20, 0.5);
+        b.insert("D:\\lib\\packed.dll", 1 << 20, 1.2);
+        b.insert("D:\\lib\\tiny.dll", 8_000, 0.5);
+        let learned = Mutex::new(Knowledge::new());
+
+        for path in ["D:\\lib\\core.dll", "D:\\lib\\packed.dll", "D:\\lib\\tiny.dll"] {
+            let task = FileTask { path: path.to_string(), action: BatchAction::Compress, item_id: 1, algorithm: WofAlgorithm::Xpress8K.into(), previous: None, listed: None };
+            let (res, size) = compress(&b, path, WofAlgorithm::Xpress8K, false);
+            learn_outcome(&learned, &task, &res, b.query_algorithm(path), b.logical_size(path), size);
+        }
+
+        // One compressed (~0.49) and one declined (1.0) observation; the tiny file is ignored
+        let stat = learned.lock().unwrap().get("dll", WofAlgorithm::Xpress8K).unwrap();
+        assert_eq!(stat.count, 2);
+        assert!(stat.ratio() > 0.7 && stat.ratio() < 0.76, "{}", stat.ratio());
+    }
+
+    #[test]
+    fn test_not_beneficial_and_forced_fallback() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\data\\movie.bin", 500_000, 1.0);
+
+        let (res, size) = compress(&b, "D:\\data\\movie.bin", WofAlgorithm::Xpress4K, false);
+        assert_eq!(skip_reason(&res).as_deref(), Some("Not beneficial"));
+        assert_eq!(size, 500_000);
+
+        // Forced: LZNT1 fallback leaves the file compressed without savings
+        let (res, _) = compress(&b, "D:\\data\\movie.bin", WofAlgorithm::Xpress4K, true);
+        assert!(matches!(res, ProcessResult::Success));
+        assert_eq!(b.query_algorithm("D:\\data\\movie.bin"), Some(WofAlgorithm::Lznt1));
+    }
+
+    #[test]
+    fn test_dry_run_plans_without_compressing() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\data\\a.txt", 200_000, 0.3);
+        b.insert("D:\\data\\b.zip", 200_000, 0.3);
+        b.insert("D:\\data\\c.log", 200_000, 0.5);
+        b.set_state("D:\\data\\c.log", Some(WofAlgorithm::Lzx));
+        b.insert("C:\\Windows\\System32\\kernel32.dll", 200_000, 0.3);
+
+        let rules = builtin_rules(RuleSet::default(), false, true, true, "zip");
+        let mut plan = DryRunPlan::default();
+        for path in ["D:\\data\\a.txt", "D:\\data\\b.zip", "D:\\data\\c.log", "C:\\Windows\\System32\\kernel32.dll"] {
+            plan.record(&dry_run_file(&b, path, &snap(&b, path), WofAlgorithm::Lzx.into(), false, true, 0.0, &rules));
+        }
+
+        // Nothing was touched
+        assert_eq!(b.query_algorithm("D:\\data\\a.txt"), None);
+        assert_eq!(b.query_algorithm("D:\\data\\c.log"), Some(WofAlgorithm::Lzx));
+
+        assert_eq!(plan.summary(), "Compress 1 with LZX, skip 3");
+        let reasons: Vec<&str> = plan.skipped.iter().map(|(r, _, _)| r.as_str()).collect();
+        assert_eq!(reasons, vec!["Filtered extension", "Already optimal", "System Path"]);
+
+        // The projection matches what a real run then does
+        let (res, size) = compress(&b, "D:\\data\\a.txt", WofAlgorithm::Lzx, false);
+        assert!(matches!(res, ProcessResult::Success));
+        assert_eq!(plan.compress.get(Some(WofAlgorithm::Lzx)).disk, size);
+        assert_eq!(plan.savings(), 200_000 - size);
+
+        let (res, _) = process_file_core(&b, "D:\\data\\b.zip", &snap(&b, "D:\\data\\b.zip"), AlgoChoice::Auto, BatchAction::DryRun, false, 0, false, 0.0, &RuleSet::default(), false, false);
+        assert!(matches!(res, ProcessResult::Success));
+        assert_eq!(b.query_algorithm("D:\\data\\b.zip"), None);
+    }
+
+    #[test]
+    fn test_verify_after_compress_and_verify_action() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\game\\good.pak", 200_000, 0.4);
+        b.insert("D:\\game\\bad.pak", 200_000, 0.4);
+        b.set_corrupt("D:\\game\\bad.pak", true);
+        let run = |path: &str, action: BatchAction| {
+            process_file_core(&b, path, &snap(&b, path), WofAlgorithm::Lzx.into(), action, false, 0, true, 0.0, &RuleSet::default(), true, false).0
+        };
+        let failure = |res: &ProcessResult| match res {
+            ProcessResult::Failed(e) => Some(*e),
+            _ => None,
+        };
+
+        assert!(matches!(run("D:\\game\\good.pak", BatchAction::Compress), ProcessResult::Success));
+        assert_eq!(b.query_algorithm("D:\\game\\good.pak"), Some(WofAlgorithm::Lzx));
+
+        // Reads back differently: undone and reported
+        let res = run("D:\\game\\bad.pak", BatchAction::Compress);
+        assert_eq!(failure(&res), Some(CompressError::VerifyFailed));
+        assert_eq!(b.query_algorithm("D:\\game\\bad.pak"), None);
+
+        assert!(matches!(run("D:\\game\\good.pak", BatchAction::Verify), ProcessResult::Success));
+        assert_eq!(skip_reason(&run("D:\\game\\bad.pak", BatchAction::Verify)).as_deref(), Some("Not compressed"));
+
+        // Only the driver read decides: a stored stream decoding differently is just logged
+        b.set_state("D:\\game\\bad.pak", Some(WofAlgorithm::Lzx));
+        assert!(matches!(run("D:\\game\\bad.pak", BatchAction::Verify), ProcessResult::Success));
+
+        // Without an original to restore from, an unreadable file is left compressed
+        b.set_error("D:\\game\\bad.pak", Some(23)); // ERROR_CRC
+        assert_eq!(failure(&run("D:\\game\\bad.pak", BatchAction::Verify)), Some(CompressError::Unreadable));
+        assert_eq!(b.query_algorithm("D:\\game\\bad.pak"), Some(WofAlgorithm::Lzx));
+    }
+
+    #[test]
+    fn test_failures_and_decompress() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\data\\locked.db", 100_000, 0.5);
+        b.set_error("D:\\data\\locked.db", Some(crate::engine::error::ERROR_SHARING_VIOLATION));
+
+        let (res, _) = compress(&b, "D:\\data\\locked.db", WofAlgorithm::Xpress8K, false);
+        assert!(matches!(res, ProcessResult::Failed(CompressError::SharingViolation)));
+
+        b.set_error("D:\\data\\locked.db", None);
+        b.set_state("D:\\data\\locked.db", Some(WofAlgorithm::Lzx));
+        let (res, size) = process_file_core(&b, "D:\\data\\locked.db", &snap(&b, "D:\\data\\locked.db"), WofAlgorithm::Lzx.into(), BatchAction::Decompress, false, 0, true, 0.0, &RuleSet::default(), false, false);
+        assert!(matches!(res, ProcessResult::Success));
+        assert_eq!(size.disk, 100_000);
+        assert_eq!(b.query_algorithm("D:\\data\\locked.db"), None);
+    }
+
+    #[test]
+    fn test_forced_runs_restore_attributes() {
+        let attrs = |b: &MemoryBackend, path| b.file_facts(path).unwrap().attributes;
+        let kept = FILE_ATTRIBUTE_READONLY | FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM;
+        let b = MemoryBackend::new();
+        b.insert("D:\\app\\setup.dll", 200_000, 0.3);
+        b.set_facts("D:\\app\\setup.dll", 0, kept);
+
+        // Not forced: read-only cannot be written, nothing changes
+        let (res, _) = compress(&b, "D:\\app\\setup.dll", WofAlgorithm::Lzx, false);
+        assert!(matches!(res, ProcessResult::Failed(_)));
+
+        let (res, _) = compress(&b, "D:\\app\\setup.dll", WofAlgorithm::Lzx, true);
+        assert!(matches!(res, ProcessResult::Success));
+        assert_eq!(attrs(&b, "D:\\app\\setup.dll"), kept);
+
+        // Forced decompress clears read-only to get in and puts it back
+        let decompress = |force| process_file_core(&b, "D:\\app\\setup.dll", &snap(&b, "D:\\app\\setup.dll"), WofAlgorithm::Lzx.into(), BatchAction::Decompress, force, 0, true, 0.0, &RuleSet::default(), false, false).0;
+        assert!(matches!(decompress(false), ProcessResult::Failed(_)));
+        assert!(matches!(decompress(true), ProcessResult::Success));
+        assert_eq!(b.query_algorithm("D:\\app\\setup.dll"), None);
+        assert_eq!(attrs(&b, "D:\\app\\setup.dll"), kept);
+
+        // Restored on failure too (verify mismatch after the attribute was cleared)
+        b.set_corrupt("D:\\app\\setup.dll", true);
+        let (res, _) = process_file_core(&b, "D:\\app\\setup.dll", &snap(&b, "D:\\app\\setup.dll"), WofAlgorithm::Lzx.into(), BatchAction::Compress, true, 0, true, 0.0, &RuleSet::default(), true, false);
+        assert!(matches!(res, ProcessResult::Failed(_)));
+        assert_eq!(attrs(&b, "D:\\app\\setup.dll"), kept);
+    }
+
+    #[test]
+    fn test_undo_restores_prior_state() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\app\\plain.txt", 200_000, 0.3);
+        b.insert("D:\\app\\legacy.log", 200_000, 0.3);
+        b.set_state("D:\\app\\legacy.log", Some(WofAlgorithm::Lznt1));
+        b.insert("D:\\app\\locked.dll", 200_000, 0.3);
+        b.set_state("D:\\app\\locked.dll", Some(WofAlgorithm::Xpress4K));
+        b.set_facts("D:\\app\\locked.dll", 0, FILE_ATTRIBUTE_READONLY);
+
+        // A forced LZX run over all three, recording what it changed
+        let mut log = UndoLog::default();
+        log.entries.push(UndoEntry::Folder { path: "D:\\app".to_string(), compressed: false });
+        b.set_folder_compressed("D:\\app", true);
+        for path in ["D:\\app\\plain.txt", "D:\\app\\legacy.log", "D:\\app\\locked.dll"] {
+            let prior = prior_state(path, &snap(&b, path));
+            let (res, _) = compress(&b, path, WofAlgorithm::Lzx, true);
+            assert!(matches!(res, ProcessResult::Success));
+            log.entries.push(prior);
+        }
+        // The forced run put the read-only attribute back
+        assert!(b.file_facts("D:\\app\\locked.dll").unwrap().attributes & FILE_ATTRIBUTE_READONLY != 0);
+
+        let mut steps = 0;
+        let (restored, failed) = undo_changes(&b, &log, &AtomicU8::new(0), &mut |_, _| steps += 1);
+        assert_eq!((restored, failed.len(), steps), (4, 0, 4));
+        assert_eq!(b.query_algorithm("D:\\app\\plain.txt"), None);
+        assert_eq!(b.query_algorithm("D:\\app\\legacy.log"), Some(WofAlgorithm::Lznt1));
+        assert_eq!(b.query_algorithm("D:\\app\\locked.dll"), Some(WofAlgorithm::Xpress4K));
+        assert!(b.file_facts("D:\\app\\locked.dll").unwrap().attributes & FILE_ATTRIBUTE_READONLY != 0);
+        assert!(!b.folder_compressed("D:\\app"));
+
+        // Gone since the run: reported, the rest still restored
+        log.entries.push(UndoEntry::File { path: "D:\\app\\deleted.bin".to_string(), prior: None, readonly: false });
+        let (_, failed) = undo_changes(&b, &log, &AtomicU8::new(0), &mut |_, _| {});
+        assert_eq!(failed, vec![("D:\\app\\deleted.bin".to_string(), CompressError::NotFound)]);
+    }
+
+    #[test]
+    fn test_undo_restores_lznt1_the_driver_declined() {
+        let b = MemoryBackend::new();
+        b.insert("D:\\app\\packed.zip", 200_000, 1.0);
+        b.set_state("D:\\app\\packed.zip", Some(WofAlgorithm::Lznt1));
+        b.insert("D:\\app\\raw.zip", 200_000, 1.0);
+
+        let mut log = UndoLog::default();
+        for path in ["D:\\app\\packed.zip", "D:\\app\\raw.zip"] {
+            let before = snap(&b, path);
+            let (res, after) = process_file_core(&b, path, &before, WofAlgorithm::Xpress4K.into(), BatchAction::Compress, false, 0, false, 0.0, &RuleSet::default(), false, false);
+            assert!(matches!(res, ProcessResult::Skipped(_)));
+            log.entries.extend(changed_state(path, &before, &after));
+        }
+        // Skipped, yet no longer LZNT1; the untouched file needs no entry
+        assert_eq!(b.query_algorithm("D:\\app\\packed.zip"), None);
+        assert_eq!(log.entries.len(), 1);
+
+        let (restored, failed) = undo_changes(&b, &log, &AtomicU8::new(0), &mut |_, _| {});
+        assert_eq!((restored, failed.len()), (1, 0));
+        assert_eq!(b.query_algorithm("D:\\app\\packed.zip"), Some(WofAlgorithm::Lznt1));
+    }
+
+    #[test]
+    fn test_journal_success_still_done() {
+        let lzx = AlgoChoice::Fixed(WofAlgorithm::Lzx);
+        assert!(still_done(BatchAction::Compress, lzx, Some(WofAlgorithm::Lzx)));
+        // Decompressed or recompressed differently since the earlier run
+        assert!(!still_done(BatchAction::Compress, lzx, None));
+        assert!(!still_done(BatchAction::Compress, lzx, Some(WofAlgorithm::Xpress4K)));
+        assert!(still_done(BatchAction::Compress, AlgoChoice::Auto, Some(WofAlgorithm::Xpress16K)));
+        assert!(!still_done(BatchAction::Compress, AlgoChoice::Auto, None));
+
+        assert!(still_done(BatchAction::Decompress, lzx, None));
+        assert!(!still_done(BatchAction::Decompress, lzx, Some(WofAlgorithm::Lzx)));
+        assert!(still_done(BatchAction::Verify, lzx, None));
+    }
+}
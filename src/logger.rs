@@ -72,11 +72,15 @@ pub fn log_internal(level: LogLevel, msg: String) {
         .as_secs();
 
     // Manual binding for GetCurrentThreadId
+    #[cfg(windows)]
     #[link(name = "kernel32")]
     unsafe extern "system" {
         fn GetCurrentThreadId() -> u32;
     }
+    #[cfg(windows)]
     let thread_id = unsafe { GetCurrentThreadId() };
+    #[cfg(not(windows))]
+    let thread_id = 0;
 
     let entry = LogEntry {
         timestamp,
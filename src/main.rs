@@ -1,14 +1,18 @@
 #![windows_subsystem = "windows"]
-#![no_main]
+// Entered through WinMainCRTStartup; the test harness and other hosts need a main
+#![cfg_attr(all(windows, not(test)), no_main)]
 #![allow(unsafe_op_in_unsafe_fn)]
 
+#[cfg(windows)]
 use std::alloc::{GlobalAlloc, Layout};
 use crate::types::*;
 use std::ptr;
 use std::sync::OnceLock;
 
+#[cfg(windows)]
 struct Win32Allocator;
 
+#[cfg(windows)]
 unsafe impl GlobalAlloc for Win32Allocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         let size = layout.size();
@@ -73,6 +77,7 @@ unsafe impl GlobalAlloc for Win32Allocator {
     }
 }
 
+#[cfg(windows)]
 #[global_allocator]
 static ALLOCATOR: Win32Allocator = Win32Allocator;
 
@@ -87,12 +92,12 @@ mod logger;
 pub mod types;
 pub mod com;
 
-use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof::AlgoChoice;
 use crate::ui::state::BatchAction;
 use crate::utils::to_wstring;
 
 // Manual binding for ExitProcess since we are bypassing standard main return
-#[link(name = "kernel32")]
+#[cfg_attr(windows, link(name = "kernel32"))]
 unsafe extern "system" {
     fn ExitProcess(uExitCode: u32);
 }
@@ -101,7 +106,7 @@ unsafe extern "system" {
 #[derive(Clone, Debug)]
 pub struct StartupItem {
     pub path: String,
-    pub algorithm: WofAlgorithm,
+    pub algorithm: AlgoChoice,
     pub action: BatchAction,
 }
 
@@ -121,23 +126,18 @@ fn parse_cli_args() -> Vec<StartupItem> {
         if args[i] == "--path" && i + 1 < args.len() {
             let path = args[i + 1].clone();
             i += 2;
-            let mut algorithm = WofAlgorithm::Xpress8K; 
+            let mut algorithm = AlgoChoice::default();
             let mut action = BatchAction::Compress; 
             
             while i < args.len() {
                 if args[i] == "--algo" && i + 1 < args.len() {
-                    algorithm = match args[i + 1].to_lowercase().as_str() {
-                        "xpress4k" => WofAlgorithm::Xpress4K,
-                        "xpress8k" => WofAlgorithm::Xpress8K,
-                        "xpress16k" => WofAlgorithm::Xpress16K,
-                        "lzx" => WofAlgorithm::Lzx,
-                        "lznt1" => WofAlgorithm::Lznt1,
-                        _ => WofAlgorithm::Xpress8K,
-                    };
+                    algorithm = AlgoChoice::from_name(&args[i + 1]).unwrap_or_default();
                     i += 2;
                 } else if args[i] == "--action" && i + 1 < args.len() {
                     action = match args[i + 1].to_lowercase().as_str() {
                         "decompress" => BatchAction::Decompress,
+                        "dryrun" => BatchAction::DryRun,
+                        "verify" => BatchAction::Verify,
                         _ => BatchAction::Compress,
                     };
                     i += 2;
@@ -155,12 +155,89 @@ fn parse_cli_args() -> Vec<StartupItem> {
     items
 }
 
+/// Path given with `--compare`, if any.
+fn compare_cli_arg() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter().position(|a| a == "--compare").and_then(|i| args.get(i + 1).cloned())
+}
+
+/// Prints the what-if comparison of every algorithm for `path` to the console that started
+/// us (`compactrs --compare <path>`), the same figures as the "Compare Algorithms" dialog.
+unsafe fn run_compare_cli(path: &str) -> u32 {
+    use crate::utils::{align_left, align_right, fixed_point, size_text};
+    AttachConsole(ATTACH_PARENT_PROCESS);
+
+    let Some(cmp) = crate::engine::compare::compare_path(path, &|| false) else { return 1 };
+    println!("{}", ["\n", path, " (", &size_text(cmp.logical), ", ", &size_text(cmp.disk), " on disk)\n"].concat());
+    println!("{}", [&align_left("Algorithm", 10), " ", &align_right("Projected", 14), " ", &align_right("+/-", 9), " ",
+        &align_right("Savings", 12), " ", &align_right("Saved", 9), " ", &align_right("CPU cost", 9)].concat());
+    for r in &cmp.rows {
+        let cost = r.cpu_cost.map_or("-".to_string(), |c| [&fixed_point((c * 10.0).round() as u64, 1), "x"].concat());
+        let saved = [&fixed_point((cmp.savings_percent(r) * 10.0).round() as u64, 1), "%"].concat();
+        println!("{}", [&align_left(&r.algo.name().to_uppercase(), 10), " ", &align_right(&size_text(r.estimate.size), 14), " ",
+            &align_right(&size_text(r.estimate.margin), 9), " ", &align_right(&size_text(cmp.savings(r)), 12), " ",
+            &align_right(&saved, 9), " ", &align_right(&cost, 9)].concat());
+    }
+    if let Some(best) = cmp.smallest() {
+        println!("{}", ["\nSmallest: ", &best.algo.name().to_uppercase()].concat());
+    }
+    0
+}
+
+/// Path given with `--dry-run`, if any, and the algorithm given with `--algo`.
+fn dry_run_cli_args() -> Option<(String, Option<AlgoChoice>)> {
+    let args: Vec<String> = std::env::args().collect();
+    let path = args.iter().position(|a| a == "--dry-run").and_then(|i| args.get(i + 1).cloned())?;
+    let algo = args.iter().position(|a| a == "--algo").and_then(|i| args.get(i + 1)).and_then(|a| AlgoChoice::from_name(a));
+    Some((path, algo))
+}
+
+/// Prints what compressing `path` would do, file by file, to the console that started us
+/// (`compactrs --dry-run <path> [--algo lzx]`). Uses the saved skip settings and rules, and
+/// the default algorithm unless one is given; no file is modified.
+unsafe fn run_dry_run_cli(path: &str, algo: Option<AlgoChoice>) -> u32 {
+    use crate::utils::{align_left, align_right, size_text};
+    AttachConsole(ATTACH_PARENT_PROCESS);
+
+    let config = crate::config::AppConfig::load();
+    let algo = algo.unwrap_or(config.default_algo());
+    let skip_list = String::from_utf16_lossy(&config.skip_extensions_buf).trim_matches(char::from(0)).to_string();
+    println!("{}", ["\nDry run of ", path, " with ", &algo.name().to_uppercase(), "\n"].concat());
+    let plan = crate::engine::worker::dry_run_path(
+        path, algo, config.force_compress, config.enable_system_guard, config.enable_skip_heuristics,
+        &skip_list, config.entropy_threshold, config.process_hidden_files,
+        &mut |file, d| println!("{}", [&align_left(&d.describe(), 36), " ", &align_right(&size_text(d.projected), 12), "  ", file].concat()),
+    );
+
+    println!("{}", ["\n", &plan.summary()].concat());
+    for (reason, files, bytes) in plan.skip_reasons() {
+        println!("{}", ["  ", &align_left(&reason, 34), " ", &align_right(&files.to_string(), 8), " files ", &align_right(&size_text(bytes), 12)].concat());
+    }
+    println!("{}", ["\nOn disk: ", &size_text(plan.current), "  Projected: ", &size_text(plan.projected()), "  Savings: ", &size_text(plan.savings())].concat());
+    0
+}
+
+/// The app is Windows-only; elsewhere the binary is built for `cargo test`.
+#[cfg(all(not(windows), not(test)))]
+fn main() {
+    eprintln!("CompactRS runs on Windows only.");
+    std::process::exit(1);
+}
+
 fn is_admin() -> bool {
     unsafe { IsUserAnAdmin() != 0 }
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "system" fn WinMainCRTStartup() {
+    // Headless comparison and dry run, no window and no elevation needed
+    if let Some(path) = compare_cli_arg() {
+        ExitProcess(run_compare_cli(&path));
+    }
+    if let Some((path, algo)) = dry_run_cli_args() {
+        ExitProcess(run_dry_run_cli(&path, algo));
+    }
+
     // Initialize Theme System early
     crate::ui::theme::init();
     crate::ui::theme::set_preferred_app_mode(true);
@@ -187,16 +264,12 @@ pub unsafe extern "system" fn WinMainCRTStartup() {
     if hwnd_existing != std::ptr::null_mut() {
         if !startup_items.is_empty() {
             for item in startup_items {
-                let algo_str = match item.algorithm {
-                        WofAlgorithm::Xpress4K => "xpress4k",
-                        WofAlgorithm::Xpress8K => "xpress8k",
-                        WofAlgorithm::Xpress16K => "xpress16k",
-                        WofAlgorithm::Lzx => "lzx",
-                        WofAlgorithm::Lznt1 => "lznt1",
-                };
+                let algo_str = item.algorithm.name();
                 let action_str = match item.action {
                     BatchAction::Compress => "compress",
                     BatchAction::Decompress => "decompress",
+                    BatchAction::DryRun => "dryrun",
+                    BatchAction::Verify => "verify",
                 };
                 let payload = [&item.path, "|", algo_str, "|", action_str].concat();
                 let payload_w = to_wstring(&payload);
@@ -111,7 +111,8 @@ unsafe fn create_menu_for_root(root_path: &str, exe_path: &str) -> Result<(), St
         ("03_xpress16k", "Compress as XPRESS16K", "--algo xpress16k"),
         ("04_lzx", "Compress as LZX", "--algo lzx"),
         ("05_lznt1", "Compress as LZNT1", "--algo lznt1"),
-        ("06_decompress", "Decompress", "--action decompress"),
+        ("06_auto", "Compress as Auto", "--algo auto"),
+        ("07_decompress", "Decompress", "--action decompress"),
     ];
     
     for (id, label, args) in menu_items {
@@ -161,6 +162,10 @@ pub fn register_context_menu() -> Result<(), String> {
     })?;
     
     unsafe {
+        // Drop entries from older versions whose keys were numbered differently
+        delete_menu_for_root("*")?;
+        delete_menu_for_root("Directory")?;
+
         // Register for files (*)
         create_menu_for_root("*", &exe_path)?;
         
@@ -153,6 +153,20 @@ pub struct BY_HANDLE_FILE_INFORMATION {
     pub nFileIndexLow: u32,
 }
 
+// FILE_INFO_BY_HANDLE_CLASS
+pub const FileCompressionInfo: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FILE_COMPRESSION_INFO {
+    pub CompressedFileSize: i64,
+    pub CompressionFormat: u16,
+    pub CompressionUnitShift: u8,
+    pub ChunkShift: u8,
+    pub ClusterShift: u8,
+    pub Reserved: [u8; 3],
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct SYSTEMTIME {
@@ -207,17 +221,17 @@ pub const GWL_STYLE: i32 = -16;
 pub const GWL_EXSTYLE: i32 = -20;
 pub const GWLP_USERDATA: i32 = -21;
 
-#[link(name = "uxtheme")]
+#[cfg_attr(windows, link(name = "uxtheme"))]
 unsafe extern "system" {
     pub fn SetWindowTheme(hwnd: HWND, pszSubAppName: LPCWSTR, pszSubIdList: LPCWSTR) -> HRESULT;
 }
 
-#[link(name = "dwmapi")]
+#[cfg_attr(windows, link(name = "dwmapi"))]
 unsafe extern "system" {
     pub fn DwmSetWindowAttribute(hwnd: HWND, dwAttribute: u32, pvAttribute: *const c_void, cbAttribute: u32) -> HRESULT;
 }
 
-#[link(name = "advapi32")]
+#[cfg_attr(windows, link(name = "advapi32"))]
 unsafe extern "system" {
     pub fn RegOpenKeyExW(hKey: HKEY, lpSubKey: LPCWSTR, ulOptions: u32, samDesired: u32, phkResult: *mut HKEY) -> i32;
     pub fn RegQueryValueExW(hKey: HKEY, lpValueName: LPCWSTR, lpReserved: *mut u32, lpType: *mut u32, lpData: *mut u8, lpcbData: *mut u32) -> i32;
@@ -364,7 +378,7 @@ pub struct LVITEMW {
 }
 
 
-#[link(name = "gdi32")]
+#[cfg_attr(windows, link(name = "gdi32"))]
 unsafe extern "system" {
     pub fn SetTextColor(hdc: HDC, color: u32) -> u32;
 
@@ -514,6 +528,7 @@ pub const LVS_NOSORTHEADER: u32 = 0x8000;
 // ListView Extended Styles
 pub const LVS_EX_FULLROWSELECT: u32 = 0x00000020;
 pub const LVS_EX_DOUBLEBUFFER: u32 = 0x00010000;
+pub const LVS_EX_INFOTIP: u32 = 0x00000400;
 
 // ListView Messages
 pub const LVM_GETITEMSTATE: u32 = 0x102C;
@@ -571,6 +586,8 @@ pub const LVM_ENSUREVISIBLE: u32 = LVM_FIRST + 19;
 pub const LVM_GETSUBITEMRECT: u32 = LVM_FIRST + 56;
 pub const LVM_SETITEMCOUNT: u32 = LVM_FIRST + 47;
 pub const LVM_REDRAWITEMS: u32 = LVM_FIRST + 21;
+pub const LVM_GETTOPINDEX: u32 = LVM_FIRST + 39;
+pub const LVM_GETCOUNTPERPAGE: u32 = LVM_FIRST + 40;
 pub const LVSICF_NOINVALIDATEALL: u32 = 0x00000001;
 
 pub const LVNI_SELECTED: u32 = 0x0002;
@@ -667,6 +684,7 @@ pub const LVN_KEYDOWN: u32 = ((-100i32) - 55) as u32;
 pub const LVN_COLUMNCLICK: u32 = ((-100i32) - 8) as u32;
 pub const LVN_GETDISPINFOW: u32 = ((-100i32) - 77) as u32;
 pub const LVN_ODCACHEHINT: u32 = ((-100i32) - 13) as u32;
+pub const LVN_GETINFOTIPW: u32 = ((-100i32) - 58) as u32;
 
 // Message Filter
 pub const MSGFLT_ALLOW: u32 = 1;
@@ -702,7 +720,7 @@ pub struct LVFINDINFOW {
 // Shell Functions
 pub type SUBCLASSPROC = Option<unsafe extern "system" fn(hWnd: HWND, uMsg: u32, wParam: WPARAM, lParam: LPARAM, uIdSubclass: usize, dwRefData: usize) -> LRESULT>;
 
-#[link(name = "comctl32")]
+#[cfg_attr(windows, link(name = "comctl32"))]
 unsafe extern "system" {
     pub fn SetWindowSubclass(hWnd: HWND, pfnSubclass: SUBCLASSPROC, uIdSubclass: usize, dwRefData: usize) -> BOOL;
     pub fn DefSubclassProc(hWnd: HWND, uMsg: u32, wParam: WPARAM, lParam: LPARAM) -> LRESULT;
@@ -772,6 +790,17 @@ pub struct NMLVDISPINFOW {
     pub item: LVITEMW,
 }
 
+#[repr(C)]
+pub struct NMLVGETINFOTIPW {
+    pub hdr: NMHDR,
+    pub dwFlags: u32,
+    pub pszText: *mut u16,
+    pub cchTextMax: i32,
+    pub iItem: i32,
+    pub iSubItem: i32,
+    pub lParam: LPARAM,
+}
+
 pub const COINIT_APARTMENTTHREADED: u32 = 0x2;
 
 #[repr(C)]
@@ -963,6 +992,13 @@ pub struct IFileOpenDialogVtbl {
     pub get_selected_items: unsafe extern "system" fn(*mut c_void, *mut *mut c_void) -> HRESULT,
 }
 
+/// File type entry for IFileDialog::SetFileTypes
+#[repr(C)]
+pub struct COMDLG_FILTERSPEC {
+    pub pszName: PCWSTR,
+    pub pszSpec: PCWSTR,
+}
+
 #[repr(C)]
 pub struct IShellItemVtbl {
     pub query_interface: unsafe extern "system" fn(*mut c_void, *const GUID, *mut *mut c_void) -> HRESULT,
@@ -1064,12 +1100,15 @@ pub struct FILE_PROVIDER_EXTERNAL_INFO_V1 {
 // --- Constants & GUIDs for COM ---
 pub const CLSID_FILE_OPEN_DIALOG: GUID = GUID { data1: 0xDC1C5A9C, data2: 0xE88A, data3: 0x4DDE, data4: [0xA5, 0xA1, 0x60, 0xF8, 0x2A, 0x20, 0xAE, 0xF7] };
 pub const IID_IFILE_OPEN_DIALOG: GUID = GUID { data1: 0xd57c7288, data2: 0xd4ad, data3: 0x4768, data4: [0xbe, 0x02, 0x9d, 0x96, 0x95, 0x32, 0xd9, 0x60] };
+pub const CLSID_FILE_SAVE_DIALOG: GUID = GUID { data1: 0xC0B4E2F3, data2: 0xBA21, data3: 0x4773, data4: [0x8D, 0xBA, 0x33, 0x5E, 0xC9, 0x46, 0xEB, 0x8B] };
+pub const IID_IFILE_SAVE_DIALOG: GUID = GUID { data1: 0x84BCCD23, data2: 0x5FDE, data3: 0x4CDB, data4: [0xAE, 0xA4, 0xAF, 0x64, 0xB8, 0x3D, 0x78, 0xAB] };
 pub const CLSID_SHELL_LINK: GUID = GUID { data1: 0x00021401, data2: 0x0000, data3: 0x0000, data4: [0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46] };
 pub const IID_ISHELL_LINK_W: GUID = GUID { data1: 0x000214F9, data2: 0x0000, data3: 0x0000, data4: [0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46] };
 pub const IID_IPERSIST_FILE: GUID = GUID { data1: 0x0000010b, data2: 0x0000, data3: 0x0000, data4: [0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46] };
 pub const IID_ITASKBAR_LIST3: GUID = GUID { data1: 0xea1afb91, data2: 0x9e28, data3: 0x4b86, data4: [0x90, 0xe9, 0x9e, 0x9f, 0x8a, 0x5e, 0xef, 0xaf] };
 
 
+pub const FOS_OVERWRITEPROMPT: u32 = 0x2;
 pub const FOS_PICKFOLDERS: u32 = 0x20;
 pub const FOS_FORCEFILESYSTEM: u32 = 0x40;
 pub const FOS_ALLOWMULTISELECT: u32 = 0x200;
@@ -1079,15 +1118,19 @@ pub const SIGDN_FILESYSPATH: u32 = 0x80058000;
 
 
 
+pub const ATTACH_PARENT_PROCESS: u32 = 0xFFFFFFFF;
+
 // External Functions
-#[link(name = "kernel32")]
+#[cfg_attr(windows, link(name = "kernel32"))]
 unsafe extern "system" {
     pub fn GetModuleHandleW(lpModuleName: LPCWSTR) -> HMODULE;
     pub fn GetModuleFileNameW(hModule: HMODULE, lpFilename: LPWSTR, nSize: u32) -> u32;
     pub fn GetLastError() -> u32;
+    pub fn AttachConsole(dwProcessId: u32) -> BOOL;
     pub fn DeleteFileW(lpFileName: LPCWSTR) -> BOOL;
     pub fn MoveFileExW(lpExistingFileName: LPCWSTR, lpNewFileName: LPCWSTR, dwFlags: u32) -> BOOL;
     pub fn GetFileInformationByHandle(hFile: HANDLE, lpFileInformation: *mut BY_HANDLE_FILE_INFORMATION) -> BOOL;
+    pub fn GetFileInformationByHandleEx(hFile: HANDLE, FileInformationClass: u32, lpFileInformation: LPVOID, dwBufferSize: u32) -> BOOL;
     
     pub fn GetProcessHeap() -> HANDLE;
     pub fn HeapAlloc(hHeap: HANDLE, dwFlags: u32, dwBytes: usize) -> LPVOID;
@@ -1095,7 +1138,7 @@ unsafe extern "system" {
     pub fn HeapReAlloc(hHeap: HANDLE, dwFlags: u32, lpMem: LPVOID, dwBytes: usize) -> LPVOID;
 }
 
-#[link(name = "user32")]
+#[cfg_attr(windows, link(name = "user32"))]
 unsafe extern "system" {
     pub fn MessageBoxW(hWnd: HWND, lpText: LPCWSTR, lpCaption: LPCWSTR, uType: u32) -> i32;
     pub fn GetDlgItem(hDlg: HWND, nIDDlgItem: i32) -> HWND;
@@ -1183,12 +1226,12 @@ unsafe extern "system" {
     pub fn FlashWindowEx(pfwi: *const FLASHWINFO) -> BOOL;
 }
 
-#[link(name = "shlwapi")]
+#[cfg_attr(windows, link(name = "shlwapi"))]
 unsafe extern "system" {
     pub fn StrFormatByteSizeW(qdw: i64, pszBuf: LPWSTR, cchBuf: u32) -> LPWSTR;
 }
 
-#[link(name = "shell32")]
+#[cfg_attr(windows, link(name = "shell32"))]
 unsafe extern "system" {
     pub fn ShellExecuteW(hwnd: HWND, lpOperation: LPCWSTR, lpFile: LPCWSTR, lpParameters: LPCWSTR, lpDirectory: LPCWSTR, nShowCmd: i32) -> HINSTANCE;
     pub fn IsUserAnAdmin() -> BOOL;
@@ -1197,7 +1240,7 @@ unsafe extern "system" {
     pub fn DragFinish(hDrop: HANDLE);
 }
 
-#[link(name = "ole32")]
+#[cfg_attr(windows, link(name = "ole32"))]
 unsafe extern "system" {
     pub fn CoInitializeEx(pvReserved: *mut c_void, dwCoInit: u32) -> HRESULT;
     pub fn CoUninitialize();
@@ -1215,8 +1258,10 @@ pub const FILE_ATTRIBUTE_READONLY: u32 = 1;
 pub const FILE_ATTRIBUTE_HIDDEN: u32 = 2;
 pub const FILE_ATTRIBUTE_SYSTEM: u32 = 4;
 pub const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x800; // Added for visual toggle
+pub const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x200;
 pub const FILE_ATTRIBUTE_NORMAL: u32 = 128;
 pub const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x02000000;
+pub const FILE_READ_ATTRIBUTES: u32 = 0x80;
 pub const FILE_SHARE_READ: u32 = 1;
 pub const FILE_SHARE_WRITE: u32 = 2;
 pub const FILE_SHARE_DELETE: u32 = 4;
@@ -1224,7 +1269,7 @@ pub const OPEN_EXISTING: u32 = 3;
 pub const ERROR_ACCESS_DENIED: u32 = 5;
 pub const INVALID_HANDLE_VALUE: HANDLE = -1isize as HANDLE;
 
-#[link(name = "kernel32")]
+#[cfg_attr(windows, link(name = "kernel32"))]
 unsafe extern "system" {
     pub fn FindFirstFileExW(lpFileName: LPCWSTR, fInfoLevelId: u32, lpFindFileData: *mut c_void, fSearchOp: u32, lpSearchFilter: *mut c_void, dwAdditionalFlags: u32) -> HANDLE;
     pub fn FindNextFileW(hFindFile: HANDLE, lpFindFileData: *mut WIN32_FIND_DATAW) -> BOOL;
@@ -1322,7 +1367,7 @@ pub struct TOKEN_PRIVILEGES {
 }
 
 // --- Registry Functions (Advapi32) ---
-#[link(name = "advapi32")]
+#[cfg_attr(windows, link(name = "advapi32"))]
 unsafe extern "system" {
     pub fn RegCreateKeyExW(hKey: HKEY, lpSubKey: LPCWSTR, Reserved: u32, lpClass: LPCWSTR, dwOptions: u32, samDesired: u32, lpSecurityAttributes: LPVOID, phkResult: *mut HKEY, lpdwDisposition: LPDWORD) -> i32;
     pub fn RegSetValueExW(hKey: HKEY, lpValueName: LPCWSTR, Reserved: u32, dwType: u32, lpData: *const u8, cbData: u32) -> i32;
@@ -1346,7 +1391,7 @@ unsafe extern "system" {
 pub const ES_CONTINUOUS: u32 = 0x80000000;
 pub const ES_SYSTEM_REQUIRED: u32 = 0x00000001;
 
-#[link(name = "kernel32")]
+#[cfg_attr(windows, link(name = "kernel32"))]
 unsafe extern "system" {
     pub fn CloseHandle(hObject: HANDLE) -> BOOL;
     pub fn SetThreadExecutionState(esFlags: u32) -> u32;
@@ -1404,7 +1449,7 @@ impl Default for RM_PROCESS_INFO {
     fn default() -> Self { unsafe { std::mem::zeroed() } }
 }
 
-#[link(name = "rstrtmgr")]
+#[cfg_attr(windows, link(name = "rstrtmgr"))]
 unsafe extern "system" {
     pub fn RmStartSession(pSessionHandle: *mut u32, dwSessionFlags: u32, strSessionKey: *mut u16) -> u32;
     pub fn RmRegisterResources(dwSessionHandle: u32, nFiles: u32, rgsFileNames: *const *const u16, nApplications: u32, rgApplications: *const std::ffi::c_void, nServices: u32, rgsServiceNames: *const *const u16) -> u32;
@@ -1459,7 +1504,7 @@ impl Default for SYSTEM_INFO {
 }
 
 // Additional Kernel32 Functions
-#[link(name = "kernel32")]
+#[cfg_attr(windows, link(name = "kernel32"))]
 unsafe extern "system" {
     pub fn CreateFileW(
         lpFileName: LPCWSTR,
@@ -1481,7 +1526,7 @@ unsafe extern "system" {
 
 pub const CREATE_ALWAYS: u32 = 2;
     
-#[link(name = "kernel32")]
+#[cfg_attr(windows, link(name = "kernel32"))]
 unsafe extern "system" {
     pub fn DeviceIoControl(
         hDevice: HANDLE,
@@ -1495,6 +1540,9 @@ unsafe extern "system" {
     ) -> BOOL;
 
     pub fn GetCompressedFileSizeW(lpFileName: LPCWSTR, lpFileSizeHigh: *mut u32) -> u32;
+    pub fn GetVolumePathNameW(lpszFileName: LPCWSTR, lpszVolumePathName: LPWSTR, cchBufferLength: u32) -> BOOL;
+    pub fn GetDiskFreeSpaceW(lpRootPathName: LPCWSTR, lpSectorsPerCluster: *mut u32, lpBytesPerSector: *mut u32, lpNumberOfFreeClusters: *mut u32, lpTotalNumberOfClusters: *mut u32) -> BOOL;
+    pub fn GetDiskFreeSpaceExW(lpDirectoryName: LPCWSTR, lpFreeBytesAvailableToCaller: *mut u64, lpTotalNumberOfBytes: *mut u64, lpTotalNumberOfFreeBytes: *mut u64) -> BOOL;
     pub fn GetFileAttributesW(lpFileName: LPCWSTR) -> u32;
     pub fn SetFileAttributesW(lpFileName: LPCWSTR, dwFileAttributes: u32) -> BOOL;
     pub fn GetCurrentThreadId() -> u32;
@@ -78,7 +78,7 @@ impl FileListView {
             hwnd,
             LVM_SETEXTENDEDLISTVIEWSTYLE,
             0,
-            (LVS_EX_FULLROWSELECT | LVS_EX_DOUBLEBUFFER) as isize,
+            (LVS_EX_FULLROWSELECT | LVS_EX_DOUBLEBUFFER | LVS_EX_INFOTIP) as isize,
         );
 
         let static_class = w!("Static");
@@ -203,6 +203,15 @@ impl FileListView {
         }
     }
 
+    /// Rows currently on screen (one extra for a partially visible last row).
+    pub fn visible_rows(&self) -> std::ops::Range<usize> {
+        unsafe {
+            let top = SendMessageW(self.hwnd, LVM_GETTOPINDEX, 0, 0).max(0) as usize;
+            let per_page = SendMessageW(self.hwnd, LVM_GETCOUNTPERPAGE, 0, 0).max(0) as usize;
+            top..top + per_page + 1
+        }
+    }
+
     pub fn get_selection_count(&self) -> usize {
         self.get_selected_indices().len()
     }
@@ -2,7 +2,7 @@
 
 //! HeaderPanel component - manages the header area with top-right buttons.
 //!
-//! This component contains the Settings, About, and Console buttons
+//! This component contains the Settings, About, Console, Watcher and Analyzer buttons
 //! positioned in the top-right corner of the main window.
 
 use crate::types::*;
@@ -17,6 +17,7 @@ const ICON_KEYBOARD: &[u16] = &[0xE765, 0]; // Keyboard
 const ICON_ABOUT: &[u16] = &[0xE946, 0];    // Info
 const ICON_CONSOLE: &[u16] = &[0xE756, 0];  // CommandPrompt
 const ICON_WATCHER: &[u16] = &[0xE9D2, 0];  // Clock/Alarm
+const ICON_ANALYZE: &[u16] = &[0xEB05, 0];  // PieSingle
 
 /// Configuration for HeaderPanel control IDs.
 pub struct HeaderPanelIds {
@@ -25,6 +26,7 @@ pub struct HeaderPanelIds {
     pub btn_shortcuts: u16,
     pub btn_console: u16,
     pub btn_watcher: u16,
+    pub btn_analyze: u16,
 }
 
 /// HeaderPanel component containing the top-right action buttons.
@@ -35,6 +37,7 @@ pub struct HeaderPanel {
     hwnd_shortcuts: HWND,
     hwnd_console: HWND,
     hwnd_watcher: HWND,
+    hwnd_analyze: HWND,
     ids: HeaderPanelIds,
 }
 
@@ -47,6 +50,7 @@ impl HeaderPanel {
             hwnd_shortcuts: std::ptr::null_mut(),
             hwnd_console: std::ptr::null_mut(),
             hwnd_watcher: std::ptr::null_mut(),
+            hwnd_analyze: std::ptr::null_mut(),
             ids,
         }
     }
@@ -81,6 +85,11 @@ impl HeaderPanel {
         self.hwnd_watcher
     }
 
+    #[inline]
+    pub fn analyze_hwnd(&self) -> HWND {
+        self.hwnd_analyze
+    }
+
     pub unsafe fn set_font(&self, hfont: HFONT) {
         let _ = hfont;
     }
@@ -130,6 +139,12 @@ impl Component for HeaderPanel {
                 .font(icon_font)
                 .build();
 
+            self.hwnd_analyze = ControlBuilder::new(parent_hwnd, self.ids.btn_analyze)
+                .text_w(ICON_ANALYZE)
+                .pos(0, 0).size(30, 25).dark_mode(is_dark)
+                .font(icon_font)
+                .build();
+
             Ok(())
         }
     }
@@ -153,6 +168,7 @@ impl Component for HeaderPanel {
             apply_button_theme(self.hwnd_shortcuts, is_dark);
             apply_button_theme(self.hwnd_console, is_dark);
             apply_button_theme(self.hwnd_watcher, is_dark);
+            apply_button_theme(self.hwnd_analyze, is_dark);
             
             crate::ui::components::panel::Panel::update_theme(self.hwnd_panel, is_dark);
         }
@@ -169,6 +185,7 @@ impl HeaderPanel {
         LayoutNode::row(0, 5)
             .justify_content(JustifyContent::FlexEnd)
             .align_items(AlignItems::Center)
+            .with(self.hwnd_analyze, Fixed(30))
             .with(self.hwnd_watcher, Fixed(30))
             .with(self.hwnd_console, Fixed(30))
             .with(self.hwnd_shortcuts, Fixed(30))
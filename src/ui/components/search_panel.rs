@@ -144,6 +144,7 @@ impl Component for SearchPanel {
         crate::ui::wrappers::ComboBox::new(self.hwnd_combo_algo).add_string("XPRESS16K");
         crate::ui::wrappers::ComboBox::new(self.hwnd_combo_algo).add_string("LZX");
         crate::ui::wrappers::ComboBox::new(self.hwnd_combo_algo).add_string("LZNT1");
+        crate::ui::wrappers::ComboBox::new(self.hwnd_combo_algo).add_string("Auto");
         crate::ui::wrappers::ComboBox::new(self.hwnd_combo_algo).set_selected_index(0);
 
         self.hwnd_combo_size = ControlBuilder::new(self.hwnd_panel, self.ids.combo_size)
@@ -0,0 +1,347 @@
+#![allow(unsafe_op_in_unsafe_fn)]
+//! Volume analyzer: ranks the folders under a root by projected savings.
+//!
+//! The analysis (`engine::analyzer`) runs on a background thread that posts its progress
+//! and result back to the dialog. Recommendations are added to the batch with
+//! `UiMessage::AddPaths`, exactly like dropped folders.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::mpsc::Sender;
+
+use crate::engine::analyzer::{self, AnalyzeProgress, AnalyzerOptions, VolumeAnalysis};
+use crate::types::*;
+use crate::ui::builder::ControlBuilder;
+use crate::ui::framework::WindowHandler;
+use crate::ui::state::{ProcessingState, UiMessage};
+use crate::ui::wrappers::{get_window_text, Button, Label, ListView};
+use crate::utils::{concat_wstrings, format_estimate, format_size, to_wstring};
+
+const ANALYZER_TITLE: &str = "Volume Analyzer";
+const WM_APP_ANALYZER_PROGRESS: u32 = 0x8000 + 13;
+const WM_APP_ANALYZER_RESULT: u32 = 0x8000 + 14;
+
+// Control IDs
+const IDC_EDIT_ROOT: u16 = 3101;
+const IDC_BTN_BROWSE: u16 = 3102;
+const IDC_BTN_ANALYZE: u16 = 3103;
+const IDC_LIST_FOLDERS: u16 = 3104;
+const IDC_LBL_STATUS: u16 = 3105;
+const IDC_BTN_ADD: u16 = 3106;
+const IDC_BTN_ADD_ALL: u16 = 3107;
+const IDC_BTN_CLOSE: u16 = 3108;
+
+struct ColumnDef {
+    id: i32,
+    title: &'static str,
+    width: i32,
+}
+
+const ANALYZER_COLUMNS: [ColumnDef; 5] = [
+    ColumnDef { id: 0, title: "Folder", width: 260 }, // Dynamic
+    ColumnDef { id: 1, title: "Size", width: 70 },
+    ColumnDef { id: 2, title: "On Disk", width: 70 },
+    ColumnDef { id: 3, title: "Projected", width: 95 },
+    ColumnDef { id: 4, title: "Savings", width: 70 },
+];
+
+struct AnalyzerState {
+    opts: AnalyzerOptions,
+    tx: Sender<UiMessage>,
+    is_dark: bool,
+    /// State of the running analysis, `None` when idle.
+    running: Option<Arc<AtomicU8>>,
+    result: Option<VolumeAnalysis>,
+}
+
+/// Shows the analyzer, projecting savings with `opts`.
+///
+/// # Safety
+/// Must be called on the UI thread; `parent` must be a valid window.
+pub unsafe fn show_analyzer_modal(parent: HWND, opts: AnalyzerOptions, tx: Sender<UiMessage>, is_dark: bool) {
+    let mut state = AnalyzerState { opts, tx, is_dark, running: None, result: None };
+
+    let class_name = "CompactRS_Analyzer";
+    let existing_hwnd = FindWindowW(to_wstring(class_name).as_ptr(), std::ptr::null());
+    if !existing_hwnd.is_null() {
+        ShowWindow(existing_hwnd, SW_RESTORE);
+        SetForegroundWindow(existing_hwnd);
+        return;
+    }
+
+    use crate::ui::framework::{WindowBuilder, WindowAlignment, show_modal};
+    show_modal(
+        WindowBuilder::new(&mut state, class_name, ANALYZER_TITLE)
+            .style(WS_POPUP | WS_CAPTION | WS_SYSMENU | WS_VISIBLE | WS_THICKFRAME | WS_MAXIMIZEBOX)
+            .size(crate::ui::theme::scale(700), crate::ui::theme::scale(450))
+            .align(WindowAlignment::CenterOnParent)
+            .background(crate::ui::theme::get_background_brush(is_dark)),
+        parent
+    );
+}
+
+/// Root of the system volume, where most reclaimable space usually is.
+fn default_root() -> String {
+    let drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+    [drive.as_str(), "\\"].concat()
+}
+
+impl WindowHandler for AnalyzerState {
+    fn is_dark_mode(&self) -> bool {
+        self.is_dark
+    }
+
+    fn on_create(&mut self, hwnd: HWND) -> LRESULT {
+        unsafe {
+            crate::ui::theme::set_window_frame_theme(hwnd, self.is_dark);
+
+            let builder = |id| ControlBuilder::new(hwnd, id).dark_mode(self.is_dark);
+            let btn = |text, id| builder(id).button().text_w(&to_wstring(text)).build();
+
+            builder(IDC_EDIT_ROOT).edit().text(&default_root()).build();
+            btn("Browse...", IDC_BTN_BROWSE);
+            btn("Analyze", IDC_BTN_ANALYZE);
+
+            let h_list = builder(IDC_LIST_FOLDERS)
+                .listview()
+                .style(LVS_REPORT | LVS_SINGLESEL | LVS_SHOWSELALWAYS)
+                .build();
+            let lv = ListView::new(h_list);
+            lv.set_extended_style(LVS_EX_FULLROWSELECT | LVS_EX_DOUBLEBUFFER);
+            lv.fix_header_dark_mode(hwnd);
+            lv.clear_columns();
+            for col in ANALYZER_COLUMNS.iter() {
+                lv.add_column(col.id, col.title, col.width);
+            }
+            lv.apply_theme(self.is_dark);
+
+            builder(IDC_LBL_STATUS).label(false)
+                .text(&["Projected savings use ", &self.opts.algo.name().to_uppercase(), ". Choose a folder or drive and press Analyze."].concat())
+                .build();
+            btn("Add", IDC_BTN_ADD);
+            btn("Add All", IDC_BTN_ADD_ALL);
+            btn("Close", IDC_BTN_CLOSE);
+            self.set_result_buttons(hwnd);
+
+            crate::ui::theme::apply_theme_recursive(hwnd, self.is_dark);
+
+            self.do_layout(hwnd, crate::utils::get_client_rect(hwnd));
+        }
+        0
+    }
+
+    fn on_message(&mut self, hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        unsafe {
+            match msg {
+                WM_APP_ANALYZER_PROGRESS => {
+                    let text = if lparam == 0 {
+                        "Scanning folders...".to_string()
+                    } else {
+                        format!("Estimating the largest folders ({} of {})...", wparam + 1, lparam)
+                    };
+                    self.set_status(hwnd, &to_wstring(&text));
+                },
+                WM_APP_ANALYZER_RESULT => {
+                    self.running = None;
+                    Button::new(GetDlgItem(hwnd, IDC_BTN_ANALYZE as i32)).set_text("Analyze");
+                    if lparam == 0 {
+                        self.set_status(hwnd, &to_wstring("Analysis stopped."));
+                    } else {
+                        let analysis = Box::from_raw(lparam as *mut VolumeAnalysis);
+                        self.show_result(hwnd, *analysis);
+                    }
+                    self.set_result_buttons(hwnd);
+                },
+                WM_NOTIFY => {
+                    if crate::ui::handlers::should_block_header_resize(lparam) {
+                        return Some(1);
+                    }
+                    let nmhdr = lparam as *const NMHDR;
+                    if (*nmhdr).code == NM_DBLCLK {
+                        let nmitem = lparam as *const NMITEMACTIVATE;
+                        if (*nmitem).iItem >= 0 {
+                            self.add_rows(hwnd, Some((*nmitem).iItem as usize));
+                        }
+                    }
+                },
+                WM_COMMAND => {
+                    let id = (wparam & 0xFFFF) as u16;
+                    let code = ((wparam >> 16) & 0xFFFF) as u16;
+                    if code != BN_CLICKED as u16 { return Some(0); }
+
+                    match id {
+                        IDC_BTN_BROWSE => {
+                            if let Ok(path) = crate::ui::file_dialog::pick_folder() {
+                                SetWindowTextW(GetDlgItem(hwnd, IDC_EDIT_ROOT as i32), to_wstring(&path).as_ptr());
+                            }
+                        },
+                        IDC_BTN_ANALYZE => match &self.running {
+                            Some(state) => state.store(ProcessingState::Stopped as u8, Ordering::Relaxed),
+                            None => self.start(hwnd),
+                        },
+                        IDC_BTN_ADD => {
+                            if let Some(row) = selected_row(GetDlgItem(hwnd, IDC_LIST_FOLDERS as i32)) {
+                                self.add_rows(hwnd, Some(row));
+                            }
+                        },
+                        IDC_BTN_ADD_ALL => self.add_rows(hwnd, None),
+                        IDC_BTN_CLOSE => {
+                            DestroyWindow(hwnd);
+                        },
+                        _ => {}
+                    }
+                },
+                WM_SIZE => {
+                    let w = (lparam & 0xFFFF) as i32;
+                    let h = ((lparam >> 16) & 0xFFFF) as i32;
+                    self.do_layout(hwnd, RECT { left: 0, top: 0, right: w, bottom: h });
+                },
+                WM_GETMINMAXINFO => {
+                    let mmi = lparam as *mut MINMAXINFO;
+                    (*mmi).ptMinTrackSize.x = 600;
+                    (*mmi).ptMinTrackSize.y = 300;
+                },
+                WM_DESTROY => {
+                    if let Some(state) = &self.running {
+                        state.store(ProcessingState::Stopped as u8, Ordering::Relaxed);
+                    }
+                    return None;
+                },
+                _ => {
+                    return None;
+                }
+            }
+        }
+        Some(0)
+    }
+}
+
+impl AnalyzerState {
+    unsafe fn start(&mut self, hwnd: HWND) {
+        let root = get_window_text(GetDlgItem(hwnd, IDC_EDIT_ROOT as i32)).trim().to_string();
+        if !std::path::Path::new(&root).is_dir() {
+            MessageBoxW(hwnd, crate::w!("Please choose an existing folder or drive.").as_ptr(), crate::w!("CompactRS").as_ptr(), MB_OK | MB_ICONERROR);
+            return;
+        }
+
+        let state = Arc::new(AtomicU8::new(ProcessingState::Running as u8));
+        self.running = Some(state.clone());
+        self.result = None;
+        ListView::new(GetDlgItem(hwnd, IDC_LIST_FOLDERS as i32)).clear();
+        Button::new(GetDlgItem(hwnd, IDC_BTN_ANALYZE as i32)).set_text("Stop");
+        self.set_result_buttons(hwnd);
+
+        let opts = self.opts;
+        let hwnd_target = hwnd as usize;
+        std::thread::spawn(move || {
+            let result = analyzer::analyze(&root, opts, &state, &mut |p| {
+                let (done, total) = match p {
+                    AnalyzeProgress::Scanning => (0, 0),
+                    AnalyzeProgress::Estimating(done, total) => (done, total),
+                };
+                PostMessageW(hwnd_target as HWND, WM_APP_ANALYZER_PROGRESS, done, total as LPARAM);
+            });
+            let ptr = result.map_or(std::ptr::null_mut(), |r| Box::into_raw(Box::new(r)));
+            if PostMessageW(hwnd_target as HWND, WM_APP_ANALYZER_RESULT, 0, ptr as LPARAM) == 0 && !ptr.is_null() {
+                drop(Box::from_raw(ptr));
+            }
+        });
+    }
+
+    unsafe fn show_result(&mut self, hwnd: HWND, analysis: VolumeAnalysis) {
+        let lv = ListView::new(GetDlgItem(hwnd, IDC_LIST_FOLDERS as i32));
+        lv.clear();
+        for (i, r) in analysis.recommendations.iter().enumerate() {
+            let row = i as i32;
+            lv.insert_item(row, &r.folder.path, 0);
+            lv.set_item_text_w(row, 1, &format_size(r.folder.logical));
+            lv.set_item_text_w(row, 2, &format_size(r.folder.disk));
+            lv.set_item_text_w(row, 3, &format_estimate(r.estimate.size, r.estimate.margin));
+            lv.set_item_text_w(row, 4, &format_size(r.savings()));
+        }
+
+        let total: u64 = analysis.recommendations.iter().map(|r| r.savings()).sum();
+        let status = if analysis.recommendations.is_empty() {
+            to_wstring("No uncompressed folder large enough to be worth compressing was found.")
+        } else {
+            concat_wstrings(&[
+                &to_wstring(&[&analysis.recommendations.len().to_string(), " folders could save "].concat()),
+                &format_size(total),
+                &to_wstring(" of "),
+                &format_size(analysis.root.disk),
+                &to_wstring(" on disk. Double-click a folder or press Add to add it to the batch."),
+            ])
+        };
+        self.set_status(hwnd, &status);
+        self.result = Some(analysis);
+    }
+
+    /// Adds recommendation `row` (or all of them) to the batch.
+    unsafe fn add_rows(&self, hwnd: HWND, row: Option<usize>) {
+        let Some(result) = &self.result else { return };
+        let paths: Vec<String> = result.recommendations.iter().enumerate()
+            .filter(|(i, _)| row.is_none_or(|r| r == *i))
+            .map(|(_, r)| r.folder.path.clone())
+            .collect();
+        if paths.is_empty() { return; }
+
+        let status = match paths.as_slice() {
+            [path] => ["Added ", path, " to the batch."].concat(),
+            _ => ["Added ", &paths.len().to_string(), " folders to the batch."].concat(),
+        };
+        let _ = self.tx.send(UiMessage::AddPaths(paths));
+        self.set_status(hwnd, &to_wstring(&status));
+    }
+
+    unsafe fn set_status(&self, hwnd: HWND, text: &[u16]) {
+        Label::new(GetDlgItem(hwnd, IDC_LBL_STATUS as i32)).set_text_w(text);
+    }
+
+    unsafe fn set_result_buttons(&self, hwnd: HWND) {
+        let has_result = self.result.as_ref().is_some_and(|r| !r.recommendations.is_empty());
+        Button::new(GetDlgItem(hwnd, IDC_BTN_ADD as i32)).set_enabled(has_result);
+        Button::new(GetDlgItem(hwnd, IDC_BTN_ADD_ALL as i32)).set_enabled(has_result);
+    }
+
+    unsafe fn do_layout(&mut self, hwnd: HWND, rect: RECT) {
+        use crate::ui::layout::{LayoutNode, SizePolicy::{Fixed, Flex}, AlignItems, JustifyContent};
+        let item = |id: u16| GetDlgItem(hwnd, id as i32);
+        let h_list = item(IDC_LIST_FOLDERS);
+
+        LayoutNode::col(10, 10)
+            .with_child(LayoutNode::row(0, 5)
+                .align_items(AlignItems::Center)
+                .with_policy(Fixed(26))
+                .with(item(IDC_EDIT_ROOT), Flex(1.0))
+                .with(item(IDC_BTN_BROWSE), Fixed(80))
+                .with(item(IDC_BTN_ANALYZE), Fixed(80))
+            )
+            .with(h_list, Flex(1.0))
+            .with(item(IDC_LBL_STATUS), Fixed(20))
+            .with_child(LayoutNode::row(0, 5)
+                .align_items(AlignItems::Center)
+                .justify_content(JustifyContent::SpaceBetween)
+                .with_policy(Fixed(28))
+                .with_child(LayoutNode::row(0, 5)
+                    .with(item(IDC_BTN_ADD), Fixed(80))
+                    .with(item(IDC_BTN_ADD_ALL), Fixed(80))
+                )
+                .with(item(IDC_BTN_CLOSE), Fixed(100))
+            )
+            .apply_layout(rect);
+
+        let lv_rect = crate::utils::get_client_rect(h_list);
+        let fixed_w: i32 = ANALYZER_COLUMNS.iter().skip(1).map(|c| c.width).sum();
+        let path_w = (lv_rect.right - lv_rect.left) - fixed_w;
+        if path_w > 100 {
+            ListView::new(h_list).set_column_width(0, path_w);
+        }
+    }
+}
+
+unsafe fn selected_row(h_list: HWND) -> Option<usize> {
+    let count = SendMessageW(h_list, LVM_GETITEMCOUNT, 0, 0) as i32;
+    (0..count)
+        .find(|&i| SendMessageW(h_list, LVM_GETITEMSTATE, i as WPARAM, LVIS_SELECTED as LPARAM) & LVIS_SELECTED as LRESULT != 0)
+        .map(|i| i as usize)
+}
@@ -0,0 +1,196 @@
+#![allow(unsafe_op_in_unsafe_fn)]
+//! "What-if" comparison of every algorithm for one batch item.
+//!
+//! The comparison runs on a background thread (`engine::compare`) and is posted back to the
+//! dialog. Its estimates are also sent to the main window as `UiMessage::UpdateEstimate`,
+//! so switching the item's algorithm afterwards shows them without another pass.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+
+use crate::engine::compare::{self, Comparison, ALGORITHMS};
+use crate::types::*;
+use crate::ui::builder::ControlBuilder;
+use crate::ui::framework::WindowHandler;
+use crate::ui::layout::{LayoutNode, SizePolicy, AlignItems};
+use crate::ui::state::UiMessage;
+use crate::ui::wrappers::Label;
+use crate::utils::{concat_wstrings, fixed_point, format_estimate, format_size, to_wstring};
+
+const COMPARE_TITLE: &str = "Compare Algorithms";
+const WM_APP_COMPARE_RESULT: u32 = 0x8000 + 12;
+const SS_RIGHT: u32 = 0x2;
+
+/// Projected size, savings and CPU cost cells of one algorithm.
+const COLUMNS: usize = 3;
+
+struct CompareState {
+    is_dark: bool,
+    id: u32,
+    path: String,
+    tx: Sender<UiMessage>,
+    cancel: Arc<AtomicBool>,
+    cells: [[HWND; COLUMNS]; ALGORITHMS.len()],
+    h_status: HWND,
+    h_font_bold: HFONT,
+    h_font_regular: HFONT,
+}
+
+/// Shows the comparison for batch item `id` at `path`.
+///
+/// # Safety
+/// Must be called on the UI thread; `parent` must be a valid window.
+pub unsafe fn show_compare_modal(parent: HWND, id: u32, path: String, tx: Sender<UiMessage>, is_dark: bool) {
+    let mut state = CompareState {
+        is_dark,
+        id,
+        path,
+        tx,
+        cancel: Arc::new(AtomicBool::new(false)),
+        cells: [[std::ptr::null_mut(); COLUMNS]; ALGORITHMS.len()],
+        h_status: std::ptr::null_mut(),
+        h_font_bold: std::ptr::null_mut(),
+        h_font_regular: std::ptr::null_mut(),
+    };
+    crate::ui::dialogs::base::show_modal_singleton(
+        parent,
+        &mut state,
+        "CompactRS_Compare",
+        COMPARE_TITLE,
+        520,
+        300,
+        is_dark
+    );
+}
+
+impl CompareState {
+    unsafe fn show_result(&self, cmp: &Comparison) {
+        for (row, cells) in ALGORITHMS.iter().zip(self.cells.iter()) {
+            let Some(r) = cmp.row(*row) else { continue };
+            Label::new(cells[0]).set_text_w(&format_estimate(r.estimate.size, r.estimate.margin));
+            Label::new(cells[1]).set_text_w(&concat_wstrings(&[
+                &format_size(cmp.savings(r)),
+                &to_wstring(&[" (", &fixed_point((cmp.savings_percent(r) * 10.0).round() as u64, 1), "%)"].concat()),
+            ]));
+            Label::new(cells[2]).set_text(&r.cpu_cost.map_or("-".to_string(), |c| [&fixed_point((c * 10.0).round() as u64, 1), "x"].concat()));
+        }
+
+        let status = match cmp.smallest() {
+            Some(best) if cmp.savings(best) > 0 => concat_wstrings(&[
+                &to_wstring(&["Smallest: ", &best.algo.name().to_uppercase(), ", saves "].concat()),
+                &format_size(cmp.savings(best)),
+                &to_wstring(" of "),
+                &format_size(cmp.disk),
+                &to_wstring(" on disk"),
+            ]),
+            _ => to_wstring("No algorithm would save space here."),
+        };
+        Label::new(self.h_status).set_text_w(&status);
+    }
+}
+
+impl WindowHandler for CompareState {
+    fn is_dark_mode(&self) -> bool {
+        self.is_dark
+    }
+
+    fn on_create(&mut self, hwnd: HWND) -> LRESULT {
+        unsafe {
+            let is_dark_mode = self.is_dark;
+            crate::ui::theme::set_window_frame_theme(hwnd, is_dark_mode);
+
+            let h_default = GetStockObject(DEFAULT_GUI_FONT);
+            let mut lf: LOGFONTW = std::mem::zeroed();
+            GetObjectW(h_default, std::mem::size_of::<LOGFONTW>() as i32, &mut lf as *mut _ as *mut _);
+
+            lf.lfWeight = FW_BOLD;
+            lf.lfHeight = crate::ui::theme::scale(-14);
+            self.h_font_bold = CreateFontIndirectW(&lf);
+
+            lf.lfWeight = FW_NORMAL;
+            self.h_font_regular = CreateFontIndirectW(&lf);
+
+            let label = |text: &str, font: HFONT, style: u32| ControlBuilder::new(hwnd, 0)
+                .label(false)
+                .text(text)
+                .font(font)
+                .style(style)
+                .dark_mode(is_dark_mode)
+                .build();
+
+            let name = std::path::Path::new(&self.path).file_name().map_or(self.path.clone(), |n| n.to_string_lossy().into_owned());
+            let h_path = label(&name, self.h_font_bold, 0);
+
+            let mut col_node = LayoutNode::col(20, 8)
+                .align_items(AlignItems::Stretch)
+                .with(h_path, SizePolicy::Fixed(24));
+
+            let row = |cells: [HWND; 4]| LayoutNode::row(0, 10)
+                .with(cells[0], SizePolicy::Fixed(100))
+                .with(cells[1], SizePolicy::Fixed(130))
+                .with(cells[2], SizePolicy::Fixed(140))
+                .with(cells[3], SizePolicy::Fixed(80))
+                .with_policy(SizePolicy::Fixed(22));
+
+            col_node.add_child(row([
+                label("Algorithm", self.h_font_bold, 0),
+                label("Projected", self.h_font_bold, SS_RIGHT),
+                label("Savings", self.h_font_bold, SS_RIGHT),
+                label("CPU cost", self.h_font_bold, SS_RIGHT),
+            ]));
+            for (algo, cells) in ALGORITHMS.iter().zip(self.cells.iter_mut()) {
+                let h_name = label(&algo.name().to_uppercase(), self.h_font_regular, 0);
+                for cell in cells.iter_mut() {
+                    *cell = label("...", self.h_font_regular, SS_RIGHT);
+                }
+                col_node.add_child(row([h_name, cells[0], cells[1], cells[2]]));
+            }
+
+            self.h_status = label("Sampling every algorithm...", self.h_font_regular, 0);
+            col_node.add_child(LayoutNode::fixed(self.h_status, 40));
+
+            let client_rect = crate::utils::get_client_rect(hwnd);
+            col_node.apply_layout(client_rect);
+
+            crate::ui::theme::apply_theme_recursive(hwnd, is_dark_mode);
+
+            let (id, path, tx, cancel) = (self.id, self.path.clone(), self.tx.clone(), self.cancel.clone());
+            let hwnd_target = hwnd as usize;
+            std::thread::spawn(move || {
+                let Some(cmp) = compare::compare_path(&path, &|| cancel.load(Ordering::Relaxed)) else { return };
+                for r in &cmp.rows {
+                    let _ = tx.send(UiMessage::UpdateEstimate(id, r.algo.into(), r.estimate.size, r.estimate.margin));
+                }
+                let ptr = Box::into_raw(Box::new(cmp));
+                if PostMessageW(hwnd_target as HWND, WM_APP_COMPARE_RESULT, 0, ptr as LPARAM) == 0 {
+                    drop(Box::from_raw(ptr));
+                }
+            });
+        }
+        0
+    }
+
+    fn on_message(&mut self, _hwnd: HWND, msg: u32, _wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        unsafe {
+            match msg {
+                WM_APP_COMPARE_RESULT => {
+                    let cmp = Box::from_raw(lparam as *mut Comparison);
+                    self.show_result(&cmp);
+                    Some(0)
+                },
+                WM_DESTROY => {
+                    self.cancel.store(true, Ordering::Relaxed);
+                    if !self.h_font_bold.is_null() {
+                        DeleteObject(self.h_font_bold);
+                    }
+                    if !self.h_font_regular.is_null() {
+                        DeleteObject(self.h_font_regular);
+                    }
+                    None
+                },
+                _ => None,
+            }
+        }
+    }
+}
@@ -10,7 +10,8 @@ use std::sync::{Arc, atomic::{AtomicU8, AtomicU64, Ordering}};
 use std::sync::mpsc::{channel, Sender, Receiver};
 use std::time::Instant;
 use crate::ui::state::{UiMessage, ProcessingState, BatchAction};
-use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof::{AlgoChoice, WofAlgorithm};
+use crate::engine::error::FailureSummary;
 use crate::ui::taskbar::{TaskbarProgress, TaskbarState};
 
 const TIMER_ID: usize = 1;
@@ -40,6 +41,8 @@ pub struct ContextDialogState {
     pub hwnd_cancel: HWND,
     
     pub item_metrics: std::collections::HashMap<u32, (u64, u64)>,
+    /// Failed files of every row, shown when the run finishes
+    pub failures: FailureSummary,
 }
 
 pub unsafe fn show(items: Vec<StartupItem>, config: AppConfig) {
@@ -63,6 +66,7 @@ pub unsafe fn show(items: Vec<StartupItem>, config: AppConfig) {
         hwnd_stats: std::ptr::null_mut(),
         hwnd_cancel: std::ptr::null_mut(),
         item_metrics: std::collections::HashMap::new(),
+        failures: FailureSummary::default(),
     };
     
     let instance = GetModuleHandleW(std::ptr::null_mut());
@@ -101,16 +105,19 @@ impl ContextDialogState {
         let disk_str = crate::utils::format_size(total_disk);
         let ratio_str = crate::utils::calculate_ratio_string(total_logical, total_disk);
         
-        let first_algo = self.items.first().map(|i| i.algorithm).unwrap_or(WofAlgorithm::Xpress8K);
+        let first_algo = self.items.first().map(|i| i.algorithm).unwrap_or_default();
         let first_action = self.items.first().map(|i| i.action).unwrap_or(BatchAction::Compress);
         let action_tag = match first_action {
             BatchAction::Decompress => crate::w!("[Decompress]  Logical: "),
+            BatchAction::DryRun => crate::w!("[Dry Run]  Logical: "),
+            BatchAction::Verify => crate::w!("[Verify]  Logical: "),
             BatchAction::Compress => match first_algo {
-                WofAlgorithm::Xpress4K => crate::w!("[XPRESS4K]  Logical: "),
-                WofAlgorithm::Xpress8K => crate::w!("[XPRESS8K]  Logical: "),
-                WofAlgorithm::Xpress16K => crate::w!("[XPRESS16K]  Logical: "),
-                WofAlgorithm::Lzx => crate::w!("[LZX]  Logical: "),
-                WofAlgorithm::Lznt1 => crate::w!("[LZNT1]  Logical: "),
+                AlgoChoice::Fixed(WofAlgorithm::Xpress4K) => crate::w!("[XPRESS4K]  Logical: "),
+                AlgoChoice::Fixed(WofAlgorithm::Xpress8K) => crate::w!("[XPRESS8K]  Logical: "),
+                AlgoChoice::Fixed(WofAlgorithm::Xpress16K) => crate::w!("[XPRESS16K]  Logical: "),
+                AlgoChoice::Fixed(WofAlgorithm::Lzx) => crate::w!("[LZX]  Logical: "),
+                AlgoChoice::Fixed(WofAlgorithm::Lznt1) => crate::w!("[LZNT1]  Logical: "),
+                AlgoChoice::Auto => crate::w!("[Auto]  Logical: "),
             }
         };
 
@@ -200,6 +207,8 @@ impl WindowHandler for ContextDialogState {
             let max_threads = self.config.max_threads;
             let enable_skip = self.config.enable_skip_heuristics;
             let skip_ext = String::from_utf16_lossy(&self.config.skip_extensions_buf).trim_matches('\0').to_string();
+            let entropy_threshold = self.config.entropy_threshold;
+            let verify = self.config.verify_after_compress;
             let set_attr = self.config.set_compressed_attr;
             let process_hidden = self.config.process_hidden_files; // Extract process_hidden_files from config
             let global_current = self.global_current.clone();
@@ -207,6 +216,7 @@ impl WindowHandler for ContextDialogState {
             
             let hwnd_usize = hwnd as usize;
             
+            crate::engine::undo::begin_run();
             self.global_state.store(ProcessingState::Running as u8, Ordering::Relaxed);
             
             std::thread::spawn(move || {
@@ -225,7 +235,7 @@ impl WindowHandler for ContextDialogState {
                 
                 crate::engine::worker::batch_process_worker(
                     items_for_worker, tx, state, force, hwnd_usize, guard, low_power, max_threads,
-                    global_current, global_total, enable_skip, skip_ext, set_attr, process_hidden
+                    global_current, global_total, enable_skip, skip_ext, entropy_threshold, verify, set_attr, process_hidden
                 );
             });
         }
@@ -267,12 +277,15 @@ impl WindowHandler for ContextDialogState {
                                     }
                                     self.update_stats_label();
                                 },
-                                UiMessage::RowFinished(id, disk, _tot, _state) => {
+                                UiMessage::RowFinished(id, disk, _tot, _state, _breakdown) => {
                                     if let Some(metrics) = self.item_metrics.get_mut(&id) {
                                         metrics.1 = disk;
                                     }
                                     self.update_stats_label();
                                 },
+                                UiMessage::RowFailed(_, failures) => {
+                                    self.failures.merge(&failures);
+                                },
                                 UiMessage::StatusText(w_str) => {
                                     crate::ui::wrappers::Label::new(self.hwnd_status).set_text_w(&w_str);
                                 },
@@ -284,9 +297,13 @@ impl WindowHandler for ContextDialogState {
                                     
                                     let secs_w = crate::utils::u64_to_wstring(elapsed_secs);
                                     let tot_w = crate::utils::u64_to_wstring(total_files);
-                                    let final_msg = crate::utils::concat_wstrings(&[
+                                    let mut final_msg = crate::utils::concat_wstrings(&[
                                         crate::w!("Finished in "), &secs_w, crate::w!("s ("), &tot_w, crate::w!(" files)")
                                     ]);
+                                    if !self.failures.is_empty() {
+                                        let failed_w = crate::utils::to_wstring(&[", ", &self.failures.summary()].concat());
+                                        final_msg = crate::utils::concat_wstrings(&[&final_msg, &failed_w]);
+                                    }
                                     
                                     crate::ui::wrappers::Label::new(self.hwnd_status).set_text_w(&final_msg);
                                     crate::ui::wrappers::Button::new(self.hwnd_cancel).set_text("Close");
@@ -11,6 +11,8 @@ pub mod watcher;
 pub mod base;
 pub mod watcher_add;
 pub mod context_dialog;
+pub mod compare;
+pub mod analyzer;
 
 // Flatten the API for consumers
 pub use settings::show_settings_modal;
@@ -18,4 +20,6 @@ pub use about::show_about_modal;
 pub use shortcuts::show_shortcuts_modal;
 pub use console::{show_console_window, append_log_entry, close_console};
 pub use force_stop::show_force_stop_dialog;
-pub use watcher::show_watcher_modal;
\ No newline at end of file
+pub use watcher::show_watcher_modal;
+pub use compare::show_compare_modal;
+pub use analyzer::show_analyzer_modal;
\ No newline at end of file
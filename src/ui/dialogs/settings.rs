@@ -1,20 +1,20 @@
 #![allow(unsafe_op_in_unsafe_fn)]
-use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof::{AlgoChoice, WofAlgorithm};
 use crate::ui::state::{AppTheme, BatchAction};
 use crate::ui::builder::ControlBuilder;
-use crate::utils::to_wstring;
+use crate::utils::{fixed_point, to_wstring};
 use crate::ui::framework::WindowHandler;
 use crate::types::*;
 use crate::ui::wrappers::{Button, Label, Trackbar, ComboBox};
 use crate::ui::declarative::{DeclarativeContext, ContainerBuilder};
 use crate::ui::layout::{LayoutNode, SizePolicy, AlignItems, JustifyContent};
 
-#[link(name = "shell32")]
+#[cfg_attr(windows, link(name = "shell32"))]
 unsafe extern "system" {
     fn ShellExecuteW(hwnd: HWND, lpOperation: LPCWSTR, lpFile: LPCWSTR, lpParameters: LPCWSTR, lpDirectory: LPCWSTR, nShowCmd: i32) -> HINSTANCE;
 }
 
-#[link(name = "user32")]
+#[cfg_attr(windows, link(name = "user32"))]
 unsafe extern "system" {
     fn EnumThreadWindows(dwThreadId: u32, lpfn: Option<unsafe extern "system" fn(HWND, LPARAM) -> BOOL>, lParam: LPARAM) -> BOOL;
     fn EnumChildWindows(hWndParent: HWND, lpEnumFunc: Option<unsafe extern "system" fn(HWND, LPARAM) -> BOOL>, lParam: LPARAM) -> BOOL;
@@ -50,6 +50,9 @@ const IDC_CHK_SET_ATTR: u16 = 2044;
 const IDC_COMBO_UI_SCALE: u16 = 2045;
 
 const IDC_CHK_PROCESS_HIDDEN: u16 = 2046;
+const IDC_EDIT_ENTROPY: u16 = 2047;
+const IDC_BTN_RESET_KNOWLEDGE: u16 = 2048;
+const IDC_CHK_VERIFY: u16 = 2049;
 
 const IDC_COMBO_DEFAULT_ALGO: u16 = 2052;
 const IDC_COMBO_DEFAULT_ACTION: u16 = 2053;
@@ -84,9 +87,11 @@ struct SettingsState {
     set_compressed_attr: bool,
     ui_scale_multiplier: f32,
     context_menu_dialog_only: bool,
-    default_algo: WofAlgorithm,
+    default_algo: AlgoChoice,
     default_action: BatchAction,
     process_hidden_files: bool,
+    entropy_threshold: f32,
+    verify_after_compress: bool,
 
     update_status: UpdateStatus,
     pending_update: Option<crate::updater::UpdateInfo>,
@@ -136,9 +141,12 @@ fn get_search_targets() -> Vec<SearchTarget> {
         
         SearchTarget { tab_idx: 3, ctrl_id: IDC_COMBO_DEFAULT_ALGO, title: "Default Algorithm", keywords: &["default", "algorithm", "compress", "xpress", "lzx"] },
         SearchTarget { tab_idx: 3, ctrl_id: IDC_COMBO_DEFAULT_ACTION, title: "Default Action", keywords: &["default", "action", "compress", "decompress"] },
+        SearchTarget { tab_idx: 3, ctrl_id: IDC_CHK_VERIFY, title: "Verify After Compression", keywords: &["verify", "integrity", "hash", "check", "corrupt", "safe"] },
         SearchTarget { tab_idx: 3, ctrl_id: IDC_CHK_PROCESS_HIDDEN, title: "Process Hidden & System Files", keywords: &["hidden", "system", "file", "folder", "process", "skip"] },
         SearchTarget { tab_idx: 3, ctrl_id: IDC_CHK_SKIP_EXT, title: "Smart Compression Skip", keywords: &["smart", "skip", "unlikely", "filter", "compress", "further"] },
         SearchTarget { tab_idx: 3, ctrl_id: IDC_EDIT_EXTENSIONS, title: "Excluded Extensions", keywords: &["exclude", "extension", "format", "zip", "rar", "default"] },
+        SearchTarget { tab_idx: 3, ctrl_id: IDC_EDIT_ENTROPY, title: "Entropy Pre-Check", keywords: &["entropy", "random", "incompressible", "threshold", "skip", "probe"] },
+        SearchTarget { tab_idx: 3, ctrl_id: IDC_BTN_RESET_KNOWLEDGE, title: "Estimator Knowledge", keywords: &["estimate", "estimator", "learned", "ratio", "calibration", "reset", "knowledge"] },
         
         SearchTarget { tab_idx: 4, ctrl_id: IDC_CHK_LOG_ENABLED, title: "Enable Diagnostic Logging", keywords: &["diagnostic", "log", "console", "real-time", "enable", "show"] },
         SearchTarget { tab_idx: 4, ctrl_id: IDC_CHK_LOG_ERRORS, title: "Log Levels (Errors, Warn, Info)", keywords: &["level", "error", "warning", "info", "trace", "log"] },
@@ -166,10 +174,12 @@ pub unsafe fn show_settings_modal(
     set_compressed_attr: bool,
     ui_scale_multiplier: f32,
     context_menu_dialog_only: bool,
-    default_algo: WofAlgorithm,
+    default_algo: AlgoChoice,
     default_action: BatchAction,
-    process_hidden_files: bool
-) -> (Option<AppTheme>, bool, bool, bool, bool, u32, u32, bool, u8, bool, [u16; 512], bool, f32, bool, WofAlgorithm, BatchAction, bool) {
+    process_hidden_files: bool,
+    entropy_threshold: f32,
+    verify_after_compress: bool
+) -> (Option<AppTheme>, bool, bool, bool, bool, u32, u32, bool, u8, bool, [u16; 512], bool, f32, bool, AlgoChoice, BatchAction, bool, f32, bool) {
 
     let skip_string = String::from_utf16_lossy(&skip_extensions_buf)
         .trim_matches(char::from(0))
@@ -195,6 +205,8 @@ pub unsafe fn show_settings_modal(
         default_algo,
         default_action,
         process_hidden_files,
+        entropy_threshold,
+        verify_after_compress,
         update_status: UpdateStatus::Idle,
         pending_update: None,
         h_font_bold: std::ptr::null_mut(),
@@ -230,9 +242,9 @@ pub unsafe fn show_settings_modal(
                 i += 1;
             }
         }
-        (state.result, state.enable_force_stop, state.enable_context_menu, state.enable_system_guard, state.low_power_mode, state.max_threads, state.max_concurrent_items, state.log_enabled, state.log_level_mask, state.enable_skip_heuristics, final_buf, state.set_compressed_attr, state.ui_scale_multiplier, state.context_menu_dialog_only, state.default_algo, state.default_action, state.process_hidden_files)
+        (state.result, state.enable_force_stop, state.enable_context_menu, state.enable_system_guard, state.low_power_mode, state.max_threads, state.max_concurrent_items, state.log_enabled, state.log_level_mask, state.enable_skip_heuristics, final_buf, state.set_compressed_attr, state.ui_scale_multiplier, state.context_menu_dialog_only, state.default_algo, state.default_action, state.process_hidden_files, state.entropy_threshold, state.verify_after_compress)
     } else {
-         (None, enable_force_stop, enable_context_menu, enable_system_guard, low_power_mode, max_threads, max_concurrent_items, log_enabled, log_level_mask, enable_skip_heuristics, skip_extensions_buf, set_compressed_attr, ui_scale_multiplier, context_menu_dialog_only, default_algo, default_action, process_hidden_files)
+         (None, enable_force_stop, enable_context_menu, enable_system_guard, low_power_mode, max_threads, max_concurrent_items, log_enabled, log_level_mask, enable_skip_heuristics, skip_extensions_buf, set_compressed_attr, ui_scale_multiplier, context_menu_dialog_only, default_algo, default_action, process_hidden_files, entropy_threshold, verify_after_compress)
     }
 }
 
@@ -641,24 +653,30 @@ impl WindowHandler for SettingsState {
                 ctx.vertical(15, 2, |v| {
                     section_header(v, "Default Operations");
                     icon_row(v, p3, "\u{E8B5}", crate::w!("Default Algorithm"), crate::w!("Algorithm applied to newly added files"), &|c| {
-                         c.combobox(IDC_COMBO_DEFAULT_ALGO, &["XPRESS4K", "XPRESS8K", "XPRESS16K", "LZX", "LZNT1"], 
+                         c.combobox(IDC_COMBO_DEFAULT_ALGO, &["XPRESS4K", "XPRESS8K", "XPRESS16K", "LZX", "LZNT1", "Auto"], 
                              match self.default_algo {
-                                 WofAlgorithm::Xpress4K => 0,
-                                 WofAlgorithm::Xpress8K => 1,
-                                 WofAlgorithm::Xpress16K => 2,
-                                 WofAlgorithm::Lzx => 3,
-                                 WofAlgorithm::Lznt1 => 4,
+                                 AlgoChoice::Fixed(WofAlgorithm::Xpress4K) => 0,
+                                 AlgoChoice::Fixed(WofAlgorithm::Xpress8K) => 1,
+                                 AlgoChoice::Fixed(WofAlgorithm::Xpress16K) => 2,
+                                 AlgoChoice::Fixed(WofAlgorithm::Lzx) => 3,
+                                 AlgoChoice::Fixed(WofAlgorithm::Lznt1) => 4,
+                                 AlgoChoice::Auto => 5,
                              }, 
                              SizePolicy::Fixed(110)); 
                     });
                     icon_row(v, p3, "\u{E768}", crate::w!("Default Action"), crate::w!("Action applied to newly added files"), &|c| {
-                         c.combobox(IDC_COMBO_DEFAULT_ACTION, &["Compress", "Decompress"], 
+                         c.combobox(IDC_COMBO_DEFAULT_ACTION, &["Compress", "Decompress", "Dry Run", "Verify"], 
                              match self.default_action {
                                  BatchAction::Compress => 0,
                                  BatchAction::Decompress => 1,
+                                 BatchAction::DryRun => 2,
+                                 BatchAction::Verify => 3,
                              }, 
                              SizePolicy::Fixed(110)); 
                     });
+                    icon_row(v, p3, "\u{E73E}", crate::w!("Verify After Compression"), crate::w!("Read each file back after compressing and undo it on a mismatch"), &|c| {
+                         c.checkbox(IDC_CHK_VERIFY, "", self.verify_after_compress, SizePolicy::Fixed(20));
+                    });
 
                     section_header(v, "File Filtering");
                     icon_row(v, p3, "\u{E7B3}", crate::w!("Process Hidden & System Files"), crate::w!("Process files with hidden or system attributes"), &|c| {
@@ -667,6 +685,15 @@ impl WindowHandler for SettingsState {
                     icon_row(v, p3, "\u{E71C}", crate::w!("Smart Compression Skip"), crate::w!("Skip files that are unlikely to compress further"), &|c| {
                          c.checkbox(IDC_CHK_SKIP_EXT, "", self.enable_skip_heuristics, SizePolicy::Fixed(20));
                     });
+                    icon_row(v, p3, "\u{E9D2}", crate::w!("Entropy Pre-Check"), crate::w!("Skip files at or above this entropy in bits/byte (0 = Off, e.g. 7.90)"), &|c| {
+                         let val_s = fixed_point((self.entropy_threshold as f64 * 100.0).round() as u64, 2);
+                         c.input(IDC_EDIT_ENTROPY, &val_s, ES_CENTER, SizePolicy::Fixed(60));
+                    });
+                    let learned = crate::engine::knowledge::global().len();
+                    let learned_desc = to_wstring(&["Ratios learned from completed batches (", &learned.to_string(), " entries)"].concat());
+                    icon_row(v, p3, "\u{E9F5}", crate::w!("Estimator Knowledge"), &learned_desc, &|c| {
+                         c.button_w(IDC_BTN_RESET_KNOWLEDGE, crate::w!("Reset"), SizePolicy::Fixed(80));
+                    });
                     v.row_with_policy(10, SizePolicy::Fixed(24), |r| {
                         r.align_items(AlignItems::Center);
                         r.label("Excluded Extensions:", SizePolicy::Flex(1.0));
@@ -1099,12 +1126,13 @@ impl WindowHandler for SettingsState {
                                  let h_combo = self.get_control(IDC_COMBO_DEFAULT_ALGO as i32);
                                  let idx = ComboBox::new(h_combo).get_selected_index();
                                  self.default_algo = match idx {
-                                     0 => WofAlgorithm::Xpress4K,
-                                     1 => WofAlgorithm::Xpress8K,
-                                     2 => WofAlgorithm::Xpress16K,
-                                     3 => WofAlgorithm::Lzx,
-                                     4 => WofAlgorithm::Lznt1,
-                                     _ => WofAlgorithm::Xpress8K,
+                                     0 => AlgoChoice::Fixed(WofAlgorithm::Xpress4K),
+                                     1 => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
+                                     2 => AlgoChoice::Fixed(WofAlgorithm::Xpress16K),
+                                     3 => AlgoChoice::Fixed(WofAlgorithm::Lzx),
+                                     4 => AlgoChoice::Fixed(WofAlgorithm::Lznt1),
+                                     5 => AlgoChoice::Auto,
+                                     _ => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
                                  };
                              }
                          },
@@ -1115,6 +1143,8 @@ impl WindowHandler for SettingsState {
                                  self.default_action = match idx {
                                      0 => BatchAction::Compress,
                                      1 => BatchAction::Decompress,
+                                     2 => BatchAction::DryRun,
+                                     3 => BatchAction::Verify,
                                      _ => BatchAction::Compress,
                                  };
                              }
@@ -1133,6 +1163,11 @@ impl WindowHandler for SettingsState {
                              }
                              DestroyWindow(hwnd);
                          },
+                         IDC_CHK_VERIFY => {
+                             if (code as u32) == BN_CLICKED {
+                                 self.verify_after_compress = Button::new(self.get_control(id as i32)).is_checked();
+                             }
+                         },
                          IDC_CHK_PROCESS_HIDDEN => {
                              if (code as u32) == BN_CLICKED {
                                  self.process_hidden_files = Button::new(self.get_control(id as i32)).is_checked();
@@ -1152,6 +1187,28 @@ impl WindowHandler for SettingsState {
                                    SetWindowTextW(self.get_control(IDC_EDIT_EXTENSIONS as i32), to_wstring(default_skip).as_ptr());
                               }
                          },
+                         IDC_BTN_RESET_KNOWLEDGE => {
+                              if (code as u32) == BN_CLICKED {
+                                   // Learned ratios cannot be brought back, so ask first
+                                   let w_msg = to_wstring("Forget the compression ratios learned from earlier batches? Estimates fall back to the built-in tables.");
+                                   if MessageBoxW(hwnd, w_msg.as_ptr(), crate::w!("Estimator Knowledge").as_ptr(), MB_YESNO | MB_ICONWARNING) == IDYES {
+                                       crate::engine::knowledge::reset();
+                                       let h_btn = self.get_control(IDC_BTN_RESET_KNOWLEDGE as i32);
+                                       Button::new(h_btn).set_text("Cleared");
+                                       Button::new(h_btn).set_enabled(false);
+                                   }
+                              }
+                         },
+                         IDC_EDIT_ENTROPY => {
+                               if (code as u32) == EN_CHANGE {
+                                   let h = self.get_control(IDC_EDIT_ENTROPY as i32);
+                                   let len = GetWindowTextLengthW(h);
+                                   let mut buf = vec![0u16; (len + 1) as usize];
+                                   GetWindowTextW(h, buf.as_mut_ptr(), len + 1);
+                                   let text = String::from_utf16_lossy(&buf[..len as usize]);
+                                   self.entropy_threshold = text.trim().parse::<f32>().map(|v| v.clamp(0.0, 8.0)).unwrap_or(0.0);
+                               }
+                         },
                          IDC_EDIT_EXTENSIONS => {
                                if (code as u32) == EN_CHANGE {
                                    let h = self.get_control(IDC_EDIT_EXTENSIONS as i32);
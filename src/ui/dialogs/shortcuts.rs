@@ -57,6 +57,8 @@ impl WindowHandler for ShortcutsState {
                 ("Ctrl + V", "Paste Files from Clipboard"),
                 ("Del", "Remove Selected Items"),
                 ("Ctrl + A", "Select All Items"),
+                ("Ctrl + Z", "Undo Last Batch"),
+                ("Ctrl + E", "Export Batch Report"),
                 ("Double Click (Path)", "Open File Location"),
                 ("Double Click (Algo)", "Cycle Compression Algorithm"),
                 ("Double Click (Action)", "Toggle Compress/Decompress"),
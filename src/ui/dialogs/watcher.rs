@@ -3,14 +3,14 @@
 use crate::ui::builder::ControlBuilder;
 use crate::ui::wrappers::ListView;
 use crate::watcher_config::{WatcherTask, WatcherConfig};
-use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof::{AlgoChoice, WofAlgorithm};
 use crate::engine::scanner::scan_path_metrics;
 use crate::w;
 use crate::utils::format_size;
 use crate::ui::framework::WindowHandler;
 use crate::types::*;
 
-#[link(name = "kernel32")]
+#[cfg_attr(windows, link(name = "kernel32"))]
 unsafe extern "system" {
     fn FileTimeToLocalFileTime(lpfiletime: *const FILETIME, lplocalfiletime: *mut FILETIME) -> i32;
     fn FileTimeToSystemTime(lpfiletime: *const FILETIME, lpsystemtime: *mut SYSTEMTIME) -> i32;
@@ -159,7 +159,7 @@ impl WindowHandler for WatcherState {
                                  tasks.get((*nmitem).iItem as usize).cloned()
                              };
                              if let Some(task) = task_opt {
-                                 let _ = self.tx.send(UiMessage::WatcherTrigger(task.get_path(), task.algorithm));
+                                 let _ = self.tx.send(UiMessage::WatcherTrigger(task.get_path(), task.algorithm()));
                                  MessageBoxW(hwnd, w!("Triggered manual run.").as_ptr(), w!("CompactRS").as_ptr(), MB_OK);
                              }
                         }
@@ -323,12 +323,13 @@ impl WatcherState {
             };
             lv.set_item_text_w(i as i32, 3, &schedule);
             
-            let algo = match task.algorithm {
-                WofAlgorithm::Xpress4K => "XPRESS4K",
-                WofAlgorithm::Xpress8K => "XPRESS8K",
-                WofAlgorithm::Xpress16K => "XPRESS16K",
-                WofAlgorithm::Lzx => "LZX",
-                WofAlgorithm::Lznt1 => "LZNT1",
+            let algo = match task.algorithm() {
+                AlgoChoice::Fixed(WofAlgorithm::Xpress4K) => "XPRESS4K",
+                AlgoChoice::Fixed(WofAlgorithm::Xpress8K) => "XPRESS8K",
+                AlgoChoice::Fixed(WofAlgorithm::Xpress16K) => "XPRESS16K",
+                AlgoChoice::Fixed(WofAlgorithm::Lzx) => "LZX",
+                AlgoChoice::Fixed(WofAlgorithm::Lznt1) => "LZNT1",
+                AlgoChoice::Auto => "Auto",
             };
             lv.set_item_text(i as i32, 4, algo);
 
@@ -4,7 +4,7 @@
 use crate::ui::builder::ControlBuilder;
 use crate::ui::wrappers::{Button, ComboBox};
 use crate::watcher_config::{WatcherTask, WatcherConfig};
-use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof::{AlgoChoice, WofAlgorithm};
 use crate::w;
 use crate::utils::to_wstring;
 use crate::ui::framework::WindowHandler;
@@ -101,6 +101,7 @@ impl WindowHandler for WatcherAddState {
             cb.add_string("XPRESS 16K");
             cb.add_string("LZX");
             cb.add_string("LZNT1");
+            cb.add_string("Auto");
             cb.set_selected_index(1);
             
             // 3. Time
@@ -166,12 +167,13 @@ impl WindowHandler for WatcherAddState {
                 if let Some(task) = tasks.get(idx) {
                     SetWindowTextW(h_path, to_wstring(&task.get_path()).as_ptr());
                     
-                    let algo_idx = match task.algorithm {
-                        WofAlgorithm::Xpress4K => 0,
-                        WofAlgorithm::Xpress8K => 1,
-                        WofAlgorithm::Xpress16K => 2,
-                        WofAlgorithm::Lzx => 3,
-                        WofAlgorithm::Lznt1 => 4,
+                    let algo_idx = match task.algorithm() {
+                        AlgoChoice::Fixed(WofAlgorithm::Xpress4K) => 0,
+                        AlgoChoice::Fixed(WofAlgorithm::Xpress8K) => 1,
+                        AlgoChoice::Fixed(WofAlgorithm::Xpress16K) => 2,
+                        AlgoChoice::Fixed(WofAlgorithm::Lzx) => 3,
+                        AlgoChoice::Fixed(WofAlgorithm::Lznt1) => 4,
+                        AlgoChoice::Auto => 5,
                     };
                     cb.set_selected_index(algo_idx);
                     
@@ -289,12 +291,13 @@ impl WatcherAddState {
          let h_combo = GetDlgItem(hwnd, IDC_COMBO_ALGO as i32);
          let idx = ComboBox::new(h_combo).get_selected_index();
          let algo = match idx {
-             0 => WofAlgorithm::Xpress4K,
-             1 => WofAlgorithm::Xpress8K,
-             2 => WofAlgorithm::Xpress16K,
-             3 => WofAlgorithm::Lzx,
-             4 => WofAlgorithm::Lznt1,
-             _ => WofAlgorithm::Xpress8K,
+             0 => AlgoChoice::Fixed(WofAlgorithm::Xpress4K),
+             1 => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
+             2 => AlgoChoice::Fixed(WofAlgorithm::Xpress16K),
+             3 => AlgoChoice::Fixed(WofAlgorithm::Lzx),
+             4 => AlgoChoice::Fixed(WofAlgorithm::Lznt1),
+             5 => AlgoChoice::Auto,
+             _ => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
          };
          
          // Get Time
@@ -349,7 +352,7 @@ impl WatcherAddState {
                  // Update existing
                  if let Some(task) = tasks.get_mut(idx) {
                      task.set_path(&path_str);
-                     task.algorithm = algo;
+                     task.set_algorithm(algo);
                      task.days_mask = mask;
                      task.time_hour = hr;
                      task.time_minute = mn;
@@ -83,6 +83,58 @@ pub unsafe fn pick_files() -> Result<Vec<String>, HRESULT> { unsafe {
     run_dialog_internal(FOS_FORCEFILESYSTEM | FOS_ALLOWMULTISELECT)
 }}
 
+/// Ask where to save a file using the native IFileSaveDialog.
+///
+/// `types` are (description, pattern) pairs such as ("CSV", "*.csv"); the extension of the
+/// selected type is appended when the user types a name without one. IFileSaveDialog
+/// starts with the same IFileDialog methods as `IFileOpenDialogVtbl`, and only those are
+/// called here.
+pub unsafe fn pick_save_file(default_name: &str, types: &[(&str, &str)]) -> Result<String, HRESULT> { unsafe {
+    let mut p_dialog: *mut c_void = std::ptr::null_mut();
+    let hr = CoCreateInstance(&CLSID_FILE_SAVE_DIALOG, std::ptr::null_mut(), CLSCTX_ALL, &IID_IFILE_SAVE_DIALOG, &mut p_dialog);
+    if hr != 0 { return Err(hr); }
+
+    let dialog = p_dialog as *mut *mut IFileOpenDialogVtbl;
+    let vtbl = (*dialog).as_ref().unwrap();
+
+    let names: Vec<Vec<u16>> = types.iter().map(|(name, _)| crate::utils::to_wstring(name)).collect();
+    let specs: Vec<Vec<u16>> = types.iter().map(|(_, spec)| crate::utils::to_wstring(spec)).collect();
+    let filters: Vec<COMDLG_FILTERSPEC> = names.iter().zip(&specs)
+        .map(|(n, s)| COMDLG_FILTERSPEC { pszName: n.as_ptr(), pszSpec: s.as_ptr() })
+        .collect();
+    (vtbl.set_file_types)(p_dialog, filters.len() as u32, filters.as_ptr() as *const c_void);
+    (vtbl.set_file_type_index)(p_dialog, 1);
+    if let Some((_, spec)) = types.first() {
+        let ext = crate::utils::to_wstring(spec.trim_start_matches("*."));
+        (vtbl.set_default_extension)(p_dialog, ext.as_ptr());
+    }
+    let name_w = crate::utils::to_wstring(default_name);
+    (vtbl.set_file_name)(p_dialog, name_w.as_ptr());
+
+    let mut current_options = 0;
+    (vtbl.get_options)(p_dialog, &mut current_options);
+    (vtbl.set_options)(p_dialog, current_options | FOS_FORCEFILESYSTEM | FOS_OVERWRITEPROMPT);
+
+    let hr = (vtbl.show)(p_dialog, 0);
+    if hr != 0 {
+        (vtbl.release)(p_dialog);
+        return Err(hr);
+    }
+
+    let mut p_item: *mut c_void = std::ptr::null_mut();
+    let hr = (vtbl.get_result)(p_dialog, &mut p_item);
+    if hr != 0 {
+        (vtbl.release)(p_dialog);
+        return Err(hr);
+    }
+    let path = get_path_from_item(p_item);
+    let item = p_item as *mut *mut IShellItemVtbl;
+    ((*item).as_ref().unwrap().release)(p_item);
+    (vtbl.release)(p_dialog);
+
+    path.ok_or(-1)
+}}
+
 /// Pick a single folder using the native IFileOpenDialog.
 pub unsafe fn pick_folder() -> Result<String, HRESULT> { unsafe {
     let paths = run_dialog_internal(FOS_PICKFOLDERS | FOS_FORCEFILESYSTEM)?;
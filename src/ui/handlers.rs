@@ -4,15 +4,15 @@ use crate::types::*;
 use std::thread;
 use std::sync::atomic::Ordering;
 
-use crate::ui::state::{AppState, BatchAction, ProcessingState, BatchStatus};
+use crate::ui::state::{AppState, BatchAction, ProcessingState, BatchStatus, UiMessage};
 use crate::ui::taskbar::TaskbarState;
 use crate::ui::controls::*;
 use crate::ui::wrappers::{Button, ComboBox, Label};
 use crate::ui::theme;
-use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof::{AlgoChoice, WofAlgorithm};
 use crate::engine::worker::batch_process_worker;
-use crate::utils::{to_wstring, u64_to_wstring, concat_wstrings, reveal_path_in_explorer};
-use crate::ui::file_dialog::{pick_files, pick_folder};
+use crate::utils::{to_wstring, u64_to_wstring, concat_wstrings, format_breakdown, format_plan, join_lines, reveal_path_in_explorer};
+use crate::ui::file_dialog::{pick_files, pick_folder, pick_save_file};
 
 // --- Command Handlers ---
 
@@ -122,6 +122,10 @@ pub unsafe fn start_processing(st: &mut AppState, hwnd: HWND, mut indices_to_pro
 
     st.active_lock_dialog = None;
     st.ignored_lock_processes.clear();
+    // Queued rows belong to the same run, so one undo covers them all
+    crate::engine::undo::begin_run();
+    // ... and one report
+    st.last_report = None;
 
     let max = st.config.max_concurrent_items as usize;
     
@@ -143,19 +147,21 @@ pub unsafe fn start_processing_internal(st: &mut AppState, hwnd: HWND, indices_t
         let idx = combo.get_selected_index();
         let use_as_listed = idx == 0;
         let global_algo = match idx {
-            1 => WofAlgorithm::Xpress4K,
-            2 => WofAlgorithm::Xpress8K,
-            3 => WofAlgorithm::Xpress16K,
-            4 => WofAlgorithm::Lzx,
-            5 => WofAlgorithm::Lznt1,
-            _ => WofAlgorithm::Xpress8K,
+            1 => AlgoChoice::Fixed(WofAlgorithm::Xpress4K),
+            2 => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
+            3 => AlgoChoice::Fixed(WofAlgorithm::Xpress16K),
+            4 => AlgoChoice::Fixed(WofAlgorithm::Lzx),
+            5 => AlgoChoice::Fixed(WofAlgorithm::Lznt1),
+            6 => AlgoChoice::Auto,
+            _ => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
         };
         
-        if !use_as_listed {
-            for &idx in &indices_to_process {
-                if let Some(item) = st.batch_items.get_mut(idx) {
-                    item.algorithm = global_algo;
-                }
+        for &idx in &indices_to_process {
+            if let Some(item) = st.batch_items.get_mut(idx) {
+                if !use_as_listed { item.algorithm = global_algo; }
+                // A new run makes the last dry run's plan and failures stale
+                item.plan = None;
+                item.failures = Default::default();
             }
         }
         
@@ -179,7 +185,7 @@ pub unsafe fn start_processing_internal(st: &mut AppState, hwnd: HWND, indices_t
         let items: Vec<_> = indices_to_process.into_iter().filter_map(|idx| {
             st.batch_items.get(idx).map(|item| {
                 let effective_action = match action_mode_idx {
-                    1 => BatchAction::Compress, 2 => BatchAction::Decompress, _ => item.action,
+                    1 => BatchAction::Compress, 2 => BatchAction::Decompress, 3 => BatchAction::DryRun, 4 => BatchAction::Verify, _ => item.action,
                 };
                 let effective_algo = if use_as_listed { item.algorithm } else { global_algo };
                 (item.path.clone(), effective_action, item.id, effective_algo)
@@ -197,6 +203,8 @@ pub unsafe fn start_processing_internal(st: &mut AppState, hwnd: HWND, indices_t
             .trim_matches(char::from(0))
             .to_string();
         
+        let entropy_threshold = st.config.entropy_threshold;
+        let verify = st.config.verify_after_compress;
         let set_attr = st.config.set_compressed_attr;
         let process_hidden = st.process_hidden_files;
         
@@ -204,18 +212,19 @@ pub unsafe fn start_processing_internal(st: &mut AppState, hwnd: HWND, indices_t
         let global_tot = st.global_progress_total.clone();
 
         thread::spawn(move || {
-            batch_process_worker(items, tx, state_global, force, main_hwnd_usize, guard, low_power, max_threads, global_cur, global_tot, enable_skip, skip_list, set_attr, process_hidden);
+            batch_process_worker(items, tx, state_global, force, main_hwnd_usize, guard, low_power, max_threads, global_cur, global_tot, enable_skip, skip_list, entropy_threshold, verify, set_attr, process_hidden);
         });
     }
 }
 
 pub unsafe fn on_stop_processing(st: &mut AppState) {
     st.global_state.store(ProcessingState::Stopped as u8, Ordering::Relaxed);
+    st.stop_count.fetch_add(1, Ordering::Relaxed);
     st.processing_queue.clear();
     
     for item in &mut st.batch_items {
         if let Some(flag) = &item.state_flag { flag.store(ProcessingState::Stopped as u8, Ordering::Relaxed); }
-        if item.status != BatchStatus::Complete {
+        if !matches!(item.status, BatchStatus::Complete | BatchStatus::Error(_)) {
              item.status_override = Some("Cancelled".to_string());
         }
     }
@@ -234,6 +243,103 @@ pub unsafe fn on_stop_processing(st: &mut AppState) {
     }
 }
 
+// Restores what the last compress run changed: each file's prior WOF/LZNT1 state and
+// read-only attribute, and the compressed attribute of its folders.
+pub unsafe fn on_undo_last_batch(st: &mut AppState, hwnd: HWND) {
+    let w_title = to_wstring("Undo Last Batch");
+    let global_state = ProcessingState::from_u8(st.global_state.load(Ordering::Relaxed));
+    if global_state != ProcessingState::Idle && global_state != ProcessingState::Stopped {
+        let w_msg = to_wstring("Wait for the current batch to finish before undoing.");
+        MessageBoxW(hwnd, w_msg.as_ptr(), w_title.as_ptr(), MB_OK | MB_ICONINFORMATION);
+        return;
+    }
+    let Some(log) = crate::engine::undo::last() else {
+        let w_msg = to_wstring("There is no compress batch to undo.");
+        MessageBoxW(hwnd, w_msg.as_ptr(), w_title.as_ptr(), MB_OK | MB_ICONINFORMATION);
+        return;
+    };
+
+    let w_msg = to_wstring(&["Restore the previous compression state of ", &log.files().to_string(), " file(s) and ",
+        &log.folders().to_string(), " folder(s) changed by the last batch?"].concat());
+    if MessageBoxW(hwnd, w_msg.as_ptr(), w_title.as_ptr(), MB_YESNO | MB_ICONWARNING) != IDYES { return; }
+
+    // Rows holding a restored path are rescanned afterwards
+    let rows: Vec<(u32, String)> = st.batch_items.iter()
+        .filter(|item| log.entries.iter().any(|e| crate::utils::path_covers(&item.path, e.path())))
+        .map(|item| (item.id, item.path.clone()))
+        .collect();
+
+    if let Some(tb) = &st.taskbar { tb.set_state(TaskbarState::Normal); }
+    if let Some(ctrls) = &st.controls {
+        Label::new(ctrls.status_bar.label_hwnd()).set_text("Undoing last batch...");
+    }
+    st.global_state.store(ProcessingState::Running as u8, Ordering::Relaxed);
+    update_process_button_state(st);
+
+    let tx = st.tx.clone();
+    let state = st.global_state.clone();
+    let process_hidden = st.process_hidden_files;
+    thread::spawn(move || {
+        let backend = crate::engine::backend::default_backend();
+        let (restored, failed) = crate::engine::worker::undo_changes(backend.as_ref(), &log, &state, &mut |cur, total| {
+            if cur % 50 == 0 || cur == total {
+                let _ = tx.send(UiMessage::Progress(cur, total));
+            }
+        });
+        if failed.is_empty() && state.load(Ordering::Relaxed) != ProcessingState::Stopped as u8 {
+            crate::engine::undo::clear();
+            // Resuming an interrupted run over the restored files would redo what was undone
+            let paths: Vec<&str> = log.entries.iter().map(|e| e.path()).collect();
+            crate::engine::journal::discard_covering(&paths);
+        }
+
+        for (id, path) in rows {
+            let metrics = crate::engine::worker::scan_path_streaming(id, &path, tx.clone(), None, process_hidden);
+            let _ = tx.send(UiMessage::BatchItemAnalyzed(id, metrics.logical_size, metrics.disk_size, metrics.compression_state, metrics.breakdown));
+        }
+
+        let mut msg = ["Undo restored ", &restored.to_string(), " path(s)"].concat();
+        if !failed.is_empty() {
+            msg = [&msg, ", ", &failed.len().to_string(), " failed (see log); undo again to retry"].concat();
+        }
+        crate::log_info!(&msg);
+        let _ = tx.send(UiMessage::StatusText(to_wstring(&msg)));
+        let _ = tx.send(UiMessage::Finished);
+    });
+}
+
+// Saves the report of the last run as CSV, JSON or HTML, by the extension picked.
+pub unsafe fn on_export_report(st: &mut AppState, hwnd: HWND) {
+    let w_title = to_wstring("Export Report");
+    let Some(report) = &st.last_report else {
+        let w_msg = to_wstring("There is no batch report yet. Process a batch first.");
+        MessageBoxW(hwnd, w_msg.as_ptr(), w_title.as_ptr(), MB_OK | MB_ICONINFORMATION);
+        return;
+    };
+
+    // "2026-10-16 14:03:09 UTC" -> compactrs-report-20261016-140309.csv
+    let digits: String = crate::engine::report::utc_timestamp(report.started).chars().filter(|c| c.is_ascii_digit()).collect();
+    let default_name = ["compactrs-report-", &digits[..8], "-", &digits[8..], ".csv"].concat();
+    let types = [("CSV (*.csv)", "*.csv"), ("JSON (*.json)", "*.json"), ("HTML (*.html)", "*.html")];
+    let Ok(path) = pick_save_file(&default_name, &types) else { return };
+
+    let format = crate::engine::report::ReportFormat::from_path(&path).unwrap_or(crate::engine::report::ReportFormat::Csv);
+    match std::fs::write(&path, report.render(format)) {
+        Ok(()) => {
+            let msg = ["Report saved to ", &path].concat();
+            crate::log_info!(&msg);
+            if let Some(ctrls) = &st.controls {
+                Label::new(ctrls.status_bar.label_hwnd()).set_text(&msg);
+            }
+        }
+        Err(e) => {
+            crate::log_error!(&["Cannot write report ", &path, ": ", &e.to_string()].concat());
+            let w_msg = to_wstring(&["Could not save the report:\n", &e.to_string()].concat());
+            MessageBoxW(hwnd, w_msg.as_ptr(), w_title.as_ptr(), MB_OK | MB_ICONERROR);
+        }
+    }
+}
+
 pub unsafe fn on_pause_clicked(st: &mut AppState) {
     let current = st.global_state.load(Ordering::Relaxed);
     let new_state = if current == ProcessingState::Running as u8 {
@@ -322,11 +428,12 @@ pub unsafe fn update_process_button_state(st: &AppState) {
 pub unsafe fn on_open_settings(st: &mut AppState, hwnd: HWND) {
     let current_theme = st.theme;
     let is_dark = theme::resolve_mode(st.theme);
-    let (new_theme, new_force, new_ctx, new_guard, new_low_power, new_threads, new_concurrent, new_log_enabled, new_log_mask, new_skip, new_skip_buf, new_set_attr, new_scale, new_ctx_dialog, new_def_algo, new_def_action, new_process_hidden) = crate::ui::dialogs::show_settings_modal(
+    let (new_theme, new_force, new_ctx, new_guard, new_low_power, new_threads, new_concurrent, new_log_enabled, new_log_mask, new_skip, new_skip_buf, new_set_attr, new_scale, new_ctx_dialog, new_def_algo, new_def_action, new_process_hidden, new_entropy, new_verify) = crate::ui::dialogs::show_settings_modal(
         hwnd, current_theme, is_dark, st.enable_force_stop, st.config.enable_context_menu, st.config.enable_system_guard, st.low_power_mode, st.config.max_threads,
         st.config.max_concurrent_items, st.config.log_enabled, st.config.log_level_mask,
         st.config.enable_skip_heuristics, st.config.skip_extensions_buf, st.config.set_compressed_attr,
-        st.config.ui_scale_multiplier, st.config.context_menu_dialog_only, st.config.default_algo, st.config.default_action, st.config.process_hidden_files
+        st.config.ui_scale_multiplier, st.config.context_menu_dialog_only, st.config.default_algo(), st.config.default_action, st.config.process_hidden_files,
+        st.config.entropy_threshold, st.config.verify_after_compress
     );
     
     if let Some(t) = new_theme {
@@ -372,11 +479,13 @@ pub unsafe fn on_open_settings(st: &mut AppState, hwnd: HWND) {
     crate::ui::theme::update_ui_scale(new_scale);
     
     st.config.context_menu_dialog_only = new_ctx_dialog;
-    st.config.default_algo = new_def_algo;
+    st.config.set_default_algo(new_def_algo);
     st.config.default_action = new_def_action;
 
     st.process_hidden_files = new_process_hidden;
     st.config.process_hidden_files = new_process_hidden;
+    st.config.entropy_threshold = new_entropy;
+    st.config.verify_after_compress = new_verify;
     
     if st.config.log_enabled {
         crate::logger::set_log_level(st.config.log_level_mask);
@@ -395,6 +504,21 @@ pub unsafe fn on_open_watcher_manager(st: &mut AppState, hwnd: HWND) {
     crate::ui::dialogs::watcher::show_watcher_modal(hwnd, tasks, st.tx.clone(), is_dark);
 }
 
+/// Opens the volume analyzer, projecting with the default algorithm.
+///
+/// # Safety
+/// Must be called on the UI thread with the main window.
+pub unsafe fn on_open_analyzer(st: &mut AppState, hwnd: HWND) {
+    let is_dark = theme::resolve_mode(st.theme);
+    let opts = crate::engine::analyzer::AnalyzerOptions {
+        algo: st.config.default_algo(),
+        skip_critical: st.config.enable_system_guard,
+        process_hidden_files: st.process_hidden_files,
+        ..Default::default()
+    };
+    crate::ui::dialogs::analyzer::show_analyzer_modal(hwnd, opts, st.tx.clone(), is_dark);
+}
+
 // --- Notification Handlers (ListView) ---
 
 pub unsafe fn should_block_header_resize(lparam: LPARAM) -> bool {
@@ -404,6 +528,27 @@ pub unsafe fn should_block_header_resize(lparam: LPARAM) -> bool {
     || code == HDN_DIVIDERDBLCLICKW || code == HDN_DIVIDERDBLCLICKA
 }
 
+/// Fills a row's tooltip with how its files are stored, one line per algorithm.
+///
+/// # Safety
+/// `lparam` must point to the `NMLVGETINFOTIPW` of an `LVN_GETINFOTIPW` notification.
+pub unsafe fn on_list_info_tip(st: &AppState, lparam: LPARAM) {
+    let tip = &mut *(lparam as *mut NMLVGETINFOTIPW);
+    if tip.pszText.is_null() || tip.cchTextMax <= 0 { return; }
+    let Some(item) = usize::try_from(tip.iItem).ok()
+        .and_then(|row| st.filtered_items.get(row))
+        .and_then(|&idx| st.batch_items.get(idx)) else { return };
+    if item.breakdown.total().files == 0 && item.plan.is_none() { return; }
+
+    let mut parts: Vec<Vec<u16>> = vec![to_wstring(&item.path)];
+    if item.breakdown.total().files > 0 { parts.push(format_breakdown(&item.breakdown)); }
+    if let Some(plan) = &item.plan { parts.push(format_plan(plan)); }
+    let text = join_lines(&parts);
+    let len = text.len().min(tip.cchTextMax as usize);
+    std::ptr::copy_nonoverlapping(text.as_ptr(), tip.pszText, len);
+    *tip.pszText.add(len - 1) = 0;
+}
+
 pub unsafe fn on_list_click(st: &mut AppState, hwnd: HWND, row: i32, col: i32, code: u32) {
     if row < 0 || (row as usize) >= st.filtered_items.len() {
         if let Some(ctrls) = &st.controls {
@@ -422,38 +567,26 @@ pub unsafe fn on_list_click(st: &mut AppState, hwnd: HWND, row: i32, col: i32, c
     } else if col == 2 && code == NM_DBLCLK { 
           if let Some(item) = st.batch_items.get_mut(item_idx) {
               item.algorithm = match item.algorithm {
-                  WofAlgorithm::Xpress4K => WofAlgorithm::Xpress8K,
-                  WofAlgorithm::Xpress8K => WofAlgorithm::Xpress16K,
-                  WofAlgorithm::Xpress16K => WofAlgorithm::Lzx,
-                  WofAlgorithm::Lzx => WofAlgorithm::Lznt1,
-                  WofAlgorithm::Lznt1 => WofAlgorithm::Xpress4K,
+                  AlgoChoice::Fixed(WofAlgorithm::Xpress4K) => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
+                  AlgoChoice::Fixed(WofAlgorithm::Xpress8K) => AlgoChoice::Fixed(WofAlgorithm::Xpress16K),
+                  AlgoChoice::Fixed(WofAlgorithm::Xpress16K) => AlgoChoice::Fixed(WofAlgorithm::Lzx),
+                  AlgoChoice::Fixed(WofAlgorithm::Lzx) => AlgoChoice::Fixed(WofAlgorithm::Lznt1),
+                  AlgoChoice::Fixed(WofAlgorithm::Lznt1) => AlgoChoice::Auto,
+                  AlgoChoice::Auto => AlgoChoice::Fixed(WofAlgorithm::Xpress4K),
               };
               let algo = item.algorithm;
-              
-                  if let Some(cached) = item.get_cached_estimate(algo) {
-                      item.estimated_size = cached;
-                      if let Some(ctrls) = &st.controls { 
-                          ctrls.file_list.redraw_item(row);
-                      }
-                  } else {
-                      let path = item.path.clone();
-                      let id = item.id;
-                      item.estimated_size = 0; // Clears to Estimating...
-                      if let Some(ctrls) = &st.controls { 
-                          ctrls.file_list.redraw_item(row);
-                      }
-                  let tx = st.tx.clone();
-                  thread::spawn(move || {
-                      let estimated = crate::engine::estimator::estimate_path(&path, algo);
-                      let _ = tx.send(crate::ui::state::UiMessage::UpdateEstimate(id, algo, estimated));
-                  });
+              st.show_estimate(item_idx, algo);
+              if let Some(ctrls) = &st.controls { 
+                  ctrls.file_list.redraw_item(row);
               }
           }
     } else if col == 3 && code == NM_DBLCLK { 
           if let Some(item) = st.batch_items.get_mut(item_idx) {
               let new_action = match item.action {
                   BatchAction::Compress => BatchAction::Decompress,
-                  BatchAction::Decompress => BatchAction::Compress,
+                  BatchAction::Decompress => BatchAction::DryRun,
+                  BatchAction::DryRun => BatchAction::Verify,
+                  BatchAction::Verify => BatchAction::Compress,
               };
               item.action = new_action;
               
@@ -598,14 +731,16 @@ pub unsafe fn on_list_rclick(st: &mut AppState, hwnd: HWND, row: i32, col: i32)
             let _ = AppendMenuW(menu, MF_STRING, 2003, to_wstring("XPRESS16K").as_ptr());
             let _ = AppendMenuW(menu, MF_STRING, 2004, to_wstring("LZX").as_ptr());
             let _ = AppendMenuW(menu, MF_STRING, 2005, to_wstring("LZNT1").as_ptr());
+            let _ = AppendMenuW(menu, MF_STRING, 2006, to_wstring("Auto").as_ptr());
 
             if let Some(item) = st.batch_items.get(item_idx) {
                 let check_id = match item.algorithm {
-                    WofAlgorithm::Xpress4K => 2001,
-                    WofAlgorithm::Xpress8K => 2002,
-                    WofAlgorithm::Xpress16K => 2003,
-                    WofAlgorithm::Lzx => 2004,
-                    WofAlgorithm::Lznt1 => 2005,
+                    AlgoChoice::Fixed(WofAlgorithm::Xpress4K) => 2001,
+                    AlgoChoice::Fixed(WofAlgorithm::Xpress8K) => 2002,
+                    AlgoChoice::Fixed(WofAlgorithm::Xpress16K) => 2003,
+                    AlgoChoice::Fixed(WofAlgorithm::Lzx) => 2004,
+                    AlgoChoice::Fixed(WofAlgorithm::Lznt1) => 2005,
+                    AlgoChoice::Auto => 2006,
                 };
                 CheckMenuItem(menu, check_id, MF_CHECKED);
             }
@@ -613,37 +748,23 @@ pub unsafe fn on_list_rclick(st: &mut AppState, hwnd: HWND, row: i32, col: i32)
             let cmd = TrackPopupMenu(menu, TPM_RETURNCMD | TPM_LEFTALIGN | TPM_RIGHTBUTTON, pt.x, pt.y, 0, hwnd, std::ptr::null());
             DestroyMenu(menu);
 
-            if cmd >= 2001 && cmd <= 2005 {
+            if cmd >= 2001 && cmd <= 2006 {
                 let new_algo = match cmd {
-                    2001 => WofAlgorithm::Xpress4K,
-                    2002 => WofAlgorithm::Xpress8K,
-                    2003 => WofAlgorithm::Xpress16K,
-                    2004 => WofAlgorithm::Lzx,
-                    2005 => WofAlgorithm::Lznt1,
-                    _ => WofAlgorithm::Xpress8K,
+                    2001 => AlgoChoice::Fixed(WofAlgorithm::Xpress4K),
+                    2002 => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
+                    2003 => AlgoChoice::Fixed(WofAlgorithm::Xpress16K),
+                    2004 => AlgoChoice::Fixed(WofAlgorithm::Lzx),
+                    2005 => AlgoChoice::Fixed(WofAlgorithm::Lznt1),
+                    2006 => AlgoChoice::Auto,
+                    _ => AlgoChoice::Fixed(WofAlgorithm::Xpress8K),
                 };
 
                 if let Some(item) = st.batch_items.get_mut(item_idx) {
                     if item.algorithm != new_algo {
                         item.algorithm = new_algo;
-                        
-                        if let Some(cached) = item.get_cached_estimate(new_algo) {
-                            item.estimated_size = cached;
-                            if let Some(ctrls) = &st.controls { 
-                                ctrls.file_list.redraw_item(row);
-                            }
-                        } else {
-                            let path = item.path.clone();
-                            let id = item.id;
-                            item.estimated_size = 0; // Trigger "Estimating..."
-                            if let Some(ctrls) = &st.controls { 
-                                ctrls.file_list.redraw_item(row);
-                            }
-                            let tx = st.tx.clone();
-                            thread::spawn(move || {
-                                let estimated = crate::engine::estimator::estimate_path(&path, new_algo);
-                                let _ = tx.send(crate::ui::state::UiMessage::UpdateEstimate(id, new_algo, estimated));
-                            });
+                        st.show_estimate(item_idx, new_algo);
+                        if let Some(ctrls) = &st.controls { 
+                            ctrls.file_list.redraw_item(row);
                         }
                     }
                 }
@@ -659,11 +780,15 @@ pub unsafe fn on_list_rclick(st: &mut AppState, hwnd: HWND, row: i32, col: i32)
         if menu != std::ptr::null_mut() {
             let _ = AppendMenuW(menu, MF_STRING, 3001, to_wstring("Compress").as_ptr());
             let _ = AppendMenuW(menu, MF_STRING, 3002, to_wstring("Decompress").as_ptr());
+            let _ = AppendMenuW(menu, MF_STRING, 3003, to_wstring("Dry Run").as_ptr());
+            let _ = AppendMenuW(menu, MF_STRING, 3004, to_wstring("Verify").as_ptr());
 
             if let Some(item) = st.batch_items.get(item_idx) {
                 let check_id = match item.action {
                     crate::ui::state::BatchAction::Compress => 3001,
                     crate::ui::state::BatchAction::Decompress => 3002,
+                    crate::ui::state::BatchAction::DryRun => 3003,
+                    crate::ui::state::BatchAction::Verify => 3004,
                 };
                 CheckMenuItem(menu, check_id, MF_CHECKED);
             }
@@ -671,10 +796,12 @@ pub unsafe fn on_list_rclick(st: &mut AppState, hwnd: HWND, row: i32, col: i32)
             let cmd = TrackPopupMenu(menu, TPM_RETURNCMD | TPM_LEFTALIGN | TPM_RIGHTBUTTON, pt.x, pt.y, 0, hwnd, std::ptr::null());
             DestroyMenu(menu);
 
-            if cmd >= 3001 && cmd <= 3002 {
+            if cmd >= 3001 && cmd <= 3004 {
                 let new_action = match cmd {
                     3001 => crate::ui::state::BatchAction::Compress,
                     3002 => crate::ui::state::BatchAction::Decompress,
+                    3003 => crate::ui::state::BatchAction::DryRun,
+                    3004 => crate::ui::state::BatchAction::Verify,
                     _ => crate::ui::state::BatchAction::Compress,
                 };
                 
@@ -736,7 +863,7 @@ pub unsafe fn handle_context_menu(st: &mut AppState, hwnd: HWND, wparam: WPARAM)
                         if let Some(item) = st.batch_items.get(idx) {
                             match item.status {
                                 BatchStatus::Processing => { any_processing = true; },
-                                BatchStatus::Pending => { any_pending = true; },
+                                BatchStatus::Pending | BatchStatus::Error(_) => { any_pending = true; },
                                 _ => {}
                             }
                         }
@@ -749,6 +876,9 @@ pub unsafe fn handle_context_menu(st: &mut AppState, hwnd: HWND, wparam: WPARAM)
                     }
                     let _ = AppendMenuW(menu, MF_STRING, 1004, to_wstring("Remove").as_ptr());
                     let _ = AppendMenuW(menu, MF_STRING, 1006, to_wstring("Open File Location").as_ptr());
+                    let _ = AppendMenuW(menu, MF_STRING, 1007, to_wstring("Compare Algorithms...").as_ptr());
+                    let _ = AppendMenuW(menu, MF_STRING, 1008, to_wstring("Undo Last Batch").as_ptr());
+                    let _ = AppendMenuW(menu, MF_STRING, 1009, to_wstring("Export Report...").as_ptr());
 
                     let _cmd = TrackPopupMenu(menu, TPM_RETURNCMD | TPM_LEFTALIGN, pt.x, pt.y, 0, hwnd, std::ptr::null());
                     DestroyMenu(menu);
@@ -766,6 +896,14 @@ pub unsafe fn handle_context_menu(st: &mut AppState, hwnd: HWND, wparam: WPARAM)
                                 }
                             }
                         },
+                        1007 => {
+                            if let Some(item) = selected.first().and_then(|&idx| st.batch_items.get(idx)) {
+                                let is_dark = crate::ui::theme::resolve_mode(st.theme);
+                                crate::ui::dialogs::show_compare_modal(hwnd, item.id, item.path.clone(), st.tx.clone(), is_dark);
+                            }
+                        },
+                        1008 => { on_undo_last_batch(st, hwnd); },
+                        1009 => { on_export_report(st, hwnd); },
                         _ => {}
                     }
                 }
@@ -13,6 +13,8 @@ pub enum InputAction {
     Delete,         // Delete
     OpenFiles,      // Ctrl + O
     OpenFolder,     // Ctrl + Shift + O
+    Undo,           // Ctrl + Z (undo last batch)
+    ExportReport,   // Ctrl + E
 }
 
 pub unsafe fn resolve_key_action(vk: i32) -> InputAction {
@@ -26,6 +28,8 @@ pub unsafe fn resolve_key_action(vk: i32) -> InputAction {
             0x56 if ctrl => InputAction::Paste,     // 'V'
             0x4F if ctrl && shift => InputAction::OpenFolder, // Ctrl+Shift+O
             0x4F if ctrl => InputAction::OpenFiles, // Ctrl+O
+            0x5A if ctrl => InputAction::Undo,      // 'Z'
+            0x45 if ctrl => InputAction::ExportReport, // 'E'
             start if start == VK_DELETE as i32 => InputAction::Delete,
             _ => InputAction::None,
         }
@@ -67,6 +71,20 @@ pub unsafe fn handle_global_shortcut(hwnd: HWND, st: &mut AppState, action: Inpu
                 handlers::on_add_folder(st);
                 true
             },
+            InputAction::Undo => {
+                // Text undo in the search box, batch undo everywhere else
+                if let Some(ctrls) = &st.controls
+                    && source_hwnd == ctrls.search_panel.search_hwnd() {
+                    SendMessageW(source_hwnd, 0x00C7, 0, 0); // EM_UNDO
+                    return true;
+                }
+                handlers::on_undo_last_batch(st, hwnd);
+                true
+            },
+            InputAction::ExportReport => {
+                handlers::on_export_report(st, hwnd);
+                true
+            },
             InputAction::Delete => {
                  // Check context?
                  // Delete in Search Box -> Delete character?
@@ -180,7 +198,7 @@ pub unsafe fn handle_subclass_dispatch(hwnd: HWND, msg: u32, wparam: usize) -> b
     if msg == crate::types::WM_KEYDOWN {
         let action = resolve_key_action(wparam as i32);
         match action {
-            InputAction::SelectAll | InputAction::Paste | InputAction::Delete | InputAction::OpenFiles | InputAction::OpenFolder => {
+            InputAction::SelectAll | InputAction::Paste | InputAction::Delete | InputAction::OpenFiles | InputAction::OpenFolder | InputAction::Undo | InputAction::ExportReport => {
                 return forward_shortcut_to_parent(hwnd, wparam);
             },
             _ => {}
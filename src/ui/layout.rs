@@ -2,7 +2,7 @@
 
 use crate::types::*;
 
-#[link(name = "user32")]
+#[cfg_attr(windows, link(name = "user32"))]
 unsafe extern "system" {
     pub fn BeginDeferWindowPos(nNumWindows: i32) -> HANDLE;
     pub fn DeferWindowPos(hWinPosInfo: HANDLE, hWnd: HWND, hWndInsertAfter: HWND, x: i32, y: i32, cx: i32, cy: i32, uFlags: u32) -> HANDLE;
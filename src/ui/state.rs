@@ -1,11 +1,15 @@
 use crate::types::HWND;
 use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::{Arc, Mutex, atomic::{AtomicU8, AtomicU64}};
+use std::sync::{Arc, Mutex, atomic::{AtomicU8, AtomicU64, Ordering}};
 use std::collections::HashMap;
-use crate::engine::wof::{WofAlgorithm, CompressionState};
+use crate::engine::wof::{AlgoBreakdown, AlgoChoice, CompressionState};
+use crate::engine::dry_run::DryRunPlan;
+use crate::engine::error::FailureSummary;
+use crate::engine::report::RunReport;
 use crate::config::AppConfig;
 use crate::ui::components::{FileListView, Component};
 use crate::engine::worker::scan_path_streaming;
+use crate::engine::estimate_jobs::{EstimateEvent, EstimateScheduler};
 use crate::utils::to_wstring;
 use crate::logger::LogEntry;
 use std::thread;
@@ -71,17 +75,35 @@ pub enum UiMessage {
     
     Finished,
     
-    /// Single item finished: (id, final_size_bytes, total_count, final_state)
-    RowFinished(u32, u64, u64, CompressionState),
+    /// Single item finished: (id, final_size_bytes, total_count, final_state, breakdown)
+    RowFinished(u32, u64, u64, CompressionState, AlgoBreakdown),
     
-    /// Item analyzed (id, logical_size, disk_size, compression_state)
-    BatchItemAnalyzed(u32, u64, u64, CompressionState),
+    /// Item analyzed (id, logical_size, disk_size, compression_state, breakdown)
+    BatchItemAnalyzed(u32, u64, u64, CompressionState, AlgoBreakdown),
+
+    /// Dry-run row planned, sent just before its `RowFinished`: (id, plan)
+    RowPlanned(u32, DryRunPlan),
+
+    /// Files of a row that failed, sent just before its `RowFinished`: (id, failures)
+    RowFailed(u32, FailureSummary),
+
+    /// What a worker run did to each file, sent just before `Finished`
+    RunReport(Box<RunReport>),
     
-    /// Estimated size update: (id, algorithm, estimated_size)
-    UpdateEstimate(u32, WofAlgorithm, u64),
+    /// Estimated size update: (id, algorithm, estimated_size, margin at 95%)
+    UpdateEstimate(u32, AlgoChoice, u64, u64),
+
+    /// Running estimate of an unfinished job: (id, algorithm, projected_size, confidence 0..1)
+    PartialEstimate(u32, AlgoChoice, u64, f32),
+
+    /// Estimate abandoned by the stop button: (id, algorithm)
+    EstimateCancelled(u32, AlgoChoice),
 
     /// Watcher triggered processing: (Path, Algorithm)
-    WatcherTrigger(String, WofAlgorithm),
+    WatcherTrigger(String, AlgoChoice),
+
+    /// Paths to add to the batch, as if dropped on the window
+    AddPaths(Vec<String>),
 }
 
 /// Action to perform on a batch item
@@ -90,6 +112,10 @@ pub enum UiMessage {
 pub enum BatchAction {
     Compress = 0,
     Decompress = 1,
+    /// Everything a compress decides, but no file is modified
+    DryRun = 2,
+    /// Check that compressed files read back intact
+    Verify = 3,
 }
 
 impl Default for BatchAction {
@@ -98,6 +124,28 @@ impl Default for BatchAction {
     }
 }
 
+impl BatchAction {
+    /// Lowercase name used on the command line, in IPC payloads and in batch journals.
+    pub fn name(self) -> &'static str {
+        match self {
+            BatchAction::Compress => "compress",
+            BatchAction::Decompress => "decompress",
+            BatchAction::DryRun => "dryrun",
+            BatchAction::Verify => "verify",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "compress" => Some(BatchAction::Compress),
+            "decompress" => Some(BatchAction::Decompress),
+            "dryrun" => Some(BatchAction::DryRun),
+            "verify" => Some(BatchAction::Verify),
+            _ => None,
+        }
+    }
+}
+
 /// Status of a batch item
 #[derive(Clone, Debug, PartialEq)]
 pub enum BatchStatus {
@@ -124,7 +172,7 @@ pub enum FilterColumn {
 pub struct SearchState {
     pub text: String,
     pub filter_column: FilterColumn,
-    pub algorithm_filter: Option<crate::engine::wof::WofAlgorithm>,
+    pub algorithm_filter: Option<AlgoChoice>,
     pub size_filter: i32, // 0 = All, 1 = Small, 2 = Large (placeholder for now)
     pub use_regex: bool,
     pub case_sensitive: bool,
@@ -149,19 +197,31 @@ pub struct BatchItem {
     pub id: u32,                    // Unique identifier
     pub path: String,               // Folder path
     pub path_lower: String,         // Cached lowercased path for fast sorting/filtering
-    pub algorithm: WofAlgorithm,    // Selected compression algorithm
-    pub action: BatchAction,        // Compress or Decompress
+    pub algorithm: AlgoChoice,      // Selected compression algorithm
+    pub action: BatchAction,        // Compress, Decompress, DryRun or Verify
     pub status: BatchStatus,        // Pending, Processing, Complete, Error
     pub status_override: Option<String>, // UI override text
     pub final_state: Option<CompressionState>, // Final detected state
+    /// How the files are stored per algorithm, once analyzed
+    pub breakdown: AlgoBreakdown,
+    /// What the last dry run of this item would do
+    pub plan: Option<DryRunPlan>,
+    /// Why files of the last run failed
+    pub failures: FailureSummary,
     pub progress: (u64, u64),       // (current, total) files
     pub state_flag: Option<Arc<AtomicU8>>, // Processing state
     // Added for sorting
     pub logical_size: u64,
     pub disk_size: u64,
     pub estimated_size: u64,        // Current estimated compressed size
-    /// Cache of estimated sizes per algorithm (avoids re-calculation)
-    pub estimation_cache: HashMap<u32, u64>,
+    /// Algorithm whose estimate is shown in `estimated_size`
+    pub estimate_algo: AlgoChoice,
+    /// `Some(confidence)` while the shown estimate is still being refined
+    pub estimate_confidence: Option<f32>,
+    /// Sampling margin (95%) of the shown estimate
+    pub estimate_margin: u64,
+    /// Cache of estimated sizes and margins per algorithm (avoids re-calculation)
+    pub estimation_cache: HashMap<u32, (u64, u64)>,
 }
 
 impl BatchItem {
@@ -171,29 +231,39 @@ impl BatchItem {
             id,
             path,
             path_lower,
-            algorithm: WofAlgorithm::Xpress8K, // Default fallback
+            algorithm: AlgoChoice::default(), // Default fallback
             action: BatchAction::Compress,
             status: BatchStatus::Pending,
             status_override: None,
             final_state: None,
+            breakdown: AlgoBreakdown::default(),
+            plan: None,
+            failures: FailureSummary::default(),
             progress: (0, 0),
             state_flag: None,
             logical_size: 0,
             disk_size: 0,
             estimated_size: 0,
+            estimate_algo: AlgoChoice::default(),
+            estimate_confidence: None,
+            estimate_margin: 0,
             estimation_cache: HashMap::new(),
         }
     }
     
     /// Get cached estimation for an algorithm, if available
-    pub fn get_cached_estimate(&self, algo: WofAlgorithm) -> Option<u64> {
-        self.estimation_cache.get(&(algo as u32)).copied()
+    pub fn get_cached_estimate(&self, algo: AlgoChoice) -> Option<(u64, u64)> {
+        self.estimation_cache.get(&algo.code()).copied()
     }
     
-    /// Cache an estimation result for an algorithm
-    pub fn cache_estimate(&mut self, algo: WofAlgorithm, size: u64) {
-        self.estimation_cache.insert(algo as u32, size);
-        self.estimated_size = size;
+    /// Cache an estimation result for an algorithm, showing it if `algo` is the one displayed
+    pub fn cache_estimate(&mut self, algo: AlgoChoice, size: u64, margin: u64) {
+        self.estimation_cache.insert(algo.code(), (size, margin));
+        if algo == self.estimate_algo {
+            self.estimated_size = size;
+            self.estimate_margin = margin;
+            self.estimate_confidence = None;
+        }
     }
 }
 
@@ -262,6 +332,8 @@ pub struct AppState {
     pub tx: Sender<UiMessage>,
     pub rx: Receiver<UiMessage>,
     pub global_state: Arc<AtomicU8>, // Global processing state (0=Idle, 1=Running, 2=Paused, 3=Stopped)
+    pub stop_count: Arc<AtomicU64>, // Times Stop was pressed; estimates queued before a Stop end with it
+    pub estimates: EstimateScheduler, // Background size estimation pool
     
     // Settings
     pub config: AppConfig,
@@ -291,6 +363,9 @@ pub struct AppState {
     pub active_lock_dialog: Option<String>,
     pub ignored_lock_processes: std::collections::HashSet<String>,
 
+    // Report of the last Process click, for "Export Report"
+    pub last_report: Option<RunReport>,
+
     pub watcher_tasks: Arc<Mutex<Vec<crate::watcher_config::WatcherTask>>>,
     pub process_hidden_files: bool,
 }
@@ -299,6 +374,22 @@ impl AppState {
     pub fn new() -> Self {
         let (tx, rx) = channel();
         let config = AppConfig::load();
+        let global_state = Arc::new(AtomicU8::new(ProcessingState::Idle as u8));
+
+        let stop_count = Arc::new(AtomicU64::new(0));
+        let stops = stop_count.clone();
+        let estimate_tx = tx.clone();
+        let estimates = EstimateScheduler::new(
+            move || stops.load(Ordering::Relaxed),
+            move |event| {
+                let msg = match event {
+                    EstimateEvent::Partial { id, algo, estimated, confidence } => UiMessage::PartialEstimate(id, algo, estimated, confidence as f32),
+                    EstimateEvent::Done { id, algo, estimated, margin } => UiMessage::UpdateEstimate(id, algo, estimated, margin),
+                    EstimateEvent::Cancelled { id, algo } => UiMessage::EstimateCancelled(id, algo),
+                };
+                let _ = estimate_tx.send(msg);
+            },
+        );
         
         // Apply Eco Mode immediately if saved in config
         if config.low_power_mode {
@@ -314,7 +405,9 @@ impl AppState {
             controls: None,
             tx,
             rx,
-            global_state: Arc::new(AtomicU8::new(ProcessingState::Idle as u8)),
+            global_state,
+            stop_count,
+            estimates,
             process_hidden_files: config.process_hidden_files,
             config,
             theme: config.theme,
@@ -332,6 +425,7 @@ impl AppState {
             global_progress_total: Arc::new(AtomicU64::new(0)),
             active_lock_dialog: None,
             ignored_lock_processes: std::collections::HashSet::new(),
+            last_report: None,
             watcher_tasks: Arc::new(Mutex::new(Vec::new())),
         }
     }
@@ -348,7 +442,7 @@ impl AppState {
         let mut item = BatchItem::new(id, path);
         
         // Apply configured defaults
-        item.algorithm = self.config.default_algo;
+        item.algorithm = self.config.default_algo();
         item.action = self.config.default_action;
         
         self.batch_items.push(item);
@@ -357,6 +451,7 @@ impl AppState {
     
     /// Remove a batch item by ID
     pub fn remove_batch_item(&mut self, id: u32) -> bool {
+        self.estimates.cancel(id);
         if let Some(pos) = self.batch_items.iter().position(|item| item.id == id) {
             self.batch_items.remove(pos);
             true
@@ -371,7 +466,7 @@ impl AppState {
     }
     
     /// Update the algorithm for a specific batch item
-    pub fn set_item_algorithm(&mut self, id: u32, algorithm: WofAlgorithm) {
+    pub fn set_item_algorithm(&mut self, id: u32, algorithm: AlgoChoice) {
         if let Some(item) = self.get_batch_item_mut(id) {
             item.algorithm = algorithm;
         }
@@ -386,6 +481,7 @@ impl AppState {
     
     /// Clear all batch items
     pub fn clear_batch(&mut self) {
+        self.estimates.cancel_all();
         self.batch_items.clear();
         self.filtered_items.clear();
     }
@@ -402,41 +498,68 @@ impl AppState {
             if !self.batch_items.iter().any(|item| item.path == path) {
                 let id = self.add_batch_item(path.clone());
                 
-                // Get the default algorithm for estimation
-                let algo = self.batch_items.iter().find(|i| i.id == id)
-                    .map(|i| i.algorithm)
-                    .unwrap_or(self.config.default_algo);
-                
                 if let Some(item) = self.get_batch_item_mut(id) {
                      item.status_override = Some("Calculating...".to_string());
                 }
 
-                items_to_analyze.push((id, path, algo));
+                // Estimate with the item's algorithm
+                let idx = self.batch_items.len() - 1;
+                self.show_estimate(idx, self.batch_items[idx].algorithm);
+
+                items_to_analyze.push((id, path));
             }
         }
         
         if items_to_analyze.is_empty() { return; }
 
         unsafe { self.refresh_file_list(); }
+        self.update_estimate_focus();
 
         let tx = self.tx.clone();
         let process_hidden_files = self.process_hidden_files;
         
         // Spawn analysis thread
         thread::spawn(move || {
-            for (id, path, algo) in items_to_analyze {
+            for (id, path) in items_to_analyze {
                  // Single-pass scan with streaming updates
                  let metrics = scan_path_streaming(id, &path, tx.clone(), None, process_hidden_files);
-                 let _ = tx.send(UiMessage::BatchItemAnalyzed(id, metrics.logical_size, metrics.disk_size, metrics.compression_state));
-                 
-                 // Estimate compressed size
-                 let estimated = crate::engine::estimator::estimate_path(&path, algo);
-                 let _ = tx.send(UiMessage::UpdateEstimate(id, algo, estimated));
+                 let _ = tx.send(UiMessage::BatchItemAnalyzed(id, metrics.logical_size, metrics.disk_size, metrics.compression_state, metrics.breakdown));
             }
             let _ = tx.send(UiMessage::StatusText(to_wstring("Ready.")));
         });
     }
 
+    /// Shows the estimate of `algo` for the item at `idx`, from the cache or by queueing a job.
+    /// The caller redraws the row.
+    pub fn show_estimate(&mut self, idx: usize, algo: AlgoChoice) {
+        let Some(item) = self.batch_items.get_mut(idx) else { return };
+        item.estimate_algo = algo;
+        if let Some((size, margin)) = item.get_cached_estimate(algo) {
+            item.estimated_size = size;
+            item.estimate_margin = margin;
+            item.estimate_confidence = None;
+        } else {
+            item.estimated_size = 0; // Clears it to "Estimating..."
+            item.estimate_margin = 0;
+            item.estimate_confidence = Some(0.0);
+            self.estimates.submit(item.id, item.path.clone(), algo);
+        }
+    }
+
+    /// Ranks queued estimates by the rows currently selected and on screen.
+    pub fn update_estimate_focus(&self) {
+        let Some(ctrls) = &self.controls else { return };
+        let ids = |rows: &mut dyn Iterator<Item = usize>| -> Vec<u32> {
+            rows.filter_map(|row| self.filtered_items.get(row))
+                .filter_map(|&idx| self.batch_items.get(idx))
+                .map(|item| item.id)
+                .collect()
+        };
+        let selected = ids(&mut ctrls.file_list.get_selected_indices().into_iter());
+        let visible = ids(&mut ctrls.file_list.visible_rows());
+        self.estimates.set_focus(&selected, &visible);
+    }
+
     pub fn sort_filtered_items(&mut self) {
         if self.sort_column < 0 { return; }
         let sort_col = self.sort_column;
@@ -455,7 +578,7 @@ impl AppState {
                     let v2 = match s2 { CompressionState::None => 0, CompressionState::Specific(_) => 1, CompressionState::Mixed => 2 };
                     v1.cmp(&v2)
                 },
-                2 => (i1.algorithm as u32).cmp(&(i2.algorithm as u32)),
+                2 => i1.algorithm.code().cmp(&i2.algorithm.code()),
                 3 => (i1.action as u32).cmp(&(i2.action as u32)),
                 4 => i1.logical_size.cmp(&i2.logical_size),
                 5 => i1.estimated_size.cmp(&i2.estimated_size),
@@ -15,7 +15,7 @@ use crate::ui::components::{
 };
 use crate::ui::theme;
 use crate::ui::handlers; 
-use crate::engine::wof::{WofAlgorithm, CompressionState};
+use crate::engine::wof::{AlgoChoice, WofAlgorithm, CompressionState};
 use crate::utils::{to_wstring, u64_to_wstring, concat_wstrings};
 use crate::w;
 use crate::ui::framework::{WindowHandler, WindowBuilder, WindowAlignment, load_app_icon};
@@ -132,6 +132,7 @@ impl WindowHandler for AppState {
                 btn_shortcuts: IDC_BTN_SHORTCUTS,
                 btn_console: IDC_BTN_CONSOLE,
                 btn_watcher: crate::ui::controls::IDC_BTN_WATCHER,
+                btn_analyze: crate::ui::controls::IDC_BTN_ANALYZE,
             });
             let _ = header_panel.create(hwnd);
 
@@ -212,6 +213,11 @@ impl WindowHandler for AppState {
             } else {
                 crate::logger::set_log_level(0);
             }
+
+            // Ask about interrupted batches once the window is up
+            if !self.ipc_active {
+                PostMessageW(hwnd, 0x800A, 0, 0);
+            }
         }
         0
     }
@@ -261,6 +267,10 @@ impl WindowHandler for AppState {
                     self.config.set_compressed_attr = wparam != 0;
                     Some(0)
                 },
+                0x800A => {
+                    self.offer_resume(hwnd);
+                    Some(0)
+                },
                 WM_COMMAND => Some(self.dispatch_command(hwnd, wparam, lparam)),
                 WM_TIMER => Some(self.handle_timer(hwnd, wparam)),
                 WM_SIZE => Some(self.handle_size(hwnd)),
@@ -332,7 +342,7 @@ impl WindowHandler for AppState {
 impl AppState {
     unsafe fn populate_ui_combos(&self, action_panel: &ActionPanel) {
         let h_combo = action_panel.combo_hwnd();
-        let algos = [w!("As Listed"), w!("XPRESS4K"), w!("XPRESS8K"), w!("XPRESS16K"), w!("LZX"), w!("LZNT1")];
+        let algos = [w!("As Listed"), w!("XPRESS4K"), w!("XPRESS8K"), w!("XPRESS16K"), w!("LZX"), w!("LZNT1"), w!("Auto")];
         
         let combo = ComboBox::new(h_combo);
         for alg in algos {
@@ -343,7 +353,7 @@ impl AppState {
         
         let h_action_mode = action_panel.action_mode_hwnd();
         let action_mode_combo = ComboBox::new(h_action_mode);
-        let action_modes = ["As Listed", "Compress All", "Decompress All"];
+        let action_modes = ["As Listed", "Compress All", "Decompress All", "Dry Run All", "Verify All"];
         for mode in action_modes {
             action_mode_combo.add_string(mode);
         }
@@ -373,6 +383,7 @@ impl AppState {
                                 item.logical_size = metrics.logical_size;
                                 item.disk_size = metrics.disk_size;
                                 item.final_state = Some(metrics.compression_state);
+                                item.breakdown = metrics.breakdown;
                             }
                             self.pending_ipc_ids.push(item_id);
                      }
@@ -392,6 +403,48 @@ impl AppState {
         }
     }
 
+    /// Offers to resume batches whose journal was left behind by a stop, crash or reboot.
+    /// Resumed rows are queued like IPC items; the worker skips the files already done.
+    unsafe fn offer_resume(&mut self, hwnd: HWND) {
+        unsafe {
+            let pending = crate::engine::journal::pending();
+            let items: Vec<_> = pending.iter().flat_map(|(_, j)| j.items.iter().cloned()).collect();
+            if items.is_empty() {
+                crate::engine::journal::discard_pending();
+                return;
+            }
+            let done: u64 = pending.iter().map(|(_, j)| j.totals().files()).sum();
+
+            let msg = to_wstring(&["A previous batch of ", &items.len().to_string(), " item(s) did not finish (",
+                &done.to_string(), " files done).\n\nResume it? Files already done are skipped."].concat());
+            let title = w!("Resume Batch");
+            if MessageBoxW(hwnd, msg.as_ptr(), title.as_ptr(), MB_YESNO | MB_ICONINFORMATION) != IDYES {
+                crate::engine::journal::discard_pending();
+                return;
+            }
+
+            self.ingest_paths(items.iter().map(|item| item.path.clone()).collect());
+            for item in &items {
+                let Some(batch_item) = self.batch_items.iter_mut().find(|b| b.path == item.path) else { continue };
+                batch_item.algorithm = item.algo;
+                batch_item.action = BatchAction::from_name(&item.action).unwrap_or_default();
+                if !self.pending_ipc_ids.contains(&batch_item.id) {
+                    self.pending_ipc_ids.push(batch_item.id);
+                }
+            }
+            if self.pending_ipc_ids.is_empty() { return; }
+
+            // Rows carry their journaled action and algorithm
+            if let Some(ctrls) = &self.controls {
+                ComboBox::new(ctrls.action_panel.combo_hwnd()).set_selected_index(0);
+                ComboBox::new(ctrls.action_panel.action_mode_hwnd()).set_selected_index(0);
+                ctrls.file_list.redraw_all();
+            }
+            self.ipc_active = true;
+            SetTimer(hwnd, 2, 500, None);
+        }
+    }
+
     unsafe fn dispatch_command(&mut self, hwnd: HWND, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
         unsafe {
             let id = (wparam & 0xFFFF) as u16;
@@ -455,11 +508,12 @@ impl AppState {
                         if let Some(ctrls) = &self.controls {
                             let idx = crate::ui::wrappers::ComboBox::new(ctrls.search_panel.algo_hwnd()).get_selected_index();
                             self.search_state.algorithm_filter = match idx {
-                                1 => Some(WofAlgorithm::Xpress4K),
-                                2 => Some(WofAlgorithm::Xpress8K),
-                                3 => Some(WofAlgorithm::Xpress16K),
-                                4 => Some(WofAlgorithm::Lzx),
-                                5 => Some(WofAlgorithm::Lznt1),
+                                1 => Some(AlgoChoice::Fixed(WofAlgorithm::Xpress4K)),
+                                2 => Some(AlgoChoice::Fixed(WofAlgorithm::Xpress8K)),
+                                3 => Some(AlgoChoice::Fixed(WofAlgorithm::Xpress16K)),
+                                4 => Some(AlgoChoice::Fixed(WofAlgorithm::Lzx)),
+                                5 => Some(AlgoChoice::Fixed(WofAlgorithm::Lznt1)),
+                                6 => Some(AlgoChoice::Auto),
                                 _ => None,
                             };
                             self.refresh_file_list();
@@ -493,6 +547,7 @@ impl AppState {
                      }
                  },
                  IDC_BTN_WATCHER => handlers::on_open_watcher_manager(self, hwnd),
+                 IDC_BTN_ANALYZE => handlers::on_open_analyzer(self, hwnd),
                  _ => {}
             }
             0
@@ -646,15 +701,20 @@ impl AppState {
                          if let Some(ctrls) = &self.controls { ctrls.file_list.redraw_item(row); }
                      }
                  },
-                 UiMessage::RowFinished(id, final_bytes, total_count, final_state) => {
+                 UiMessage::RowFinished(id, final_bytes, total_count, final_state, breakdown) => {
                      if let Some(pos) = self.batch_items.iter().position(|i| i.id == id) {
                          if let Some(item) = self.batch_items.get_mut(pos) {
                              item.disk_size = final_bytes;
-                             item.status = BatchStatus::Complete;
+                             item.status = if item.failures.is_empty() {
+                                 BatchStatus::Complete
+                             } else {
+                                 BatchStatus::Error(item.failures.summary())
+                             };
                              item.state_flag = None;
                              item.progress = (total_count, total_count);
                              item.status_override = None;
                              item.final_state = Some(final_state);
+                             item.breakdown = breakdown;
                          }
                      }
                      if let Some(row) = self.find_ui_row_by_id(id) {
@@ -662,6 +722,22 @@ impl AppState {
                      }
                      handlers::update_process_button_state(self);
                  },
+                 UiMessage::RowPlanned(id, plan) => {
+                     if let Some(item) = self.batch_items.iter_mut().find(|i| i.id == id) {
+                         item.plan = Some(plan);
+                     }
+                 },
+                 UiMessage::RowFailed(id, failures) => {
+                     if let Some(item) = self.batch_items.iter_mut().find(|i| i.id == id) {
+                         item.failures = failures;
+                     }
+                 },
+                 UiMessage::RunReport(report) => match self.last_report.as_mut() {
+                     // Queued rows of the same Process click run as later workers
+                     Some(last) => last.merge(*report),
+                     None => self.last_report = Some(*report),
+                 },
+                 UiMessage::AddPaths(paths) => self.ingest_paths(paths),
                  UiMessage::WatcherTrigger(path, algo) => {
                      if !self.batch_items.iter().any(|item| item.path == path) {
                          let id = self.add_batch_item(path.clone());
@@ -685,13 +761,14 @@ impl AppState {
                          }
                      }
                  },
-                 UiMessage::BatchItemAnalyzed(id, log, disk, state) => {
+                 UiMessage::BatchItemAnalyzed(id, log, disk, state, breakdown) => {
                      if let Some(pos) = self.batch_items.iter().position(|item| item.id == id) {
                          if let Some(item) = self.batch_items.get_mut(pos) {
                              item.logical_size = log;
                              item.disk_size = disk;
                              item.status_override = None;
                              item.final_state = Some(state);
+                             item.breakdown = breakdown;
                          }
                          if let Some(row) = self.find_ui_row_by_id(id) {
                              if let Some(ctrls) = &self.controls { ctrls.file_list.redraw_item(row); }
@@ -705,10 +782,10 @@ impl AppState {
                          handlers::update_process_button_state(self);
                      }
                  },
-                 UiMessage::UpdateEstimate(id, algo, est_size) => {
+                 UiMessage::UpdateEstimate(id, algo, est_size, margin) => {
                      if let Some(pos) = self.batch_items.iter().position(|item| item.id == id) {
                          if let Some(item) = self.batch_items.get_mut(pos) {
-                             item.cache_estimate(algo, est_size);
+                             item.cache_estimate(algo, est_size, margin);
                          }
                          if let Some(row) = self.find_ui_row_by_id(id) {
                              if let Some(ctrls) = &self.controls { ctrls.file_list.redraw_item(row); }
@@ -716,6 +793,24 @@ impl AppState {
                      }
                      self.update_accuracy_label();
                  },
+                 UiMessage::PartialEstimate(id, algo, est_size, confidence) => {
+                     if let Some(item) = self.get_batch_item_mut(id) && item.estimate_algo == algo && item.estimate_confidence.is_some() {
+                         item.estimated_size = est_size;
+                         item.estimate_confidence = Some(confidence);
+                     }
+                     if let Some(row) = self.find_ui_row_by_id(id) && let Some(ctrls) = &self.controls {
+                         ctrls.file_list.redraw_item(row);
+                     }
+                 },
+                 UiMessage::EstimateCancelled(id, algo) => {
+                     if let Some(item) = self.get_batch_item_mut(id) && item.estimate_algo == algo {
+                         item.estimated_size = 0;
+                         item.estimate_confidence = None;
+                     }
+                     if let Some(row) = self.find_ui_row_by_id(id) && let Some(ctrls) = &self.controls {
+                         ctrls.file_list.redraw_item(row);
+                     }
+                 },
                  _ => {}
             }
         }
@@ -763,7 +858,7 @@ impl AppState {
         if let Some(ctrls) = &self.controls {
             let (mut sum_est, mut sum_disk) = (0u64, 0u64);
             for item in &self.batch_items {
-                if item.estimated_size > 0 && item.disk_size > 0 && item.disk_size < item.logical_size {
+                if item.estimated_size > 0 && item.estimate_confidence.is_none() && item.disk_size > 0 && item.disk_size < item.logical_size {
                     sum_est += item.estimated_size;
                     sum_disk += item.disk_size;
                 }
@@ -789,44 +884,28 @@ impl AppState {
                 let idx = ComboBox::new(ctrls.action_panel.combo_hwnd()).get_selected_index();
                 match idx {
                     0 => None,
-                    1 => Some(WofAlgorithm::Xpress4K),
-                    2 => Some(WofAlgorithm::Xpress8K),
-                    3 => Some(WofAlgorithm::Xpress16K),
-                    4 => Some(WofAlgorithm::Lzx),
-                    5 => Some(WofAlgorithm::Lznt1),
-                    _ => Some(WofAlgorithm::Xpress8K),
+                    1 => Some(AlgoChoice::Fixed(WofAlgorithm::Xpress4K)),
+                    2 => Some(AlgoChoice::Fixed(WofAlgorithm::Xpress8K)),
+                    3 => Some(AlgoChoice::Fixed(WofAlgorithm::Xpress16K)),
+                    4 => Some(AlgoChoice::Fixed(WofAlgorithm::Lzx)),
+                    5 => Some(AlgoChoice::Fixed(WofAlgorithm::Lznt1)),
+                    6 => Some(AlgoChoice::Auto),
+                    _ => Some(AlgoChoice::Fixed(WofAlgorithm::Xpress8K)),
                 }
             } else {
                 return;
             };
 
-            let mut items_to_estimate: Vec<(u32, String, WofAlgorithm)> = Vec::new();
-            
-            for item in self.batch_items.iter_mut() {
-                let effective_algo = algo.unwrap_or(item.algorithm);
-                if let Some(cached) = item.get_cached_estimate(effective_algo) {
-                    item.estimated_size = cached;
-                } else {
-                    items_to_estimate.push((item.id, item.path.clone(), effective_algo));
-                    item.estimated_size = 0; // Clears it to "Estimating..."
-                }
+            // Queued jobs for the previous algorithm are superseded row by row
+            self.update_estimate_focus();
+            for idx in 0..self.batch_items.len() {
+                let effective_algo = algo.unwrap_or(self.batch_items[idx].algorithm);
+                self.show_estimate(idx, effective_algo);
             }
 
             if let Some(ctrls) = &self.controls {
                 ctrls.file_list.redraw_all();
             }
-
-            if items_to_estimate.is_empty() {
-                return;
-            }
-
-            let tx = self.tx.clone();
-            std::thread::spawn(move || {
-                for (id, path, algo) in items_to_estimate {
-                    let estimated = crate::engine::estimator::estimate_path(&path, algo);
-                    let _ = tx.send(UiMessage::UpdateEstimate(id, algo, estimated));
-                }
-            });
     }
 
     unsafe fn handle_destroy(&mut self, hwnd: HWND) {
@@ -867,16 +946,11 @@ impl AppState {
                  
                  if parts.len() >= 3 {
                      let path = parts[0].to_string();
-                     let algo = match parts[1] {
-                         "xpress4k" => WofAlgorithm::Xpress4K,
-                         "xpress8k" => WofAlgorithm::Xpress8K,
-                         "xpress16k" => WofAlgorithm::Xpress16K,
-                         "lznt1" => WofAlgorithm::Lznt1,
-                         "lzx" => WofAlgorithm::Lzx,
-                         _ => WofAlgorithm::Xpress8K,
-                     };
+                     let algo = AlgoChoice::from_name(parts[1]).unwrap_or_default();
                      let action = match parts[2] {
                          "decompress" => BatchAction::Decompress,
+                         "dryrun" => BatchAction::DryRun,
+                         "verify" => BatchAction::Verify,
                          _ => BatchAction::Compress,
                      };
                      
@@ -930,6 +1004,8 @@ impl AppState {
                             let mut text: Option<Vec<u16>> = None;
                             match col {
                                 0 => text = Some(to_wstring(&item.path)),
+                                // Files stored several ways (a partial run, or drift): "82% LZX, 15% uncompressed"
+                                1 if !item.breakdown.is_uniform() => text = Some(to_wstring(&item.breakdown.summary())),
                                 1 => {
                                     let state = item.final_state.unwrap_or(CompressionState::None);
                                     text = Some(match state {
@@ -946,21 +1022,33 @@ impl AppState {
                                 },
                                 2 => {
                                     text = Some(match item.algorithm {
-                                        WofAlgorithm::Xpress4K => w!("XPRESS4K").to_vec(),
-                                        WofAlgorithm::Xpress8K => w!("XPRESS8K").to_vec(),
-                                        WofAlgorithm::Xpress16K => w!("XPRESS16K").to_vec(),
-                                        WofAlgorithm::Lzx => w!("LZX").to_vec(),
-                                        WofAlgorithm::Lznt1 => w!("LZNT1").to_vec(),
+                                        AlgoChoice::Fixed(WofAlgorithm::Xpress4K) => w!("XPRESS4K").to_vec(),
+                                        AlgoChoice::Fixed(WofAlgorithm::Xpress8K) => w!("XPRESS8K").to_vec(),
+                                        AlgoChoice::Fixed(WofAlgorithm::Xpress16K) => w!("XPRESS16K").to_vec(),
+                                        AlgoChoice::Fixed(WofAlgorithm::Lzx) => w!("LZX").to_vec(),
+                                        AlgoChoice::Fixed(WofAlgorithm::Lznt1) => w!("LZNT1").to_vec(),
+                                        AlgoChoice::Auto => w!("Auto").to_vec(),
                                     });
                                 },
-                                3 => text = Some(if item.action == BatchAction::Compress { w!("Compress").to_vec() } else { w!("Decompress").to_vec() }),
+                                3 => text = Some(match item.action {
+                                    BatchAction::Compress => w!("Compress").to_vec(),
+                                    BatchAction::Decompress => w!("Decompress").to_vec(),
+                                    BatchAction::DryRun => w!("Dry Run").to_vec(),
+                                    BatchAction::Verify => w!("Verify").to_vec(),
+                                }),
                                 4 => text = Some(crate::utils::format_size(item.logical_size)),
                                 5 => {
-                                    if item.estimated_size > 0 {
-                                        text = Some(crate::utils::format_size(item.estimated_size));
-                                    } else {
-                                        text = Some(w!("Estimating...").to_vec());
-                                    }
+                                    text = Some(match (item.estimated_size, item.estimate_confidence) {
+                                        (0, Some(_)) => w!("Estimating...").to_vec(),
+                                        (0, None) => w!("-").to_vec(),
+                                        // Sampled: "1.2 GB ±3%" (95% interval), exact below 1%
+                                        (size, None) => crate::utils::format_estimate(size, item.estimate_margin),
+                                        // Still sampling: "~1.2 GB (40%)"
+                                        (size, Some(c)) => {
+                                            let pct = u64_to_wstring((c * 100.0) as u64);
+                                            crate::utils::concat_wstrings(&[w!("~"), &crate::utils::format_size(size), w!(" ("), &pct, w!("%)")])
+                                        },
+                                    });
                                 },
                                 6 => {
                                     if item.disk_size > 0 {
@@ -978,12 +1066,15 @@ impl AppState {
                                 9 => {
                                     if let Some(ref override_msg) = item.status_override {
                                         text = Some(to_wstring(override_msg));
+                                    } else if let (Some(plan), BatchStatus::Complete) = (&item.plan, &item.status) {
+                                        // "Compress 120 with LZX, skip 30 | saves 1.2 GB"
+                                        text = Some(crate::utils::concat_wstrings(&[&to_wstring(&plan.summary()), w!(" | saves "), &crate::utils::format_size(plan.savings())]));
                                     } else {
                                         let st = match &item.status {
                                             BatchStatus::Pending => w!("Pending").to_vec(),
                                             BatchStatus::Processing => w!("Processing").to_vec(),
                                             BatchStatus::Complete => w!("Complete").to_vec(),
-                                            BatchStatus::Error(_) => w!("Error").to_vec(),
+                                            BatchStatus::Error(e) => to_wstring(e),
                                         };
                                         text = Some(st);
                                     }
@@ -1033,6 +1124,8 @@ impl AppState {
                         }
                     }
                     return 0; // handled
+                } else if nmhdr.code == LVN_GETINFOTIPW {
+                    handlers::on_list_info_tip(self, lparam);
                 } else if nmhdr.code == NM_CLICK || nmhdr.code == NM_DBLCLK {
                     let nmia = &*(lparam as *const NMITEMACTIVATE);
                     handlers::on_list_click(self, hwnd, nmia.iItem, nmia.iSubItem, nmhdr.code);
@@ -1040,6 +1133,10 @@ impl AppState {
                     handlers::on_column_click(self, lparam);
                 } else if nmhdr.code == LVN_ITEMCHANGED {
                    handlers::update_process_button_state(self);
+                   self.update_estimate_focus();
+                } else if nmhdr.code == LVN_ODCACHEHINT {
+                   // Sent when the range of rows on screen changes (scroll, resize)
+                   self.update_estimate_focus();
                 } else if nmhdr.code == NM_RCLICK {
                      let nmia = &*(lparam as *const NMITEMACTIVATE);
                      if handlers::on_list_rclick(self, hwnd, nmia.iItem, nmia.iSubItem) {
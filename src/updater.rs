@@ -4,7 +4,7 @@ use std::{ffi::c_void, ptr::{null, null_mut}};
 
 const API: &str = "https://api.github.com/repos/IRedDragonICY/compactrs/releases/latest";
 
-#[link(name = "winhttp")]
+#[cfg_attr(windows, link(name = "winhttp"))]
 unsafe extern "system" {
     fn WinHttpOpen(a: LPCWSTR, b: u32, c: LPCWSTR, d: LPCWSTR, e: u32) -> *mut c_void;
     fn WinHttpConnect(a: *mut c_void, b: LPCWSTR, c: u16, d: u32) -> *mut c_void;
@@ -156,7 +156,19 @@ pub fn concat_wstrings(parts: &[&[u16]]) -> Vec<u16> {
     res
 }
 
+/// Joins UTF-16 lines with "\r\n" (as edit controls and tooltips expect) into one
+/// null-terminated vector.
+pub fn join_lines(lines: &[Vec<u16>]) -> Vec<u16> {
+    let mut parts: Vec<&[u16]> = Vec::with_capacity(lines.len() * 2);
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 { parts.push(w!("\r\n")); }
+        parts.push(line);
+    }
+    concat_wstrings(&parts)
+}
+
 /// Formats a byte size into a human-readable string using the Windows Shell API.
+#[cfg(windows)]
 pub fn format_size(bytes: u64) -> Vec<u16> {
     let mut buffer: [u16; 32] = [0; 32];
     
@@ -179,6 +191,84 @@ pub fn format_size(bytes: u64) -> Vec<u16> {
     }
 }
 
+/// Same three significant digits as `StrFormatByteSizeW` ("532 bytes", "1.46 KB", "585 KB").
+#[cfg(not(windows))]
+pub fn format_size(bytes: u64) -> Vec<u16> {
+    const UNITS: [&str; 6] = ["KB", "MB", "GB", "TB", "PB", "EB"];
+    if bytes < 1024 { return to_wstring(&[&bytes.to_string(), " bytes"].concat()); }
+    // Hundredths of a unit, truncated like the Shell does
+    let mut scaled = bytes as u128 * 100 / 1024;
+    let mut unit = 0;
+    while scaled >= 1000 * 100 && unit < UNITS.len() - 1 {
+        scaled /= 1024;
+        unit += 1;
+    }
+    let decimals = if scaled < 10 * 100 { 2 } else if scaled < 100 * 100 { 1 } else { 0 };
+    let value = (scaled / 10u128.pow(2 - decimals)) as u64;
+    to_wstring(&[&fixed_point(value, decimals), " ", UNITS[unit]].concat())
+}
+
+/// `format_size` as a plain string, for logs, the console and reports.
+pub fn size_text(bytes: u64) -> String {
+    let w = format_size(bytes);
+    String::from_utf16_lossy(&w[..w.len().saturating_sub(1)])
+}
+
+/// Formats an estimate with its 95% margin as "1.2 GB ±3%"; the margin is left out below 1%.
+pub fn format_estimate(size: u64, margin: u64) -> Vec<u16> {
+    let pct = margin * 100 / size.max(1);
+    if pct == 0 {
+        format_size(size)
+    } else {
+        concat_wstrings(&[&format_size(size), &to_wstring(" \u{00B1}"), &u64_to_wstring(pct), w!("%")])
+    }
+}
+
+/// One line per storage kind of `breakdown`, e.g. "LZX: 1204 files, 3.2 GB (1.1 GB on disk)".
+pub fn format_breakdown(breakdown: &crate::engine::wof::AlgoBreakdown) -> Vec<u16> {
+    let lines: Vec<Vec<u16>> = breakdown.entries().iter().map(|(algo, t)| {
+        let name = algo.map_or("Uncompressed".to_string(), |a| a.name().to_uppercase());
+        concat_wstrings(&[
+            &to_wstring(&[&name, ": ", &t.files.to_string(), " files, "].concat()),
+            &format_size(t.logical),
+            w!(" ("),
+            &format_size(t.disk),
+            w!(" on disk)"),
+        ])
+    }).collect();
+    join_lines(&lines)
+}
+
+/// Tooltip lines for a dry-run plan: the projected savings, then one line per algorithm
+/// ("LZX: 100 files, 2.0 GB to 1.1 GB") and per skip reason.
+pub fn format_plan(plan: &crate::engine::dry_run::DryRunPlan) -> Vec<u16> {
+    let mut lines: Vec<Vec<u16>> = vec![concat_wstrings(&[
+        w!("Dry run saves "),
+        &format_size(plan.savings()),
+        w!(" ("),
+        &format_size(plan.current),
+        w!(" to "),
+        &format_size(plan.projected()),
+        w!(")"),
+    ])];
+    for (algo, t) in plan.compress.entries() {
+        let Some(algo) = algo else { continue };
+        lines.push(concat_wstrings(&[
+            &to_wstring(&[&algo.name().to_uppercase(), ": ", &t.files.to_string(), " files, "].concat()),
+            &format_size(t.logical),
+            w!(" to "),
+            &format_size(t.disk),
+        ]));
+    }
+    for (reason, files, bytes) in plan.skip_reasons() {
+        lines.push(concat_wstrings(&[
+            &to_wstring(&["Skip (", &reason, "): ", &files.to_string(), " files, "].concat()),
+            &format_size(bytes),
+        ]));
+    }
+    join_lines(&lines)
+}
+
 /// Reveal a file or folder in Windows Explorer
 pub fn reveal_path_in_explorer(path: &str) {
     let select_prefix = w!("/select,\"");
@@ -216,18 +306,33 @@ pub fn calculate_ratio_string(logical: u64, disk: u64) -> Vec<u16> {
     // Use u128 to prevent overflow during multiplication if file sizes are huge (exabytes)
     // Though u64 max is 18 EB, * 1000 would overflow.
     let ratio_10x = (saved as u128 * 1000) / (logical as u128);
-    let whole = (ratio_10x / 10) as u32;
-    let decimal = (ratio_10x % 10) as u32;
+    to_wstring(&[&fixed_point(ratio_10x as u64, 1), "%"].concat())
+}
 
-    let mut buffer = [0u16; 32];
-    let fmt = crate::w!("%u.%u%%");
-    
-    unsafe {
-        crate::types::wsprintfW(buffer.as_mut_ptr(), fmt.as_ptr(), whole, decimal);
-    }
-    
-    let len = (0..32).take_while(|&i| buffer[i] != 0).count();
-    buffer[..=len].to_vec()
+/// Formats a fixed-point number holding `decimals` fractional digits, e.g. `(790, 2)` -> "7.90".
+pub fn fixed_point(scaled: u64, decimals: u32) -> String {
+    let scale = 10u64.pow(decimals);
+    let whole = (scaled / scale).to_string();
+    if decimals == 0 { return whole; }
+    let frac = (scaled % scale).to_string();
+    [&whole, ".", &"0".repeat(decimals as usize - frac.len()), &frac].concat()
+}
+
+/// `s` followed by spaces up to `width` characters, for console columns.
+pub fn align_left(s: &str, width: usize) -> String {
+    [s, &" ".repeat(width.saturating_sub(s.chars().count()))].concat()
+}
+
+/// `s` preceded by spaces up to `width` characters, for console columns.
+pub fn align_right(s: &str, width: usize) -> String {
+    [&" ".repeat(width.saturating_sub(s.chars().count())), s].concat()
+}
+
+/// Whether `path` is `root` or lies under it, case-insensitively and by whole segments:
+/// "D:\Games" covers "D:\games\a.exe" but not "D:\Games2".
+pub fn path_covers(root: &str, path: &str) -> bool {
+    let root = root.trim_end_matches('\\').to_lowercase();
+    path.to_lowercase().strip_prefix(&root).is_some_and(|rest| rest.is_empty() || rest.starts_with('\\'))
 }
 
 /// Helper to get client rect (safe wrapper)
@@ -283,6 +388,27 @@ impl PathBuffer {
         pb
     }
     
+    /// Creates a PathBuffer from an OS path without going through `str`, so names that are
+    /// not valid Unicode (unpaired surrogates) are kept. Same `\\?\` prefixing as `from`.
+    #[cfg(windows)]
+    pub fn from_os(s: &std::ffi::OsStr) -> Self {
+        use std::os::windows::ffi::OsStrExt;
+        let wide: Vec<u16> = s.encode_wide().map(|c| if c == b'/' as u16 { b'\\' as u16 } else { c }).collect();
+        let prefix = &w!("\\\\?\\")[..4]; // without the terminator
+        let mut pb = Self::with_capacity(wide.len() + 10);
+        if wide.len() > 240 && wide.contains(&(b':' as u16)) && !wide.starts_with(prefix) {
+            pb.buf.extend_from_slice(prefix);
+        }
+        pb.buf.extend_from_slice(&wide);
+        pb.buf.push(0);
+        pb
+    }
+
+    #[cfg(not(windows))]
+    pub fn from_os(s: &std::ffi::OsStr) -> Self {
+        Self::from(&s.to_string_lossy())
+    }
+
     /// Appends a component to the path, adding a backslash if needed.
     /// Normalizes `/` to `\` on the fly without allocation.
     pub fn push(&mut self, s: &str) {
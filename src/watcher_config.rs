@@ -1,7 +1,7 @@
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::mem;
-use crate::engine::wof::WofAlgorithm;
+use crate::engine::wof::AlgoChoice;
 
 pub const MAX_PATH_LEN: usize = 260; // Standard MAX_PATH
 
@@ -10,7 +10,8 @@ pub const MAX_PATH_LEN: usize = 260; // Standard MAX_PATH
 pub struct WatcherTask {
     pub id: u32,
     pub path: [u16; MAX_PATH_LEN],
-    pub algorithm: WofAlgorithm,
+    /// `AlgoChoice::code()`; see `algorithm()`.
+    pub algorithm_code: u32,
     pub days_mask: u8, // Bit 0=Mon, 1=Tue, ... 6=Sun, 7=Every Day
     pub time_hour: u8,
     pub time_minute: u8,
@@ -23,7 +24,7 @@ impl Default for WatcherTask {
         Self {
             id: 0,
             path: [0; MAX_PATH_LEN],
-            algorithm: WofAlgorithm::Xpress8K,
+            algorithm_code: AlgoChoice::default().code(),
             days_mask: 0,
             time_hour: 0,
             time_minute: 0,
@@ -34,7 +35,7 @@ impl Default for WatcherTask {
 }
 
 impl WatcherTask {
-    pub fn new(id: u32, path_str: &str, algorithm: WofAlgorithm, days_mask: u8, hour: u8, minute: u8) -> Self {
+    pub fn new(id: u32, path_str: &str, algorithm: AlgoChoice, days_mask: u8, hour: u8, minute: u8) -> Self {
         let mut path = [0u16; MAX_PATH_LEN];
         let mut i = 0;
         for c in path_str.encode_utf16() {
@@ -47,7 +48,7 @@ impl WatcherTask {
         Self {
             id,
             path,
-            algorithm,
+            algorithm_code: algorithm.code(),
             days_mask,
             time_hour: hour,
             time_minute: minute,
@@ -56,6 +57,14 @@ impl WatcherTask {
         }
     }
 
+    pub fn algorithm(&self) -> AlgoChoice {
+        AlgoChoice::from_code(self.algorithm_code)
+    }
+
+    pub fn set_algorithm(&mut self, algo: AlgoChoice) {
+        self.algorithm_code = algo.code();
+    }
+
     pub fn get_path(&self) -> String {
         let end = self.path.iter().position(|&c| c == 0).unwrap_or(MAX_PATH_LEN);
         String::from_utf16_lossy(&self.path[..end])